@@ -0,0 +1,311 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+//! Stable problem fingerprints for cached solution lookups
+//!
+//! A fingerprint is a SHA-256 over the problem's normalized terms:
+//! lowercased, tokenized, with PIDs, paths, IPs and other numbers redacted,
+//! stopwords dropped, failure phrasings ("won't", "fails to", "cannot")
+//! folded into one term, plain negations ("not", "doesn't") into another,
+//! and the rest stemmed. The terms are sorted, so
+//! word order doesn't matter either. Keys carry [`VERSION`]; bumping it when
+//! the normalization changes makes old keys miss instead of colliding.
+
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap};
+
+/// Normalization version, part of every fingerprint
+pub const VERSION: u32 = 2;
+
+/// Least term similarity for a nearest-neighbour match
+pub const NEIGHBOUR_THRESHOLD: f64 = 0.6;
+
+const STOPWORDS: &[&str] = &[
+    "a", "after", "all", "am", "an", "and", "any", "are", "as", "at", "be", "been", "being", "but",
+    "by", "does", "for", "from", "get", "gets", "getting", "got", "has", "have", "how", "i", "im",
+    "in", "into", "is", "it", "its", "just", "keeps", "me", "my", "of", "on", "or", "so", "some",
+    "still", "that", "the", "then", "there", "this", "to", "up", "us", "was", "we", "what", "when",
+    "why", "will", "with",
+];
+
+/// Ways of saying an attempt doesn't work, folded into `fail`
+const FAILURE_WORDS: &[&str] = &[
+    "broken", "can't", "cannot", "cant", "couldn't", "fail", "failed", "failing", "fails",
+    "failure", "unable", "won't", "wont",
+];
+
+/// Plain negations, folded into `not`; "is not running" isn't a failure
+const NEGATION_WORDS: &[&str] = &[
+    "aren't", "doesn't", "doesnt", "don't", "dont", "isn't", "never", "no", "not", "wasn't",
+];
+
+/// The normalized terms of a problem description
+pub fn terms(problem: &str) -> BTreeSet<String> {
+    problem
+        .replace('\u{2019}', "'")
+        .split_whitespace()
+        .flat_map(|word| {
+            let word = word.trim_matches(|c: char| ",;:!?(){}<>\"'`".contains(c));
+            redact(word).unwrap_or_else(|| {
+                word.to_lowercase()
+                    .split(|c: char| !c.is_alphanumeric() && c != '\'')
+                    .map(str::to_string)
+                    .collect()
+            })
+        })
+        .filter_map(|token| {
+            let token = token.trim_matches('\'');
+            if token.is_empty() || STOPWORDS.contains(&token) {
+                None
+            } else if FAILURE_WORDS.contains(&token) {
+                Some("fail".to_string())
+            } else if NEGATION_WORDS.contains(&token) {
+                Some("not".to_string())
+            } else if token.chars().all(|c| c.is_ascii_digit()) {
+                Some("<num>".to_string())
+            } else {
+                Some(stem(token))
+            }
+        })
+        .collect()
+}
+
+/// Stable lookup key for a problem: `v{VERSION}-` and a digest, e.g. `v2-3f2a…`
+pub fn fingerprint(problem: &str) -> String {
+    let normalized = terms(problem).into_iter().collect::<Vec<_>>().join(" ");
+    let digest = ring::digest::digest(&ring::digest::SHA256, normalized.as_bytes());
+    let hex: String = digest.as_ref()[..16]
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect();
+    format!("v{}-{}", VERSION, hex)
+}
+
+/// Term-set similarity, 0 to 1
+fn jaccard(a: &BTreeSet<String>, b: &BTreeSet<String>) -> f64 {
+    let union = a.union(b).count();
+    if union == 0 {
+        return 0.0;
+    }
+    a.intersection(b).count() as f64 / union as f64
+}
+
+/// Fingerprints and terms of the known problems, so a lookup only compares
+/// against problems that share a term instead of re-reading every one
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Index {
+    version: u32,
+    /// Fingerprint to solution ID
+    keys: HashMap<String, String>,
+    /// Term to the IDs of solutions whose problem has it
+    postings: HashMap<String, Vec<String>>,
+    /// Solution ID to its problem's terms
+    terms: HashMap<String, BTreeSet<String>>,
+}
+
+impl Index {
+    pub fn build<'a>(problems: impl IntoIterator<Item = (&'a str, &'a str)>) -> Self {
+        let mut index = Self {
+            version: VERSION,
+            ..Default::default()
+        };
+        for (id, problem) in problems {
+            let terms = terms(problem);
+            index.keys.insert(fingerprint(problem), id.to_string());
+            for term in &terms {
+                index.postings.entry(term.clone()).or_default().push(id.to_string());
+            }
+            index.terms.insert(id.to_string(), terms);
+        }
+        index
+    }
+
+    /// Built with the current normalization
+    pub fn is_current(&self) -> bool {
+        self.version == VERSION
+    }
+
+    /// The ID whose problem reads most like this one and how closely, if any
+    /// is close enough; an equal fingerprint wins outright
+    pub fn nearest(&self, problem: &str) -> Option<(&str, f64)> {
+        if let Some(id) = self.keys.get(&fingerprint(problem)) {
+            return Some((id, 1.0));
+        }
+        let query = terms(problem);
+        let candidates: BTreeSet<&String> = query
+            .iter()
+            .filter_map(|term| self.postings.get(term))
+            .flatten()
+            .collect();
+        candidates
+            .into_iter()
+            .map(|id| (id.as_str(), jaccard(&query, &self.terms[id])))
+            .filter(|(_, similarity)| *similarity >= NEIGHBOUR_THRESHOLD)
+            .max_by(|a, b| a.1.total_cmp(&b.1))
+    }
+}
+
+/// Placeholders for volatile details that shouldn't split otherwise equal
+/// problems, or `None` if the word has none
+fn redact(word: &str) -> Option<Vec<String>> {
+    let is_number = |n: &str| !n.is_empty() && n.chars().all(|c| c.is_ascii_digit());
+    let lower = word.to_ascii_lowercase();
+
+    if word.starts_with('/') || word.starts_with("~/") {
+        return Some(vec!["<path>".to_string()]);
+    }
+    let bare = word.trim_start_matches('[').trim_end_matches(']');
+    if bare.parse::<std::net::IpAddr>().is_ok()
+        || word.rsplit_once(':').is_some_and(|(ip, port)| {
+            ip.parse::<std::net::Ipv4Addr>().is_ok() && port.parse::<u16>().is_ok()
+        })
+    {
+        return Some(vec!["<ip>".to_string()]);
+    }
+    // pid=1234, pid:1234
+    let pid = lower
+        .strip_prefix("pid=")
+        .or_else(|| lower.strip_prefix("pid:"));
+    if pid.is_some_and(is_number) {
+        return Some(vec!["pid".to_string(), "<pid>".to_string()]);
+    }
+    // Syslog-style nginx[1234]
+    let (name, pid) = lower.strip_suffix(']')?.rsplit_once('[')?;
+    (!name.is_empty() && is_number(pid)).then(|| vec![name.to_string(), "<pid>".to_string()])
+}
+
+/// Light suffix stripping, enough to fold plurals and verb forms
+///
+/// Every word goes through the same steps, so all forms of a word meet at
+/// one stem: plural `s`, then `ing`/`ed` (undoubling the consonant left
+/// behind), then a final `e`.
+fn stem(word: &str) -> String {
+    let mut word = word.to_string();
+    let len = |w: &str| w.chars().count();
+
+    if word.ends_with("sses") {
+        word.truncate(word.len() - 2);
+    } else if word.ends_with("ies") && len(&word) > 4 {
+        word.truncate(word.len() - 3);
+        word.push('y');
+    } else if word.ends_with('s')
+        && len(&word) > 3
+        && !["ss", "us", "is"].iter().any(|e| word.ends_with(e))
+    {
+        word.pop();
+    }
+
+    for suffix in ["ing", "ed"] {
+        if let Some(stem) = word.strip_suffix(suffix) {
+            if len(stem) >= 3 {
+                let mut stem = stem.to_string();
+                let mut last = stem.chars().rev();
+                if let (Some(a), Some(b)) = (last.next(), last.next()) {
+                    if a == b && a.is_ascii_alphabetic() && !"aeioulsz".contains(a) {
+                        stem.pop();
+                    }
+                }
+                word = stem;
+            }
+            break;
+        }
+    }
+
+    if word.ends_with('e') && len(&word) > 3 {
+        word.pop();
+    }
+    word
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_paraphrases_share_a_fingerprint() {
+        let key = fingerprint("nginx won't start");
+        assert!(key.starts_with("v2-"));
+        assert_eq!(key.len(), 3 + 32);
+        assert_eq!(fingerprint("Nginx fails to start"), key);
+        assert_eq!(fingerprint("nginx failed to start!"), key);
+        assert_eq!(fingerprint("nginx: starting fails"), key);
+        assert_ne!(fingerprint("nginx won't stop"), key);
+        assert_eq!(fingerprint("The disk is full"), fingerprint("disk full"));
+    }
+
+    #[test]
+    fn test_negation_and_errors_are_not_failures() {
+        assert_ne!(fingerprint("nginx is not running"), fingerprint("nginx running fails"));
+        assert_ne!(fingerprint("disk error"), fingerprint("disk failure"));
+        assert_eq!(fingerprint("nginx isn't running"), fingerprint("nginx not running"));
+        assert_eq!(fingerprint("wifi doesn't connect"), fingerprint("wifi does not connect"));
+        assert!(terms("no error").contains("not"));
+        assert!(terms("no error").contains("error"));
+    }
+
+    #[test]
+    fn test_word_forms_share_a_stem() {
+        for forms in [
+            &["process", "processes", "processing", "processed"][..],
+            &["stop", "stops", "stopped", "stopping"],
+            &["cache", "caches", "cached", "caching"],
+            &["service", "services"],
+            &["run", "runs", "running"],
+            &["install", "installs", "installed", "installing"],
+            &["dependency", "dependencies"],
+        ] {
+            let stems: BTreeSet<String> = forms.iter().map(|w| stem(w)).collect();
+            assert_eq!(stems.len(), 1, "{:?} -> {:?}", forms, stems);
+        }
+        assert_eq!(stem("status"), "status");
+        assert_eq!(stem("dns"), "dns");
+    }
+
+    #[test]
+    fn test_index_nearest() {
+        let index = Index::build([
+            ("a", "nginx won't start"),
+            ("b", "wifi drops every few minutes"),
+            ("c", "nginx won't start after upgrade on boot"),
+        ]);
+        assert!(index.is_current());
+        assert_eq!(index.nearest("Nginx fails to start"), Some(("a", 1.0)));
+        let (id, similarity) = index.nearest("wifi drops every minute").unwrap();
+        assert_eq!(id, "b");
+        assert!(similarity >= NEIGHBOUR_THRESHOLD);
+        assert!(index.nearest("printer jammed").is_none());
+    }
+
+    #[test]
+    fn test_volatile_details_are_redacted() {
+        assert_eq!(
+            fingerprint("sshd[1234] refused connection from 10.0.0.5"),
+            fingerprint("sshd[98] refused connection from 192.168.1.20")
+        );
+        assert_eq!(
+            fingerprint("cannot write /var/log/app.log"),
+            fingerprint("can't write /tmp/x")
+        );
+        assert_eq!(
+            fingerprint("process pid=42 killed by OOM"),
+            fingerprint("process pid=31337 killed by OOM")
+        );
+        assert_eq!(
+            fingerprint("timeout to 10.1.1.1:8080"),
+            fingerprint("timeout to [::1]")
+        );
+    }
+
+    fn similarity(a: &str, b: &str) -> f64 {
+        jaccard(&terms(a), &terms(b))
+    }
+
+    #[test]
+    fn test_similarity() {
+        assert_eq!(similarity("nginx won't start", "nginx fails to start"), 1.0);
+        assert!(
+            similarity("nginx won't start after upgrade", "nginx fails to start")
+                >= NEIGHBOUR_THRESHOLD
+        );
+        assert!(similarity("nginx won't start", "wifi drops") < NEIGHBOUR_THRESHOLD);
+        assert_eq!(similarity("", "the"), 0.0);
+    }
+}
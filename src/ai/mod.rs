@@ -1,35 +1,68 @@
 // SPDX-License-Identifier: AGPL-3.0-or-later
 //! AI/SLM integration - local model with Claude fallback
 
+pub mod fingerprint;
+
 use anyhow::Result;
-use crate::storage::Storage;
+use crate::storage::{normalize_problem, Solution, Storage};
 use crate::cache::Cache;
+use crate::reasoning::{self, ReasoningEngine, Term};
+
+/// Cache key of the fingerprint index over the knowledge base
+const INDEX_KEY: &str = "fingerprints";
+
+/// How long the index is used before new solutions are picked up
+const INDEX_TTL: std::time::Duration = std::time::Duration::from_secs(300);
 
 /// Diagnose a problem using AI
+///
+/// With `explain`, fixes inferred by the reasoning engine are shown with
+/// their proofs.
 pub async fn diagnose(
     problem: &str,
     local_only: bool,
-    _storage: &Storage,
+    explain: bool,
+    storage: &Storage,
     cache: &Cache,
 ) -> Result<()> {
     println!("Diagnosing: {}", problem);
     println!("{}", "-".repeat(50));
 
     // Step 1: Check rules first
-    println!("\n[1/3] Checking rules...");
+    println!("\n[1/4] Checking rules...");
     // Would check rules engine here
 
-    // Step 2: Search knowledge base
-    println!("[2/3] Searching knowledge base...");
-    let cached = cache.get_solution_lookup(&hash_problem(problem)).await?;
-    if let Some(solution_id) = cached {
-        println!("  Found cached solution: {}", solution_id);
-        // Would retrieve and display solution
+    // Step 2: Infer fixes from known problems and how they relate
+    println!("[2/4] Reasoning over known problems...");
+    if infer_fixes(problem, explain, storage).await? {
         return Ok(());
     }
 
-    // Step 3: Query SLM
-    println!("[3/3] Querying SLM...");
+    // Step 3: Search knowledge base
+    println!("[3/4] Searching knowledge base...");
+    let key = fingerprint::fingerprint(problem);
+    let cached = match cache.get_solution_lookup(&key).await? {
+        Some(id) => storage.get(&id).await?,
+        None => None,
+    };
+    if let Some(solution) = cached {
+        println!("  Found cached solution: {}", solution.id);
+        print_solution(&solution);
+        return Ok(());
+    }
+    if let Some((solution, similarity)) = nearest_solution(problem, storage, cache).await? {
+        println!(
+            "  Found similar problem ({:.0}% match): {}",
+            similarity * 100.0,
+            solution.problem
+        );
+        print_solution(&solution);
+        cache.cache_solution_lookup(&key, &solution.id).await?;
+        return Ok(());
+    }
+
+    // Step 4: Query SLM
+    println!("[4/4] Querying SLM...");
 
     if local_only {
         query_local_slm(problem).await?;
@@ -49,13 +82,76 @@ pub async fn diagnose(
     Ok(())
 }
 
-fn hash_problem(problem: &str) -> String {
-    use std::collections::hash_map::DefaultHasher;
-    use std::hash::{Hash, Hasher};
+/// Print the commands the reasoning engine concludes fix the problem, most
+/// confident first; false if it found none
+async fn infer_fixes(problem: &str, explain: bool, storage: &Storage) -> Result<bool> {
+    let engine = ReasoningEngine::from_storage(storage).await?;
+    let goal = reasoning::compound(
+        "fix",
+        vec![
+            reasoning::atom(&normalize_problem(problem)),
+            reasoning::var("Command"),
+        ],
+    );
+    let answers = engine.explain(&goal);
+    if answers.is_empty() {
+        return Ok(false);
+    }
+
+    println!("  Inferred {} fix(es):", answers.len());
+    for answer in &answers {
+        let command = match &answer.bindings["Command"] {
+            Term::Atom(command) => command.clone(),
+            other => other.to_string(),
+        };
+        println!("  $ {}  ({:.0}% confidence)", command, answer.confidence * 100.0);
+        if explain {
+            for proof in &answer.proofs {
+                for line in proof.to_string().lines() {
+                    println!("      {}", line);
+                }
+            }
+        }
+    }
+    if !explain {
+        println!("  (run with --explain to see how each was derived)");
+    }
+    Ok(true)
+}
+
+/// The known solution whose problem reads most like this one, if any is
+/// close enough
+///
+/// Looks up the cached fingerprint index, building it from the knowledge
+/// base only when it has expired.
+async fn nearest_solution(
+    problem: &str,
+    storage: &Storage,
+    cache: &Cache,
+) -> Result<Option<(Solution, f64)>> {
+    let index = match cache.get::<fingerprint::Index>(INDEX_KEY).await? {
+        Some(index) if index.is_current() => index,
+        _ => {
+            let solutions = storage.all_solutions().await?;
+            let index = fingerprint::Index::build(
+                solutions.iter().map(|s| (s.id.as_str(), s.problem.as_str())),
+            );
+            cache.set(INDEX_KEY, &index, Some(INDEX_TTL)).await?;
+            index
+        }
+    };
+    let Some((id, similarity)) = index.nearest(problem) else {
+        return Ok(None);
+    };
+    // A solution deleted since the index was built is simply not found
+    Ok(storage.get(id).await?.map(|s| (s, similarity)))
+}
 
-    let mut hasher = DefaultHasher::new();
-    problem.to_lowercase().hash(&mut hasher);
-    format!("{:x}", hasher.finish())
+fn print_solution(solution: &Solution) {
+    println!("\n{}", solution.solution);
+    for cmd in &solution.commands {
+        println!("  $ {}", cmd);
+    }
 }
 
 async fn query_local_slm(problem: &str) -> Result<String> {
@@ -0,0 +1,528 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+//! Append-only, tamper-evident audit log of automated actions
+//!
+//! Every action the rules engine executes is appended as one JSON line to
+//! `audit.jsonl` in the data directory. Each record carries the SHA-256 of its
+//! predecessor, so editing, reordering or deleting a line breaks the chain and
+//! is reported by `psa audit verify`.
+//!
+//! Truncating the tail of the log cannot be detected from the chain alone;
+//! `psa audit verify` prints the head hash so it can be pinned elsewhere.
+
+// Allow dead code - the writer side is only used by the rules engine, not the CLI binary
+#![allow(dead_code)]
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::io::{BufRead, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+/// File name of the audit log inside the data directory
+const AUDIT_FILE: &str = "audit.jsonl";
+
+/// Hash used as `prev_hash` for the first record
+const GENESIS_HASH: &str = "0000000000000000000000000000000000000000000000000000000000000000";
+
+/// Maximum bytes of command output kept per record
+const MAX_OUTPUT_BYTES: usize = 4096;
+
+/// Audit action types
+#[derive(Debug, Clone)]
+pub enum AuditAction {
+    Verify,
+    Show { since: Option<String>, rule: Option<String> },
+}
+
+/// What happened when an action ran - input to [`AuditLog::append`]
+#[derive(Debug, Clone)]
+pub struct AuditEntry {
+    pub rule_id: String,
+    pub rule_version: String,
+    pub action: String,
+    pub argv: Vec<String>,
+    pub exit_status: Option<i32>,
+    pub success: bool,
+    pub output: String,
+    pub duration_ms: f64,
+}
+
+/// A single hash-chained record as stored on disk
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditRecord {
+    pub seq: u64,
+    pub timestamp: String,
+    pub rule_id: String,
+    pub rule_version: String,
+    pub correlation_id: Option<String>,
+    pub action: String,
+    pub argv: Vec<String>,
+    pub exit_status: Option<i32>,
+    pub success: bool,
+    pub output: String,
+    pub output_truncated: bool,
+    pub duration_ms: f64,
+    pub prev_hash: String,
+    pub hash: String,
+}
+
+impl AuditRecord {
+    /// Compute the chain hash: SHA-256 over the record serialized with an empty `hash`
+    fn compute_hash(&self) -> Result<String> {
+        let mut unhashed = self.clone();
+        unhashed.hash = String::new();
+        let bytes = serde_json::to_vec(&unhashed)?;
+        Ok(sha256_hex(&bytes))
+    }
+}
+
+/// Outcome of verifying the whole chain
+#[derive(Debug, Clone)]
+pub struct VerifyReport {
+    pub records: u64,
+    pub head_hash: String,
+    pub problems: Vec<String>,
+}
+
+impl VerifyReport {
+    pub fn is_intact(&self) -> bool {
+        self.problems.is_empty()
+    }
+}
+
+/// Handle to the append-only audit log
+///
+/// The daemon and CLI processes append to the same file, so every append
+/// takes an exclusive lock and chains to the record actually on disk.
+pub struct AuditLog {
+    path: PathBuf,
+}
+
+impl AuditLog {
+    /// Open the audit log in a directory (created if missing)
+    pub fn open(dir: &Path) -> Result<Self> {
+        std::fs::create_dir_all(dir)?;
+        Ok(Self {
+            path: dir.join(AUDIT_FILE),
+        })
+    }
+
+    /// Open the audit log in the default data directory
+    pub fn open_default() -> Result<Self> {
+        Self::open(&crate::dirs::data_dir())
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Append an entry, chaining it to the previous record
+    ///
+    /// Holds an exclusive lock on the file from reading the head until the
+    /// record is written, so concurrent writers never chain to a stale head.
+    pub fn append(&self, entry: AuditEntry) -> Result<AuditRecord> {
+        let mut options = std::fs::OpenOptions::new();
+        options.create(true).read(true).append(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o600);
+        }
+        let mut file = options.open(&self.path)?;
+        file.lock()?;
+
+        let (last_seq, prev_hash) = read_head(&mut file)?;
+        let (output, output_truncated) = truncate_output(&entry.output);
+        let mut record = AuditRecord {
+            seq: last_seq + 1,
+            timestamp: chrono::Utc::now().to_rfc3339(),
+            rule_id: entry.rule_id,
+            rule_version: entry.rule_version,
+            correlation_id: crate::correlation::get().map(str::to_string),
+            action: entry.action,
+            argv: entry.argv,
+            exit_status: entry.exit_status,
+            success: entry.success,
+            output,
+            output_truncated,
+            duration_ms: entry.duration_ms,
+            prev_hash,
+            hash: String::new(),
+        };
+        record.hash = record.compute_hash()?;
+
+        let mut line = serde_json::to_string(&record)?;
+        line.push('\n');
+        file.write_all(line.as_bytes())?;
+        file.sync_data()?;
+        file.unlock()?;
+
+        Ok(record)
+    }
+
+    /// Read all records in order
+    pub fn records(&self) -> Result<Vec<AuditRecord>> {
+        if !self.path.exists() {
+            return Ok(vec![]);
+        }
+
+        let file = std::fs::File::open(&self.path)?;
+        let mut records = vec![];
+        for (i, line) in std::io::BufReader::new(file).lines().enumerate() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let record: AuditRecord = serde_json::from_str(&line)
+                .map_err(|e| anyhow::anyhow!("Malformed audit record on line {}: {}", i + 1, e))?;
+            records.push(record);
+        }
+        Ok(records)
+    }
+
+    /// Walk the chain and report every broken link
+    pub fn verify(&self) -> Result<VerifyReport> {
+        let mut problems = vec![];
+        let mut expected_prev = GENESIS_HASH.to_string();
+        let mut expected_seq = 1u64;
+        let mut count = 0u64;
+
+        if self.path.exists() {
+            let file = std::fs::File::open(&self.path)?;
+            for (i, line) in std::io::BufReader::new(file).lines().enumerate() {
+                let line = line?;
+                let line_no = i + 1;
+                if line.trim().is_empty() {
+                    continue;
+                }
+                count += 1;
+
+                let record: AuditRecord = match serde_json::from_str(&line) {
+                    Ok(r) => r,
+                    Err(e) => {
+                        problems.push(format!("line {}: unparseable record ({})", line_no, e));
+                        continue;
+                    }
+                };
+
+                if record.seq != expected_seq {
+                    problems.push(format!(
+                        "line {}: sequence {} (expected {})",
+                        line_no, record.seq, expected_seq
+                    ));
+                }
+                if record.prev_hash != expected_prev {
+                    problems.push(format!(
+                        "line {}: prev_hash does not match previous record",
+                        line_no
+                    ));
+                }
+                if record.compute_hash()? != record.hash {
+                    problems.push(format!("line {}: record hash mismatch (contents modified)", line_no));
+                }
+
+                expected_seq = record.seq + 1;
+                expected_prev = record.hash;
+            }
+        }
+
+        Ok(VerifyReport {
+            records: count,
+            head_hash: expected_prev,
+            problems,
+        })
+    }
+}
+
+/// Find the sequence number and hash of the last record by reading the file
+/// backwards from its end
+fn read_head(file: &mut std::fs::File) -> Result<(u64, String)> {
+    const CHUNK: u64 = 4096;
+
+    let len = file.seek(SeekFrom::End(0))?;
+    let mut tail: Vec<u8> = vec![];
+    let mut start = len;
+    loop {
+        // The last line is complete once a newline precedes its content
+        let content = tail.iter().rposition(|b| !b.is_ascii_whitespace());
+        if let Some(end) = content {
+            if let Some(newline) = tail[..end].iter().rposition(|&b| b == b'\n') {
+                tail.drain(..=newline);
+                break;
+            }
+        }
+        if start == 0 {
+            break;
+        }
+        let read = CHUNK.min(start);
+        start -= read;
+        let mut chunk = vec![0; read as usize];
+        file.seek(SeekFrom::Start(start))?;
+        file.read_exact(&mut chunk)?;
+        chunk.extend_from_slice(&tail);
+        tail = chunk;
+    }
+
+    let line = String::from_utf8_lossy(&tail);
+    let line = line.trim();
+    if line.is_empty() {
+        return Ok((0, GENESIS_HASH.to_string()));
+    }
+    let record: AuditRecord = serde_json::from_str(line)
+        .map_err(|e| anyhow::anyhow!("Malformed last audit record: {}", e))?;
+    Ok((record.seq, record.hash))
+}
+
+/// Handle audit subcommands
+pub async fn handle(action: AuditAction) -> Result<()> {
+    let log = AuditLog::open_default()?;
+
+    match action {
+        AuditAction::Verify => verify(&log)?,
+        AuditAction::Show { since, rule } => show(&log, since.as_deref(), rule.as_deref())?,
+    }
+    Ok(())
+}
+
+fn verify(log: &AuditLog) -> Result<()> {
+    let report = log.verify()?;
+
+    println!("Audit Log Verification");
+    println!("{}", "=".repeat(50));
+    println!("Path:      {}", log.path().display());
+    println!("Records:   {}", report.records);
+    println!("Head hash: {}", report.head_hash);
+
+    if report.is_intact() {
+        println!("\n✓ Hash chain intact");
+        Ok(())
+    } else {
+        println!("\n⚠ Tampering detected:");
+        for problem in &report.problems {
+            println!("  • {}", problem);
+        }
+        Err(anyhow::anyhow!(
+            "Audit log failed verification ({} problems)",
+            report.problems.len()
+        ))
+    }
+}
+
+fn show(log: &AuditLog, since: Option<&str>, rule: Option<&str>) -> Result<()> {
+    let since = since.map(parse_since).transpose()?;
+
+    println!(
+        "{:>6} {:<25} {:<30} {:<16} {:>6} {:>9}",
+        "SEQ", "TIME", "RULE", "ACTION", "EXIT", "MS"
+    );
+    println!("{}", "-".repeat(97));
+
+    for record in log.records()? {
+        if let Some(since) = since {
+            let at = chrono::DateTime::parse_from_rfc3339(&record.timestamp)
+                .map(|t| t.with_timezone(&chrono::Utc));
+            if at.map(|t| t < since).unwrap_or(false) {
+                continue;
+            }
+        }
+        if rule.is_some_and(|r| r != record.rule_id) {
+            continue;
+        }
+
+        let exit = match record.exit_status {
+            Some(code) => code.to_string(),
+            None if record.success => "ok".to_string(),
+            None => "err".to_string(),
+        };
+        println!(
+            "{:>6} {:<25} {:<30} {:<16} {:>6} {:>9.1}",
+            record.seq,
+            record.timestamp.get(..25).unwrap_or(&record.timestamp),
+            format!("{}@{}", record.rule_id, record.rule_version),
+            record.action,
+            exit,
+            record.duration_ms
+        );
+        if !record.argv.is_empty() {
+            println!("       argv: {:?}", record.argv);
+        }
+        if let Some(corr) = &record.correlation_id {
+            println!("       correlation: {}", corr);
+        }
+        let output = record.output.trim();
+        if !output.is_empty() {
+            let first = output.lines().next().unwrap_or("");
+            let more = if record.output_truncated || output.lines().count() > 1 { " …" } else { "" };
+            println!("       output: {}{}", first, more);
+        }
+    }
+
+    Ok(())
+}
+
+/// Parse a `--since` value: a relative age (`30m`, `6h`, `2d`, `1w`) or an RFC 3339 timestamp
+pub fn parse_since(s: &str) -> Result<chrono::DateTime<chrono::Utc>> {
+    let s = s.trim();
+    if let Ok(t) = chrono::DateTime::parse_from_rfc3339(s) {
+        return Ok(t.with_timezone(&chrono::Utc));
+    }
+
+    let invalid = || anyhow::anyhow!("Invalid --since value: {}", s);
+    let (split, unit) = s.char_indices().last().ok_or_else(invalid)?;
+    let num: i64 = s[..split].parse().map_err(|_| invalid())?;
+
+    let age = match unit {
+        's' => chrono::TimeDelta::try_seconds(num),
+        'm' => chrono::TimeDelta::try_minutes(num),
+        'h' => chrono::TimeDelta::try_hours(num),
+        'd' => chrono::TimeDelta::try_days(num),
+        'w' => chrono::TimeDelta::try_weeks(num),
+        _ => return Err(anyhow::anyhow!("Invalid --since unit '{}' (use s, m, h, d or w)", unit)),
+    };
+    age.and_then(|age| chrono::Utc::now().checked_sub_signed(age))
+        .ok_or_else(|| anyhow::anyhow!("--since value out of range: {}", s))
+}
+
+/// Truncate output to [`MAX_OUTPUT_BYTES`] on a char boundary
+fn truncate_output(output: &str) -> (String, bool) {
+    if output.len() <= MAX_OUTPUT_BYTES {
+        return (output.to_string(), false);
+    }
+    let mut end = MAX_OUTPUT_BYTES;
+    while !output.is_char_boundary(end) {
+        end -= 1;
+    }
+    (output[..end].to_string(), true)
+}
+
+/// Lowercase hex SHA-256 digest
+pub(crate) fn sha256_hex(bytes: &[u8]) -> String {
+    ring::digest::digest(&ring::digest::SHA256, bytes)
+        .as_ref()
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_log() -> AuditLog {
+        let dir = std::env::temp_dir().join(format!("psa-audit-{}", uuid::Uuid::new_v4()));
+        AuditLog::open(&dir).unwrap()
+    }
+
+    fn entry(rule_id: &str) -> AuditEntry {
+        AuditEntry {
+            rule_id: rule_id.to_string(),
+            rule_version: "1.0.0".to_string(),
+            action: "Shell".to_string(),
+            argv: vec!["sh".to_string(), "-c".to_string(), "true".to_string()],
+            exit_status: Some(0),
+            success: true,
+            output: String::new(),
+            duration_ms: 1.0,
+        }
+    }
+
+    #[test]
+    fn test_chain_verifies() {
+        let log = temp_log();
+        log.append(entry("rule-a")).unwrap();
+        log.append(entry("rule-b")).unwrap();
+
+        let report = log.verify().unwrap();
+        assert!(report.is_intact(), "{:?}", report.problems);
+        assert_eq!(report.records, 2);
+
+        let records = log.records().unwrap();
+        assert_eq!(records[1].prev_hash, records[0].hash);
+        assert_eq!(report.head_hash, records[1].hash);
+    }
+
+    #[test]
+    fn test_detects_modified_record() {
+        let log = temp_log();
+        log.append(entry("rule-a")).unwrap();
+        log.append(entry("rule-b")).unwrap();
+
+        let content = std::fs::read_to_string(log.path()).unwrap();
+        std::fs::write(log.path(), content.replacen("rule-a", "rule-x", 1)).unwrap();
+
+        let report = log.verify().unwrap();
+        assert!(!report.is_intact());
+    }
+
+    #[test]
+    fn test_detects_deleted_record() {
+        let log = temp_log();
+        for id in ["rule-a", "rule-b", "rule-c"] {
+            log.append(entry(id)).unwrap();
+        }
+
+        let content = std::fs::read_to_string(log.path()).unwrap();
+        let kept: Vec<&str> = content.lines().enumerate().filter(|(i, _)| *i != 1).map(|(_, l)| l).collect();
+        std::fs::write(log.path(), kept.join("\n") + "\n").unwrap();
+
+        let report = log.verify().unwrap();
+        assert!(!report.is_intact());
+    }
+
+    #[test]
+    fn test_reopen_continues_chain() {
+        let log = temp_log();
+        log.append(entry("rule-a")).unwrap();
+
+        let reopened = AuditLog::open(log.path().parent().unwrap()).unwrap();
+        let record = reopened.append(entry("rule-b")).unwrap();
+        assert_eq!(record.seq, 2);
+        assert!(reopened.verify().unwrap().is_intact());
+    }
+
+    #[test]
+    fn test_interleaved_writers_keep_one_chain() {
+        let log = temp_log();
+        let other = AuditLog::open(log.path().parent().unwrap()).unwrap();
+        log.append(entry("rule-a")).unwrap();
+        other.append(entry("rule-b")).unwrap();
+        let record = log.append(entry("rule-c")).unwrap();
+        assert_eq!(record.seq, 3);
+
+        let long = AuditEntry {
+            output: "x".repeat(MAX_OUTPUT_BYTES),
+            ..entry("rule-d")
+        };
+        other.append(long).unwrap();
+        assert_eq!(log.append(entry("rule-e")).unwrap().seq, 5);
+        assert!(log.verify().unwrap().is_intact());
+    }
+
+    #[test]
+    fn test_truncate_output() {
+        let long = "é".repeat(MAX_OUTPUT_BYTES);
+        let (out, truncated) = truncate_output(&long);
+        assert!(truncated);
+        assert!(out.len() <= MAX_OUTPUT_BYTES);
+
+        let (out, truncated) = truncate_output("short");
+        assert_eq!(out, "short");
+        assert!(!truncated);
+    }
+
+    #[test]
+    fn test_parse_since() {
+        let now = chrono::Utc::now();
+        let six_hours = parse_since("6h").unwrap();
+        assert!((now - six_hours).num_minutes() >= 359);
+        assert!(parse_since("2024-01-01T00:00:00Z").is_ok());
+        assert!(parse_since("6x").is_err());
+        assert!(parse_since("").is_err());
+        // A multibyte unit is an error, not a panic
+        assert!(parse_since("5µ").is_err());
+        assert!(parse_since("ää").is_err());
+        assert!(parse_since("µ").is_err());
+        // So are ages beyond what a timestamp can hold
+        assert!(parse_since("99999999999w").is_err());
+        assert!(parse_since("9223372036854775807s").is_err());
+    }
+}
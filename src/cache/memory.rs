@@ -0,0 +1,77 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+//! Bounded in-process cache with per-entry TTLs
+
+use lru::LruCache;
+use std::num::NonZeroUsize;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Least-recently-used map of encoded values; expired entries read as absent
+pub struct MemoryCache {
+    entries: Mutex<LruCache<String, (String, Instant)>>,
+}
+
+impl MemoryCache {
+    pub fn new(capacity: usize) -> Self {
+        let capacity = NonZeroUsize::new(capacity).unwrap_or(NonZeroUsize::MIN);
+        Self {
+            entries: Mutex::new(LruCache::new(capacity)),
+        }
+    }
+
+    fn entries(&self) -> std::sync::MutexGuard<'_, LruCache<String, (String, Instant)>> {
+        self.entries.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    pub fn get(&self, key: &str) -> Option<String> {
+        let mut entries = self.entries();
+        match entries.get(key) {
+            Some((value, expires)) if *expires > Instant::now() => Some(value.clone()),
+            Some(_) => {
+                entries.pop(key);
+                None
+            }
+            None => None,
+        }
+    }
+
+    pub fn set(&self, key: &str, value: String, ttl: Duration) {
+        self.entries()
+            .put(key.to_string(), (value, Instant::now() + ttl));
+    }
+
+    #[allow(dead_code)] // only reached through Cache::delete
+    pub fn delete(&self, key: &str) {
+        self.entries().pop(key);
+    }
+
+    #[cfg(test)]
+    fn len(&self) -> usize {
+        self.entries().len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lru_eviction_and_ttl() {
+        let cache = MemoryCache::new(2);
+        let hour = Duration::from_secs(3600);
+        cache.set("a", "1".to_string(), hour);
+        cache.set("b", "2".to_string(), hour);
+        // Touch "a" so "b" is the least recently used
+        assert_eq!(cache.get("a").as_deref(), Some("1"));
+        cache.set("c", "3".to_string(), hour);
+        assert_eq!(cache.get("b"), None);
+        assert_eq!(cache.len(), 2);
+
+        cache.set("a", "expired".to_string(), Duration::ZERO);
+        assert_eq!(cache.get("a"), None);
+        assert_eq!(cache.len(), 1);
+
+        cache.delete("c");
+        assert_eq!(cache.get("c"), None);
+    }
+}
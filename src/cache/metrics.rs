@@ -0,0 +1,415 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+//! System metrics snapshots
+//!
+//! The daemon samples a [`SystemMetrics`] snapshot every health tick and
+//! publishes it through the cache, so CLI tools can read a recent picture of
+//! the system instead of each doing a full scan. Without Dragonfly/Redis the
+//! snapshot is shared through a file in the cache directory instead.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use sysinfo::{Disks, Networks, ProcessesToUpdate, System};
+
+/// Processes kept in a snapshot, busiest first
+const TOP_PROCESSES: usize = 10;
+
+/// How old a published snapshot may be for CLI tools to use it: two of the
+/// daemon's default health ticks
+pub const FRESH: Duration = Duration::from_secs(120);
+
+/// Filesystems that hold no disk space of their own, or are always full
+const PSEUDO_FILESYSTEMS: &[&str] = &[
+    "autofs", "cgroup", "cgroup2", "configfs", "debugfs", "devpts", "devtmpfs", "efivarfs",
+    "fusectl", "hugetlbfs", "mqueue", "nsfs", "overlay", "proc", "pstore", "ramfs", "securityfs",
+    "squashfs", "sysfs", "tmpfs", "tracefs",
+];
+
+/// Cached system metrics
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SystemMetrics {
+    pub cpu_usage: f32,
+    #[serde(default)]
+    pub cpu_count: usize,
+    pub memory_used: u64,
+    pub memory_total: u64,
+    #[serde(default)]
+    pub swap_used: u64,
+    #[serde(default)]
+    pub swap_total: u64,
+    /// Totals over the real filesystems, each device counted once
+    pub disk_used: u64,
+    pub disk_total: u64,
+    #[serde(default)]
+    pub disks: Vec<DiskMetrics>,
+    #[serde(default)]
+    pub interfaces: Vec<InterfaceMetrics>,
+    #[serde(default)]
+    pub pressure: PressureMetrics,
+    #[serde(default)]
+    pub top_processes: Vec<ProcessMetrics>,
+    pub load_avg: [f64; 3],
+    /// Unix time of the sample
+    pub timestamp: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DiskMetrics {
+    pub mount_point: String,
+    pub device: String,
+    pub file_system: String,
+    pub total: u64,
+    pub available: u64,
+    pub removable: bool,
+}
+
+impl DiskMetrics {
+    pub fn used(&self) -> u64 {
+        self.total.saturating_sub(self.available)
+    }
+
+    pub fn used_pct(&self) -> f64 {
+        percent(self.used(), self.total)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InterfaceMetrics {
+    pub name: String,
+    pub rx_bytes: u64,
+    pub tx_bytes: u64,
+    pub rx_errors: u64,
+    pub tx_errors: u64,
+    /// Bytes per second since the previous sample (0 for a first sample)
+    pub rx_rate: f64,
+    pub tx_rate: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProcessMetrics {
+    pub pid: u32,
+    pub name: String,
+    pub cpu_usage: f32,
+    pub memory: u64,
+}
+
+/// Pressure stall information from `/proc/pressure` (Linux 4.20+)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PressureMetrics {
+    pub cpu: Option<Pressure>,
+    pub memory: Option<Pressure>,
+    pub io: Option<Pressure>,
+}
+
+/// Share of time some (or all) tasks were stalled on a resource
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct Pressure {
+    pub some: PressureAverages,
+    pub full: Option<PressureAverages>,
+}
+
+/// Stall percentages averaged over 10 s, 60 s and 300 s
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct PressureAverages {
+    pub avg10: f64,
+    pub avg60: f64,
+    pub avg300: f64,
+}
+
+impl SystemMetrics {
+    /// One-off sample, for when no recent snapshot is cached
+    pub async fn collect() -> Self {
+        let mut collector = MetricsCollector::new();
+        tokio::time::sleep(sysinfo::MINIMUM_CPU_UPDATE_INTERVAL).await;
+        collector.sample()
+    }
+
+    /// Where the daemon shares its snapshot when there is no Dragonfly/Redis
+    pub fn snapshot_path() -> PathBuf {
+        crate::dirs::cache_dir().join("metrics.json")
+    }
+
+    /// Write the snapshot file; a reader never sees half a file
+    #[allow(dead_code)] // called by the daemon, which only the library builds
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let tmp = path.with_extension("json.tmp");
+        std::fs::write(&tmp, serde_json::to_vec(self)?)
+            .with_context(|| format!("Failed to write {}", tmp.display()))?;
+        std::fs::rename(&tmp, path)?;
+        Ok(())
+    }
+
+    /// Read a snapshot file; `None` if it is missing or unreadable
+    pub fn load(path: &Path) -> Option<Self> {
+        let content = std::fs::read(path).ok()?;
+        serde_json::from_slice(&content).ok()
+    }
+
+    /// Time since the sample was taken
+    pub fn age(&self) -> Duration {
+        let secs = chrono::Utc::now().timestamp() - self.timestamp;
+        Duration::from_secs(secs.max(0) as u64)
+    }
+
+    pub fn memory_pct(&self) -> f64 {
+        percent(self.memory_used, self.memory_total)
+    }
+
+    pub fn swap_pct(&self) -> f64 {
+        percent(self.swap_used, self.swap_total)
+    }
+}
+
+/// Keeps sysinfo state between samples so CPU usage and network rates are
+/// measured over the interval rather than from a cold start
+pub struct MetricsCollector {
+    sys: System,
+    disks: Disks,
+    networks: Networks,
+    last_sample: Instant,
+}
+
+impl Default for MetricsCollector {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MetricsCollector {
+    pub fn new() -> Self {
+        let mut sys = System::new();
+        sys.refresh_cpu_usage();
+        sys.refresh_processes(ProcessesToUpdate::All);
+        Self {
+            sys,
+            disks: Disks::new_with_refreshed_list(),
+            networks: Networks::new_with_refreshed_list(),
+            last_sample: Instant::now(),
+        }
+    }
+
+    pub fn sample(&mut self) -> SystemMetrics {
+        let elapsed = self.last_sample.elapsed().as_secs_f64();
+        self.last_sample = Instant::now();
+
+        self.sys.refresh_cpu_usage();
+        self.sys.refresh_memory();
+        self.sys.refresh_processes(ProcessesToUpdate::All);
+        self.disks.refresh_list();
+        self.networks.refresh_list();
+
+        let disks = real_disks(self.disks.list().iter().map(|d| DiskMetrics {
+                mount_point: d.mount_point().display().to_string(),
+                device: d.name().to_string_lossy().into_owned(),
+                file_system: d.file_system().to_string_lossy().into_owned(),
+                total: d.total_space(),
+                available: d.available_space(),
+                removable: d.is_removable(),
+            }));
+
+        let rate = |bytes: u64| {
+            if elapsed > 0.0 {
+                bytes as f64 / elapsed
+            } else {
+                0.0
+            }
+        };
+        let mut interfaces: Vec<InterfaceMetrics> = self
+            .networks
+            .iter()
+            .map(|(name, data)| InterfaceMetrics {
+                name: name.clone(),
+                rx_bytes: data.total_received(),
+                tx_bytes: data.total_transmitted(),
+                rx_errors: data.total_errors_on_received(),
+                tx_errors: data.total_errors_on_transmitted(),
+                rx_rate: rate(data.received()),
+                tx_rate: rate(data.transmitted()),
+            })
+            .collect();
+        interfaces.sort_by(|a, b| a.name.cmp(&b.name));
+
+        let mut top_processes: Vec<ProcessMetrics> = self
+            .sys
+            .processes()
+            .iter()
+            .map(|(pid, p)| ProcessMetrics {
+                pid: pid.as_u32(),
+                name: p.name().to_string_lossy().into_owned(),
+                cpu_usage: p.cpu_usage(),
+                memory: p.memory(),
+            })
+            .collect();
+        top_processes.sort_by(|a, b| b.cpu_usage.total_cmp(&a.cpu_usage));
+        top_processes.truncate(TOP_PROCESSES);
+
+        let load = System::load_average();
+        SystemMetrics {
+            cpu_usage: self.sys.global_cpu_usage(),
+            cpu_count: self.sys.cpus().len(),
+            memory_used: self.sys.used_memory(),
+            memory_total: self.sys.total_memory(),
+            swap_used: self.sys.used_swap(),
+            swap_total: self.sys.total_swap(),
+            disk_used: disks.iter().map(DiskMetrics::used).sum(),
+            disk_total: disks.iter().map(|d| d.total).sum(),
+            disks,
+            interfaces,
+            pressure: PressureMetrics::read(),
+            top_processes,
+            load_avg: [load.one, load.five, load.fifteen],
+            timestamp: chrono::Utc::now().timestamp(),
+        }
+    }
+}
+
+/// Drop pseudo filesystems, and keep one mount per device so bind mounts and
+/// subvolumes aren't counted twice; the shortest mount point stands for it
+fn real_disks(disks: impl Iterator<Item = DiskMetrics>) -> Vec<DiskMetrics> {
+    let mut real: Vec<DiskMetrics> = vec![];
+    for disk in disks {
+        if PSEUDO_FILESYSTEMS.contains(&disk.file_system.as_str()) {
+            continue;
+        }
+        match real.iter_mut().find(|d| d.device == disk.device) {
+            Some(seen) if disk.mount_point.len() < seen.mount_point.len() => *seen = disk,
+            Some(_) => {}
+            None => real.push(disk),
+        }
+    }
+    real
+}
+
+impl PressureMetrics {
+    /// Read `/proc/pressure`; resources the kernel doesn't report are `None`
+    pub fn read() -> Self {
+        let read = |resource: &str| {
+            std::fs::read_to_string(format!("/proc/pressure/{}", resource))
+                .ok()
+                .and_then(|content| parse_pressure(&content))
+        };
+        Self {
+            cpu: read("cpu"),
+            memory: read("memory"),
+            io: read("io"),
+        }
+    }
+}
+
+/// Parse a `/proc/pressure/*` file:
+/// `some avg10=0.12 avg60=0.05 avg300=0.01 total=12345`, then an optional
+/// `full ...` line
+fn parse_pressure(content: &str) -> Option<Pressure> {
+    let mut some = None;
+    let mut full = None;
+    for line in content.lines() {
+        let mut fields = line.split_whitespace();
+        let kind = fields.next()?;
+        let mut averages = PressureAverages::default();
+        for field in fields {
+            let Some((key, value)) = field.split_once('=') else {
+                continue;
+            };
+            match key {
+                "avg10" => averages.avg10 = value.parse().ok()?,
+                "avg60" => averages.avg60 = value.parse().ok()?,
+                "avg300" => averages.avg300 = value.parse().ok()?,
+                _ => {}
+            }
+        }
+        match kind {
+            "some" => some = Some(averages),
+            "full" => full = Some(averages),
+            _ => {}
+        }
+    }
+    Some(Pressure { some: some?, full })
+}
+
+fn percent(used: u64, total: u64) -> f64 {
+    if total > 0 {
+        used as f64 / total as f64 * 100.0
+    } else {
+        0.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_pressure() {
+        let io = "some avg10=1.50 avg60=0.75 avg300=0.20 total=123456\n\
+                  full avg10=0.50 avg60=0.25 avg300=0.00 total=65432\n";
+        let pressure = parse_pressure(io).unwrap();
+        assert_eq!(pressure.some.avg10, 1.5);
+        assert_eq!(pressure.some.avg300, 0.2);
+        assert_eq!(pressure.full.unwrap().avg60, 0.25);
+
+        // Older kernels report no `full` line for CPU
+        let cpu = parse_pressure("some avg10=0.00 avg60=0.00 avg300=0.00 total=0\n").unwrap();
+        assert!(cpu.full.is_none());
+
+        assert!(parse_pressure("").is_none());
+        assert!(parse_pressure("some avg10=x avg60=0 avg300=0 total=0").is_none());
+    }
+
+    fn disk(mount_point: &str, device: &str, file_system: &str) -> DiskMetrics {
+        DiskMetrics {
+            mount_point: mount_point.to_string(),
+            device: device.to_string(),
+            file_system: file_system.to_string(),
+            total: 100,
+            available: 40,
+            removable: false,
+        }
+    }
+
+    #[test]
+    fn test_real_disks() {
+        let disks = real_disks(
+            [
+                disk("/var/lib/docker", "/dev/sda2", "ext4"),
+                disk("/", "/dev/sda2", "ext4"),
+                disk("/tmp", "tmpfs", "tmpfs"),
+                disk("/var/lib/docker/overlay2/x/merged", "overlay", "overlay"),
+                disk("/snap/core/1", "/dev/loop0", "squashfs"),
+                disk("/home", "/dev/sdb1", "xfs"),
+            ]
+            .into_iter(),
+        );
+        let mounts: Vec<_> = disks.iter().map(|d| d.mount_point.as_str()).collect();
+        assert_eq!(mounts, ["/", "/home"]);
+    }
+
+    #[tokio::test]
+    async fn test_snapshot_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("metrics.json");
+        assert!(SystemMetrics::load(&path).is_none());
+
+        let metrics = SystemMetrics::collect().await;
+        metrics.save(&path).unwrap();
+        let loaded = SystemMetrics::load(&path).unwrap();
+        assert_eq!(loaded.timestamp, metrics.timestamp);
+        assert_eq!(loaded.disk_total, metrics.disk_total);
+    }
+
+    #[test]
+    fn test_snapshot_round_trips_and_ages() {
+        let mut metrics = MetricsCollector::new().sample();
+        assert!(metrics.memory_total > 0);
+        assert!(metrics.top_processes.len() <= TOP_PROCESSES);
+
+        metrics.timestamp -= 30;
+        let encoded = serde_json::to_string(&metrics).unwrap();
+        let decoded: SystemMetrics = serde_json::from_str(&encoded).unwrap();
+        assert_eq!(decoded.disks.len(), metrics.disks.len());
+        assert!(decoded.age() >= Duration::from_secs(30));
+    }
+}
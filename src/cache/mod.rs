@@ -1,25 +1,43 @@
 // SPDX-License-Identifier: AGPL-3.0-or-later
 //! Dragonfly (Redis-compatible) cache layer for fast lookups
 
-// Allow dead code - scaffolding for future cache integration
-#![allow(dead_code)]
+pub mod memory;
+pub mod metrics;
 
-use anyhow::Result;
+use anyhow::{Context, Result};
+use redis::aio::{ConnectionManager, ConnectionManagerConfig};
+use redis::{AsyncCommands, ConnectionAddr, ConnectionInfo, RedisConnectionInfo};
+use serde::{Deserialize, Serialize};
 use std::time::Duration;
 
-/// Cache client wrapping Dragonfly/Redis
+use memory::MemoryCache;
+pub use metrics::SystemMetrics;
+
+/// Give up on an unreachable server quickly; the memory cache takes over
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(2);
+const RESPONSE_TIMEOUT: Duration = Duration::from_millis(500);
+
+/// Cache client wrapping Dragonfly/Redis, with an in-process fallback
 pub struct Cache {
-    // TODO: Add redis client when Dragonfly is configured
-    // client: redis::aio::ConnectionManager,
+    client: Option<ConnectionManager>,
+    memory: MemoryCache,
     config: CacheConfig,
 }
 
-#[derive(Debug, Clone)]
+/// Cache settings, read from `cache.toml` in the config directory
+///
+/// Without the file psa only uses the in-process cache.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct CacheConfig {
     pub host: String,
     pub port: u16,
+    pub password: Option<String>,
     pub prefix: String,
-    pub default_ttl: Duration,
+    /// Default entry lifetime in seconds
+    pub default_ttl: u64,
+    /// Entries kept by the in-process cache
+    pub memory_entries: usize,
 }
 
 impl Default for CacheConfig {
@@ -27,56 +45,186 @@ fn default() -> Self {
         Self {
             host: "localhost".to_string(),
             port: 6379,
+            password: None,
             prefix: "psa:".to_string(),
-            default_ttl: Duration::from_secs(3600), // 1 hour
+            default_ttl: 3600, // 1 hour
+            memory_entries: 1024,
+        }
+    }
+}
+
+impl CacheConfig {
+    /// Load `cache.toml`; `None` if Dragonfly/Redis isn't configured
+    pub fn load() -> Result<Option<Self>> {
+        let path = crate::dirs::config_dir().join("cache.toml");
+        match std::fs::read_to_string(&path) {
+            Ok(content) => Self::parse(&content)
+                .map(Some)
+                .with_context(|| format!("Invalid cache config {}", path.display())),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Parse `cache.toml` content; missing keys take their defaults
+    pub fn parse(content: &str) -> Result<Self> {
+        Ok(toml::from_str(content)?)
+    }
+
+    /// Where and how to connect, built directly so the password needs no escaping
+    pub fn connection_info(&self) -> ConnectionInfo {
+        ConnectionInfo {
+            addr: ConnectionAddr::Tcp(self.host.clone(), self.port),
+            redis: RedisConnectionInfo {
+                password: self.password.clone(),
+                ..Default::default()
+            },
         }
     }
 }
 
+/// Where cached values live
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CacheMode {
+    Redis,
+    Memory,
+}
+
 impl Cache {
-    /// Create new cache connection
+    /// Connect to the configured Dragonfly/Redis, or fall back to memory
     pub async fn new() -> Result<Self> {
-        let config = CacheConfig::default();
+        let Some(config) = CacheConfig::load()? else {
+            tracing::info!("Cache initialized (memory mode - Dragonfly not configured)");
+            return Ok(Self::memory(CacheConfig::default()));
+        };
 
-        // TODO: Connect to Dragonfly/Redis
-        tracing::info!("Cache initialized (memory mode - Dragonfly not configured)");
+        match connect(&config).await {
+            Ok(client) => {
+                tracing::info!(
+                    "Cache initialized (Dragonfly at {}:{})",
+                    config.host,
+                    config.port
+                );
+                Ok(Self {
+                    client: Some(client),
+                    memory: MemoryCache::new(config.memory_entries),
+                    config,
+                })
+            }
+            Err(e) => {
+                tracing::warn!(
+                    "Cache initialized (memory mode - Dragonfly at {}:{} unreachable: {})",
+                    config.host,
+                    config.port,
+                    e
+                );
+                Ok(Self::memory(config))
+            }
+        }
+    }
 
-        Ok(Self { config })
+    /// In-process cache only
+    pub fn memory(config: CacheConfig) -> Self {
+        Self {
+            client: None,
+            memory: MemoryCache::new(config.memory_entries),
+            config,
+        }
+    }
+
+    pub fn mode(&self) -> CacheMode {
+        if self.client.is_some() {
+            CacheMode::Redis
+        } else {
+            CacheMode::Memory
+        }
     }
 
     /// Get cached value
     pub async fn get<T: serde::de::DeserializeOwned>(&self, key: &str) -> Result<Option<T>> {
         let full_key = format!("{}{}", self.config.prefix, key);
         tracing::trace!("Cache GET: {}", full_key);
-        // TODO: Redis GET
-        Ok(None)
+
+        let encoded = match &self.client {
+            Some(client) => match client.clone().get::<_, Option<String>>(&full_key).await {
+                Ok(value) => value,
+                Err(e) => {
+                    tracing::debug!("Cache GET failed, using memory: {}", e);
+                    self.memory.get(&full_key)
+                }
+            },
+            None => self.memory.get(&full_key),
+        };
+        // A value that no longer decodes (e.g. after an upgrade) is a miss
+        Ok(encoded.and_then(|v| serde_json::from_str(&v).ok()))
     }
 
     /// Set cached value with TTL
-    pub async fn set<T: serde::Serialize>(&self, key: &str, _value: &T, ttl: Option<Duration>) -> Result<()> {
+    pub async fn set<T: serde::Serialize>(&self, key: &str, value: &T, ttl: Option<Duration>) -> Result<()> {
         let full_key = format!("{}{}", self.config.prefix, key);
-        let ttl = ttl.unwrap_or(self.config.default_ttl);
+        let ttl = ttl.unwrap_or(Duration::from_secs(self.config.default_ttl));
         tracing::trace!("Cache SET: {} (TTL: {:?})", full_key, ttl);
-        // TODO: Redis SETEX
+
+        let encoded = serde_json::to_string(value)?;
+        if let Some(client) = &self.client {
+            // SETEX needs at least a second
+            let seconds = ttl.as_secs().max(1);
+            match client.clone().set_ex::<_, _, ()>(&full_key, &encoded, seconds).await {
+                Ok(()) => return Ok(()),
+                Err(e) => tracing::debug!("Cache SET failed, using memory: {}", e),
+            }
+        }
+        self.memory.set(&full_key, encoded, ttl);
         Ok(())
     }
 
     /// Delete cached value
+    #[allow(dead_code)] // only tests call it so far
     pub async fn delete(&self, key: &str) -> Result<()> {
         let full_key = format!("{}{}", self.config.prefix, key);
         tracing::trace!("Cache DEL: {}", full_key);
-        // TODO: Redis DEL
+
+        // Also clear any copy written while the server was unreachable
+        self.memory.delete(&full_key);
+        if let Some(client) = &self.client {
+            if let Err(e) = client.clone().del::<_, ()>(&full_key).await {
+                tracing::debug!("Cache DEL failed: {}", e);
+            }
+        }
         Ok(())
     }
 
-    /// Cache system metrics for quick access
-    pub async fn cache_metrics(&self, metrics: &SystemMetrics) -> Result<()> {
-        self.set("metrics:current", metrics, Some(Duration::from_secs(10))).await
+    /// Cache system metrics for quick access, until the next snapshot is due
+    ///
+    /// The memory cache is private to this process, so in memory mode the
+    /// snapshot is also written to a file for other processes.
+    #[allow(dead_code)] // called by the daemon, which only the library builds
+    pub async fn cache_metrics(&self, metrics: &SystemMetrics, ttl: Duration) -> Result<()> {
+        self.set("metrics:current", metrics, Some(ttl)).await?;
+        if self.mode() == CacheMode::Memory {
+            metrics.save(&SystemMetrics::snapshot_path())?;
+        }
+        Ok(())
     }
 
     /// Get cached system metrics
     pub async fn get_metrics(&self) -> Result<Option<SystemMetrics>> {
-        self.get("metrics:current").await
+        let cached = self.get("metrics:current").await?;
+        if cached.is_none() && self.mode() == CacheMode::Memory {
+            return Ok(SystemMetrics::load(&SystemMetrics::snapshot_path()));
+        }
+        Ok(cached)
+    }
+
+    /// The cached snapshot if it is at most `max_age` old, else a fresh sample
+    pub async fn current_metrics(&self, max_age: Duration) -> SystemMetrics {
+        match self.get_metrics().await {
+            Ok(Some(metrics)) if metrics.age() <= max_age => {
+                tracing::debug!("Using cached metrics ({}s old)", metrics.age().as_secs());
+                metrics
+            }
+            _ => SystemMetrics::collect().await,
+        }
     }
 
     /// Cache solution lookup for fast retrieval
@@ -90,14 +238,66 @@ pub async fn get_solution_lookup(&self, problem_hash: &str) -> Result<Option<Str
     }
 }
 
-/// Cached system metrics
-#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
-pub struct SystemMetrics {
-    pub cpu_usage: f32,
-    pub memory_used: u64,
-    pub memory_total: u64,
-    pub disk_used: u64,
-    pub disk_total: u64,
-    pub load_avg: [f64; 3],
-    pub timestamp: i64,
+async fn connect(config: &CacheConfig) -> Result<ConnectionManager> {
+    let client = redis::Client::open(config.connection_info())?;
+    let manager_config = ConnectionManagerConfig::new()
+        .set_number_of_retries(1)
+        .set_connection_timeout(CONNECT_TIMEOUT)
+        .set_response_timeout(RESPONSE_TIMEOUT);
+    let mut manager = tokio::time::timeout(
+        CONNECT_TIMEOUT * 2,
+        ConnectionManager::new_with_config(client, manager_config),
+    )
+    .await
+    .context("connection timed out")??;
+    redis::cmd("PING").query_async::<()>(&mut manager).await?;
+    Ok(manager)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_config_parsing() {
+        let content = "host = \"cache.lan\"\npassword = \"p@ss/:w#rd\"";
+        let config = CacheConfig::parse(content).unwrap();
+        assert_eq!(config.host, "cache.lan");
+        assert_eq!(config.port, 6379);
+        assert_eq!(config.prefix, "psa:");
+
+        let info = config.connection_info();
+        match &info.addr {
+            ConnectionAddr::Tcp(host, port) => {
+                assert_eq!((host.as_str(), *port), ("cache.lan", 6379))
+            }
+            other => panic!("unexpected address {:?}", other),
+        }
+        // Passed through verbatim, not parsed out of a URL
+        assert_eq!(info.redis.password.as_deref(), Some("p@ss/:w#rd"));
+        assert_eq!(info.redis.db, 0);
+
+        assert!(CacheConfig::parse("port = \"high\"").is_err());
+        assert!(CacheConfig::parse("").unwrap().password.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_memory_mode_round_trip() {
+        let cache = Cache::memory(CacheConfig::default());
+        assert_eq!(cache.mode(), CacheMode::Memory);
+
+        cache.cache_solution_lookup("abc", "sol-1").await.unwrap();
+        assert_eq!(
+            cache.get_solution_lookup("abc").await.unwrap().as_deref(),
+            Some("sol-1")
+        );
+        // Wrong type decodes as a miss rather than an error
+        assert!(cache.get::<u32>("lookup:abc").await.unwrap().is_none());
+
+        cache.delete("lookup:abc").await.unwrap();
+        assert!(cache.get_solution_lookup("abc").await.unwrap().is_none());
+
+        cache.set("short", &1u8, Some(Duration::ZERO)).await.unwrap();
+        assert!(cache.get::<u8>("short").await.unwrap().is_none());
+    }
 }
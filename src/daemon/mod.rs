@@ -8,8 +8,10 @@
 //! - Isolated (runs as unprivileged user, sandboxed)
 //! - On-demand (user can query via socket or CLI)
 
-use anyhow::Result;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
 use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, PoisonError};
 use tokio::sync::mpsc;
 
 /// Security configuration for the daemon
@@ -74,16 +76,32 @@ pub struct Daemon {
     resp_tx: mpsc::Sender<DaemonResponse>,
     /// Rules engine
     rules: crate::rules::RulesEngine,
+    /// Rule health, proposals and CVE tracking (persisted in the data dir),
+    /// shared with the blocking task that runs the checks
+    lifecycle: Arc<Mutex<crate::rules::lifecycle::LifecycleManager>>,
+    /// Where metrics snapshots are published for CLI tools
+    cache: crate::cache::Cache,
+    /// Samples system metrics each health tick
+    metrics: crate::cache::metrics::MetricsCollector,
+    /// Where each sample is kept for `psa history`
+    history: crate::history::History,
     /// Background tasks
     tasks: Vec<tokio::task::JoinHandle<()>>,
 }
 
-#[derive(Debug, Clone)]
+/// Daemon settings, read from `daemon.toml` in the config directory
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct DaemonConfig {
     /// How often to check system health (seconds)
     pub health_check_interval: u64,
     /// How often to apply rules (seconds)
     pub rule_check_interval: u64,
+    /// How often to reassess rule health and obsolescence (seconds)
+    pub lifecycle_check_interval: u64,
+    /// Warn when a filesystem's usage trend projects it full within this
+    /// many seconds
+    pub disk_fill_horizon: u64,
     /// Notification settings
     pub notify: NotifyConfig,
     /// Log file path
@@ -95,13 +113,29 @@ fn default() -> Self {
         Self {
             health_check_interval: 60,
             rule_check_interval: 300,
+            lifecycle_check_interval: 3600,
+            disk_fill_horizon: 24 * 3600,
             notify: NotifyConfig::default(),
             log_path: crate::dirs::data_dir().join("daemon.log"),
         }
     }
 }
 
-#[derive(Debug, Clone)]
+impl DaemonConfig {
+    /// Load `daemon.toml`, or the defaults without one
+    pub fn load() -> Result<Self> {
+        let path = crate::dirs::config_dir().join("daemon.toml");
+        match std::fs::read_to_string(&path) {
+            Ok(content) => toml::from_str(&content)
+                .with_context(|| format!("Invalid daemon config {}", path.display())),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e.into()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct NotifyConfig {
     /// Only notify on errors
     pub errors_only: bool,
@@ -211,16 +245,22 @@ impl Daemon {
     pub fn new(
         cmd_rx: mpsc::Receiver<DaemonCommand>,
         resp_tx: mpsc::Sender<DaemonResponse>,
+        cache: crate::cache::Cache,
     ) -> Result<Self> {
         let rules_dir = crate::dirs::data_dir().join("rules");
         let rules = crate::rules::RulesEngine::new(&rules_dir)?;
+        let lifecycle = crate::rules::lifecycle::LifecycleManager::open_default()?;
 
         Ok(Self {
-            config: DaemonConfig::default(),
+            config: DaemonConfig::load()?,
             security: SecurityConfig::default(),
             cmd_rx,
             resp_tx,
             rules,
+            lifecycle: Arc::new(Mutex::new(lifecycle)),
+            cache,
+            metrics: crate::cache::metrics::MetricsCollector::new(),
+            history: crate::history::History::open_default()?,
             tasks: vec![],
         })
     }
@@ -267,9 +307,12 @@ pub async fn run(&mut self) -> Result<()> {
         // Start background health check task
         let health_interval = tokio::time::Duration::from_secs(self.config.health_check_interval);
         let rule_interval = tokio::time::Duration::from_secs(self.config.rule_check_interval);
+        let lifecycle_interval =
+            tokio::time::Duration::from_secs(self.config.lifecycle_check_interval);
 
         let mut health_timer = tokio::time::interval(health_interval);
         let mut rule_timer = tokio::time::interval(rule_interval);
+        let mut lifecycle_timer = tokio::time::interval(lifecycle_interval);
 
         tracing::info!("Daemon started");
 
@@ -356,22 +399,75 @@ pub async fn run(&mut self) -> Result<()> {
                         issues_resolved += resolved;
                     }
                 }
+
+                // Periodic lifecycle checks (health, obsolescence)
+                _ = lifecycle_timer.tick() => {
+                    if !paused {
+                        self.run_lifecycle_checks().await;
+                    }
+                }
             }
         }
 
+        for task in self.tasks.drain(..) {
+            task.abort();
+        }
+        let mut lifecycle = self.lifecycle.lock().unwrap_or_else(PoisonError::into_inner);
+        if let Err(e) = lifecycle.save() {
+            tracing::error!("Failed to save lifecycle state: {}", e);
+        }
+
         Ok(())
     }
 
+    /// Reassess rule health and obsolescence, then persist the results
+    ///
+    /// The checks query the package manager, so they run on a blocking task.
+    async fn run_lifecycle_checks(&mut self) {
+        let lifecycle = Arc::clone(&self.lifecycle);
+        let rules = self.rules.list().to_vec();
+        let checks = tokio::task::spawn_blocking(move || {
+            let mut lifecycle = lifecycle.lock().unwrap_or_else(PoisonError::into_inner);
+            let changed = lifecycle.run_checks(&rules);
+            if let Err(e) = lifecycle.save() {
+                tracing::error!("Failed to save lifecycle state: {}", e);
+            }
+            changed
+        });
+
+        match checks.await {
+            Ok(changed) => {
+                for (rule_id, health) in &changed {
+                    tracing::info!("Rule {} is now {}", rule_id, health);
+                }
+            }
+            Err(e) => tracing::error!("Lifecycle checks failed: {}", e),
+        }
+    }
+
+    /// Sample system metrics and publish them for CLI tools until the next tick
+    async fn publish_metrics(&mut self) -> crate::cache::SystemMetrics {
+        let metrics = self.metrics.sample();
+        let ttl = std::time::Duration::from_secs(self.config.health_check_interval * 2);
+        if let Err(e) = self.cache.cache_metrics(&metrics, ttl).await {
+            tracing::warn!("Failed to publish metrics: {}", e);
+        }
+        metrics
+    }
+
     /// Run a health check
-    async fn run_health_check(&self) -> HealthReport {
+    async fn run_health_check(&mut self) -> HealthReport {
         let mut issues = vec![];
 
         // Check system resources
-        let mut sys = sysinfo::System::new_all();
-        sys.refresh_all();
+        let metrics = self.publish_metrics().await;
+        self.history.record(&metrics);
+        if let Err(e) = self.history.save() {
+            tracing::warn!("Failed to save metrics history: {}", e);
+        }
 
         // CPU check
-        let cpu_usage = sys.global_cpu_info().cpu_usage();
+        let cpu_usage = metrics.cpu_usage;
         if cpu_usage > 90.0 {
             issues.push(HealthIssue {
                 severity: HealthLevel::Warning,
@@ -382,9 +478,7 @@ async fn run_health_check(&self) -> HealthReport {
         }
 
         // Memory check
-        let mem_used = sys.used_memory();
-        let mem_total = sys.total_memory();
-        let mem_pct = (mem_used as f64 / mem_total as f64) * 100.0;
+        let mem_pct = metrics.memory_pct();
         if mem_pct > 90.0 {
             issues.push(HealthIssue {
                 severity: HealthLevel::Warning,
@@ -395,18 +489,57 @@ async fn run_health_check(&self) -> HealthReport {
         }
 
         // Disk check
-        for disk in sys.disks() {
-            let used_pct = 100.0 - (disk.available_space() as f64 / disk.total_space() as f64 * 100.0);
+        for disk in &metrics.disks {
+            let used_pct = disk.used_pct();
             if used_pct > 90.0 {
                 issues.push(HealthIssue {
                     severity: HealthLevel::Warning,
                     category: "disk".to_string(),
-                    message: format!("Disk {} at {:.1}% capacity", disk.mount_point().display(), used_pct),
+                    message: format!("Disk {} at {:.1}% capacity", disk.mount_point, used_pct),
                     suggestion: Some("Find large files with 'psa disk large'".to_string()),
                 });
             }
         }
 
+        // Fill forecast: usage trending towards full within the horizon
+        let horizon = std::time::Duration::from_secs(self.config.disk_fill_horizon);
+        for disk in &metrics.disks {
+            let Some(forecast) = self.history.forecast_fill(&disk.mount_point, metrics.timestamp)
+            else {
+                continue;
+            };
+            if forecast.full_in.is_some_and(|full_in| full_in < horizon) {
+                issues.push(HealthIssue {
+                    severity: HealthLevel::Warning,
+                    category: "disk".to_string(),
+                    message: format!("Disk {} {}", disk.mount_point, forecast),
+                    suggestion: Some(format!(
+                        "See what is growing with 'psa disk large' and 'psa history disk:{}'",
+                        disk.mount_point
+                    )),
+                });
+            }
+        }
+
+        // Pressure check: tasks fully stalled on memory or I/O
+        let pressure = [
+            ("memory", metrics.pressure.memory, "psa ps list -s mem"),
+            ("io", metrics.pressure.io, "psa disk io"),
+        ];
+        for (resource, pressure, hint) in pressure {
+            if let Some(full) = pressure.and_then(|p| p.full).filter(|f| f.avg60 > 10.0) {
+                issues.push(HealthIssue {
+                    severity: HealthLevel::Warning,
+                    category: resource.to_string(),
+                    message: format!(
+                        "Tasks stalled on {} {:.1}% of the last minute",
+                        resource, full.avg60
+                    ),
+                    suggestion: Some(format!("Check with '{}'", hint)),
+                });
+            }
+        }
+
         // Check for failed services
         if let Ok(output) = std::process::Command::new("systemctl")
             .args(["--user", "--failed", "--no-legend"])
@@ -446,20 +579,26 @@ async fn run_health_check(&self) -> HealthReport {
     /// Apply matching rules
     async fn apply_rules(&mut self) -> u32 {
         let context = crate::rules::ProblemContext::default();
-        let matching = self.rules.find_matching(&context);
+        let matching: Vec<String> = self
+            .rules
+            .find_matching_async(&context)
+            .await
+            .iter()
+            .map(|r| r.id.clone())
+            .collect();
 
         let mut resolved = 0u32;
-        for rule in matching {
-            match self.rules.execute(&rule.id).await {
+        for rule_id in matching {
+            match self.rules.execute(&rule_id).await {
                 Ok(result) if result.success => {
-                    tracing::debug!("Rule {} applied successfully", rule.id);
+                    tracing::debug!("Rule {} applied successfully", rule_id);
                     resolved += 1;
                 }
                 Ok(result) => {
-                    tracing::warn!("Rule {} failed: {:?}", rule.id, result.error);
+                    tracing::warn!("Rule {} failed: {:?}", rule_id, result.error);
                 }
                 Err(e) => {
-                    tracing::error!("Rule {} execution error: {}", rule.id, e);
+                    tracing::error!("Rule {} execution error: {}", rule_id, e);
                 }
             }
         }
@@ -470,10 +609,12 @@ async fn apply_rules(&mut self) -> u32 {
     /// Query for a problem
     async fn query(&self, problem: &str) -> QueryResult {
         // First, check rules
-        let mut context = crate::rules::ProblemContext::default();
-        context.problem_text = problem.to_string();
+        let context = crate::rules::ProblemContext {
+            problem_text: problem.to_string(),
+            ..Default::default()
+        };
 
-        let matching = self.rules.find_matching(&context);
+        let matching = self.rules.find_matching_async(&context).await;
         if let Some(rule) = matching.first() {
             return QueryResult {
                 answer: format!("Matched rule: {}\n\nActions: {:?}", rule.name, rule.then),
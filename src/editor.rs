@@ -0,0 +1,40 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+//! Interactive editing of structured values in the user's editor
+
+use anyhow::Result;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::io::Write;
+
+/// Open `value` as TOML in `$VISUAL`/`$EDITOR` (falling back to `vi`) and
+/// parse the result; `name` labels the temporary file
+pub fn edit_toml<T: Serialize + DeserializeOwned>(name: &str, value: &T) -> Result<T> {
+    // Created exclusively and owner-only, so nobody can plant or read it
+    let mut file = tempfile::Builder::new()
+        .prefix(&format!("psa-{}-", name))
+        .suffix(".toml")
+        .tempfile()?;
+    file.write_all(toml::to_string_pretty(value)?.as_bytes())?;
+    file.flush()?;
+    let path = file.path().to_path_buf();
+
+    let editor = std::env::var("VISUAL")
+        .or_else(|_| std::env::var("EDITOR"))
+        .unwrap_or_else(|_| "vi".to_string());
+    let mut parts = editor.split_whitespace();
+    let program = parts.next().unwrap_or("vi");
+    let status = std::process::Command::new(program)
+        .args(parts)
+        .arg(&path)
+        .status();
+
+    let result = match status {
+        Ok(s) if s.success() => std::fs::read_to_string(&path)
+            .map_err(anyhow::Error::from)
+            .and_then(|c| toml::from_str(&c).map_err(anyhow::Error::from)),
+        Ok(s) => Err(anyhow::anyhow!("editor exited with {}", s)),
+        Err(e) => Err(anyhow::anyhow!("failed to run editor '{}': {}", program, e)),
+    };
+    drop(file);
+    result
+}
@@ -27,7 +27,7 @@ pub async fn search(
         for solution in &local_results {
             println!("  • {} (confidence: {:.0}%)",
                 solution.problem,
-                (solution.success_count as f32 / (solution.success_count + solution.failure_count + 1) as f32) * 100.0
+                crate::storage::confidence(solution) * 100.0
             );
         }
     }
@@ -0,0 +1,790 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+//! Time-series history of system metrics
+//!
+//! The daemon records each health tick's [`SystemMetrics`] snapshot into
+//! `history.bin` in the data directory, so `psa history` can show when
+//! something started and not only how it is now. Every series is kept in
+//! fixed-resolution ring buffers: one value per minute for a day, and one per
+//! 15 minutes (the mean of the minute values) for 30 days. The file therefore
+//! stops growing once the rings have filled, and series with no values left in
+//! any ring are dropped.
+//!
+//! Series are named `cpu`, `memory`, `swap`, `load`, `disk:<mount>` (percent
+//! used), `net:<interface>:rx` / `:tx` (bytes per second) and
+//! `proc:<name>:cpu` / `:mem` for the busiest processes.
+
+// Allow dead code - recording is only done by the daemon, not the CLI binary
+#![allow(dead_code)]
+
+use anyhow::{bail, Context, Result};
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use crate::cache::SystemMetrics;
+
+/// File name of the history inside the data directory
+const HISTORY_FILE: &str = "history.bin";
+
+/// Start of the file, including the format version
+const MAGIC: &[u8; 8] = b"PSAHIST1";
+
+/// Seconds per slot and slots of each ring, finest first
+const RINGS: [(i64, usize); 2] = [(60, 24 * 60), (15 * 60, 30 * 24 * 4)];
+
+/// Busiest processes recorded per sample
+const RECORDED_PROCESSES: usize = 5;
+
+/// Columns of a sparkline
+const SPARKLINE_WIDTH: usize = 60;
+
+/// Stretch of recent usage a disk fill forecast is fitted to
+const FORECAST_WINDOW: i64 = 6 * 3600;
+
+/// Least stretch of usage, and most age of the newest sample, for a forecast
+const FORECAST_MIN_SPAN: i64 = 30 * 60;
+const FORECAST_MAX_AGE: i64 = 15 * 60;
+
+/// Projections further out than this are reported as stable
+const FORECAST_LIMIT: Duration = Duration::from_secs(365 * 86_400);
+
+const BARS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+/// One resolution of every series
+#[derive(Debug, Clone, PartialEq)]
+struct Ring {
+    /// Seconds per slot
+    step: i64,
+    slots: usize,
+    /// Bucket (time / step) of the newest slot written
+    newest: Option<i64>,
+    /// Values by series; `NaN` marks a slot without a value
+    series: BTreeMap<String, Vec<f32>>,
+}
+
+impl Ring {
+    fn new(step: i64, slots: usize) -> Self {
+        Self {
+            step,
+            slots,
+            newest: None,
+            series: BTreeMap::new(),
+        }
+    }
+
+    fn slot(&self, bucket: i64) -> usize {
+        bucket.rem_euclid(self.slots as i64) as usize
+    }
+
+    /// Whether `bucket` is still held, i.e. not yet overwritten
+    fn holds(&self, bucket: i64) -> bool {
+        self.newest
+            .is_some_and(|newest| bucket <= newest && bucket > newest - self.slots as i64)
+    }
+
+    /// Move forward to `bucket`, clearing the slots passed over; false if
+    /// `bucket` has already been overwritten
+    fn advance(&mut self, bucket: i64) -> bool {
+        if let Some(newest) = self.newest {
+            if bucket <= newest {
+                return self.holds(bucket);
+            }
+            let cleared = (bucket - newest).min(self.slots as i64);
+            for b in bucket - cleared + 1..=bucket {
+                let slot = self.slot(b);
+                for values in self.series.values_mut() {
+                    values[slot] = f32::NAN;
+                }
+            }
+        }
+        self.newest = Some(bucket);
+        true
+    }
+
+    fn set(&mut self, name: &str, bucket: i64, value: f64) {
+        let (slots, slot) = (self.slots, self.slot(bucket));
+        self.series
+            .entry(name.to_string())
+            .or_insert_with(|| vec![f32::NAN; slots])[slot] = value as f32;
+    }
+
+    fn get(&self, name: &str, bucket: i64) -> Option<f64> {
+        if !self.holds(bucket) {
+            return None;
+        }
+        let value = self.series.get(name)?[self.slot(bucket)];
+        (!value.is_nan()).then_some(value as f64)
+    }
+
+    /// Mean of the values held for buckets `from..=to`
+    fn mean(&self, name: &str, from: i64, to: i64) -> Option<f64> {
+        let values: Vec<f64> = (from..=to).filter_map(|b| self.get(name, b)).collect();
+        (!values.is_empty()).then(|| values.iter().sum::<f64>() / values.len() as f64)
+    }
+}
+
+/// A stretch of one series at a fixed resolution
+#[derive(Debug, Clone)]
+pub struct Series {
+    pub name: String,
+    /// Seconds per value
+    pub step: i64,
+    /// Unix time of the first value
+    pub start: i64,
+    /// `None` where nothing was recorded
+    pub values: Vec<Option<f64>>,
+}
+
+impl Series {
+    /// Unix time and value of every recorded point
+    pub fn points(&self) -> impl Iterator<Item = (i64, f64)> + '_ {
+        self.values
+            .iter()
+            .enumerate()
+            .filter_map(|(i, v)| v.map(|v| (self.start + i as i64 * self.step, v)))
+    }
+
+    pub fn latest(&self) -> Option<f64> {
+        self.values.iter().rev().find_map(|v| *v)
+    }
+}
+
+/// Ring-buffered metrics history on disk
+pub struct History {
+    path: PathBuf,
+    rings: Vec<Ring>,
+}
+
+impl History {
+    pub fn open_default() -> Result<Self> {
+        Self::open(crate::dirs::data_dir().join(HISTORY_FILE))
+    }
+
+    /// Open a history file; a missing, unreadable or differently laid out one
+    /// starts an empty history, as the data is only ever a convenience
+    pub fn open(path: impl Into<PathBuf>) -> Result<Self> {
+        let path = path.into();
+        let empty = || {
+            RINGS
+                .iter()
+                .map(|&(step, slots)| Ring::new(step, slots))
+                .collect()
+        };
+
+        let rings = match std::fs::read(&path) {
+            Ok(bytes) => match decode(&bytes) {
+                Ok(rings) if same_layout(&rings) => rings,
+                Ok(_) => {
+                    tracing::info!("Metrics history layout changed, starting afresh");
+                    empty()
+                }
+                Err(e) => {
+                    tracing::warn!("Discarding unreadable {}: {}", path.display(), e);
+                    empty()
+                }
+            },
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => empty(),
+            Err(e) => return Err(e).with_context(|| format!("Failed to read {}", path.display())),
+        };
+        Ok(Self { path, rings })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Add a snapshot
+    pub fn record(&mut self, metrics: &SystemMetrics) {
+        self.record_values(metrics.timestamp, &sample_values(metrics));
+    }
+
+    fn record_values(&mut self, at: i64, values: &[(String, f64)]) {
+        let fine = &mut self.rings[0];
+        let bucket = at.div_euclid(fine.step);
+        if !fine.advance(bucket) {
+            return;
+        }
+        for (name, value) in values {
+            fine.set(name, bucket, *value);
+        }
+
+        // Each coarser slot is the mean of the finer ring over its span
+        for i in 1..self.rings.len() {
+            let (finer, coarser) = self.rings.split_at_mut(i);
+            let (finer, ring) = (&finer[i - 1], &mut coarser[0]);
+            let bucket = at.div_euclid(ring.step);
+            if !ring.advance(bucket) {
+                break;
+            }
+            let from = (bucket * ring.step).div_euclid(finer.step);
+            let to = at.div_euclid(finer.step);
+            for name in finer.series.keys() {
+                if let Some(mean) = finer.mean(name, from, to) {
+                    ring.set(name, bucket, mean);
+                }
+            }
+        }
+
+        self.prune();
+    }
+
+    /// Drop series that no longer have a value in any ring
+    fn prune(&mut self) {
+        let empty: Vec<String> = self
+            .names()
+            .into_iter()
+            .filter(|name| {
+                self.rings.iter().all(|r| {
+                    r.series
+                        .get(name)
+                        .is_none_or(|values| values.iter().all(|v| v.is_nan()))
+                })
+            })
+            .collect();
+        for ring in &mut self.rings {
+            for name in &empty {
+                ring.series.remove(name);
+            }
+        }
+    }
+
+    pub fn save(&self) -> Result<()> {
+        if let Some(dir) = self.path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        // Write then rename, so a reader never sees half a file
+        let tmp = self.path.with_extension("bin.tmp");
+        std::fs::write(&tmp, encode(&self.rings))
+            .with_context(|| format!("Failed to write {}", tmp.display()))?;
+        std::fs::rename(&tmp, &self.path)?;
+        Ok(())
+    }
+
+    /// Names of all recorded series
+    pub fn names(&self) -> BTreeSet<String> {
+        self.rings
+            .iter()
+            .flat_map(|r| r.series.keys().cloned())
+            .collect()
+    }
+
+    /// A series from `since` until `now` (Unix times), at the finest
+    /// resolution that reaches back that far
+    pub fn query(&self, name: &str, since: i64, now: i64) -> Option<Series> {
+        let ring = self
+            .rings
+            .iter()
+            .find(|r| now - since <= r.step * r.slots as i64)
+            .or(self.rings.last())?;
+        if !ring.series.contains_key(name) {
+            return None;
+        }
+        let to = now.div_euclid(ring.step);
+        let from = since.div_euclid(ring.step).max(to - ring.slots as i64 + 1);
+        Some(Series {
+            name: name.to_string(),
+            step: ring.step,
+            start: from * ring.step,
+            values: (from..=to).map(|b| ring.get(name, b)).collect(),
+        })
+    }
+
+    /// Fit a trend to a filesystem's recent usage and project when it fills
+    pub fn forecast_fill(&self, mount_point: &str, now: i64) -> Option<FillForecast> {
+        let series = self.query(&format!("disk:{}", mount_point), now - FORECAST_WINDOW, now)?;
+        fill_forecast(&series.points().collect::<Vec<_>>(), now)
+    }
+}
+
+/// Where a filesystem's usage is heading
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FillForecast {
+    /// Growth in percentage points per hour (negative when shrinking)
+    pub rate: f64,
+    /// Time from now until full; `None` if it isn't filling up
+    pub full_in: Option<Duration>,
+}
+
+impl std::fmt::Display for FillForecast {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.full_in {
+            Some(full_in) => write!(
+                f,
+                "full in {} ({:+.2}%/h)",
+                format_duration(full_in),
+                self.rate
+            ),
+            None => write!(f, "stable ({:+.2}%/h)", self.rate),
+        }
+    }
+}
+
+/// Least-squares line through (time, percent used) points, extended to 100%
+fn fill_forecast(points: &[(i64, f64)], now: i64) -> Option<FillForecast> {
+    let (first, last) = (points.first()?, points.last()?);
+    if last.0 - first.0 < FORECAST_MIN_SPAN || now - last.0 > FORECAST_MAX_AGE {
+        return None;
+    }
+
+    let n = points.len() as f64;
+    let mean_t = points.iter().map(|p| p.0 as f64).sum::<f64>() / n;
+    let mean_v = points.iter().map(|p| p.1).sum::<f64>() / n;
+    let (mut covariance, mut variance) = (0.0, 0.0);
+    for &(t, v) in points {
+        covariance += (t as f64 - mean_t) * (v - mean_v);
+        variance += (t as f64 - mean_t).powi(2);
+    }
+    let slope = covariance / variance;
+    let current = mean_v + slope * (now as f64 - mean_t);
+
+    let full_in = (slope > 0.0)
+        .then(|| Duration::from_secs(((100.0 - current) / slope).max(0.0).round() as u64))
+        .filter(|d| *d < FORECAST_LIMIT);
+    Some(FillForecast {
+        rate: slope * 3600.0,
+        full_in,
+    })
+}
+
+/// A duration to the nearest minute, e.g. `2d 4h` or `3h 20m`
+pub fn format_duration(duration: Duration) -> String {
+    let minutes = duration.as_secs() / 60;
+    let (days, hours, minutes) = (minutes / 1440, minutes / 60 % 24, minutes % 60);
+    if days > 0 {
+        format!("{}d {}h", days, hours)
+    } else if hours > 0 {
+        format!("{}h {}m", hours, minutes)
+    } else {
+        format!("{}m", minutes)
+    }
+}
+
+fn same_layout(rings: &[Ring]) -> bool {
+    rings.len() == RINGS.len()
+        && rings
+            .iter()
+            .zip(RINGS)
+            .all(|(r, (step, slots))| r.step == step && r.slots == slots)
+}
+
+/// The series values of a snapshot
+fn sample_values(metrics: &SystemMetrics) -> Vec<(String, f64)> {
+    let mut values = vec![
+        ("cpu".to_string(), metrics.cpu_usage as f64),
+        ("memory".to_string(), metrics.memory_pct()),
+        ("load".to_string(), metrics.load_avg[0]),
+    ];
+    if metrics.swap_total > 0 {
+        values.push(("swap".to_string(), metrics.swap_pct()));
+    }
+    for disk in metrics.disks.iter().filter(|d| d.total > 0) {
+        values.push((format!("disk:{}", disk.mount_point), disk.used_pct()));
+    }
+    for net in metrics.interfaces.iter().filter(|i| i.name != "lo") {
+        values.push((format!("net:{}:rx", net.name), net.rx_rate));
+        values.push((format!("net:{}:tx", net.name), net.tx_rate));
+    }
+
+    // Processes by name, so a pool of workers counts as one consumer
+    let mut processes: BTreeMap<&str, (f64, f64)> = BTreeMap::new();
+    for process in &metrics.top_processes {
+        let entry = processes.entry(&process.name).or_default();
+        entry.0 += process.cpu_usage as f64;
+        entry.1 += process.memory as f64;
+    }
+    let mut processes: Vec<_> = processes.into_iter().collect();
+    processes.sort_by(|a, b| b.1 .0.total_cmp(&a.1 .0));
+    for (name, (cpu, memory)) in processes.into_iter().take(RECORDED_PROCESSES) {
+        values.push((format!("proc:{}:cpu", name), cpu));
+        values.push((format!("proc:{}:mem", name), memory));
+    }
+    values
+}
+
+/// Binary layout, little-endian: the magic, a ring count, then per ring its
+/// step (i64), slots (u32), newest bucket (i64, `i64::MIN` for none) and
+/// series count (u32); per series its name (u16 length + UTF-8) and runs of
+/// recorded slots (u32 count, then start slot and length as u32 followed by
+/// that many f32 values). Empty slots take no space.
+fn encode(rings: &[Ring]) -> Vec<u8> {
+    let mut out = MAGIC.to_vec();
+    out.push(rings.len() as u8);
+    for ring in rings {
+        out.extend_from_slice(&ring.step.to_le_bytes());
+        out.extend_from_slice(&(ring.slots as u32).to_le_bytes());
+        out.extend_from_slice(&ring.newest.unwrap_or(i64::MIN).to_le_bytes());
+        out.extend_from_slice(&(ring.series.len() as u32).to_le_bytes());
+        for (name, values) in &ring.series {
+            out.extend_from_slice(&(name.len() as u16).to_le_bytes());
+            out.extend_from_slice(name.as_bytes());
+
+            let mut runs = vec![];
+            let mut i = 0;
+            while i < values.len() {
+                if values[i].is_nan() {
+                    i += 1;
+                    continue;
+                }
+                let start = i;
+                while i < values.len() && !values[i].is_nan() {
+                    i += 1;
+                }
+                runs.push(start..i);
+            }
+            out.extend_from_slice(&(runs.len() as u32).to_le_bytes());
+            for run in runs {
+                out.extend_from_slice(&(run.start as u32).to_le_bytes());
+                out.extend_from_slice(&(run.len() as u32).to_le_bytes());
+                for value in &values[run] {
+                    out.extend_from_slice(&value.to_le_bytes());
+                }
+            }
+        }
+    }
+    out
+}
+
+fn decode(bytes: &[u8]) -> Result<Vec<Ring>> {
+    let mut input = Input { bytes };
+    if input.take(MAGIC.len())? != MAGIC {
+        bail!("not a metrics history");
+    }
+    let ring_count = input.take(1)?[0];
+    let mut rings = vec![];
+    for _ in 0..ring_count {
+        let step = i64::from_le_bytes(input.array()?);
+        let slots = u32::from_le_bytes(input.array()?) as usize;
+        let newest = Some(i64::from_le_bytes(input.array()?)).filter(|&n| n != i64::MIN);
+        if step <= 0 || slots == 0 {
+            bail!("invalid ring layout");
+        }
+        let mut ring = Ring {
+            step,
+            slots,
+            newest,
+            series: BTreeMap::new(),
+        };
+        for _ in 0..u32::from_le_bytes(input.array()?) {
+            let len = u16::from_le_bytes(input.array()?) as usize;
+            let name = String::from_utf8(input.take(len)?.to_vec())?;
+            let mut values = vec![f32::NAN; slots];
+            for _ in 0..u32::from_le_bytes(input.array()?) {
+                let start = u32::from_le_bytes(input.array()?) as usize;
+                let len = u32::from_le_bytes(input.array()?) as usize;
+                let Some(run) = values.get_mut(start..start.saturating_add(len)) else {
+                    bail!("run outside the ring");
+                };
+                for value in run {
+                    *value = f32::from_le_bytes(input.array()?);
+                }
+            }
+            ring.series.insert(name, values);
+        }
+        rings.push(ring);
+    }
+    Ok(rings)
+}
+
+/// Bytes still to decode
+struct Input<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Input<'a> {
+    fn take(&mut self, n: usize) -> Result<&'a [u8]> {
+        if self.bytes.len() < n {
+            bail!("truncated");
+        }
+        let (head, rest) = self.bytes.split_at(n);
+        self.bytes = rest;
+        Ok(head)
+    }
+
+    fn array<const N: usize>(&mut self) -> Result<[u8; N]> {
+        Ok(self.take(N)?.try_into()?)
+    }
+}
+
+/// Show a metric's history as a sparkline, or list the recorded metrics
+///
+/// A metric without an exact match shows every series under it, so `disk`
+/// covers each `disk:<mount>`.
+pub fn show(metric: Option<&str>, since: &str) -> Result<()> {
+    let history = History::open_default()?;
+    let since = crate::audit::parse_since(since)?.timestamp();
+    let now = chrono::Utc::now().timestamp();
+    let names = history.names();
+
+    let Some(metric) = metric else {
+        if names.is_empty() {
+            println!("No metrics history yet; the daemon records one sample per health check.");
+            return Ok(());
+        }
+        let width = names.iter().map(String::len).max().unwrap_or(0).max(6);
+        println!("{:<w$}  {:>12}", "METRIC", "LATEST", w = width);
+        for name in &names {
+            let latest = history
+                .query(name, since, now)
+                .and_then(|s| s.latest())
+                .map(|v| format_value(name, v))
+                .unwrap_or_else(|| "-".to_string());
+            println!("{:<w$}  {:>12}", name, latest, w = width);
+        }
+        return Ok(());
+    };
+
+    let prefix = format!("{}:", metric);
+    let matching: Vec<&String> = match names.get(metric) {
+        Some(name) => vec![name],
+        None => names.iter().filter(|n| n.starts_with(&prefix)).collect(),
+    };
+    if matching.is_empty() {
+        bail!(
+            "No history for '{}' (run 'psa history' to list metrics)",
+            metric
+        );
+    }
+
+    for name in matching {
+        if let Some(series) = history.query(name, since, now) {
+            print_series(&series);
+        }
+    }
+    Ok(())
+}
+
+fn print_series(series: &Series) {
+    let values: Vec<f64> = series.values.iter().flatten().copied().collect();
+    println!("\n{}  ({} min steps)", series.name, series.step / 60);
+    if values.is_empty() {
+        println!("  no samples in this period");
+        return;
+    }
+
+    let line = sparkline(&series.values, SPARKLINE_WIDTH);
+    let end = series.start + (series.values.len() as i64 - 1) * series.step;
+    let span = end - series.start;
+    let label = |at: i64| {
+        let at = chrono::DateTime::from_timestamp(at, 0)
+            .unwrap_or_default()
+            .with_timezone(&chrono::Local);
+        if span > 86_400 {
+            at.format("%m-%d %H:%M").to_string()
+        } else {
+            at.format("%H:%M").to_string()
+        }
+    };
+    println!("  {}", line);
+    let (start, end) = (label(series.start), label(end));
+    let width = line.chars().count().max(start.len() + end.len() + 1);
+    println!("  {}{:>w$}", start, end, w = width - start.len());
+
+    let (min, max) = values
+        .iter()
+        .fold((f64::MAX, f64::MIN), |(lo, hi), &v| (lo.min(v), hi.max(v)));
+    let (peak_at, _) = series.points().fold((series.start, f64::MIN), |best, p| {
+        if p.1 > best.1 {
+            p
+        } else {
+            best
+        }
+    });
+    let avg = values.iter().sum::<f64>() / values.len() as f64;
+    println!(
+        "  min {}  avg {}  max {} at {}  now {}",
+        format_value(&series.name, min),
+        format_value(&series.name, avg),
+        format_value(&series.name, max),
+        label(peak_at),
+        series
+            .latest()
+            .map(|v| format_value(&series.name, v))
+            .unwrap_or_default()
+    );
+}
+
+/// Render values as at most `width` bars scaled between their minimum and
+/// maximum; gaps stay blank
+fn sparkline(values: &[Option<f64>], width: usize) -> String {
+    let columns = values.len().min(width);
+    let means: Vec<Option<f64>> = (0..columns)
+        .map(|c| {
+            let chunk = &values[c * values.len() / columns..(c + 1) * values.len() / columns];
+            let present: Vec<f64> = chunk.iter().flatten().copied().collect();
+            (!present.is_empty()).then(|| present.iter().sum::<f64>() / present.len() as f64)
+        })
+        .collect();
+
+    let (min, max) = means
+        .iter()
+        .flatten()
+        .fold((f64::MAX, f64::MIN), |(lo, hi), &v| (lo.min(v), hi.max(v)));
+    means
+        .iter()
+        .map(|v| match v {
+            None => ' ',
+            Some(_) if max == min => BARS[0],
+            Some(v) => BARS[(((v - min) / (max - min)) * (BARS.len() - 1) as f64).round() as usize],
+        })
+        .collect()
+}
+
+/// A value in the series' unit
+fn format_value(name: &str, value: f64) -> String {
+    if name == "load" {
+        format!("{:.2}", value)
+    } else if name.starts_with("net:") {
+        format!("{}/s", format_bytes(value))
+    } else if name.starts_with("proc:") && name.ends_with(":mem") {
+        format_bytes(value)
+    } else {
+        format!("{:.1}%", value)
+    }
+}
+
+fn format_bytes(bytes: f64) -> String {
+    const UNITS: [&str; 5] = ["B", "KB", "MB", "GB", "TB"];
+    let mut value = bytes;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", value, UNITS[unit])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A history in its own directory, removed when the guard drops
+    fn history() -> (tempfile::TempDir, History) {
+        let dir = tempfile::tempdir().unwrap();
+        let history = History::open(dir.path().join("history.bin")).unwrap();
+        (dir, history)
+    }
+
+    fn sample(name: &str, value: f64) -> Vec<(String, f64)> {
+        vec![(name.to_string(), value)]
+    }
+
+    #[test]
+    fn test_rings_downsample_and_wrap() {
+        let (_dir, mut history) = history();
+        // 30 minutes of one sample per minute, starting on a 15 minute boundary
+        let t0 = 2_000_000 * 900;
+        for minute in 0..30 {
+            history.record_values(t0 + minute * 60, &sample("cpu", minute as f64));
+        }
+
+        let now = t0 + 29 * 60;
+        let fine = history.query("cpu", now - 3600, now).unwrap();
+        assert_eq!(fine.step, 60);
+        assert_eq!(fine.latest(), Some(29.0));
+        assert_eq!(fine.points().count(), 30);
+        assert_eq!(fine.points().next(), Some((t0, 0.0)));
+
+        // Beyond a day, the 15 minute means 0..=14 and 15..=29
+        let coarse = history.query("cpu", now - 7 * 86_400, now).unwrap();
+        assert_eq!(coarse.step, 900);
+        let points: Vec<_> = coarse.points().collect();
+        assert_eq!(points, vec![(t0, 7.0), (t0 + 900, 22.0)]);
+
+        // A day and a minute later the minute ring has wrapped past all of it,
+        // while the 15 minute ring still has it
+        let later = now + 86_400 + 60;
+        history.record_values(later, &sample("memory", 50.0));
+        assert_eq!(
+            history.query("cpu", later - 3600, later).unwrap().latest(),
+            None
+        );
+        assert_eq!(history.query("cpu", t0, later).unwrap().points().count(), 2);
+
+        // Out of date samples are ignored
+        history.record_values(t0, &sample("cpu", 99.0));
+        assert_eq!(history.query("cpu", t0, later).unwrap().points().count(), 2);
+    }
+
+    #[test]
+    fn test_series_without_values_are_pruned() {
+        let (_dir, mut history) = history();
+        history.record_values(0, &sample("proc:make:cpu", 90.0));
+        history.record_values(60, &sample("cpu", 1.0));
+        assert!(history.names().contains("proc:make:cpu"));
+
+        // Once the slot has been overwritten everywhere, the series goes
+        history.record_values(31 * 86_400, &sample("cpu", 1.0));
+        assert_eq!(
+            history.names().into_iter().collect::<Vec<_>>(),
+            vec!["cpu".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_save_and_reopen() {
+        let (_dir, mut history) = history();
+        for i in 0..100 {
+            history.record_values(i * 60, &sample("cpu", i as f64));
+        }
+        history.record_values(200 * 60, &sample("disk:/", 80.5));
+        history.save().unwrap();
+
+        let size = std::fs::metadata(history.path()).unwrap().len();
+        // Sparse: nowhere near the 4320 slots per series a dense file would take
+        assert!(size < 2000, "history file is {} bytes", size);
+
+        let reopened = History::open(history.path()).unwrap();
+        assert_eq!(
+            encode(&reopened.rings),
+            encode(&history.rings),
+            "round trip changed the history"
+        );
+        assert_eq!(reopened.rings[0].newest, Some(200));
+
+        // Damaged files start afresh rather than failing
+        assert!(decode(&encode(&history.rings)[..40]).is_err());
+        assert!(decode(b"not a history").is_err());
+    }
+
+    #[test]
+    fn test_fill_forecast() {
+        let now = 16_667 * 60;
+        // 0.5 points a minute from 40% over the last hour: 70% now, an hour to go
+        let filling: Vec<_> = (0..=60)
+            .map(|m| (now - 3600 + m * 60, 40.0 + m as f64 * 0.5))
+            .collect();
+        let forecast = fill_forecast(&filling, now).unwrap();
+        assert!((forecast.rate - 30.0).abs() < 1e-9);
+        assert_eq!(forecast.full_in, Some(Duration::from_secs(3600)));
+        assert_eq!(forecast.to_string(), "full in 1h 0m (+30.00%/h)");
+
+        let shrinking: Vec<_> = filling.iter().map(|&(t, v)| (t, 100.0 - v)).collect();
+        assert_eq!(fill_forecast(&shrinking, now).unwrap().full_in, None);
+        let flat: Vec<_> = filling.iter().map(|&(t, _)| (t, 50.0)).collect();
+        assert_eq!(fill_forecast(&flat, now).unwrap().to_string(), "stable (+0.00%/h)");
+
+        // Too little or too old a history gives no forecast
+        assert_eq!(fill_forecast(&filling[50..], now), None);
+        assert_eq!(fill_forecast(&filling, now + 3600), None);
+
+        let (_dir, mut history) = history();
+        for &(t, v) in &filling {
+            history.record_values(t, &sample("disk:/var", v));
+        }
+        assert_eq!(history.forecast_fill("/var", now), Some(forecast));
+        assert_eq!(history.forecast_fill("/home", now), None);
+        assert_eq!(format_duration(Duration::from_secs(2 * 86_400 + 4 * 3600 + 59)), "2d 4h");
+    }
+
+    #[test]
+    fn test_sparkline() {
+        let values: Vec<Option<f64>> = (0..8).map(|v| Some(v as f64)).collect();
+        assert_eq!(sparkline(&values, 60), "▁▂▃▄▅▆▇█");
+        // Averaged down to the width, with gaps left blank
+        let values = [Some(0.0), Some(0.0), None, None, Some(10.0), Some(10.0)];
+        assert_eq!(sparkline(&values, 3), "▁ █");
+        assert_eq!(sparkline(&[Some(5.0), Some(5.0)], 60), "▁▁");
+        assert_eq!(format_value("net:eth0:rx", 2048.0), "2.0 KB/s");
+        assert_eq!(format_value("disk:/", 91.26), "91.3%");
+    }
+}
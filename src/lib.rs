@@ -20,6 +20,18 @@
 /// Correlation ID support for cross-tool distributed tracing
 pub mod correlation;
 
+/// Hash-chained audit log of automated actions
+pub mod audit;
+
+/// Ring-buffered history of system metrics, recorded by the daemon
+pub mod history;
+
+/// Editing structured values in the user's `$EDITOR`
+pub mod editor;
+
+/// Background daemon: scheduled health checks, rule application and lifecycle checks
+pub mod daemon;
+
 // Tools declared after correlation so crisis.rs can import it
 pub mod tools;
 
@@ -20,6 +20,13 @@
 mod p2p;
 mod validation;
 mod correlation;
+mod audit;
+mod history;
+mod editor;
+mod rules;
+
+// Application directories are stateless, so share the library's definitions
+use personal_sysadmin::dirs;
 
 // Re-use action enums from tool modules
 use tools::process::ProcessAction;
@@ -28,6 +35,11 @@
 use tools::service::ServiceAction;
 use tools::security::SecurityAction;
 use p2p::MeshAction;
+use audit::AuditAction;
+use reasoning::ReasonAction;
+use rules::RulesAction;
+use rules::lifecycle::{LifecycleAction, ProposalsAction};
+use storage::kb::KbAction;
 
 #[derive(Parser)]
 #[command(name = "psa")]
@@ -91,6 +103,9 @@ enum Commands {
         /// Use only local SLM (no Claude)
         #[arg(long)]
         local_only: bool,
+        /// Show how each inferred fix was derived
+        #[arg(long)]
+        explain: bool,
     },
 
     /// Search for solutions in knowledge base and forums
@@ -109,6 +124,15 @@ enum Commands {
         category: String,
         /// Solution file or stdin
         solution: Option<String>,
+        /// Problem it solves (defaults to the first line of the solution)
+        #[arg(short, long)]
+        problem: Option<String>,
+    },
+
+    /// Inspect and curate the knowledge base
+    Kb {
+        #[command(subcommand)]
+        action: KbActionCli,
     },
 
     /// P2P mesh commands
@@ -123,6 +147,46 @@ enum Commands {
     /// Show system health summary
     Health,
 
+    /// Show how a metric changed over time (e.g. cpu, memory, disk:/home)
+    History {
+        /// Metric or metric group; lists recorded metrics when omitted
+        metric: Option<String>,
+        /// How far back to look (e.g. "30m", "6h", "7d")
+        #[arg(long, default_value = "6h")]
+        since: String,
+    },
+
+    /// Manage crystallized rules and signed rule bundles
+    Rules {
+        #[command(subcommand)]
+        action: RulesActionCli,
+    },
+
+    /// Rule health, proposals and retirement
+    Lifecycle {
+        #[command(subcommand)]
+        action: LifecycleActionCli,
+    },
+
+    /// Review rule proposals: edit, approve (crystallize) or reject
+    Proposals {
+        #[command(subcommand)]
+        action: ProposalsActionCli,
+    },
+
+    /// Review and verify the audit log of automated actions
+    Audit {
+        #[command(subcommand)]
+        action: AuditActionCli,
+    },
+
+    /// Explore the reasoning knowledge base interactively, or manage it
+    Reason {
+        /// Without one, start an interactive session
+        #[command(subcommand)]
+        action: Option<ReasonActionCli>,
+    },
+
     /// Crisis mode - analyze incident bundle from emergency-room
     Crisis {
         /// Path to incident bundle from system-emergency-room
@@ -243,6 +307,187 @@ enum MeshActionCli {
     Status,
 }
 
+#[derive(Subcommand, Clone)]
+enum RulesActionCli {
+    /// List rules
+    List {
+        /// Only rules with this tag
+        #[arg(short, long)]
+        tag: Option<String>,
+    },
+    /// Enable a rule
+    Enable { id: String },
+    /// Disable a rule
+    Disable { id: String },
+    /// Export rules as a signed bundle (tar, written to stdout by default)
+    Export {
+        /// Only rules with this tag
+        #[arg(short, long)]
+        tag: Option<String>,
+        /// Write the bundle to a file instead of stdout
+        #[arg(short, long)]
+        output: Option<String>,
+    },
+    /// Import a signed bundle; rules land disabled
+    Import {
+        /// Path to bundle
+        bundle: String,
+        /// Show the diff without importing
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Show (and create if needed) the local bundle signing key
+    Key,
+    /// Trust a public key for bundle imports
+    Trust {
+        /// Name for the key owner
+        name: String,
+        /// Hex-encoded ed25519 public key
+        public_key: String,
+    },
+    /// Run rule tests (*.test.toml beside the rules) against simulated systems
+    Test {
+        /// Only run tests whose rule ID or case name contains this text
+        filter: Option<String>,
+    },
+}
+
+#[derive(Subcommand, Clone)]
+enum KbActionCli {
+    /// Show a solution with its outcome counts and linked problems
+    Show {
+        /// Solution ID
+        id: String,
+    },
+    /// Edit a solution in $EDITOR (the previous state is kept in its history)
+    Edit {
+        /// Solution ID
+        id: String,
+        /// Note recorded with the change
+        #[arg(short, long)]
+        message: Option<String>,
+    },
+    /// Show how a solution changed over time
+    History {
+        /// Solution ID
+        id: String,
+    },
+    /// Fold one solution into another, combining counts and problem links
+    Merge {
+        /// Solution to keep
+        keep: String,
+        /// Duplicate to fold in and delete
+        other: String,
+    },
+    /// Record whether a solution worked when you applied it
+    Feedback {
+        /// Solution ID
+        id: String,
+        /// It fixed the problem
+        #[arg(long, conflicts_with = "failed", required_unless_present = "failed")]
+        worked: bool,
+        /// It did not fix the problem
+        #[arg(long)]
+        failed: bool,
+        /// What happened
+        #[arg(short, long)]
+        note: Option<String>,
+    },
+    /// Write the knowledge base as a portable JSON-lines dump
+    Export {
+        /// Output file (defaults to stdout)
+        #[arg(short, long)]
+        output: Option<std::path::PathBuf>,
+        /// Only solutions in this category
+        #[arg(short, long)]
+        category: Option<String>,
+    },
+    /// Merge a dump written by `psa kb export`
+    Import {
+        /// Dump file
+        file: std::path::PathBuf,
+        /// When a solution already exists: newer, confidence or both
+        #[arg(long, default_value = "newer")]
+        on_conflict: String,
+        /// Show what would change without writing anything
+        #[arg(long)]
+        dry_run: bool,
+    },
+}
+
+#[derive(Subcommand, Clone)]
+enum LifecycleActionCli {
+    /// Show rule health and proposal summary
+    Report,
+    /// List rule proposals
+    Proposals {
+        /// Include rejected and crystallized proposals
+        #[arg(short, long)]
+        all: bool,
+    },
+    /// Retire a rule: disable it and stop assessing it
+    Retire {
+        /// Rule ID
+        rule: String,
+        /// Why the rule is retired
+        #[arg(short, long)]
+        reason: Option<String>,
+    },
+    /// Import offline advisory data (OSV, Arch, Debian tracker JSON)
+    ImportAdvisories {
+        /// Advisory files or directories
+        #[arg(required = true)]
+        paths: Vec<std::path::PathBuf>,
+        /// Debian release codename (defaults to the host's)
+        #[arg(long)]
+        release: Option<String>,
+    },
+    /// List tracked CVEs with host status and linked rules
+    Cves,
+}
+
+#[derive(Subcommand, Clone)]
+enum ProposalsActionCli {
+    /// Interactively review proposals pending review
+    Review {
+        /// Review only this proposal
+        id: Option<String>,
+    },
+    /// Approve a proposal and crystallize it into a rule
+    Approve { id: String },
+    /// Reject a proposal
+    Reject {
+        id: String,
+        /// Why the proposal is rejected
+        #[arg(short, long)]
+        reason: String,
+    },
+}
+
+#[derive(Subcommand, Clone)]
+enum AuditActionCli {
+    /// Verify the hash chain and report tampering
+    Verify,
+    /// Show audit records
+    Show {
+        /// Only records newer than this (e.g. "6h", "2d", or RFC 3339 timestamp)
+        #[arg(long)]
+        since: Option<String>,
+        /// Only records for this rule ID
+        #[arg(long)]
+        rule: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+enum ReasonActionCli {
+    /// Check a .pl or .kb file and add it to the knowledge base
+    Load {
+        /// Knowledge base file
+        path: String,
+    },
+}
+
 // Conversion helpers
 impl From<ProcessActionCli> for ProcessAction {
     fn from(cli: ProcessActionCli) -> Self {
@@ -317,6 +562,86 @@ fn from(cli: MeshActionCli) -> Self {
     }
 }
 
+impl From<RulesActionCli> for RulesAction {
+    fn from(cli: RulesActionCli) -> Self {
+        match cli {
+            RulesActionCli::List { tag } => RulesAction::List { tag },
+            RulesActionCli::Enable { id } => RulesAction::Enable { id },
+            RulesActionCli::Disable { id } => RulesAction::Disable { id },
+            RulesActionCli::Export { tag, output } => RulesAction::Export { tag, output },
+            RulesActionCli::Import { bundle, dry_run } => RulesAction::Import { bundle, dry_run },
+            RulesActionCli::Key => RulesAction::Key,
+            RulesActionCli::Trust { name, public_key } => RulesAction::Trust { name, public_key },
+            RulesActionCli::Test { filter } => RulesAction::Test { filter },
+        }
+    }
+}
+
+impl From<KbActionCli> for KbAction {
+    fn from(cli: KbActionCli) -> Self {
+        match cli {
+            KbActionCli::Show { id } => KbAction::Show { id },
+            KbActionCli::Edit { id, message } => KbAction::Edit { id, message },
+            KbActionCli::History { id } => KbAction::History { id },
+            KbActionCli::Merge { keep, other } => KbAction::Merge { keep, other },
+            KbActionCli::Feedback { id, worked, note, .. } => {
+                KbAction::Feedback { id, worked, note }
+            }
+            KbActionCli::Export { output, category } => KbAction::Export { output, category },
+            KbActionCli::Import {
+                file,
+                on_conflict,
+                dry_run,
+            } => KbAction::Import {
+                file,
+                on_conflict,
+                dry_run,
+            },
+        }
+    }
+}
+
+impl From<LifecycleActionCli> for LifecycleAction {
+    fn from(cli: LifecycleActionCli) -> Self {
+        match cli {
+            LifecycleActionCli::Report => LifecycleAction::Report,
+            LifecycleActionCli::Proposals { all } => LifecycleAction::Proposals { all },
+            LifecycleActionCli::Retire { rule, reason } => LifecycleAction::Retire { rule, reason },
+            LifecycleActionCli::ImportAdvisories { paths, release } => {
+                LifecycleAction::ImportAdvisories { paths, release }
+            }
+            LifecycleActionCli::Cves => LifecycleAction::Cves,
+        }
+    }
+}
+
+impl From<ProposalsActionCli> for ProposalsAction {
+    fn from(cli: ProposalsActionCli) -> Self {
+        match cli {
+            ProposalsActionCli::Review { id } => ProposalsAction::Review { id },
+            ProposalsActionCli::Approve { id } => ProposalsAction::Approve { id },
+            ProposalsActionCli::Reject { id, reason } => ProposalsAction::Reject { id, reason },
+        }
+    }
+}
+
+impl From<AuditActionCli> for AuditAction {
+    fn from(cli: AuditActionCli) -> Self {
+        match cli {
+            AuditActionCli::Verify => AuditAction::Verify,
+            AuditActionCli::Show { since, rule } => AuditAction::Show { since, rule },
+        }
+    }
+}
+
+impl From<ReasonActionCli> for ReasonAction {
+    fn from(cli: ReasonActionCli) -> Self {
+        match cli {
+            ReasonActionCli::Load { path } => ReasonAction::Load { path },
+        }
+    }
+}
+
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
     let cli = Cli::parse();
@@ -359,14 +684,17 @@ async fn main() -> anyhow::Result<()> {
         Commands::Security { action } => {
             tools::security::handle(action.into(), &storage, &cache).await?;
         }
-        Commands::Diagnose { problem, local_only } => {
-            ai::diagnose(&problem, local_only, &storage, &cache).await?;
+        Commands::Diagnose { problem, local_only, explain } => {
+            ai::diagnose(&problem, local_only, explain, &storage, &cache).await?;
         }
         Commands::Search { query, online } => {
             forum::search(&query, online, &storage, &cache).await?;
         }
-        Commands::Learn { category, solution } => {
-            reasoning::learn(&category, solution, &storage).await?;
+        Commands::Learn { category, solution, problem } => {
+            reasoning::learn(&category, solution, problem, &storage).await?;
+        }
+        Commands::Kb { action } => {
+            storage::kb::handle(action.into(), &storage).await?;
         }
         Commands::Mesh { action } => {
             p2p::handle(action.into(), &storage, &cache).await?;
@@ -377,6 +705,25 @@ async fn main() -> anyhow::Result<()> {
         Commands::Health => {
             tools::health::show(&storage, &cache).await?;
         }
+        Commands::History { metric, since } => {
+            history::show(metric.as_deref(), &since)?;
+        }
+        Commands::Rules { action } => {
+            rules::handle(action.into()).await?;
+        }
+        Commands::Lifecycle { action } => {
+            rules::lifecycle::handle(action.into()).await?;
+        }
+        Commands::Proposals { action } => {
+            rules::lifecycle::handle_proposals(action.into(), &storage).await?;
+        }
+        Commands::Audit { action } => {
+            audit::handle(action.into()).await?;
+        }
+        Commands::Reason { action } => {
+            let action = action.map_or(ReasonAction::Repl, Into::into);
+            reasoning::handle(action, &storage).await?;
+        }
         Commands::Crisis { incident, correlation_id } => {
             tools::crisis::analyze(&incident, correlation_id.as_deref(), &storage, &cache).await?;
         }
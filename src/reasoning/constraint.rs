@@ -0,0 +1,279 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+//! Constraints: disequality and arithmetic over numeric atoms
+//!
+//! A constraint that can't be decided yet (`X =/= a` with `X` unbound, or
+//! `M > 90` before `M` is known) is kept in the [`State`] and checked again
+//! whenever unification adds bindings, so goal order doesn't matter and a
+//! constraint only lives as long as the branch that introduced it. Numbers
+//! are atoms such as `90` or `92.5`; arithmetic on anything else fails.
+//! A negation whose variables aren't bound yet waits here too, until the
+//! engine can run it (see [`Constraint::Not`]). Whatever is still pending
+//! when an answer is found is reported with it.
+
+use super::kanren::{self, State};
+use super::{Substitution, Term};
+
+/// A comparison between two arithmetic expressions
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Comparison {
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+    Equal,
+    NotEqual,
+}
+
+impl Comparison {
+    /// The comparison an operator names, e.g. `>` or `=<`
+    pub fn from_operator(op: &str) -> Option<Self> {
+        Some(match op {
+            "<" => Self::Less,
+            "=<" | "<=" => Self::LessOrEqual,
+            ">" => Self::Greater,
+            ">=" => Self::GreaterOrEqual,
+            "=:=" => Self::Equal,
+            "=\\=" => Self::NotEqual,
+            _ => return None,
+        })
+    }
+
+    /// The operator naming the comparison
+    pub fn operator(self) -> &'static str {
+        match self {
+            Self::Less => "<",
+            Self::LessOrEqual => "=<",
+            Self::Greater => ">",
+            Self::GreaterOrEqual => ">=",
+            Self::Equal => "=:=",
+            Self::NotEqual => "=\\=",
+        }
+    }
+
+    fn holds(self, a: f64, b: f64) -> bool {
+        match self {
+            Self::Less => a < b,
+            Self::LessOrEqual => a <= b,
+            Self::Greater => a > b,
+            Self::GreaterOrEqual => a >= b,
+            Self::Equal => a == b,
+            Self::NotEqual => a != b,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum Constraint {
+    /// `a =/= b`: the terms must never become equal
+    Differ(Term, Term),
+    /// `a > b` and friends
+    Compare(Comparison, Term, Term),
+    /// `x is expr`: bind `x` to the value once it can be computed
+    Is(Term, Term),
+    /// `\+ goal`, waiting for the `watched` variables to be ground; only
+    /// the engine can run the goal, at nesting `depth`
+    Not {
+        goal: Term,
+        watched: Vec<Term>,
+        depth: usize,
+    },
+}
+
+/// Where a constraint stands under the current bindings
+enum Check {
+    Holds,
+    Fails,
+    Pending,
+    /// Holds given these extra bindings
+    Bind(Substitution),
+}
+
+impl Constraint {
+    /// The constraint a goal such as `X =/= a` or `M > 90` states, if any
+    pub fn from_term(term: &Term) -> Option<Self> {
+        let Term::Compound(op, args) = term else {
+            return None;
+        };
+        let [a, b] = args.as_slice() else {
+            return None;
+        };
+        let (a, b) = (a.clone(), b.clone());
+        Some(match op.as_str() {
+            "=/=" => Constraint::Differ(a, b),
+            "is" => Constraint::Is(a, b),
+            op => Constraint::Compare(Comparison::from_operator(op)?, a, b),
+        })
+    }
+
+    /// The goal stating the constraint
+    pub fn to_term(&self) -> Term {
+        let (op, a, b) = match self {
+            Constraint::Differ(a, b) => ("=/=", a, b),
+            Constraint::Compare(op, a, b) => (op.operator(), a, b),
+            Constraint::Is(a, b) => ("is", a, b),
+            Constraint::Not { goal, .. } => {
+                return Term::Compound("\\+".to_string(), vec![goal.clone()])
+            }
+        };
+        Term::Compound(op.to_string(), vec![a.clone(), b.clone()])
+    }
+
+    /// The constraint with `f` applied to each of its terms
+    pub fn map(&self, mut f: impl FnMut(&Term) -> Term) -> Self {
+        match self {
+            Constraint::Differ(a, b) => Constraint::Differ(f(a), f(b)),
+            Constraint::Compare(op, a, b) => Constraint::Compare(*op, f(a), f(b)),
+            Constraint::Is(a, b) => Constraint::Is(f(a), f(b)),
+            Constraint::Not {
+                goal,
+                watched,
+                depth,
+            } => Constraint::Not {
+                goal: f(goal),
+                watched: watched.iter().map(f).collect(),
+                depth: *depth,
+            },
+        }
+    }
+
+    fn check(&self, subst: &Substitution) -> Check {
+        match self {
+            Constraint::Differ(a, b) => match kanren::unify(a, b, subst) {
+                None => Check::Holds,
+                // Equal without binding anything
+                Some(unified) if unified.len() == subst.len() => Check::Fails,
+                Some(_) => Check::Pending,
+            },
+            Constraint::Compare(op, a, b) => {
+                let (a, b) = (kanren::walk_star(a, subst), kanren::walk_star(b, subst));
+                if !is_ground(&a) || !is_ground(&b) {
+                    return Check::Pending;
+                }
+                match (evaluate(&a), evaluate(&b)) {
+                    (Some(a), Some(b)) if op.holds(a, b) => Check::Holds,
+                    _ => Check::Fails,
+                }
+            }
+            Constraint::Is(x, expr) => {
+                let expr = kanren::walk_star(expr, subst);
+                if !is_ground(&expr) {
+                    return Check::Pending;
+                }
+                match evaluate(&expr).and_then(|v| kanren::unify(x, &number(v), subst)) {
+                    Some(subst) => Check::Bind(subst),
+                    None => Check::Fails,
+                }
+            }
+            // Decided by the engine, see `ReasoningEngine::wake`
+            Constraint::Not { .. } => Check::Pending,
+        }
+    }
+}
+
+/// Recheck the state's constraints, dropping those that now hold; `None` if
+/// one fails
+pub fn solve(mut state: State) -> Option<State> {
+    loop {
+        let mut bound = false;
+        let mut pending = Vec::with_capacity(state.constraints.len());
+        for constraint in std::mem::take(&mut state.constraints) {
+            match constraint.check(&state.subst) {
+                Check::Holds => {}
+                Check::Fails => return None,
+                Check::Pending => pending.push(constraint),
+                Check::Bind(subst) => {
+                    state.subst = subst;
+                    bound = true;
+                }
+            }
+        }
+        state.constraints = pending;
+        // New bindings may decide constraints checked before them
+        if !bound {
+            return Some(state);
+        }
+    }
+}
+
+/// The value of a ground arithmetic expression: numbers, `+`, `-`, `*`,
+/// `/`, `mod`, `min`, `max` and `abs`
+pub fn evaluate(expr: &Term) -> Option<f64> {
+    let value = match expr {
+        Term::Atom(a) => a.parse().ok()?,
+        Term::Compound(op, args) => match (op.as_str(), args.as_slice()) {
+            ("-", [a]) => -evaluate(a)?,
+            ("abs", [a]) => evaluate(a)?.abs(),
+            (op, [a, b]) => {
+                let (a, b) = (evaluate(a)?, evaluate(b)?);
+                match op {
+                    "+" => a + b,
+                    "-" => a - b,
+                    "*" => a * b,
+                    "/" => a / b,
+                    "mod" => a.rem_euclid(b),
+                    "min" => a.min(b),
+                    "max" => a.max(b),
+                    _ => return None,
+                }
+            }
+            _ => return None,
+        },
+        _ => return None,
+    };
+    // Also rejects "inf" and "NaN" atoms, and division by zero
+    value.is_finite().then_some(value)
+}
+
+/// The atom for a number: `14` rather than `14.0`
+pub fn number(value: f64) -> Term {
+    if value.fract() == 0.0 && value.abs() < 1e15 {
+        Term::Atom(format!("{}", value as i64))
+    } else {
+        Term::Atom(value.to_string())
+    }
+}
+
+pub fn is_ground(term: &Term) -> bool {
+    match term {
+        Term::Var(_) => false,
+        Term::Atom(_) => true,
+        Term::Compound(_, args) | Term::List(args) => args.iter().all(is_ground),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::reasoning::{atom, compound};
+
+    #[test]
+    fn test_evaluate() {
+        let expr = compound(
+            "*",
+            vec![atom("2"), compound("+", vec![atom("3"), atom("4.5")])],
+        );
+        assert_eq!(evaluate(&expr), Some(15.0));
+        assert_eq!(number(15.0), atom("15"));
+        assert_eq!(number(0.25), atom("0.25"));
+        assert_eq!(
+            evaluate(&compound("mod", vec![atom("-7"), atom("3")])),
+            Some(2.0)
+        );
+        assert_eq!(evaluate(&compound("/", vec![atom("1"), atom("0")])), None);
+        assert_eq!(
+            evaluate(&compound("+", vec![atom("nginx"), atom("1")])),
+            None
+        );
+        assert_eq!(evaluate(&atom("inf")), None);
+    }
+
+    #[test]
+    fn test_constraint_terms() {
+        for goal in ["X =/= a", "M > 90", "M =< 5", "F is 100 - M", "A =\\= B"] {
+            let term = crate::reasoning::parser::parse_term(goal).unwrap();
+            let constraint = Constraint::from_term(&term).unwrap();
+            assert_eq!(constraint.to_term(), term, "{}", goal);
+        }
+        assert!(Constraint::from_term(&compound("loaded", vec![atom("x")])).is_none());
+    }
+}
@@ -0,0 +1,116 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+//! Facts derived from the solution store
+//!
+//! Every stored [`Solution`] contributes, for its normalized problem `P`:
+//!
+//! - `solves(P, Command)` for each command, weighted by the solution's record
+//! - `category(P, Category)` and `tag(P, Tag)`
+//! - `symptom(S, P)` for each other problem `S` linked to it by a
+//!   [`ProblemRelation`], weighted by the relation's confidence
+//!
+//! The store stays the source of truth: these clauses carry the solution's
+//! ID as their source, and are replaced whenever it changes.
+
+use super::{atom, compound, Clause, Term};
+use crate::storage::{confidence, normalize_problem, ProblemRelation, Solution};
+
+/// Rules over the derived facts, loaded before any knowledge base file
+pub const PRELUDE: &str = "\
+% A problem is fixed by what solves it, or by what fixes its cause
+fix(Problem, Command) :- solves(Problem, Command).
+fix(Problem, Command) :- symptom(Problem, Cause), fix(Cause, Command).
+";
+
+/// The facts a solution and its problem links contribute
+pub fn solution_facts(solution: &Solution, relations: &[ProblemRelation]) -> Vec<Clause> {
+    let problem = normalize_problem(&solution.problem);
+    if problem.is_empty() {
+        return vec![];
+    }
+    let fact = |head: Term, confidence: f32| Clause {
+        head,
+        body: vec![],
+        confidence,
+        source: Some(solution.id.clone()),
+    };
+
+    let mut facts: Vec<Clause> = solution
+        .commands
+        .iter()
+        .map(|command| {
+            fact(
+                compound("solves", vec![atom(&problem), atom(command)]),
+                confidence(solution) as f32,
+            )
+        })
+        .collect();
+    facts.push(fact(
+        compound("category", vec![atom(&problem), atom(&solution.category)]),
+        1.0,
+    ));
+    for tag in &solution.tags {
+        facts.push(fact(compound("tag", vec![atom(&problem), atom(tag)]), 1.0));
+    }
+    for relation in relations {
+        let symptom = normalize_problem(&relation.from_problem);
+        if !symptom.is_empty() && symptom != problem {
+            facts.push(fact(
+                compound("symptom", vec![atom(&symptom), atom(&problem)]),
+                relation.confidence,
+            ));
+        }
+    }
+    facts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::SolutionSource;
+
+    #[test]
+    fn test_solution_facts() {
+        let now = chrono::Utc::now();
+        let solution = Solution {
+            id: "s1".to_string(),
+            category: "network".to_string(),
+            problem: "WiFi  drops after suspend".to_string(),
+            solution: String::new(),
+            commands: vec!["modprobe -r iwlwifi".to_string()],
+            tags: vec!["wifi".to_string()],
+            success_count: 2,
+            failure_count: 0,
+            source: SolutionSource::Manual,
+            created_at: now,
+            updated_at: now,
+        };
+        let relation = |problem: &str| ProblemRelation {
+            from_problem: problem.to_string(),
+            to_solution: "s1".to_string(),
+            confidence: 0.7,
+            context: vec![],
+        };
+        let facts = solution_facts(
+            &solution,
+            &[
+                relation("no network after resume"),
+                relation("wifi drops after suspend"),
+            ],
+        );
+
+        let heads: Vec<_> = facts.iter().map(|c| c.head.clone()).collect();
+        let problem = atom("wifi drops after suspend");
+        assert_eq!(
+            heads,
+            vec![
+                compound("solves", vec![problem.clone(), atom("modprobe -r iwlwifi")]),
+                compound("category", vec![problem.clone(), atom("network")]),
+                compound("tag", vec![problem.clone(), atom("wifi")]),
+                compound("symptom", vec![atom("no network after resume"), problem]),
+            ]
+        );
+        assert_eq!(facts[0].confidence, 0.75);
+        assert_eq!(facts[3].confidence, 0.7);
+        assert!(facts.iter().all(|c| c.source.as_deref() == Some("s1")));
+    }
+}
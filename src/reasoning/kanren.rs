@@ -0,0 +1,483 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+//! miniKanren core: goals, states and interleaving lazy streams
+//!
+//! A [`Goal`] maps a [`State`] to a [`Stream`] of states in which it holds.
+//! Streams are lazy: a [`Stream::Delayed`] step is only run when an answer is
+//! pulled, and [`disj`] swaps its branches at every delay, so an infinite
+//! branch cannot starve the other one. Unification does an occurs check, so
+//! `X == f(X)` fails instead of building a cyclic term. Constraints that
+//! can't be decided yet ride along in the state and are rechecked whenever
+//! unification binds something (see [`super::constraint`]), and each state
+//! keeps a trace of the steps that led to it (see [`super::proof`]).
+//!
+//! ```
+//! use personal_sysadmin::reasoning::{atom, kanren::*};
+//!
+//! // run 2 (q) (conde ((== q 'tea)) ((== q 'cup)))
+//! let answers = run(Some(2), |q| disj(eq(q.clone(), atom("tea")), eq(q, atom("cup"))));
+//! assert_eq!(answers, vec![atom("tea"), atom("cup")]);
+//! ```
+
+use std::rc::Rc;
+
+use super::constraint::{self, Constraint};
+use super::proof::{Event, Trace};
+use super::{Substitution, Term};
+
+/// What a goal sees: bindings so far and the bookkeeping of the search
+#[derive(Debug, Clone)]
+pub struct State {
+    pub subst: Substitution,
+    /// Counter for fresh and renamed variables
+    pub next_var: usize,
+    /// Product of the confidences of the clauses used to get here
+    pub confidence: f32,
+    /// Constraints waiting for bindings to be decided
+    pub constraints: Vec<Constraint>,
+    /// The latest step of the search that got here
+    pub trace: Option<Rc<Trace>>,
+}
+
+impl Default for State {
+    fn default() -> Self {
+        Self {
+            subst: Substitution::new(),
+            next_var: 0,
+            confidence: 1.0,
+            constraints: vec![],
+            trace: None,
+        }
+    }
+}
+
+impl State {
+    /// A variable no other part of the search uses
+    pub fn fresh_var(&mut self) -> Term {
+        self.next_var += 1;
+        Term::Var(format!("_#{}", self.next_var))
+    }
+
+    /// Add a step to the trace
+    pub fn record(&mut self, event: Event) {
+        self.trace = Some(Rc::new(Trace {
+            event,
+            prev: self.trace.take(),
+        }));
+    }
+
+    /// This state with `u` and `v` unified, if they can be without breaking
+    /// a constraint
+    pub fn unify(self, u: &Term, v: &Term) -> Option<State> {
+        let subst = unify(u, v, &self.subst)?;
+        let bound = subst.len() != self.subst.len();
+        let state = State { subst, ..self };
+        if bound && !state.constraints.is_empty() {
+            constraint::solve(state)
+        } else {
+            Some(state)
+        }
+    }
+}
+
+/// A lazy sequence of states
+pub enum Stream<'a> {
+    Empty,
+    /// An answer, then the rest
+    Cons(State, Box<Stream<'a>>),
+    /// Work that hasn't been done yet
+    Delayed(Box<dyn FnOnce() -> Stream<'a> + 'a>),
+}
+
+impl<'a> Stream<'a> {
+    pub fn unit(state: State) -> Self {
+        Stream::Cons(state, Box::new(Stream::Empty))
+    }
+
+    pub fn delayed(f: impl FnOnce() -> Stream<'a> + 'a) -> Self {
+        Stream::Delayed(Box::new(f))
+    }
+}
+
+/// Pulling from a stream runs delayed work until the next answer
+impl Iterator for Stream<'_> {
+    type Item = State;
+
+    fn next(&mut self) -> Option<State> {
+        loop {
+            match std::mem::replace(self, Stream::Empty) {
+                Stream::Empty => return None,
+                Stream::Cons(state, rest) => {
+                    *self = *rest;
+                    return Some(state);
+                }
+                Stream::Delayed(f) => *self = f(),
+            }
+        }
+    }
+}
+
+/// A relation over states
+pub type Goal<'a> = Rc<dyn Fn(State) -> Stream<'a> + 'a>;
+
+/// Answers of either stream, alternating at every delay
+pub fn mplus<'a>(s1: Stream<'a>, s2: Stream<'a>) -> Stream<'a> {
+    match s1 {
+        Stream::Empty => s2,
+        Stream::Cons(state, rest) => {
+            Stream::Cons(state, Box::new(Stream::delayed(move || mplus(*rest, s2))))
+        }
+        Stream::Delayed(f) => Stream::delayed(move || mplus(s2, f())),
+    }
+}
+
+/// Run `goal` from every state of `stream`
+pub fn bind<'a>(stream: Stream<'a>, goal: Goal<'a>) -> Stream<'a> {
+    match stream {
+        Stream::Empty => Stream::Empty,
+        Stream::Cons(state, rest) => {
+            let first = goal(state);
+            mplus(first, Stream::delayed(move || bind(*rest, goal)))
+        }
+        Stream::Delayed(f) => Stream::delayed(move || bind(f(), goal)),
+    }
+}
+
+pub fn succeed<'a>() -> Goal<'a> {
+    Rc::new(Stream::unit)
+}
+
+pub fn fail<'a>() -> Goal<'a> {
+    Rc::new(|_| Stream::Empty)
+}
+
+/// `u == v`
+pub fn eq<'a>(u: Term, v: Term) -> Goal<'a> {
+    Rc::new(move |state: State| match state.unify(&u, &v) {
+        Some(state) => Stream::unit(state),
+        None => Stream::Empty,
+    })
+}
+
+/// `u =/= v`: the terms never become equal on this branch
+pub fn neq<'a>(u: Term, v: Term) -> Goal<'a> {
+    constrain(Constraint::Differ(u, v))
+}
+
+/// Add a constraint, failing now if it already can't hold
+pub fn constrain<'a>(c: Constraint) -> Goal<'a> {
+    Rc::new(move |mut state: State| {
+        state.constraints.push(c.clone());
+        match constraint::solve(state) {
+            Some(state) => Stream::unit(state),
+            None => Stream::Empty,
+        }
+    })
+}
+
+/// Both goals
+pub fn conj<'a>(g1: Goal<'a>, g2: Goal<'a>) -> Goal<'a> {
+    Rc::new(move |state| bind(g1(state), g2.clone()))
+}
+
+/// Either goal, interleaving their answers
+pub fn disj<'a>(g1: Goal<'a>, g2: Goal<'a>) -> Goal<'a> {
+    Rc::new(move |state: State| mplus(g1(state.clone()), g2(state)))
+}
+
+/// `(cond -> then ; otherwise)`: `then` on the first answer of `cond`, or
+/// `otherwise` if it has none
+pub fn ifte<'a>(cond: Goal<'a>, then: Goal<'a>, otherwise: Goal<'a>) -> Goal<'a> {
+    Rc::new(move |state: State| match cond(state.clone()).next() {
+        Some(state) => then(state),
+        None => otherwise(state),
+    })
+}
+
+/// All of the goals; succeeds for none
+pub fn conj_all<'a>(goals: Vec<Goal<'a>>) -> Goal<'a> {
+    goals.into_iter().reduce(conj).unwrap_or_else(succeed)
+}
+
+/// Any of the goals; fails for none
+pub fn disj_all<'a>(goals: Vec<Goal<'a>>) -> Goal<'a> {
+    goals.into_iter().reduce(disj).unwrap_or_else(fail)
+}
+
+/// A disjunction of conjunctions, one per line
+pub fn conde<'a>(lines: Vec<Vec<Goal<'a>>>) -> Goal<'a> {
+    disj_all(lines.into_iter().map(conj_all).collect())
+}
+
+/// Introduce `n` fresh variables
+pub fn fresh<'a>(n: usize, f: impl Fn(&[Term]) -> Goal<'a> + 'a) -> Goal<'a> {
+    Rc::new(move |mut state: State| {
+        let vars: Vec<Term> = (0..n).map(|_| state.fresh_var()).collect();
+        f(&vars)(state)
+    })
+}
+
+/// Build the goal only when it runs, so recursive relations terminate
+/// while being defined and yield to other branches while being searched
+pub fn delay<'a>(f: impl Fn() -> Goal<'a> + 'a) -> Goal<'a> {
+    let f = Rc::new(f);
+    Rc::new(move |state| {
+        let f = f.clone();
+        Stream::delayed(move || f()(state))
+    })
+}
+
+/// Up to `n` values of `q` that satisfy the goal (all of them for `None`)
+pub fn run<'a>(n: Option<usize>, f: impl FnOnce(Term) -> Goal<'a>) -> Vec<Term> {
+    let mut state = State::default();
+    let q = state.fresh_var();
+    let stream = f(q.clone())(state);
+    stream
+        .take(n.unwrap_or(usize::MAX))
+        .map(|state| reify(&q, &state.subst))
+        .collect()
+}
+
+/// Every value of `q` that satisfies the goal; doesn't return if there are
+/// infinitely many
+pub fn run_star<'a>(f: impl FnOnce(Term) -> Goal<'a>) -> Vec<Term> {
+    run(None, f)
+}
+
+/// Follow a variable's bindings to a non-variable or an unbound variable
+/// (without resolving inside compounds)
+pub fn walk(term: &Term, subst: &Substitution) -> Term {
+    resolve(term, subst).clone()
+}
+
+/// [`walk`] without copying the result, which matters for deep terms
+fn resolve<'t>(mut term: &'t Term, subst: &'t Substitution) -> &'t Term {
+    while let Term::Var(v) = term {
+        match subst.get(v) {
+            Some(bound) => term = bound,
+            None => break,
+        }
+    }
+    term
+}
+
+/// Resolve a term and everything inside it
+pub fn walk_star(term: &Term, subst: &Substitution) -> Term {
+    match resolve(term, subst) {
+        Term::Compound(name, args) => Term::Compound(
+            name.clone(),
+            args.iter().map(|a| walk_star(a, subst)).collect(),
+        ),
+        Term::List(items) => Term::List(items.iter().map(|t| walk_star(t, subst)).collect()),
+        term => term.clone(),
+    }
+}
+
+/// A fully resolved term, with its remaining variables renamed `_0`, `_1`, …
+/// in order of appearance
+pub fn reify(term: &Term, subst: &Substitution) -> Term {
+    reify_with(term, subst, &mut vec![])
+}
+
+/// [`reify`] several terms, continuing the numbering of `names`, the
+/// variables named so far
+pub fn reify_with(term: &Term, subst: &Substitution, names: &mut Vec<String>) -> Term {
+    fn rename(term: Term, names: &mut Vec<String>) -> Term {
+        match term {
+            Term::Var(v) => {
+                let n = names.iter().position(|n| *n == v).unwrap_or_else(|| {
+                    names.push(v);
+                    names.len() - 1
+                });
+                Term::Var(format!("_{}", n))
+            }
+            Term::Compound(name, args) => {
+                Term::Compound(name, args.into_iter().map(|a| rename(a, names)).collect())
+            }
+            Term::List(items) => Term::List(items.into_iter().map(|t| rename(t, names)).collect()),
+            atom => atom,
+        }
+    }
+    rename(walk_star(term, subst), names)
+}
+
+/// Unify two terms, extending the substitution, or `None` if they can't be
+pub fn unify(u: &Term, v: &Term, subst: &Substitution) -> Option<Substitution> {
+    match (resolve(u, subst), resolve(v, subst)) {
+        (Term::Var(a), Term::Var(b)) if a == b => Some(subst.clone()),
+        (Term::Var(var), term) | (term, Term::Var(var)) => {
+            if occurs(var, term, subst) {
+                return None;
+            }
+            let mut extended = subst.clone();
+            extended.insert(var.clone(), term.clone());
+            Some(extended)
+        }
+        (Term::Atom(a), Term::Atom(b)) => (a == b).then(|| subst.clone()),
+        (Term::Compound(f, xs), Term::Compound(g, ys)) if f == g && xs.len() == ys.len() => {
+            unify_all(xs, ys, subst)
+        }
+        (Term::List(xs), Term::List(ys)) if xs.len() == ys.len() => unify_all(xs, ys, subst),
+        _ => None,
+    }
+}
+
+fn unify_all(xs: &[Term], ys: &[Term], subst: &Substitution) -> Option<Substitution> {
+    xs.iter()
+        .zip(ys)
+        .try_fold(subst.clone(), |subst, (x, y)| unify(x, y, &subst))
+}
+
+/// Whether `var` appears in `term`
+fn occurs(var: &str, term: &Term, subst: &Substitution) -> bool {
+    match resolve(term, subst) {
+        Term::Var(v) => v == var,
+        Term::Compound(_, args) | Term::List(args) => args.iter().any(|a| occurs(var, a, subst)),
+        Term::Atom(_) => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::reasoning::{atom, compound, var};
+
+    /// `parent(X, Y)` over a small family
+    fn parent<'a>(x: Term, y: Term) -> Goal<'a> {
+        conde(
+            [
+                ("tom", "bob"),
+                ("bob", "ann"),
+                ("bob", "pat"),
+                ("pat", "jim"),
+            ]
+            .iter()
+            .map(|(p, c)| vec![eq(x.clone(), atom(p)), eq(y.clone(), atom(c))])
+            .collect(),
+        )
+    }
+
+    /// `ancestor(X, Y) :- parent(X, Y) ; parent(X, Z), ancestor(Z, Y)`
+    fn ancestor<'a>(x: Term, y: Term) -> Goal<'a> {
+        disj(
+            parent(x.clone(), y.clone()),
+            fresh(1, move |z| {
+                let (x, y, z) = (x.clone(), y.clone(), z[0].clone());
+                conj(
+                    parent(x, z.clone()),
+                    delay(move || ancestor(z.clone(), y.clone())),
+                )
+            }),
+        )
+    }
+
+    /// An infinite relation: `q` is `value`, again and again
+    fn forever<'a>(q: Term, value: &'static str) -> Goal<'a> {
+        disj(
+            eq(q.clone(), atom(value)),
+            delay(move || forever(q.clone(), value)),
+        )
+    }
+
+    #[test]
+    fn test_eq_and_reify() {
+        assert_eq!(run_star(|q| eq(q, atom("tea"))), vec![atom("tea")]);
+        assert!(run_star(|_| eq(atom("tea"), atom("cup"))).is_empty());
+        // Unbound variables reify to _0, _1, … in order of appearance
+        let answers = run_star(|q| {
+            fresh(2, move |v| {
+                eq(
+                    q.clone(),
+                    compound("pair", vec![v[1].clone(), v[0].clone(), v[1].clone()]),
+                )
+            })
+        });
+        assert_eq!(
+            answers,
+            vec![compound("pair", vec![var("_0"), var("_1"), var("_0")])]
+        );
+    }
+
+    #[test]
+    fn test_occurs_check() {
+        let subst = Substitution::new();
+        let cyclic = compound("f", vec![var("X")]);
+        assert!(unify(&var("X"), &cyclic, &subst).is_none());
+        // Also through an existing binding
+        let subst = unify(&var("Y"), &cyclic, &subst).unwrap();
+        assert!(unify(&var("X"), &var("Y"), &subst).is_none());
+        assert!(run_star(|q| eq(q.clone(), compound("f", vec![q]))).is_empty());
+    }
+
+    #[test]
+    fn test_conj_disj_and_conde() {
+        assert_eq!(
+            run_star(|q| disj(eq(q.clone(), atom("tea")), eq(q, atom("cup")))),
+            vec![atom("tea"), atom("cup")]
+        );
+        assert!(run_star(|q| conj(eq(q.clone(), atom("tea")), eq(q, atom("cup")))).is_empty());
+        assert_eq!(run_star(|_| conj_all(vec![])), vec![var("_0")]);
+        assert!(run_star(|_| disj_all(vec![])).is_empty());
+
+        // Every child of bob, and every ancestor of jim
+        assert_eq!(
+            run_star(|q| parent(atom("bob"), q)),
+            vec![atom("ann"), atom("pat")]
+        );
+        let mut ancestors = run_star(|q| ancestor(q, atom("jim")));
+        ancestors.sort_by_key(|t| format!("{:?}", t));
+        assert_eq!(ancestors, vec![atom("bob"), atom("pat"), atom("tom")]);
+    }
+
+    #[test]
+    fn test_fresh_variables_are_distinct() {
+        let answers = run_star(|q| {
+            fresh(2, move |v| {
+                conj(
+                    eq(q.clone(), compound("p", vec![v[0].clone(), v[1].clone()])),
+                    eq(v[0].clone(), atom("a")),
+                )
+            })
+        });
+        assert_eq!(answers, vec![compound("p", vec![atom("a"), var("_0")])]);
+    }
+
+    #[test]
+    fn test_infinite_streams_interleave() {
+        // Without interleaving the first branch would starve the second
+        let answers = run(Some(4), |q| {
+            disj(forever(q.clone(), "tea"), forever(q, "cup"))
+        });
+        assert_eq!(answers.len(), 4);
+        assert!(answers.contains(&atom("tea")));
+        assert!(answers.contains(&atom("cup")));
+
+        // A failing goal after an infinite one still finds the other branch
+        let answers = run(Some(1), |q| {
+            conj(
+                disj(forever(q.clone(), "tea"), eq(q.clone(), atom("cup"))),
+                eq(q, atom("cup")),
+            )
+        });
+        assert_eq!(answers, vec![atom("cup")]);
+    }
+
+    #[test]
+    fn test_streams_are_lazy() {
+        let ran = std::cell::Cell::new(0);
+        let counted = |q: Term| -> Goal<'_> {
+            let ran = &ran;
+            delay(move || {
+                ran.set(ran.get() + 1);
+                eq(q.clone(), atom("x"))
+            })
+        };
+        let stream = disj(counted(var("Q")), counted(var("Q")))(State::default());
+        assert_eq!(ran.get(), 0);
+        assert_eq!(stream.count(), 2);
+        assert_eq!(ran.get(), 2);
+
+        // Nothing after a failed conjunct runs
+        assert_eq!(conj(fail(), counted(var("Q")))(State::default()).count(), 0);
+        assert_eq!(ran.get(), 2);
+    }
+}
@@ -1,8 +1,8 @@
 // SPDX-License-Identifier: AGPL-3.0-or-later
 //! miniKanren-style logic programming for solution learning and inference
 //!
-//! This module implements a simple relational programming engine inspired by
-//! miniKanren for:
+//! This module implements a relational programming engine on a miniKanren
+//! core ([`kanren`]) for:
 //! - Learning problem→solution relationships
 //! - Inferring solutions from partial problem descriptions
 //! - Building confidence scores based on success/failure feedback
@@ -10,12 +10,33 @@
 // Allow dead code - this is scaffolding for future reasoning capabilities
 #![allow(dead_code)]
 
-use anyhow::Result;
-use crate::storage::Storage;
-use std::collections::HashMap;
+pub mod constraint;
+pub mod facts;
+pub mod kanren;
+pub mod parser;
+pub mod proof;
+pub mod repl;
+pub mod table;
+
+use anyhow::{bail, Context, Result};
+use crate::storage::{Learned, ProblemRelation, Solution, Storage};
+use kanren::{Goal, State, Stream};
+use std::cell::{Cell, RefCell};
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::path::Path;
+use std::rc::Rc;
+use constraint::Constraint;
+use proof::{Event, Proof};
+use table::{Lookup, Tables};
+
+/// Deepest nesting of clause uses before a branch is cut off
+pub const DEFAULT_MAX_DEPTH: usize = 64;
+
+/// Extensions of knowledge base files in the [`parser`] syntax
+pub const KNOWLEDGE_EXTENSIONS: &[&str] = &["pl", "kb"];
 
 /// A logical term in our knowledge base
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum Term {
     /// A concrete value
     Atom(String),
@@ -36,15 +57,49 @@ pub struct Clause {
     pub body: Vec<Term>,
     /// Confidence score (0.0 - 1.0)
     pub confidence: f32,
+    /// ID of the stored solution it was derived from (see [`facts`])
+    pub source: Option<String>,
 }
 
 /// Substitution mapping variables to terms
 pub type Substitution = HashMap<String, Term>;
 
+/// A predicate: name and arity
+pub type Predicate = (String, usize);
+
+/// An answer to a query
+#[derive(Debug, Clone)]
+pub struct Answer {
+    /// Values of the query's variables
+    pub bindings: Substitution,
+    /// Product of the confidences of the clauses used
+    pub confidence: f32,
+    /// Constraints that were still undecided, such as `_0 > 10`, or a
+    /// negation whose variables were never bound; the answer only holds if
+    /// they do
+    pub residual: Vec<Term>,
+    /// How each goal the query called was proven, in order
+    pub proofs: Vec<Rc<Proof>>,
+}
+
 /// The reasoning engine
+///
+/// Recursive predicates (and any declared with [`ReasoningEngine::table`])
+/// are tabled: their answers are computed to a fixpoint and memoized until
+/// the clauses change, so transitive rules terminate and each answer carries
+/// the confidence of its best derivation. Other calls nest at most
+/// [`DEFAULT_MAX_DEPTH`] clauses deep.
 pub struct ReasoningEngine {
     /// Known facts and rules
     clauses: Vec<Clause>,
+    /// Predicates tabled even though they aren't recursive
+    tabled: HashSet<Predicate>,
+    /// Recursive predicates, worked out when first needed
+    recursive: RefCell<Option<HashSet<Predicate>>>,
+    tables: RefCell<Tables>,
+    max_depth: usize,
+    /// Whether a branch was cut off by the depth or pass limit
+    truncated: Cell<bool>,
 }
 
 impl Default for ReasoningEngine {
@@ -55,119 +110,717 @@ fn default() -> Self {
 
 impl ReasoningEngine {
     pub fn new() -> Self {
-        Self { clauses: vec![] }
+        Self {
+            clauses: vec![],
+            tabled: HashSet::new(),
+            recursive: RefCell::new(None),
+            tables: RefCell::new(Tables::default()),
+            max_depth: DEFAULT_MAX_DEPTH,
+            truncated: Cell::new(false),
+        }
     }
 
     /// Add a fact to the knowledge base
     pub fn add_fact(&mut self, head: Term, confidence: f32) {
-        self.clauses.push(Clause {
+        self.add_rule(head, vec![], confidence);
+    }
+
+    /// Add a rule to the knowledge base
+    pub fn add_rule(&mut self, head: Term, body: Vec<Term>, confidence: f32) {
+        self.add_clause(Clause {
             head,
-            body: vec![],
+            body,
             confidence,
+            source: None,
         });
     }
 
-    /// Add a rule to the knowledge base
-    pub fn add_rule(&mut self, head: Term, body: Vec<Term>, confidence: f32) {
-        self.clauses.push(Clause { head, body, confidence });
+    pub fn add_clause(&mut self, clause: Clause) {
+        self.clauses.push(clause);
+        self.changed();
+    }
+
+    /// An engine with the [`facts::PRELUDE`], the knowledge base files in
+    /// the data directory and the facts derived from every stored solution
+    pub async fn from_storage(storage: &Storage) -> Result<Self> {
+        let mut engine = Self::new();
+        engine
+            .load(facts::PRELUDE)
+            .map_err(|e| anyhow::anyhow!(e.render("prelude", facts::PRELUDE)))?;
+        engine.load_dir(&crate::dirs::data_dir());
+        engine.sync_storage(storage).await?;
+        Ok(engine)
+    }
+
+    /// Derive facts from every stored solution, replacing any derived
+    /// before and dropping those of solutions no longer stored; returns how
+    /// many solutions contributed
+    pub async fn sync_storage(&mut self, storage: &Storage) -> Result<usize> {
+        let solutions = storage.all_solutions().await?;
+        let ids: HashSet<&str> = solutions.iter().map(|s| s.id.as_str()).collect();
+        self.clauses
+            .retain(|c| c.source.as_deref().is_none_or(|id| ids.contains(id)));
+        self.changed();
+        for solution in &solutions {
+            let relations = storage.relations(&solution.id).await?;
+            self.sync_solution(solution, &relations);
+        }
+        Ok(solutions.len())
+    }
+
+    /// Replace the facts derived from a solution with ones matching its
+    /// current state
+    pub fn sync_solution(&mut self, solution: &Solution, relations: &[ProblemRelation]) {
+        self.clauses.retain(|c| c.source.as_deref() != Some(solution.id.as_str()));
+        self.clauses.extend(facts::solution_facts(solution, relations));
+        self.changed();
+    }
+
+    /// Memoize a predicate's answers even if it isn't recursive
+    pub fn table(&mut self, name: &str, arity: usize) {
+        self.tabled.insert((name.to_string(), arity));
+        self.changed();
+    }
+
+    /// Add the clauses and table declarations of a knowledge base in the
+    /// [`parser`] syntax, returning how many clauses it had. Nothing is added
+    /// if it doesn't parse.
+    pub fn load(&mut self, source: &str) -> Result<usize, parser::ParseError> {
+        let items = parser::parse(source)?;
+        let mut clauses = 0;
+        for item in items {
+            match item {
+                parser::Item::Clause(clause) => {
+                    self.add_clause(clause);
+                    clauses += 1;
+                }
+                parser::Item::Table((name, arity)) => self.table(&name, arity),
+            }
+        }
+        Ok(clauses)
+    }
+
+    /// [`Self::load`] a file, with parse errors pointing into it
+    pub fn load_file(&mut self, path: &Path) -> Result<usize> {
+        let source = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        self.load(&source)
+            .map_err(|e| anyhow::anyhow!(e.render(&path.display().to_string(), &source)))
+    }
+
+    /// Load every knowledge base file in a directory, in name order; broken
+    /// files are skipped with a warning
+    pub fn load_dir(&mut self, dir: &Path) -> usize {
+        let mut paths: Vec<_> = match std::fs::read_dir(dir) {
+            Ok(entries) => entries.filter_map(|e| e.ok().map(|e| e.path())).collect(),
+            Err(_) => return 0,
+        };
+        paths.retain(|p| is_knowledge_file(p));
+        paths.sort();
+
+        let mut clauses = 0;
+        for path in paths {
+            match self.load_file(&path) {
+                Ok(n) => clauses += n,
+                Err(e) => tracing::warn!("Skipping knowledge base file: {:#}", e),
+            }
+        }
+        clauses
+    }
+
+    /// The clauses, in the order they're tried
+    pub fn clauses(&self) -> &[Clause] {
+        &self.clauses
+    }
+
+    /// Remove the clause at `index`
+    pub fn retract(&mut self, index: usize) -> Option<Clause> {
+        if index >= self.clauses.len() {
+            return None;
+        }
+        let clause = self.clauses.remove(index);
+        self.changed();
+        Some(clause)
+    }
+
+    /// Remove every clause whose head unifies with `head`, returning how
+    /// many there were
+    pub fn retract_all(&mut self, head: &Term) -> usize {
+        let before = self.clauses.len();
+        self.clauses
+            .retain(|c| kanren::unify(head, &rename(&c.head, 0), &Substitution::new()).is_none());
+        self.changed();
+        before - self.clauses.len()
+    }
+
+    /// Change the confidence of the clause at `index`; false if there's no
+    /// such clause
+    pub fn set_confidence(&mut self, index: usize, confidence: f32) -> bool {
+        let Some(clause) = self.clauses.get_mut(index) else {
+            return false;
+        };
+        clause.confidence = confidence;
+        self.changed();
+        true
+    }
+
+    /// The predicates declared tabled, in order
+    pub fn tabled(&self) -> Vec<Predicate> {
+        let tabled: BTreeSet<_> = self.tabled.iter().cloned().collect();
+        tabled.into_iter().collect()
+    }
+
+    /// The predicates with clauses, in order
+    pub fn predicates(&self) -> Vec<Predicate> {
+        let predicates: BTreeSet<_> =
+            self.clauses.iter().filter_map(|c| predicate(&c.head)).collect();
+        predicates.into_iter().collect()
+    }
+
+    pub fn set_max_depth(&mut self, depth: usize) {
+        self.max_depth = depth;
+    }
+
+    /// Whether the last run was cut short by the depth limit, or a table
+    /// didn't reach a fixpoint, so answers may be missing
+    pub fn truncated(&self) -> bool {
+        self.truncated.get()
+    }
+
+    /// Forget everything derived from the clauses
+    fn changed(&mut self) {
+        *self.recursive.get_mut() = None;
+        self.tables.get_mut().clear();
+    }
+
+    fn is_tabled(&self, term: &Term) -> bool {
+        let Some(predicate) = predicate(term) else {
+            return false;
+        };
+        if self.tabled.contains(&predicate) {
+            return true;
+        }
+        self.recursive
+            .borrow_mut()
+            .get_or_insert_with(|| recursive_predicates(&self.clauses))
+            .contains(&predicate)
     }
 
     /// Unify two terms, returning a substitution if successful
     pub fn unify(&self, t1: &Term, t2: &Term, subst: &Substitution) -> Option<Substitution> {
-        let t1 = self.walk(t1, subst);
-        let t2 = self.walk(t2, subst);
-
-        match (&t1, &t2) {
-            (Term::Var(v1), Term::Var(v2)) if v1 == v2 => Some(subst.clone()),
-            (Term::Var(v), t) | (t, Term::Var(v)) => {
-                let mut new_subst = subst.clone();
-                new_subst.insert(v.clone(), t.clone());
-                Some(new_subst)
+        kanren::unify(t1, t2, subst)
+    }
+
+    /// The goal that `term` holds
+    ///
+    /// `true`, `fail`, `A = B`, `(A, B)`, `(A ; B)`, `(C -> T)`,
+    /// `(C -> T ; E)`, `\+ A` and `A \= B` (as `\+ A = B`) are built in, as
+    /// are the identity checks `==` and `\==`, the constraints `A =/= B`,
+    /// `X is Expr` and the comparisons `<`, `=<`, `>`, `>=`, `=:=` and `=\=`;
+    /// anything else is resolved against the clauses for its predicate.
+    pub fn goal<'a>(&'a self, term: &Term) -> Goal<'a> {
+        self.goal_at(term, 0)
+    }
+
+    /// The goal for a term nested `depth` clauses deep
+    fn goal_at<'a>(&'a self, term: &Term, depth: usize) -> Goal<'a> {
+        let goal = match term {
+            Term::Atom(a) if a == "true" => return kanren::succeed(),
+            Term::Atom(a) if a == "fail" || a == "false" => return kanren::fail(),
+            Term::Compound(op, args) if args.len() == 1 && (op == "\\+" || op == "not") => {
+                self.negation(&args[0], depth)
             }
-            (Term::Atom(a1), Term::Atom(a2)) if a1 == a2 => Some(subst.clone()),
-            (Term::Compound(n1, args1), Term::Compound(n2, args2))
-                if n1 == n2 && args1.len() == args2.len() =>
-            {
-                let mut current_subst = subst.clone();
-                for (a1, a2) in args1.iter().zip(args2.iter()) {
-                    current_subst = self.unify(a1, a2, &current_subst)?;
+            Term::Compound(op, args) if args.len() == 2 => match op.as_str() {
+                "=" => kanren::eq(args[0].clone(), args[1].clone()),
+                "\\=" => self.negation(&Term::Compound("=".to_string(), args.clone()), depth),
+                "==" | "\\==" => identical(args[0].clone(), args[1].clone(), op == "=="),
+                "->" => {
+                    return kanren::ifte(
+                        self.goal_at(&args[0], depth),
+                        self.goal_at(&args[1], depth),
+                        kanren::fail(),
+                    )
                 }
-                Some(current_subst)
-            }
-            (Term::List(l1), Term::List(l2)) if l1.len() == l2.len() => {
-                let mut current_subst = subst.clone();
-                for (t1, t2) in l1.iter().zip(l2.iter()) {
-                    current_subst = self.unify(t1, t2, &current_subst)?;
+                ";" => {
+                    if let Term::Compound(arrow, branch) = &args[0] {
+                        if arrow == "->" && branch.len() == 2 {
+                            return kanren::ifte(
+                                self.goal_at(&branch[0], depth),
+                                self.goal_at(&branch[1], depth),
+                                self.goal_at(&args[1], depth),
+                            );
+                        }
+                    }
+                    return kanren::disj(
+                        self.goal_at(&args[0], depth),
+                        self.goal_at(&args[1], depth),
+                    );
+                }
+                "," => {
+                    return kanren::conj(
+                        self.goal_at(&args[0], depth),
+                        self.goal_at(&args[1], depth),
+                    )
                 }
-                Some(current_subst)
+                _ => match Constraint::from_term(term) {
+                    Some(constraint) => kanren::constrain(constraint),
+                    None => return self.woken(self.call(term, depth)),
+                },
+            },
+            _ => return self.woken(self.call(term, depth)),
+        };
+        // A built-in is a leaf of the proof
+        let term = term.clone();
+        self.woken(Rc::new(move |mut state: State| {
+            state.record(Event::BuiltIn(term.clone()));
+            goal(state)
+        }))
+    }
+
+    /// `\+ goal`: succeeds, binding nothing, when the goal has no answer
+    /// under the current bindings. It waits until its named variables are
+    /// bound, so `\+ blacklisted(M), loaded(M)` checks each loaded `M`;
+    /// variables starting with `_` are local to the negation. If they never
+    /// are, the negation is left in the answer's residual. A negated tabled
+    /// predicate that is still being computed only has its answers so far,
+    /// so keep negation out of recursive cycles.
+    fn negation<'a>(&'a self, goal: &Term, depth: usize) -> Goal<'a> {
+        let watched: Vec<Term> = variables(goal)
+            .into_iter()
+            .filter(|v| !v.starts_with('_'))
+            .map(Term::Var)
+            .collect();
+        kanren::constrain(Constraint::Not {
+            goal: goal.clone(),
+            watched,
+            depth,
+        })
+    }
+
+    /// A goal followed by running any negation whose variables it bound
+    fn woken<'a>(&'a self, goal: Goal<'a>) -> Goal<'a> {
+        Rc::new(move |state: State| {
+            kanren::bind(goal(state), Rc::new(|state: State| self.wake(state)))
+        })
+    }
+
+    /// Run the waiting negations whose variables are now ground
+    fn wake<'a>(&'a self, mut state: State) -> Stream<'a> {
+        let ready = |c: &Constraint, state: &State| match c {
+            Constraint::Not { watched, .. } => watched
+                .iter()
+                .all(|v| constraint::is_ground(&kanren::walk_star(v, &state.subst))),
+            _ => false,
+        };
+        if !state.constraints.iter().any(|c| ready(c, &state)) {
+            return Stream::unit(state);
+        }
+        let (negations, rest): (Vec<_>, Vec<_>) = std::mem::take(&mut state.constraints)
+            .into_iter()
+            .partition(|c| ready(c, &state));
+        state.constraints = rest;
+        for negation in negations {
+            let Constraint::Not { goal, depth, .. } = negation else {
+                unreachable!("only negations are ready");
+            };
+            if self.goal_at(&goal, depth)(state.clone()).next().is_some() {
+                return Stream::Empty;
             }
-            _ => None,
         }
+        Stream::unit(state)
     }
 
-    /// Walk a term through a substitution to resolve variables
-    fn walk(&self, term: &Term, subst: &Substitution) -> Term {
-        match term {
-            Term::Var(v) => {
-                if let Some(t) = subst.get(v) {
-                    self.walk(t, subst)
-                } else {
-                    term.clone()
+    fn call<'a>(&'a self, term: &Term, depth: usize) -> Goal<'a> {
+        if depth >= self.max_depth {
+            self.truncated.set(true);
+            return kanren::fail();
+        }
+        if self.is_tabled(term) {
+            return self.tabled_call(term.clone());
+        }
+        self.clauses_goal(term.clone(), depth)
+    }
+
+    /// Try every clause of the term's predicate; delayed so recursive
+    /// predicates are only expanded as answers are pulled
+    fn clauses_goal<'a>(&'a self, term: Term, depth: usize) -> Goal<'a> {
+        kanren::delay(move || {
+            kanren::disj_all(
+                self.clauses
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, c)| same_predicate(&c.head, &term))
+                    .map(|(i, _)| self.resolve(i, term.clone(), depth))
+                    .collect(),
+            )
+        })
+    }
+
+    /// One use of the clause at `index`: its variables renamed apart from
+    /// every other use, the head unified with the goal, then the body
+    fn resolve<'a>(&'a self, index: usize, goal: Term, depth: usize) -> Goal<'a> {
+        let clause = &self.clauses[index];
+        Rc::new(move |mut state: State| {
+            state.next_var += 1;
+            let suffix = state.next_var;
+            state.confidence *= clause.confidence;
+            state.record(Event::Enter {
+                goal: goal.clone(),
+                clause: index,
+                suffix,
+            });
+
+            let mut goals = vec![kanren::eq(goal.clone(), rename(&clause.head, suffix))];
+            goals.extend(
+                clause
+                    .body
+                    .iter()
+                    .map(|t| self.goal_at(&rename(t, suffix), depth + 1)),
+            );
+            goals.push(Rc::new(|mut state: State| {
+                state.record(Event::Exit);
+                Stream::unit(state)
+            }));
+            kanren::conj_all(goals)(state)
+        })
+    }
+
+    /// Unify the call with each answer in its table, restoring the
+    /// answer's residual constraints
+    fn tabled_call<'a>(&'a self, term: Term) -> Goal<'a> {
+        Rc::new(move |state: State| {
+            let call = kanren::walk_star(&term, &state.subst);
+            let mut states = vec![];
+            for answer in self.table_answers(&call) {
+                let mut state = state.clone();
+                state.next_var += 1;
+                let suffix = state.next_var;
+                let Some(mut state) = state.unify(&call, &rename(&answer.term, suffix)) else {
+                    continue;
+                };
+                state.constraints.extend(
+                    answer
+                        .residual
+                        .iter()
+                        .map(|c| c.map(|t| rename(t, suffix))),
+                );
+                if let Some(mut state) = constraint::solve(state) {
+                    state.confidence *= answer.confidence;
+                    state.record(Event::Tabled(answer.proof));
+                    states.push(state);
                 }
             }
-            _ => term.clone(),
-        }
+            states
+                .into_iter()
+                .rev()
+                .fold(Stream::Empty, |rest, state| Stream::Cons(state, Box::new(rest)))
+        })
     }
 
-    /// Query the knowledge base for solutions
-    pub fn query(&self, goal: &Term) -> Vec<(Substitution, f32)> {
-        let mut results = vec![];
-
-        for clause in &self.clauses {
-            if let Some(subst) = self.unify(goal, &clause.head, &HashMap::new()) {
-                if clause.body.is_empty() {
-                    // Fact - direct match
-                    results.push((subst, clause.confidence));
-                } else {
-                    // Rule - need to prove body
-                    if let Some((final_subst, body_confidence)) = self.prove_body(&clause.body, &subst) {
-                        let combined_confidence = clause.confidence * body_confidence;
-                        results.push((final_subst, combined_confidence));
-                    }
+    /// The answers to a tabled call, computing its table if need be
+    fn table_answers(&self, call: &Term) -> Vec<table::Answer> {
+        // Evaluate the call with its variables named apart from the clauses'
+        let call = kanren::reify(call, &Substitution::new());
+        let key = table::variant_key(&call);
+
+        let lookup = self.tables.borrow_mut().lookup(&key);
+        match lookup {
+            Lookup::Answers { answers, truncated } => {
+                if truncated {
+                    self.truncated.set(true);
                 }
+                answers
             }
+            Lookup::Lead => {
+                while self.tables.borrow_mut().next_pass() {
+                    self.evaluate(&key, &call);
+                }
+                let mut tables = self.tables.borrow_mut();
+                if tables.exhausted() {
+                    self.truncated.set(true);
+                }
+                tables.finish();
+                tables.answers(&key)
+            }
+            Lookup::Evaluate => {
+                self.evaluate(&key, &call);
+                self.tables.borrow().answers(&key)
+            }
+        }
+    }
+
+    /// One pass over a tabled call's clauses; recursive calls within it see
+    /// the answers found so far
+    fn evaluate(&self, key: &str, call: &Term) {
+        self.tables.borrow_mut().enter(key);
+        let found: Vec<table::Answer> = self
+            .clauses_goal(call.clone(), 0)(State::default())
+            .map(|state| {
+                let mut names = vec![];
+                let term = kanren::reify_with(call, &state.subst, &mut names);
+                let residual = state
+                    .constraints
+                    .iter()
+                    .map(|c| c.map(|t| kanren::reify_with(t, &state.subst, &mut names)))
+                    .collect();
+                let trace = state.trace.as_ref();
+                let proof = proof::proofs(trace, &state.subst, &self.clauses, &mut names)
+                    .pop()
+                    .expect("a clause answered the call");
+                table::Answer {
+                    term,
+                    residual,
+                    confidence: state.confidence,
+                    proof,
+                }
+            })
+            .collect();
+
+        let mut tables = self.tables.borrow_mut();
+        for answer in found {
+            tables.add(key, answer);
         }
+    }
+
+    /// Up to `n` answers (all for `None`) in search order, with their
+    /// proofs
+    pub fn prove(&self, n: Option<usize>, goal: &Term) -> Vec<Answer> {
+        self.truncated.set(false);
+        let vars = variables(goal);
+        let query = Term::List(vars.iter().map(|v| Term::Var(v.clone())).collect());
+        self.goal(goal)(State::default())
+            .take(n.unwrap_or(usize::MAX))
+            .map(|state| {
+                let mut names = vec![];
+                let Term::List(values) = kanren::reify_with(&query, &state.subst, &mut names)
+                else {
+                    unreachable!("a list reifies to a list");
+                };
+                let residual = state
+                    .constraints
+                    .iter()
+                    .map(|c| kanren::reify_with(&c.to_term(), &state.subst, &mut names))
+                    .collect();
+                Answer {
+                    bindings: vars.iter().cloned().zip(values).collect(),
+                    confidence: state.confidence,
+                    residual,
+                    proofs: proof::proofs(
+                        state.trace.as_ref(),
+                        &state.subst,
+                        &self.clauses,
+                        &mut names,
+                    ),
+                }
+            })
+            .collect()
+    }
+
+    /// Every answer with its proofs, most confident first; doesn't return
+    /// if there are infinitely many
+    pub fn explain(&self, goal: &Term) -> Vec<Answer> {
+        let mut answers = self.prove(None, goal);
+        answers.sort_by(|a, b| b.confidence.total_cmp(&a.confidence));
+        answers
+    }
+
+    /// The most confident proof that `command` fixes `problem`, going by the
+    /// `fix/2` rules of the [`facts::PRELUDE`]
+    pub fn explain_fix(&self, problem: &str, command: &str) -> Option<Rc<Proof>> {
+        let goal = compound(
+            "fix",
+            vec![atom(&crate::storage::normalize_problem(problem)), atom(command)],
+        );
+        self.explain(&goal).into_iter().next()?.proofs.pop()
+    }
+
+    /// Up to `n` answers (all for `None`) in search order, each binding the
+    /// goal's variables, with the product of the confidences of the clauses
+    /// used
+    pub fn run(&self, n: Option<usize>, goal: &Term) -> Vec<(Substitution, f32)> {
+        self.prove(n, goal)
+            .into_iter()
+            .map(|answer| (answer.bindings, answer.confidence))
+            .collect()
+    }
 
-        // Sort by confidence
-        results.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
+    /// Every answer; doesn't return if there are infinitely many
+    pub fn run_star(&self, goal: &Term) -> Vec<(Substitution, f32)> {
+        self.run(None, goal)
+    }
+
+    /// Query the knowledge base for solutions, most confident first
+    pub fn query(&self, goal: &Term) -> Vec<(Substitution, f32)> {
+        let mut results = self.run_star(goal);
+        results.sort_by(|a, b| b.1.total_cmp(&a.1));
         results
     }
+}
+
+/// Whether a clause head defines the predicate a goal calls
+fn same_predicate(head: &Term, goal: &Term) -> bool {
+    match (head, goal) {
+        (Term::Compound(f, xs), Term::Compound(g, ys)) => f == g && xs.len() == ys.len(),
+        (Term::Atom(a), Term::Atom(b)) => a == b,
+        _ => false,
+    }
+}
+
+/// `a == b`, or `a \== b` unless `same`: whether the terms are already
+/// identical under the current bindings; binds nothing
+fn identical<'a>(a: Term, b: Term, same: bool) -> Goal<'a> {
+    Rc::new(move |state: State| {
+        let identical = kanren::walk_star(&a, &state.subst) == kanren::walk_star(&b, &state.subst);
+        if identical == same {
+            Stream::unit(state)
+        } else {
+            Stream::Empty
+        }
+    })
+}
+
+/// The predicate a goal calls
+pub fn predicate(term: &Term) -> Option<Predicate> {
+    match term {
+        Term::Compound(name, args) => Some((name.clone(), args.len())),
+        Term::Atom(name) => Some((name.clone(), 0)),
+        _ => None,
+    }
+}
+
+/// Predicates a clause body calls, looking inside conjunctions,
+/// disjunctions and if-then-else
+fn called_predicates(goal: &Term, called: &mut Vec<Predicate>) {
+    match goal {
+        Term::Compound(op, args) if args.len() == 2 && matches!(op.as_str(), "," | ";" | "->") => {
+            args.iter().for_each(|a| called_predicates(a, called))
+        }
+        Term::Compound(op, args) if args.len() == 1 && (op == "\\+" || op == "not") => {
+            called_predicates(&args[0], called)
+        }
+        goal => called.extend(predicate(goal)),
+    }
+}
+
+/// Predicates that can call themselves, directly or through others
+fn recursive_predicates(clauses: &[Clause]) -> HashSet<Predicate> {
+    let mut calls: HashMap<Predicate, Vec<Predicate>> = HashMap::new();
+    for clause in clauses.iter().filter(|c| !c.body.is_empty()) {
+        let Some(head) = predicate(&clause.head) else {
+            continue;
+        };
+        let callees = calls.entry(head).or_default();
+        for goal in &clause.body {
+            called_predicates(goal, callees);
+        }
+    }
 
-    /// Prove all goals in a body
-    fn prove_body(&self, body: &[Term], subst: &Substitution) -> Option<(Substitution, f32)> {
-        let mut current_subst = subst.clone();
-        let mut total_confidence = 1.0f32;
+    calls
+        .keys()
+        .filter(|&start| {
+            let mut seen = HashSet::new();
+            let mut stack: Vec<&Predicate> = calls[start].iter().collect();
+            while let Some(p) = stack.pop() {
+                if p == start {
+                    return true;
+                }
+                if seen.insert(p) {
+                    stack.extend(calls.get(p).into_iter().flatten());
+                }
+            }
+            false
+        })
+        .cloned()
+        .collect()
+}
 
-        for goal in body {
-            let resolved_goal = self.walk(goal, &current_subst);
-            let solutions = self.query(&resolved_goal);
+/// A term with every variable `X` renamed `X#suffix`
+fn rename(term: &Term, suffix: usize) -> Term {
+    match term {
+        Term::Var(v) => Term::Var(format!("{}#{}", v, suffix)),
+        Term::Compound(name, args) => {
+            Term::Compound(name.clone(), args.iter().map(|a| rename(a, suffix)).collect())
+        }
+        Term::List(items) => Term::List(items.iter().map(|t| rename(t, suffix)).collect()),
+        Term::Atom(_) => term.clone(),
+    }
+}
 
-            if let Some((new_subst, conf)) = solutions.into_iter().next() {
-                current_subst.extend(new_subst);
-                total_confidence *= conf;
-            } else {
-                return None;
+/// The variables of a term, in order of first appearance
+pub fn variables(term: &Term) -> Vec<String> {
+    fn collect(term: &Term, vars: &mut Vec<String>) {
+        match term {
+            Term::Var(v) if !vars.contains(v) => vars.push(v.clone()),
+            Term::Compound(_, args) | Term::List(args) => {
+                args.iter().for_each(|a| collect(a, vars))
             }
+            _ => {}
         }
+    }
+    let mut vars = vec![];
+    collect(term, &mut vars);
+    vars
+}
+
+fn is_knowledge_file(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|e| KNOWLEDGE_EXTENSIONS.iter().any(|k| e == *k))
+}
+
+/// Reasoning subcommands
+#[derive(Debug, Clone)]
+pub enum ReasonAction {
+    /// Explore the knowledge base interactively
+    Repl,
+    Load { path: String },
+}
+
+pub async fn handle(action: ReasonAction, storage: &Storage) -> Result<()> {
+    match action {
+        ReasonAction::Repl => repl::run(storage).await,
+        ReasonAction::Load { path } => install(Path::new(&path)),
+    }
+}
 
-        Some((current_subst, total_confidence))
+/// Check a knowledge base file and copy it into the data directory, where
+/// every engine loads it from
+fn install(path: &Path) -> Result<()> {
+    if !is_knowledge_file(path) {
+        bail!("{} isn't a knowledge base file (.pl or .kb)", path.display());
     }
+    let mut engine = ReasoningEngine::new();
+    let clauses = engine.load_file(path)?;
+
+    let dir = crate::dirs::data_dir();
+    std::fs::create_dir_all(&dir)?;
+    let target = dir.join(path.file_name().context("Knowledge base path has no file name")?);
+    let replaced = target.exists();
+    if !replaced || std::fs::canonicalize(path)? != std::fs::canonicalize(&target)? {
+        std::fs::copy(path, &target)
+            .with_context(|| format!("Failed to copy to {}", target.display()))?;
+    }
+
+    println!(
+        "Loaded {} clauses for {} predicates from {}",
+        clauses,
+        engine.predicates().len(),
+        path.display()
+    );
+    println!(
+        "{} {}",
+        if replaced { "Replaced" } else { "Installed as" },
+        target.display()
+    );
+    Ok(())
 }
 
 /// Learn a new solution and add it to the knowledge base
+///
+/// A near duplicate of an existing solution strengthens that one instead.
 pub async fn learn(
     category: &str,
     solution: Option<String>,
+    problem: Option<String>,
     storage: &Storage,
 ) -> Result<()> {
     let solution_text = match solution {
@@ -186,14 +839,15 @@ pub async fn learn(
 
     // Parse solution and extract problem→solution relationship
     // TODO: Use SLM to extract structured data from solution text
+    let problem = problem.unwrap_or_else(|| first_line(&solution_text));
+    let commands = extract_commands(&solution_text);
 
-    // Store in ArangoDB
     let solution = crate::storage::Solution {
         id: uuid::Uuid::new_v4().to_string(),
         category: category.to_string(),
-        problem: String::new(), // TODO: Extract from text
+        problem,
         solution: solution_text,
-        commands: vec![], // TODO: Extract commands
+        commands,
         tags: vec![category.to_string()],
         success_count: 0,
         failure_count: 0,
@@ -202,12 +856,42 @@ pub async fn learn(
         updated_at: chrono::Utc::now(),
     };
 
-    storage.store_solution(&solution).await?;
-
-    println!("Learned solution: {}", solution.id);
+    match storage.learn(solution).await? {
+        Learned::New(id) => println!("Learned solution: {}", id),
+        Learned::Strengthened { id, similarity } => println!(
+            "Strengthened existing solution {} ({:.0}% similar)",
+            id,
+            similarity * 100.0
+        ),
+    }
     Ok(())
 }
 
+/// First non-blank line, without Markdown heading marks
+fn first_line(text: &str) -> String {
+    text.lines()
+        .map(|l| l.trim().trim_start_matches('#').trim())
+        .find(|l| !l.is_empty())
+        .unwrap_or_default()
+        .to_string()
+}
+
+/// Commands in a solution: `$ `-prefixed lines and lines of fenced code blocks
+fn extract_commands(text: &str) -> Vec<String> {
+    let mut commands = vec![];
+    let mut fenced = false;
+    for line in text.lines().map(str::trim) {
+        if line.starts_with("```") {
+            fenced = !fenced;
+        } else if let Some(command) = line.strip_prefix("$ ") {
+            commands.push(command.trim().to_string());
+        } else if fenced && !line.is_empty() && !line.starts_with('#') {
+            commands.push(line.to_string());
+        }
+    }
+    commands
+}
+
 // Helper to create terms
 pub fn atom(s: &str) -> Term {
     Term::Atom(s.to_string())
@@ -264,4 +948,507 @@ fn test_query() {
         assert_eq!(results.len(), 2);
         assert!(results[0].1 >= results[1].1); // Sorted by confidence
     }
+
+    /// Loaded modules, and which driver each one belongs to
+    fn driver_engine() -> ReasoningEngine {
+        let mut engine = ReasoningEngine::new();
+        engine.add_fact(compound("loaded", vec![atom("nouveau")]), 1.0);
+        engine.add_fact(compound("loaded", vec![atom("iwlwifi")]), 1.0);
+        engine.add_fact(compound("loaded", vec![atom("nvidia")]), 1.0);
+        engine.add_fact(compound("driver_of", vec![atom("nvidia"), atom("gpu")]), 0.9);
+        engine.add_fact(compound("driver_of", vec![atom("nouveau"), atom("gpu")]), 0.8);
+        engine.add_fact(compound("driver_of", vec![atom("iwlwifi"), atom("wifi")]), 1.0);
+        // has_driver(Device, Module) :- loaded(Module), driver_of(Module, Device).
+        engine.add_rule(
+            compound("has_driver", vec![var("Device"), var("Module")]),
+            vec![
+                compound("loaded", vec![var("Module")]),
+                compound("driver_of", vec![var("Module"), var("Device")]),
+            ],
+            1.0,
+        );
+        engine
+    }
+
+    #[test]
+    fn test_rules_backtrack_into_every_answer() {
+        let engine = driver_engine();
+
+        // The first loaded module isn't a wifi driver; the search backtracks
+        let wifi = engine.query(&compound("has_driver", vec![atom("wifi"), var("M")]));
+        assert_eq!(wifi.len(), 1);
+        assert_eq!(wifi[0].0.get("M"), Some(&atom("iwlwifi")));
+
+        // Both GPU drivers, with their fact's confidence
+        let gpu = engine.query(&compound("has_driver", vec![atom("gpu"), var("M")]));
+        let found: Vec<_> = gpu.iter().map(|(s, c)| (s["M"].clone(), *c)).collect();
+        assert_eq!(found, vec![(atom("nvidia"), 0.9), (atom("nouveau"), 0.8)]);
+
+        // run stops after n answers, in search order
+        let first = engine.run(Some(1), &compound("has_driver", vec![var("D"), var("M")]));
+        assert_eq!(first.len(), 1);
+        assert_eq!(first[0].0["M"], atom("nouveau"));
+        assert_eq!(engine.run_star(&compound("has_driver", vec![var("D"), var("M")])).len(), 3);
+    }
+
+    #[test]
+    fn test_clause_variables_are_renamed_apart() {
+        let mut engine = ReasoningEngine::new();
+        for (a, b) in [("nginx", "network"), ("network", "dbus"), ("dbus", "systemd")] {
+            engine.add_fact(compound("requires", vec![atom(a), atom(b)]), 1.0);
+        }
+        // needs(X, Y) :- requires(X, Y) ; requires(X, Z), needs(Z, Y).
+        // Every use of the clause has its own X, Y and Z
+        engine.add_rule(
+            compound("needs", vec![var("X"), var("Y")]),
+            vec![compound(
+                ";",
+                vec![
+                    compound("requires", vec![var("X"), var("Y")]),
+                    compound(
+                        ",",
+                        vec![
+                            compound("requires", vec![var("X"), var("Z")]),
+                            compound("needs", vec![var("Z"), var("Y")]),
+                        ],
+                    ),
+                ],
+            )],
+            1.0,
+        );
+
+        let needs = engine.query(&compound("needs", vec![atom("nginx"), var("Y")]));
+        let mut found: Vec<_> = needs.iter().map(|(s, _)| s["Y"].clone()).collect();
+        found.sort_by_key(|t| format!("{:?}", t));
+        assert_eq!(found, vec![atom("dbus"), atom("network"), atom("systemd")]);
+
+        // The query's own variable names don't clash with the clause's
+        let needs = engine.query(&compound("needs", vec![var("Z"), atom("systemd")]));
+        assert_eq!(needs.len(), 3);
+        // Unbound answers reify to fresh names
+        let any = engine.run(Some(1), &compound("=", vec![var("A"), var("B")]));
+        assert_eq!(any[0].0["A"], var("_0"));
+    }
+
+    #[test]
+    fn test_left_recursion_is_tabled() {
+        let mut engine = ReasoningEngine::new();
+        for (a, b, confidence) in [
+            ("app", "db", 0.9),
+            ("db", "storage", 0.8),
+            ("app", "storage", 0.5),
+            ("storage", "app", 1.0),
+        ] {
+            engine.add_fact(compound("requires", vec![atom(a), atom(b)]), confidence);
+        }
+        // depends_on(A, C) :- depends_on(A, B), depends_on(B, C).
+        engine.add_rule(
+            compound("depends_on", vec![var("A"), var("C")]),
+            vec![
+                compound("depends_on", vec![var("A"), var("B")]),
+                compound("depends_on", vec![var("B"), var("C")]),
+            ],
+            1.0,
+        );
+        engine.add_rule(
+            compound("depends_on", vec![var("A"), var("B")]),
+            vec![compound("requires", vec![var("A"), var("B")])],
+            1.0,
+        );
+
+        let found: HashMap<String, f32> = engine
+            .query(&compound("depends_on", vec![atom("app"), var("X")]))
+            .into_iter()
+            .map(|(s, confidence)| (format!("{:?}", s["X"]), confidence))
+            .collect();
+        assert_eq!(found.len(), 3);
+        assert!(!engine.truncated());
+        // The best path, not the direct 0.5 fact or a product of every path
+        assert!((found["Atom(\"storage\")"] - 0.72).abs() < 1e-6);
+        assert!((found["Atom(\"db\")"] - 0.9).abs() < 1e-6);
+        assert!((found["Atom(\"app\")"] - 0.72).abs() < 1e-6);
+
+        // Tables are reused, and rebuilt when the clauses change
+        let all = engine.query(&compound("depends_on", vec![var("P"), var("Q")]));
+        assert_eq!(all.len(), 9);
+        engine.add_fact(compound("requires", vec![atom("db"), atom("kernel")]), 1.0);
+        let all = engine.query(&compound("depends_on", vec![var("P"), var("Q")]));
+        assert_eq!(all.len(), 12);
+    }
+
+    #[test]
+    fn test_mutual_recursion_and_limits() {
+        let mut engine = ReasoningEngine::new();
+        engine.add_fact(compound("edge", vec![atom("a"), atom("b")]), 1.0);
+        engine.add_fact(compound("edge", vec![atom("b"), atom("c")]), 1.0);
+        // reach(X, Y) :- edge(X, Y) ; via(X, Z), edge(Z, Y).
+        // via(X, Y) :- reach(X, Y).
+        engine.add_rule(
+            compound("reach", vec![var("X"), var("Y")]),
+            vec![compound(
+                ";",
+                vec![
+                    compound("edge", vec![var("X"), var("Y")]),
+                    compound(
+                        ",",
+                        vec![
+                            compound("via", vec![var("X"), var("Z")]),
+                            compound("edge", vec![var("Z"), var("Y")]),
+                        ],
+                    ),
+                ],
+            )],
+            1.0,
+        );
+        engine.add_rule(
+            compound("via", vec![var("X"), var("Y")]),
+            vec![compound("reach", vec![var("X"), var("Y")])],
+            1.0,
+        );
+        assert_eq!(engine.query(&compound("reach", vec![var("X"), var("Y")])).len(), 3);
+
+        // A predicate with infinitely many answers stops at the pass limit
+        engine.add_fact(compound("nat", vec![atom("z")]), 1.0);
+        engine.add_rule(
+            compound("nat", vec![compound("s", vec![var("N")])]),
+            vec![compound("nat", vec![var("N")])],
+            1.0,
+        );
+        let nats = engine.run(Some(3), &compound("nat", vec![var("N")]));
+        assert_eq!(nats.len(), 3);
+        assert!(engine.truncated());
+        // Asking again reads the table, which remembers it is partial
+        let nats = engine.run(Some(3), &compound("nat", vec![var("N")]));
+        assert_eq!(nats.len(), 3);
+        assert!(engine.truncated());
+        // So does a table built on it
+        engine.add_rule(
+            compound("even", vec![var("N")]),
+            vec![compound("nat", vec![var("N")])],
+            1.0,
+        );
+        engine.table("even", 1);
+        engine.run(Some(1), &compound("nat", vec![var("N")]));
+        for _ in 0..2 {
+            assert!(!engine.run(Some(1), &compound("even", vec![var("N")])).is_empty());
+            assert!(engine.truncated());
+        }
+
+        // Plain calls stop at the depth limit
+        engine.add_fact(atom("p3"), 1.0);
+        engine.add_rule(atom("p2"), vec![atom("p3")], 1.0);
+        engine.add_rule(atom("p1"), vec![atom("p2")], 1.0);
+        engine.set_max_depth(2);
+        assert!(engine.query(&atom("p1")).is_empty());
+        assert!(engine.truncated());
+        engine.set_max_depth(DEFAULT_MAX_DEPTH);
+        assert_eq!(engine.query(&atom("p1")).len(), 1);
+        assert!(!engine.truncated());
+    }
+
+    #[test]
+    fn test_negation_and_constraints() {
+        let mut engine = ReasoningEngine::new();
+        engine.add_fact(compound("loaded", vec![atom("nvidia")]), 1.0);
+        engine.add_fact(compound("loaded", vec![atom("iwlwifi")]), 1.0);
+        engine.add_fact(compound("blacklisted", vec![atom("nouveau")]), 1.0);
+        engine.add_fact(compound("module_for", vec![atom("nvidia"), atom("nouveau")]), 1.0);
+        engine.add_fact(compound("module_for", vec![atom("iwlwifi"), atom("iwlmvm")]), 1.0);
+        // suggest_reload(D) :- loaded(D), module_for(D, M), \+ blacklisted(M).
+        engine.add_rule(
+            compound("suggest_reload", vec![var("D")]),
+            vec![
+                compound("loaded", vec![var("D")]),
+                compound("module_for", vec![var("D"), var("M")]),
+                compound("\\+", vec![compound("blacklisted", vec![var("M")])]),
+            ],
+            1.0,
+        );
+        let found = engine.query(&compound("suggest_reload", vec![var("D")]));
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].0["D"], atom("iwlwifi"));
+
+        engine.add_fact(compound("mem_pct", vec![atom("92.5")]), 1.0);
+        let solve = |goals: Vec<Term>| {
+            let goal = goals.into_iter().reduce(|a, b| compound(",", vec![a, b])).unwrap();
+            engine.run(None, &goal)
+        };
+
+        // A disequality waits for its variables and only lives on its branch
+        let x_neq_nvidia = compound("=/=", vec![var("X"), atom("nvidia")]);
+        let x_is_nvidia = compound("=", vec![var("X"), atom("nvidia")]);
+        let loaded_x = compound("loaded", vec![var("X")]);
+        let found = solve(vec![x_neq_nvidia.clone(), loaded_x.clone()]);
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].0["X"], atom("iwlwifi"));
+        assert_eq!(solve(vec![loaded_x, x_neq_nvidia.clone()]).len(), 1);
+        assert!(solve(vec![x_neq_nvidia.clone(), x_is_nvidia.clone()]).is_empty());
+        let either = compound(";", vec![x_neq_nvidia, atom("true")]);
+        assert_eq!(solve(vec![either, x_is_nvidia]).len(), 1);
+        assert!(solve(vec![compound("=/=", vec![var("X"), var("X")])]).is_empty());
+
+        // Comparisons over numeric atoms, in either order
+        let mem = compound("mem_pct", vec![var("M")]);
+        let over = |n: &str| compound(">", vec![var("M"), atom(n)]);
+        assert_eq!(solve(vec![mem.clone(), over("90")]).len(), 1);
+        assert_eq!(solve(vec![over("90"), mem.clone()]).len(), 1);
+        assert!(solve(vec![over("95"), mem.clone()]).is_empty());
+        assert!(solve(vec![compound(">", vec![atom("nginx"), atom("1")])]).is_empty());
+
+        // `is` computes a value as soon as its expression is ground
+        let headroom = compound("-", vec![atom("100"), var("M")]);
+        let found = solve(vec![compound("is", vec![var("F"), headroom]), mem]);
+        assert_eq!(found[0].0["F"], atom("7.5"));
+        let product = compound("*", vec![atom("2"), atom("21")]);
+        let found = solve(vec![compound("is", vec![var("X"), product])]);
+        assert_eq!(found[0].0["X"], atom("42"));
+    }
+
+    #[test]
+    fn test_negation_waits_for_its_variables() {
+        let mut engine = ReasoningEngine::new();
+        engine
+            .load(
+                "module_for(nvidia, nouveau).\n\
+                 module_for(iwlwifi, iwlmvm).\n\
+                 blacklisted(nouveau).\n",
+            )
+            .unwrap();
+        let prove = |goal: &str| engine.prove(None, &parser::parse_term(goal).unwrap());
+
+        // Checked once M is known, not up front against an unbound M
+        let found = prove("\\+ blacklisted(M), module_for(D, M)");
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].bindings["M"], atom("iwlmvm"));
+        assert!(found[0].residual.is_empty());
+
+        // Underscore variables are the negation's own
+        assert!(prove("\\+ module_for(nvidia, _M)").is_empty());
+        assert_eq!(prove("\\+ module_for(amdgpu, _M)").len(), 1);
+
+        // A negation that is never decided is reported, not assumed
+        let found = prove("\\+ blacklisted(M)");
+        assert_eq!(found.len(), 1);
+        let residual: Vec<String> = found[0].residual.iter().map(|t| t.to_string()).collect();
+        assert_eq!(residual, ["\\+ blacklisted(_0)"]);
+
+        // So are comparisons and `is` that never got their values
+        let found = prove("X > 10, Y is X + 1");
+        let residual: Vec<String> = found[0].residual.iter().map(|t| t.to_string()).collect();
+        assert_eq!(residual, ["_0 > 10", "_1 is _0 + 1"]);
+    }
+
+    #[test]
+    fn test_identity_and_if_then_else() {
+        let mut engine = ReasoningEngine::new();
+        engine
+            .load(include_str!("../../gitvisor/rules/cicd_rules.lgt"))
+            .unwrap();
+        engine
+            .load(
+                "find_unpinned_actions(web, [checkout, 'setup-node']).\n\
+                 find_unpinned_actions(docs, []).\n\
+                 pin_actions_to_sha(_, _).\n\
+                 size(web, 3).\n\
+                 size(docs, 0).\n\
+                 kind(R, K) :- size(R, N), (N > 0 -> K = code ; K = empty).\n",
+            )
+            .unwrap();
+        let prove = |goal: &str| engine.prove(None, &parser::parse_term(goal).unwrap());
+
+        // `Actions \== []` keeps the repo with actions to pin
+        let found = prove("auto_fix(Repo, unpinned_actions)");
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].bindings["Repo"], atom("web"));
+
+        // Identity compares without binding; `\=` is `\+ =`
+        assert_eq!(prove("X == X").len(), 1);
+        assert!(prove("X == Y").is_empty());
+        assert_eq!(prove("X \\== Y").len(), 1);
+        assert!(prove("X = a, X \\== a").is_empty());
+        assert!(prove("a \\= a").is_empty());
+        assert_eq!(prove("a \\= b").len(), 1);
+        assert!(prove("X \\= a, X = a").is_empty());
+
+        // If-then-else commits to the first answer of the condition
+        let found = prove("kind(web, K), kind(docs, E)");
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].bindings["K"], atom("code"));
+        assert_eq!(found[0].bindings["E"], atom("empty"));
+        assert_eq!(prove("(size(R, _) -> true)").len(), 1);
+        assert!(prove("(size(nope, _) -> true)").is_empty());
+    }
+
+    #[test]
+    fn test_tabled_answers_keep_their_constraints() {
+        let mut engine = ReasoningEngine::new();
+        engine.load(":- table big/1.\nbig(X) :- X > 10.\n").unwrap();
+        let query = |goal: &str| engine.run(None, &parser::parse_term(goal).unwrap());
+
+        // The table holds big(_0) with `_0 > 10` still pending
+        assert!(query("big(X), X = 5").is_empty());
+        assert_eq!(query("big(X), X = 50").len(), 1);
+        assert!(query("X = 5, big(X)").is_empty());
+        assert!(query("big(X), X = 7").is_empty());
+    }
+
+    #[test]
+    fn test_load_knowledge_base() {
+        let mut engine = ReasoningEngine::new();
+        let loaded = engine.load(
+            "0.9 :: requires(app, db).\n\
+             0.8 :: requires(db, storage).\n\
+             depends_on(A, B) :- requires(A, B).\n\
+             depends_on(A, C) :- depends_on(A, B), depends_on(B, C).\n\
+             at_risk(S) :- depends_on(S, storage), \\+ redundant(storage).\n",
+        );
+        assert_eq!(loaded, Ok(5));
+        // Sorted by confidence
+        let found = engine.query(&parser::parse_term("at_risk(S)").unwrap());
+        assert_eq!(found.len(), 2);
+        assert_eq!(found[1].0["S"], atom("app"));
+        assert!((found[1].1 - 0.72).abs() < 1e-6);
+
+        // A broken file adds nothing
+        assert!(engine.load("redundant(storage).\nbroken(").is_err());
+        assert_eq!(engine.query(&parser::parse_term("at_risk(S)").unwrap()).len(), 2);
+        assert_eq!(engine.predicates().len(), 3);
+    }
+
+    #[test]
+    fn test_answers_carry_proofs() {
+        let mut engine = ReasoningEngine::new();
+        engine.load(facts::PRELUDE).unwrap();
+        engine
+            .load(
+                "0.8 :: symptom('no network', 'wifi drops').\n\
+                 0.5 :: solves('wifi drops', 'modprobe -r iwlwifi').\n",
+            )
+            .unwrap();
+        let answers = engine.explain(&parser::parse_term("fix('no network', C)").unwrap());
+        assert_eq!(answers.len(), 1);
+        let answer = &answers[0];
+        assert!((answer.confidence - 0.4).abs() < 1e-6);
+        assert_eq!(answer.proofs.len(), 1);
+
+        // fix is recursive, so the answer comes from its table with the
+        // derivation it was recorded with
+        let proof = &answer.proofs[0];
+        assert_eq!(
+            proof.goal.to_string(),
+            "fix('no network', 'modprobe -r iwlwifi')"
+        );
+        assert!((proof.confidence - answer.confidence).abs() < 1e-6);
+        let goals: Vec<String> = proof.derivation().iter().map(|p| p.goal.to_string()).collect();
+        assert_eq!(
+            goals,
+            [
+                "symptom('no network', 'wifi drops')",
+                "solves('wifi drops', 'modprobe -r iwlwifi')",
+                "fix('wifi drops', 'modprobe -r iwlwifi')",
+                "fix('no network', 'modprobe -r iwlwifi')",
+            ]
+        );
+        let proof::Step::Clause { bindings, .. } = &proof.step else {
+            panic!("fix is resolved by a clause");
+        };
+        assert!(bindings.contains(&("Cause".to_string(), atom("wifi drops"))));
+        let tree = proof.to_string();
+        assert!(tree.contains(
+            "by fix(Problem, Command) :- symptom(Problem, Cause), fix(Cause, Command)."
+        ));
+        assert!(tree.contains("0.40 = 1.00 × 0.80 × 0.50"));
+        assert!(tree.contains("└─ fix('wifi drops', 'modprobe -r iwlwifi')  0.50"));
+
+        // Built-ins are leaves; a plain query's proofs follow its goals
+        engine.add_fact(compound("mem_pct", vec![atom("92.5")]), 1.0);
+        let answers = engine.prove(None, &parser::parse_term("mem_pct(M), M > 90").unwrap());
+        let goals: Vec<String> = answers[0].proofs.iter().map(|p| p.goal.to_string()).collect();
+        assert_eq!(goals, ["mem_pct(92.5)", "92.5 > 90"]);
+        assert!(matches!(answers[0].proofs[1].step, proof::Step::BuiltIn));
+    }
+
+    #[tokio::test]
+    async fn test_facts_follow_the_store() {
+        use crate::storage::embedded::SqliteStore;
+        use crate::storage::{SolutionSource, StorageConfig};
+
+        let storage = Storage::with_backend(
+            Box::new(SqliteStore::open_in_memory().unwrap()),
+            StorageConfig::default(),
+        );
+        let now = chrono::Utc::now();
+        let solution = Solution {
+            id: "s1".to_string(),
+            category: "network".to_string(),
+            problem: "WiFi drops after suspend".to_string(),
+            solution: String::new(),
+            commands: vec!["modprobe -r iwlwifi".to_string()],
+            tags: vec!["wifi".to_string()],
+            success_count: 0,
+            failure_count: 0,
+            source: SolutionSource::Manual,
+            created_at: now,
+            updated_at: now,
+        };
+        storage.store_solution(&solution).await.unwrap();
+        storage
+            .relate(&ProblemRelation {
+                from_problem: "No network after resume".to_string(),
+                to_solution: "s1".to_string(),
+                confidence: 0.8,
+                context: vec![],
+            })
+            .await
+            .unwrap();
+
+        let mut engine = ReasoningEngine::new();
+        engine.load(facts::PRELUDE).unwrap();
+        assert_eq!(engine.sync_storage(&storage).await.unwrap(), 1);
+        let fix = parser::parse_term("fix('no network after resume', C)").unwrap();
+        let found = engine.query(&fix);
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].0["C"], atom("modprobe -r iwlwifi"));
+        assert!((found[0].1 - 0.4).abs() < 1e-6);
+
+        // Outcomes move the derived confidence, without duplicating facts
+        storage.record_outcome("s1", true).await.unwrap();
+        storage.record_outcome("s1", true).await.unwrap();
+        engine.sync_storage(&storage).await.unwrap();
+        let found = engine.query(&fix);
+        assert_eq!(found.len(), 1);
+        assert!((found[0].1 - 0.6).abs() < 1e-6);
+        let rebuilt = {
+            let mut engine = ReasoningEngine::new();
+            engine.load(facts::PRELUDE).unwrap();
+            engine.sync_storage(&storage).await.unwrap();
+            engine.query(&fix)
+        };
+        assert!((rebuilt[0].1 - found[0].1).abs() < 1e-6);
+
+        // A solution merged away takes its facts with it
+        let other = Solution {
+            id: "s2".to_string(),
+            problem: "Network gone after resume".to_string(),
+            ..solution.clone()
+        };
+        storage.store_solution(&other).await.unwrap();
+        assert_eq!(engine.sync_storage(&storage).await.unwrap(), 2);
+        storage.merge("s1", "s2").await.unwrap();
+        assert_eq!(engine.sync_storage(&storage).await.unwrap(), 1);
+        assert!(engine.clauses().iter().all(|c| c.source.as_deref() != Some("s2")));
+        assert!(engine
+            .query(&parser::parse_term("solves('network gone after resume', C)").unwrap())
+            .is_empty());
+    }
+
+    #[test]
+    fn test_extract_problem_and_commands() {
+        let text = "# WiFi drops after suspend\n\nReload the driver:\n\n```sh\n# as root\nmodprobe -r iwlwifi\nmodprobe iwlwifi\n```\n\nor\n$ nmcli radio wifi on\n";
+        assert_eq!(first_line(text), "WiFi drops after suspend");
+        assert_eq!(
+            extract_commands(text),
+            ["modprobe -r iwlwifi", "modprobe iwlwifi", "nmcli radio wifi on"]
+        );
+    }
 }
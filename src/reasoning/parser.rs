@@ -0,0 +1,1109 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+//! Textual syntax for the knowledge base
+//!
+//! Clauses are written as in Prolog (and Logtalk, so the rules under
+//! `gitvisor/rules/` parse too), with an optional ProbLog-style confidence:
+//!
+//! ```text
+//! % Facts and rules end with a full stop
+//! requires(nginx, network).
+//! 0.9 :: requires(network, 'NetworkManager').
+//! 0.8 :: suggest(reload(D)) :- loaded(D), module_for(D, M), \+ blacklisted(M).
+//! high_memory :- mem_pct(P), P > 90.
+//!
+//! :- table depends_on/2.
+//! depends_on(A, C) :- depends_on(A, B), depends_on(B, C).
+//! ```
+//!
+//! Atoms are lowercase names, numbers or quoted (`'NetworkManager'`);
+//! variables start with an uppercase letter or `_`, and each `_` is a
+//! different variable (named `_#1`, `_#2`… so it can't clash with a named
+//! one). Terms use the usual operators (`,`, `;`, `->`, `\+`, `=`, `\=`,
+//! `==`, `\==`, `=/=`, `is`, comparisons and arithmetic) with Prolog
+//! priorities; the standard order (`@<` and the like) and `=..` aren't
+//! supported. Logtalk entity and declaration directives (`:- object(...)`,
+//! `:- public(...)`, `:- info(...)` and the like) are accepted and ignored.
+
+use std::fmt;
+
+use super::{Clause, Predicate, Term};
+
+/// Byte offsets of a piece of source
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+}
+
+impl Span {
+    fn to(self, other: Span) -> Span {
+        Span {
+            start: self.start,
+            end: other.end,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+#[error("{message}")]
+pub struct ParseError {
+    pub message: String,
+    pub span: Span,
+}
+
+impl ParseError {
+    fn new(message: impl Into<String>, span: Span) -> Self {
+        Self {
+            message: message.into(),
+            span,
+        }
+    }
+
+    /// The error with its position in `source` and the offending line
+    /// underlined, compiler style
+    pub fn render(&self, name: &str, source: &str) -> String {
+        let start = self.span.start.min(source.len());
+        let line_start = source[..start].rfind('\n').map_or(0, |i| i + 1);
+        let line_end = source[start..]
+            .find('\n')
+            .map_or(source.len(), |i| start + i);
+        let line = &source[line_start..line_end];
+        let line_no = source[..start].matches('\n').count() + 1;
+        let column = source[line_start..start].chars().count();
+        let width = source[start..self.span.end.clamp(start, line_end)]
+            .chars()
+            .count()
+            .max(1);
+
+        let gutter = " ".repeat(line_no.to_string().len());
+        format!(
+            "{}\n{}--> {}:{}:{}\n{} |\n{} | {}\n{} | {}{}",
+            self.message,
+            gutter,
+            name,
+            line_no,
+            column + 1,
+            gutter,
+            line_no,
+            line,
+            gutter,
+            " ".repeat(column),
+            "^".repeat(width)
+        )
+    }
+}
+
+/// What a knowledge base file says
+#[derive(Debug, Clone)]
+pub enum Item {
+    Clause(Clause),
+    /// `:- table name/arity.`
+    Table(Predicate),
+}
+
+/// Parse a knowledge base
+pub fn parse(source: &str) -> Result<Vec<Item>, ParseError> {
+    let mut parser = Parser::new(source)?;
+    let mut items = vec![];
+    while !parser.at_end() {
+        let (term, span) = parser.clause_term()?;
+        parser.anonymous = 0;
+        items.extend(items_of(&term, span)?);
+    }
+    Ok(items)
+}
+
+/// Parse a single term, such as a query; a final `.` is optional
+pub fn parse_term(source: &str) -> Result<Term, ParseError> {
+    let mut parser = Parser::new(source)?;
+    if parser.at_end() {
+        return Err(ParseError::new("expected a term", parser.end_span()));
+    }
+    let term = parser.term(1200)?.term;
+    if let Some(Token::End) = parser.peek_token() {
+        parser.pos += 1;
+    }
+    match parser.tokens.get(parser.pos) {
+        None => Ok(term),
+        Some(token) => Err(ParseError::new("expected the end of the term", token.span)),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Name(String),
+    /// A quoted atom, which is never an operator
+    Quoted(String),
+    Var(String),
+    Number(String),
+    Punct(char),
+    /// The `.` ending a clause
+    End,
+}
+
+#[derive(Debug, Clone)]
+struct Lexed {
+    token: Token,
+    span: Span,
+    /// Whether whitespace or a comment comes before it; `f(x)` is a compound
+    /// but `f (x)` is `f` applied as a prefix operator
+    spaced: bool,
+}
+
+const SYMBOL_CHARS: &str = "+-*/\\^<>=~:?@#&$.";
+
+fn tokenize(source: &str) -> Result<Vec<Lexed>, ParseError> {
+    let chars: Vec<(usize, char)> = source.char_indices().collect();
+    let offset = |i: usize| chars.get(i).map_or(source.len(), |&(o, _)| o);
+    let at = |i: usize| chars.get(i).map(|&(_, c)| c);
+    let span = |start: usize, end: usize| Span {
+        start: offset(start),
+        end: offset(end),
+    };
+
+    let mut tokens = vec![];
+    let mut i = 0;
+    let mut spaced = true;
+    while let Some(c) = at(i) {
+        let start = i;
+        let token = match c {
+            c if c.is_whitespace() => {
+                i += 1;
+                spaced = true;
+                continue;
+            }
+            '%' => {
+                while at(i).is_some_and(|c| c != '\n') {
+                    i += 1;
+                }
+                spaced = true;
+                continue;
+            }
+            '/' if at(i + 1) == Some('*') => {
+                i += 2;
+                while !(at(i) == Some('*') && at(i + 1) == Some('/')) {
+                    if at(i).is_none() {
+                        return Err(ParseError::new(
+                            "unterminated comment",
+                            span(start, start + 2),
+                        ));
+                    }
+                    i += 1;
+                }
+                i += 2;
+                spaced = true;
+                continue;
+            }
+            '.' if at(i + 1).is_none_or(|c| c.is_whitespace() || c == '%') => {
+                i += 1;
+                Token::End
+            }
+            c if c.is_ascii_digit() => {
+                while at(i).is_some_and(|c| c.is_ascii_digit()) {
+                    i += 1;
+                }
+                if at(i) == Some('.') && at(i + 1).is_some_and(|c| c.is_ascii_digit()) {
+                    i += 1;
+                    while at(i).is_some_and(|c| c.is_ascii_digit()) {
+                        i += 1;
+                    }
+                }
+                if matches!(at(i), Some('e' | 'E')) {
+                    let digits = if matches!(at(i + 1), Some('+' | '-')) {
+                        i + 2
+                    } else {
+                        i + 1
+                    };
+                    if at(digits).is_some_and(|c| c.is_ascii_digit()) {
+                        i = digits;
+                        while at(i).is_some_and(|c| c.is_ascii_digit()) {
+                            i += 1;
+                        }
+                    }
+                }
+                Token::Number(source[offset(start)..offset(i)].to_string())
+            }
+            c if c.is_alphabetic() || c == '_' => {
+                while at(i).is_some_and(|c| c.is_alphanumeric() || c == '_') {
+                    i += 1;
+                }
+                let name = source[offset(start)..offset(i)].to_string();
+                if c.is_lowercase() {
+                    Token::Name(name)
+                } else {
+                    Token::Var(name)
+                }
+            }
+            '\'' | '"' => {
+                let quote = c;
+                let mut text = String::new();
+                i += 1;
+                loop {
+                    match at(i) {
+                        None => {
+                            return Err(ParseError::new("unterminated quoted atom", span(start, i)))
+                        }
+                        Some(c) if c == quote && at(i + 1) == Some(quote) => {
+                            text.push(quote);
+                            i += 2;
+                        }
+                        Some(c) if c == quote => {
+                            i += 1;
+                            break;
+                        }
+                        Some('\\') => {
+                            text.push(match at(i + 1) {
+                                Some('n') => '\n',
+                                Some('t') => '\t',
+                                Some(c @ ('\\' | '\'' | '"')) => c,
+                                _ => {
+                                    return Err(ParseError::new(
+                                        "unknown escape sequence",
+                                        span(i, i + 2),
+                                    ))
+                                }
+                            });
+                            i += 2;
+                        }
+                        Some(c) => {
+                            text.push(c);
+                            i += 1;
+                        }
+                    }
+                }
+                Token::Quoted(text)
+            }
+            '!' | ';' => {
+                i += 1;
+                Token::Name(c.to_string())
+            }
+            '(' | ')' | '[' | ']' | '{' | '}' | ',' | '|' => {
+                i += 1;
+                Token::Punct(c)
+            }
+            c if SYMBOL_CHARS.contains(c) => {
+                while at(i).is_some_and(|c| SYMBOL_CHARS.contains(c)) {
+                    i += 1;
+                }
+                Token::Name(source[offset(start)..offset(i)].to_string())
+            }
+            c => {
+                return Err(ParseError::new(
+                    format!("unexpected character `{}`", c),
+                    span(start, start + 1),
+                ))
+            }
+        };
+        tokens.push(Lexed {
+            token,
+            span: span(start, i),
+            spaced,
+        });
+        spaced = false;
+    }
+    Ok(tokens)
+}
+
+/// How an operator's arguments may bind: `x` needs a lower priority, `y`
+/// allows the same
+#[derive(Clone, Copy)]
+enum Fixity {
+    Xfx,
+    Xfy,
+    Yfx,
+    Fy,
+    Fx,
+}
+
+fn infix(name: &str) -> Option<(u16, Fixity)> {
+    Some(match name {
+        ":-" => (1200, Fixity::Xfx),
+        ";" | "|" => (1100, Fixity::Xfy),
+        "->" => (1050, Fixity::Xfy),
+        "," => (1000, Fixity::Xfy),
+        "=" | "\\=" | "==" | "\\==" | "=/=" | "is" | "<" | ">" | "=<" | ">=" | "=:=" | "=\\="
+        | "@<" | "@>" | "@=<" | "@>=" | "=.." => (700, Fixity::Xfx),
+        "+" | "-" => (500, Fixity::Yfx),
+        "*" | "/" | "//" | "mod" | "rem" => (400, Fixity::Yfx),
+        "**" => (200, Fixity::Xfx),
+        "^" | ":" | "::" => (200, Fixity::Xfy),
+        _ => return None,
+    })
+}
+
+/// Standard operators the engine doesn't implement, rejected rather than
+/// read as goals that can never succeed
+const UNSUPPORTED: &[&str] = &["@<", "@>", "@=<", "@>=", "=.."];
+
+fn prefix(name: &str) -> Option<(u16, Fixity)> {
+    Some(match name {
+        ":-" => (1200, Fixity::Fx),
+        "table" | "dynamic" | "discontiguous" => (1150, Fixity::Fx),
+        "\\+" => (900, Fixity::Fy),
+        "-" | "+" => (200, Fixity::Fy),
+        _ => return None,
+    })
+}
+
+/// A parsed term with its priority and where it came from
+struct Parsed {
+    term: Term,
+    priority: u16,
+    span: Span,
+}
+
+struct Parser {
+    tokens: Vec<Lexed>,
+    pos: usize,
+    source_len: usize,
+    /// `_` variables so far in this clause
+    anonymous: usize,
+}
+
+impl Parser {
+    fn new(source: &str) -> Result<Self, ParseError> {
+        Ok(Self {
+            tokens: tokenize(source)?,
+            pos: 0,
+            source_len: source.len(),
+            anonymous: 0,
+        })
+    }
+
+    fn at_end(&self) -> bool {
+        self.pos >= self.tokens.len()
+    }
+
+    /// Just after the last token, where a missing one would go
+    fn end_span(&self) -> Span {
+        let end = self.tokens.last().map_or(self.source_len, |t| t.span.end);
+        Span { start: end, end }
+    }
+
+    fn peek_token(&self) -> Option<&Token> {
+        self.tokens.get(self.pos).map(|t| &t.token)
+    }
+
+    fn next(&mut self, expected: &str) -> Result<Lexed, ParseError> {
+        let token = self.tokens.get(self.pos).cloned().ok_or_else(|| {
+            ParseError::new(
+                format!("expected {}, found the end", expected),
+                self.end_span(),
+            )
+        })?;
+        self.pos += 1;
+        Ok(token)
+    }
+
+    fn expect(&mut self, punct: char, expected: &str) -> Result<Span, ParseError> {
+        let token = self.next(expected)?;
+        if token.token == Token::Punct(punct) {
+            Ok(token.span)
+        } else {
+            Err(ParseError::new(
+                format!("expected {}", expected),
+                token.span,
+            ))
+        }
+    }
+
+    /// A term ended by `.`
+    fn clause_term(&mut self) -> Result<(Term, Span), ParseError> {
+        let parsed = self.term(1200)?;
+        match self.tokens.get(self.pos) {
+            Some(Lexed {
+                token: Token::End,
+                span,
+                ..
+            }) => {
+                self.pos += 1;
+                Ok((parsed.term, parsed.span.to(*span)))
+            }
+            Some(token) => Err(ParseError::new(
+                "expected an operator or `.` after the term",
+                token.span,
+            )),
+            None => Err(ParseError::new(
+                "expected `.` at the end of the clause",
+                Span {
+                    start: parsed.span.end,
+                    end: parsed.span.end,
+                },
+            )),
+        }
+    }
+
+    /// The name of the infix operator at the current token, if there is one
+    fn infix_here(&self) -> Option<(String, u16, Fixity)> {
+        let token = self.tokens.get(self.pos)?;
+        let name = match &token.token {
+            Token::Name(name) => name.clone(),
+            Token::Punct(c @ (',' | '|')) => c.to_string(),
+            _ => return None,
+        };
+        // `-(1)` is a compound, not a subtraction with a missing operand
+        let functional = matches!(
+            self.tokens.get(self.pos + 1),
+            Some(Lexed {
+                token: Token::Punct('('),
+                spaced: false,
+                ..
+            })
+        );
+        if functional && name != "," && name != "|" {
+            return None;
+        }
+        let (priority, fixity) = infix(&name)?;
+        // `|` only as an alternative to `;` in bodies
+        let name = if name == "|" { ";".to_string() } else { name };
+        Some((name, priority, fixity))
+    }
+
+    /// Whether the current token can start a term
+    fn starts_term(&self) -> bool {
+        match self.tokens.get(self.pos).map(|t| &t.token) {
+            None | Some(Token::End) => false,
+            Some(Token::Punct(c)) => matches!(c, '(' | '['),
+            Some(Token::Name(name)) => infix(name).is_none() || prefix(name).is_some(),
+            _ => true,
+        }
+    }
+
+    fn term(&mut self, max: u16) -> Result<Parsed, ParseError> {
+        let mut left = self.primary(max)?;
+        while let Some((name, priority, fixity)) = self.infix_here() {
+            let (left_max, right_max) = match fixity {
+                Fixity::Xfx => (priority - 1, priority - 1),
+                Fixity::Xfy => (priority - 1, priority),
+                _ => (priority, priority - 1),
+            };
+            if priority > max || left.priority > left_max {
+                break;
+            }
+            if UNSUPPORTED.contains(&name.as_str()) {
+                return Err(ParseError::new(
+                    format!("`{}` isn't supported", name),
+                    self.tokens[self.pos].span,
+                ));
+            }
+            self.pos += 1;
+            let right = self.term(right_max)?;
+            left = Parsed {
+                term: Term::Compound(name, vec![left.term, right.term]),
+                priority,
+                span: left.span.to(right.span),
+            };
+        }
+        Ok(left)
+    }
+
+    fn primary(&mut self, max: u16) -> Result<Parsed, ParseError> {
+        let Lexed { token, span, .. } = self.next("a term")?;
+        let atom = |term: Term| Parsed {
+            term,
+            priority: 0,
+            span,
+        };
+        match token {
+            Token::Number(n) => Ok(atom(Term::Atom(n))),
+            Token::Var(name) if name == "_" => {
+                self.anonymous += 1;
+                Ok(atom(Term::Var(format!("_#{}", self.anonymous))))
+            }
+            Token::Var(name) => Ok(atom(Term::Var(name))),
+            Token::Punct('(') => {
+                let inner = self.term(1200)?;
+                let close = self.expect(')', "`)`")?;
+                Ok(Parsed {
+                    term: inner.term,
+                    priority: 0,
+                    span: span.to(close),
+                })
+            }
+            Token::Punct('[') => {
+                let mut items = vec![];
+                if self.peek_token() != Some(&Token::Punct(']')) {
+                    loop {
+                        items.push(self.term(999)?.term);
+                        let next = self.next("`,` or `]`")?;
+                        match next.token {
+                            Token::Punct(',') => continue,
+                            Token::Punct(']') => {
+                                self.pos -= 1;
+                                break;
+                            }
+                            Token::Punct('|') => {
+                                return Err(ParseError::new(
+                                    "list tails (`[H|T]`) aren't supported",
+                                    next.span,
+                                ))
+                            }
+                            _ => return Err(ParseError::new("expected `,` or `]`", next.span)),
+                        }
+                    }
+                }
+                let close = self.expect(']', "`]`")?;
+                Ok(Parsed {
+                    term: Term::List(items),
+                    priority: 0,
+                    span: span.to(close),
+                })
+            }
+            Token::Punct('{') => Err(ParseError::new("`{...}` terms aren't supported", span)),
+            Token::Punct(c) => Err(ParseError::new(format!("unexpected `{}`", c), span)),
+            Token::End => Err(ParseError::new("unexpected `.`, expected a term", span)),
+            Token::Name(name) | Token::Quoted(name)
+                if matches!(
+                    self.tokens.get(self.pos),
+                    Some(Lexed {
+                        token: Token::Punct('('),
+                        spaced: false,
+                        ..
+                    })
+                ) =>
+            {
+                self.pos += 1;
+                let mut args = vec![self.term(999)?.term];
+                while self.peek_token() == Some(&Token::Punct(',')) {
+                    self.pos += 1;
+                    args.push(self.term(999)?.term);
+                }
+                let close = self.expect(')', "`,` or `)`")?;
+                Ok(Parsed {
+                    term: Term::Compound(name, args),
+                    priority: 0,
+                    span: span.to(close),
+                })
+            }
+            Token::Name(name) => match prefix(&name) {
+                Some((priority, fixity)) if self.starts_term() => {
+                    // -1 is a number rather than -(1)
+                    if let (
+                        "-",
+                        Some(Lexed {
+                            token: Token::Number(n),
+                            spaced: false,
+                            span: number,
+                        }),
+                    ) = (name.as_str(), self.tokens.get(self.pos))
+                    {
+                        let (n, number) = (n.clone(), *number);
+                        self.pos += 1;
+                        return Ok(Parsed {
+                            term: Term::Atom(format!("-{}", n)),
+                            priority: 0,
+                            span: span.to(number),
+                        });
+                    }
+                    if priority > max {
+                        return Err(ParseError::new(
+                            format!("`{}` needs parentheses here", name),
+                            span,
+                        ));
+                    }
+                    let operand_max = match fixity {
+                        Fixity::Fy => priority,
+                        _ => priority - 1,
+                    };
+                    let operand = self.term(operand_max)?;
+                    Ok(Parsed {
+                        term: Term::Compound(name, vec![operand.term]),
+                        priority,
+                        span: span.to(operand.span),
+                    })
+                }
+                _ => Ok(atom(Term::Atom(name))),
+            },
+            Token::Quoted(name) => Ok(atom(Term::Atom(name))),
+        }
+    }
+}
+
+/// Logtalk and Prolog directives that declare rather than define, and so
+/// don't matter here
+const IGNORED_DIRECTIVES: &[&str] = &[
+    "object",
+    "end_object",
+    "protocol",
+    "end_protocol",
+    "category",
+    "end_category",
+    "info",
+    "public",
+    "protected",
+    "private",
+    "mode",
+    "dynamic",
+    "discontiguous",
+    "uses",
+    "encoding",
+    "set_logtalk_flag",
+];
+
+/// Goals the engine defines itself, which a clause can't redefine
+const BUILT_INS: &[(&str, usize)] = &[
+    ("true", 0),
+    ("fail", 0),
+    ("false", 0),
+    (",", 2),
+    (";", 2),
+    ("\\+", 1),
+    ("not", 1),
+    ("->", 2),
+    ("=", 2),
+    ("\\=", 2),
+    ("==", 2),
+    ("\\==", 2),
+    ("=/=", 2),
+    ("is", 2),
+    ("<", 2),
+    ("=<", 2),
+    (">", 2),
+    (">=", 2),
+    ("=:=", 2),
+    ("=\\=", 2),
+];
+
+fn items_of(term: &Term, span: Span) -> Result<Vec<Item>, ParseError> {
+    match term {
+        Term::Compound(op, args) if op == ":-" && args.len() == 1 => directive(&args[0], span),
+        Term::Compound(op, args) if op == ":-" && args.len() == 2 => {
+            callable(&args[1], span)?;
+            let mut goals = vec![];
+            conjuncts(&args[1], &mut goals);
+            Ok(vec![clause(&args[0], goals, span)?])
+        }
+        head => Ok(vec![clause(head, vec![], span)?]),
+    }
+}
+
+/// Check that a body can be called, looking inside control constructs
+fn callable(goal: &Term, span: Span) -> Result<(), ParseError> {
+    match goal {
+        Term::Var(v) => Err(ParseError::new(
+            format!("`{}` is a variable and can't be called as a goal", v),
+            span,
+        )),
+        Term::List(_) => Err(ParseError::new("a list can't be called as a goal", span)),
+        Term::Compound(op, args)
+            if (args.len() == 2 && matches!(op.as_str(), "," | ";" | "->"))
+                || (args.len() == 1 && matches!(op.as_str(), "\\+" | "not")) =>
+        {
+            args.iter().try_for_each(|a| callable(a, span))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// The parts of a `,` chain
+fn conjuncts(term: &Term, parts: &mut Vec<Term>) {
+    match term {
+        Term::Compound(op, args) if op == "," && args.len() == 2 => {
+            args.iter().for_each(|t| conjuncts(t, parts))
+        }
+        term => parts.push(term.clone()),
+    }
+}
+
+fn clause(head: &Term, body: Vec<Term>, span: Span) -> Result<Item, ParseError> {
+    let (confidence, head) = match head {
+        Term::Compound(op, args) if op == "::" && args.len() == 2 => {
+            let confidence = match &args[0] {
+                Term::Atom(n) => n.parse::<f32>().ok(),
+                _ => None,
+            };
+            match confidence {
+                Some(c) if (0.0..=1.0).contains(&c) => (c, &args[1]),
+                _ => {
+                    return Err(ParseError::new(
+                        "confidence must be a number from 0 to 1",
+                        span,
+                    ))
+                }
+            }
+        }
+        head => (1.0, head),
+    };
+
+    let (name, arity) = match head {
+        Term::Atom(name) if name.parse::<f64>().is_err() => (name.as_str(), 0),
+        Term::Compound(name, args) => (name.as_str(), args.len()),
+        _ => {
+            return Err(ParseError::new(
+                "a clause head must be an atom or compound",
+                span,
+            ))
+        }
+    };
+    if BUILT_INS.contains(&(name, arity)) {
+        return Err(ParseError::new(
+            format!("`{}/{}` is built in and can't be redefined", name, arity),
+            span,
+        ));
+    }
+    Ok(Item::Clause(Clause {
+        head: head.clone(),
+        body,
+        confidence,
+        source: None,
+    }))
+}
+
+fn directive(term: &Term, span: Span) -> Result<Vec<Item>, ParseError> {
+    let name = match term {
+        Term::Atom(name) | Term::Compound(name, _) => name.as_str(),
+        _ => "?",
+    };
+    if IGNORED_DIRECTIVES.contains(&name) {
+        return Ok(vec![]);
+    }
+    match term {
+        // `:- table a/1, b/2.`
+        Term::Compound(name, args) if name == "table" && args.len() == 1 => {
+            let mut indicators = vec![];
+            conjuncts(&args[0], &mut indicators);
+            indicators
+                .iter()
+                .map(|indicator| match indicator {
+                    Term::Compound(slash, pa) if slash == "/" && pa.len() == 2 => {
+                        match (&pa[0], &pa[1]) {
+                            (Term::Atom(name), Term::Atom(arity)) => arity
+                                .parse()
+                                .ok()
+                                .map(|arity| Item::Table((name.clone(), arity))),
+                            _ => None,
+                        }
+                    }
+                    _ => None,
+                })
+                .collect::<Option<Vec<_>>>()
+                .ok_or_else(|| ParseError::new("expected `:- table name/arity.`", span))
+        }
+        _ => Err(ParseError::new(
+            format!("unknown directive `{}`", name),
+            span,
+        )),
+    }
+}
+
+/// Terms print in the syntax they're parsed from, with operators infix and
+/// atoms quoted where they need to be
+impl fmt::Display for Term {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write_term(f, self, 1200)
+    }
+}
+
+/// Clauses print as they'd be written in a knowledge base file
+impl fmt::Display for Clause {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.confidence != 1.0 {
+            write!(f, "{} :: ", self.confidence)?;
+            write_term(f, &self.head, 200)?;
+        } else {
+            write_term(f, &self.head, 1199)?;
+        }
+        for (i, goal) in self.body.iter().enumerate() {
+            f.write_str(if i == 0 { " :- " } else { ", " })?;
+            write_term(f, goal, 999)?;
+        }
+        f.write_str(".")
+    }
+}
+
+/// Write a term where at most priority `max` fits without parentheses
+fn write_term(f: &mut fmt::Formatter, term: &Term, max: u16) -> fmt::Result {
+    match term {
+        // Each anonymous variable occurs once, so `_` reads back the same
+        Term::Var(name) if name.starts_with("_#") => f.write_str("_"),
+        Term::Var(name) => f.write_str(name),
+        Term::Atom(name) => write_atom(f, name),
+        Term::List(items) => {
+            f.write_str("[")?;
+            write_args(f, items)?;
+            f.write_str("]")
+        }
+        Term::Compound(op, args) => {
+            let (priority, fixity) = match args.len() {
+                2 if op != "|" => infix(op),
+                1 => prefix(op).filter(|(p, _)| *p < 1200),
+                _ => None,
+            }
+            .unwrap_or((0, Fixity::Xfx));
+            if priority == 0 {
+                write_atom(f, op)?;
+                f.write_str("(")?;
+                write_args(f, args)?;
+                return f.write_str(")");
+            }
+
+            if priority > max {
+                f.write_str("(")?;
+            }
+            let (left, right) = match fixity {
+                Fixity::Xfy | Fixity::Fy => (priority - 1, priority),
+                Fixity::Yfx => (priority, priority - 1),
+                Fixity::Xfx | Fixity::Fx => (priority - 1, priority - 1),
+            };
+            match args.as_slice() {
+                [operand] => {
+                    write!(f, "{} ", op)?;
+                    write_term(f, operand, right)?;
+                }
+                [a, b] => {
+                    write_term(f, a, left)?;
+                    if op == "," {
+                        f.write_str(", ")?;
+                    } else {
+                        write!(f, " {} ", op)?;
+                    }
+                    write_term(f, b, right)?;
+                }
+                _ => unreachable!("operators take one or two arguments"),
+            }
+            if priority > max {
+                f.write_str(")")?;
+            }
+            Ok(())
+        }
+    }
+}
+
+fn write_args(f: &mut fmt::Formatter, args: &[Term]) -> fmt::Result {
+    for (i, arg) in args.iter().enumerate() {
+        if i > 0 {
+            f.write_str(", ")?;
+        }
+        write_term(f, arg, 999)?;
+    }
+    Ok(())
+}
+
+/// An atom, quoted unless it reads back as the same single name or number
+fn write_atom(f: &mut fmt::Formatter, name: &str) -> fmt::Result {
+    let bare = match tokenize(name).as_deref() {
+        Ok(
+            [Lexed {
+                token: Token::Name(n) | Token::Number(n),
+                ..
+            }],
+        ) => n == name,
+        // A negative number
+        Ok(
+            [Lexed {
+                token: Token::Name(minus),
+                ..
+            }, Lexed {
+                token: Token::Number(n),
+                spaced: false,
+                ..
+            }],
+        ) => minus == "-" && name.strip_prefix('-') == Some(n.as_str()),
+        _ => false,
+    };
+    if bare {
+        return f.write_str(name);
+    }
+    f.write_str("'")?;
+    for c in name.chars() {
+        match c {
+            '\'' => f.write_str("\\'")?,
+            '\\' => f.write_str("\\\\")?,
+            '\n' => f.write_str("\\n")?,
+            '\t' => f.write_str("\\t")?,
+            c => write!(f, "{}", c)?,
+        }
+    }
+    f.write_str("'")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::reasoning::{atom, compound, var, variables};
+
+    fn clauses(source: &str) -> Vec<Clause> {
+        parse(source)
+            .unwrap()
+            .into_iter()
+            .filter_map(|item| match item {
+                Item::Clause(c) => Some(c),
+                Item::Table(_) => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_clauses_and_confidence() {
+        let parsed = clauses(
+            "% services\n\
+             requires(nginx, network).\n\
+             0.9 :: requires(network, 'NetworkManager').\n\
+             /* a rule */ 0.8 :: fix(D, reload) :- loaded(D), \\+ blacklisted(D), X is -1 + 2 * 3.\n\
+             high :- mem_pct(P), P >= 90.5 ; swap_pct(_), X =/= [a, _].\n",
+        );
+        assert_eq!(parsed.len(), 4);
+        assert_eq!(
+            parsed[0].head,
+            compound("requires", vec![atom("nginx"), atom("network")])
+        );
+        assert_eq!(parsed[0].confidence, 1.0);
+        assert_eq!(
+            parsed[1].head.clone(),
+            compound("requires", vec![atom("network"), atom("NetworkManager")])
+        );
+        assert!((parsed[1].confidence - 0.9).abs() < 1e-6);
+
+        let fix = &parsed[2];
+        assert!((fix.confidence - 0.8).abs() < 1e-6);
+        assert_eq!(fix.body.len(), 3);
+        assert_eq!(
+            fix.body[1],
+            compound("\\+", vec![compound("blacklisted", vec![var("D")])])
+        );
+        // Arithmetic binds tighter than `is`, `*` tighter than `+`
+        assert_eq!(
+            fix.body[2],
+            compound(
+                "is",
+                vec![
+                    var("X"),
+                    compound(
+                        "+",
+                        vec![atom("-1"), compound("*", vec![atom("2"), atom("3")])]
+                    ),
+                ],
+            )
+        );
+
+        // `;` binds looser than `,`, so the body is one disjunction; each `_`
+        // is its own variable
+        let high = &parsed[3];
+        assert_eq!(high.body.len(), 1);
+        let Term::Compound(op, branches) = &high.body[0] else {
+            panic!()
+        };
+        assert_eq!(op, ";");
+        assert_eq!(
+            branches[1],
+            compound(
+                ",",
+                vec![
+                    compound("swap_pct", vec![var("_#1")]),
+                    compound(
+                        "=/=",
+                        vec![var("X"), Term::List(vec![atom("a"), var("_#2")])]
+                    ),
+                ],
+            )
+        );
+    }
+
+    #[test]
+    fn test_directives() {
+        let items = parse(
+            ":- object(kb).\n\
+             :- info([version is 1:0:0, date is 2025-12-29]).\n\
+             :- table depends_on/2, needs/2.\n\
+             depends_on(A, B) :- requires(A, B).\n\
+             :- end_object.\n",
+        )
+        .unwrap();
+        assert_eq!(items.len(), 3);
+        assert!(matches!(&items[0], Item::Table((name, 2)) if name == "depends_on"));
+        assert!(matches!(&items[1], Item::Table((name, 2)) if name == "needs"));
+
+        let err = parse(":- frobnicate.").unwrap_err();
+        assert_eq!(err.message, "unknown directive `frobnicate`");
+    }
+
+    #[test]
+    fn test_errors_have_spans() {
+        let source = "ok(a).\nfix(X) :- broken(X)\nnext(b).\n";
+        let err = parse(source).unwrap_err();
+        assert_eq!(&source[err.span.start..err.span.end], "next");
+        assert_eq!(
+            err.render("kb.pl", source),
+            "expected an operator or `.` after the term\n \
+             --> kb.pl:3:1\n  |\n3 | next(b).\n  | ^^^^"
+        );
+
+        let err = parse("1.5 :: flaky(x).").unwrap_err();
+        assert_eq!(err.message, "confidence must be a number from 0 to 1");
+        let err = parse("a :- b, X.").unwrap_err();
+        assert!(err.message.contains("can't be called"));
+        for nested in [
+            "a :- b ; X.",
+            "a :- \\+ X.",
+            "a :- b, (c ; \\+ (d, X)).",
+            "a :- (b ; [c]).",
+        ] {
+            assert!(parse(nested).is_err(), "{}", nested);
+        }
+        let err = parse("X = Y :- true.").unwrap_err();
+        assert!(err.message.contains("built in"));
+        let err = parse("p('abc).").unwrap_err();
+        assert_eq!(err.span.start, 2);
+        let err = parse("p(a, b").unwrap_err();
+        assert_eq!(err.message, "expected `,` or `)`, found the end");
+
+        // Operators the engine can't run are refused where they appear
+        let source = "newer(A, B) :- A @> B.";
+        let err = parse(source).unwrap_err();
+        assert_eq!(&source[err.span.start..err.span.end], "@>");
+        assert_eq!(err.message, "`@>` isn't supported");
+        assert!(parse("p(T) :- T =.. [f, x].").is_err());
+    }
+
+    #[test]
+    fn test_logtalk_rules_parse() {
+        for source in [
+            include_str!("../../gitvisor/rules/cicd_rules.lgt"),
+            include_str!("../../gitvisor/rules/rule_distiller.lgt"),
+        ] {
+            assert!(!clauses(source).is_empty());
+        }
+        assert_eq!(
+            parse_term("secret_pattern('(?i)password\\\\s*=')").unwrap(),
+            compound("secret_pattern", vec![atom("(?i)password\\s*=")])
+        );
+    }
+
+    #[test]
+    fn test_display_reads_back() {
+        let source = "0.9 :: requires(network, 'NetworkManager').\n\
+                      fix(D, 'modprobe -r nouveau') :- \
+                          loaded(D), \\+ blacklisted(D), X is -1 + 2 * (3 - 4).\n\
+                      high :- (mem_pct(P), P >= 90.5 ; swap(_)), X =/= [a, 'it''s'].\n";
+        let parsed = clauses(source);
+        let printed: Vec<String> = parsed.iter().map(|c| c.to_string()).collect();
+        assert_eq!(printed[0], "0.9 :: requires(network, 'NetworkManager').");
+        assert_eq!(
+            printed[1],
+            "fix(D, 'modprobe -r nouveau') :- loaded(D), \\+ blacklisted(D), X is -1 + 2 * (3 - 4)."
+        );
+        let reparsed = clauses(&printed.join("\n"));
+        for (a, b) in parsed.iter().zip(&reparsed) {
+            assert_eq!(a.head, b.head);
+            assert_eq!(a.body, b.body);
+            assert_eq!(a.confidence, b.confidence);
+        }
+        assert!(printed[2].contains("swap(_)"));
+        assert_eq!(compound("-", vec![atom("1")]).to_string(), "- 1");
+        assert_eq!(atom("").to_string(), "''");
+    }
+
+    #[test]
+    fn test_parse_term() {
+        assert_eq!(
+            parse_term("depends_on(nginx, X).").unwrap(),
+            compound("depends_on", vec![atom("nginx"), var("X")])
+        );
+        assert_eq!(parse_term("- (1)").unwrap(), compound("-", vec![atom("1")]));
+        // `_` never means a variable the user named `_1`
+        let term = parse_term("p(_, _1, _)").unwrap();
+        assert_eq!(variables(&term), ["_#1", "_1", "_#2"]);
+        assert!(parse_term("a b").is_err());
+        assert!(parse_term("").is_err());
+    }
+}
@@ -0,0 +1,237 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+//! Proofs: how an answer was derived
+//!
+//! Every [`super::kanren::State`] carries a [`Trace`] of the search steps
+//! that led to it: which clause was entered for which goal, where the
+//! clause's body ended, and which built-ins and tabled answers held. The
+//! trace is a persistent list, so branches share what they have in common
+//! and recording a step costs one allocation. Once a state is an answer, its
+//! trace is read back into a [`Proof`] tree with the answer's bindings
+//! filled in.
+
+use std::fmt;
+use std::rc::Rc;
+
+use super::{kanren, rename, variables, Clause, Substitution, Term};
+
+/// A step of the search
+#[derive(Debug)]
+pub enum Event {
+    /// Resolving `goal` with the clause at index `clause`, its variables
+    /// renamed with `suffix`
+    Enter {
+        goal: Term,
+        clause: usize,
+        suffix: usize,
+    },
+    /// The end of the body of the clause entered last
+    Exit,
+    /// A built-in goal or constraint
+    BuiltIn(Term),
+    /// An answer read from a table, with its derivation
+    Tabled(Rc<Proof>),
+}
+
+/// The steps that led to a state, latest first
+#[derive(Debug)]
+pub struct Trace {
+    pub event: Event,
+    pub prev: Option<Rc<Trace>>,
+}
+
+/// A derivation of a goal
+#[derive(Debug, Clone)]
+pub struct Proof {
+    /// The goal, with the answer's bindings
+    pub goal: Term,
+    pub step: Step,
+    /// Proofs of the goals of the clause body, in order
+    pub premises: Vec<Rc<Proof>>,
+    /// Product of the confidences of the clauses used
+    pub confidence: f32,
+}
+
+/// What a goal holds by
+#[derive(Debug, Clone)]
+pub enum Step {
+    /// A clause whose head matched the goal, with the values its variables
+    /// took
+    Clause {
+        clause: Clause,
+        bindings: Vec<(String, Term)>,
+    },
+    /// A built-in goal or constraint, such as `M > 90` or `\+ redundant(db)`
+    BuiltIn,
+}
+
+/// Read a trace back into the proofs of the goals it resolved, in order
+///
+/// Remaining variables are named `_0`, `_1`, … continuing from `names`.
+pub fn proofs(
+    trace: Option<&Rc<Trace>>,
+    subst: &Substitution,
+    clauses: &[Clause],
+    names: &mut Vec<String>,
+) -> Vec<Rc<Proof>> {
+    let mut events = vec![];
+    let mut next = trace;
+    while let Some(trace) = next {
+        events.push(&trace.event);
+        next = trace.prev.as_ref();
+    }
+    let mut events = events.into_iter().rev();
+    premises(&mut events, subst, clauses, names)
+}
+
+/// Proofs up to the end of the current clause body
+fn premises<'e>(
+    events: &mut impl Iterator<Item = &'e Event>,
+    subst: &Substitution,
+    clauses: &[Clause],
+    names: &mut Vec<String>,
+) -> Vec<Rc<Proof>> {
+    let mut proofs = vec![];
+    while let Some(event) = events.next() {
+        let proof = match event {
+            Event::Exit => break,
+            Event::Tabled(proof) => proof.clone(),
+            Event::BuiltIn(goal) => Rc::new(Proof {
+                goal: kanren::reify_with(goal, subst, names),
+                step: Step::BuiltIn,
+                premises: vec![],
+                confidence: 1.0,
+            }),
+            Event::Enter {
+                goal,
+                clause,
+                suffix,
+            } => {
+                let clause = &clauses[*clause];
+                let goal = kanren::reify_with(goal, subst, names);
+                let mut all = vec![clause.head.clone()];
+                all.extend(clause.body.iter().cloned());
+                let bindings = variables(&Term::List(all))
+                    .into_iter()
+                    .filter(|v| !v.starts_with('_'))
+                    .map(|v| {
+                        let value = rename(&Term::Var(v.clone()), *suffix);
+                        (v, kanren::reify_with(&value, subst, names))
+                    })
+                    .collect();
+                let premises = premises(events, subst, clauses, names);
+                let confidence = premises
+                    .iter()
+                    .fold(clause.confidence, |c, p| c * p.confidence);
+                Rc::new(Proof {
+                    goal,
+                    step: Step::Clause {
+                        clause: clause.clone(),
+                        bindings,
+                    },
+                    premises,
+                    confidence,
+                })
+            }
+        };
+        proofs.push(proof);
+    }
+    proofs
+}
+
+impl Proof {
+    /// The clause nodes in the order their conclusions were established:
+    /// each one after its premises
+    pub fn derivation(&self) -> Vec<&Proof> {
+        fn walk<'p>(proof: &'p Proof, nodes: &mut Vec<&'p Proof>) {
+            for premise in &proof.premises {
+                walk(premise, nodes);
+            }
+            if matches!(proof.step, Step::Clause { .. }) {
+                nodes.push(proof);
+            }
+        }
+        let mut nodes = vec![];
+        walk(self, &mut nodes);
+        nodes
+    }
+
+    /// Confidence of the premises together, before the clause's own is
+    /// applied
+    pub fn premise_confidence(&self) -> f32 {
+        self.premises.iter().map(|p| p.confidence).product()
+    }
+
+    /// What the goal holds by, in a line: the rule and its bindings, the
+    /// fact and where it came from, or that it's built in
+    pub fn justification(&self) -> String {
+        match &self.step {
+            Step::BuiltIn => "built-in".to_string(),
+            Step::Clause { clause, .. } if clause.body.is_empty() => match &clause.source {
+                Some(id) => format!("fact from solution {}", id),
+                None => "fact".to_string(),
+            },
+            Step::Clause { clause, bindings } if bindings.is_empty() => format!("by {}", clause),
+            Step::Clause { clause, bindings } => {
+                format!("by {} ({})", clause, write_bindings(bindings))
+            }
+        }
+    }
+
+    fn write(&self, f: &mut fmt::Formatter, first: &str, rest: &str) -> fmt::Result {
+        write!(f, "{}{}", first, self.goal)?;
+        let (clause, bindings) = match &self.step {
+            Step::BuiltIn => return writeln!(f, "  [{}]", self.justification()),
+            Step::Clause { clause, .. } if clause.body.is_empty() => {
+                return writeln!(f, "  {:.2}  [{}]", self.confidence, self.justification())
+            }
+            Step::Clause { clause, bindings } => (clause, bindings),
+        };
+        writeln!(f, "  {:.2}", self.confidence)?;
+
+        let bar = if self.premises.is_empty() {
+            "  "
+        } else {
+            "│ "
+        };
+        writeln!(f, "{}{}by {}", rest, bar, clause)?;
+        if !bindings.is_empty() {
+            writeln!(f, "{}{}with {}", rest, bar, write_bindings(bindings))?;
+        }
+        let factors: Vec<String> = std::iter::once(clause.confidence)
+            .chain(self.premises.iter().map(|p| p.confidence))
+            .map(|c| format!("{:.2}", c))
+            .collect();
+        writeln!(
+            f,
+            "{}{}{:.2} = {}",
+            rest,
+            bar,
+            self.confidence,
+            factors.join(" × ")
+        )?;
+        for (i, premise) in self.premises.iter().enumerate() {
+            if i + 1 == self.premises.len() {
+                premise.write(f, &format!("{}└─ ", rest), &format!("{}   ", rest))?;
+            } else {
+                premise.write(f, &format!("{}├─ ", rest), &format!("{}│  ", rest))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The proof as a tree, one goal per line with what it holds by and the
+/// confidences multiplied
+impl fmt::Display for Proof {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.write(f, "", "")
+    }
+}
+
+fn write_bindings(bindings: &[(String, Term)]) -> String {
+    let bindings: Vec<String> = bindings
+        .iter()
+        .map(|(name, value)| format!("{} = {}", name, value))
+        .collect();
+    bindings.join(", ")
+}
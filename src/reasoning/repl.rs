@@ -0,0 +1,516 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+//! Interactive reasoning session: `psa reason`
+//!
+//! The session starts from the same knowledge base `psa diagnose` reasons
+//! over (the prelude, the knowledge base files in the data directory and the
+//! facts derived from stored solutions) and lets you add, retract and
+//! reweigh clauses and query the result. A plain query stops after
+//! [`QUERY_LIMIT`] answers, so one with infinitely many doesn't hang the
+//! session; `all` asks for every answer. Changes only last for the session
+//! unless `save`d to a file. Input history is appended to a file in the data
+//! directory line by line, and Tab completes commands and predicate names.
+
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Context as _, Result};
+use rustyline::completion::Completer;
+use rustyline::error::ReadlineError;
+use rustyline::highlight::Highlighter;
+use rustyline::hint::Hinter;
+use rustyline::history::DefaultHistory;
+use rustyline::validate::Validator;
+use rustyline::{Editor, Helper};
+
+use super::{parser, variables, Answer, Clause, ReasoningEngine, Term};
+use crate::storage::Storage;
+
+/// Answers a plain query or `explain` looks for before stopping
+pub const QUERY_LIMIT: usize = 20;
+
+const HELP: &str = "\
+GOAL                  up to 20 answers, most confident first (also query GOAL)
+all GOAL              every answer, most confident first; may not return
+add CLAUSE            add a fact or rule, e.g. add 0.9 :: requires(nginx, network).
+run N GOAL            the first N answers, in search order
+explain GOAL          up to 20 answers with their proofs
+list [NAME[/ARITY]]   the clauses of a predicate, numbered; all predicates without one
+retract N | HEAD      remove clause N, or every clause whose head matches HEAD
+confidence N VALUE    set the confidence of clause N (0 to 1)
+load PATH             add the clauses of a knowledge base file
+save PATH             write the clauses added or changed in this session to a file
+help                  this list
+quit                  leave (also Ctrl-D)";
+
+const COMMANDS: &[&str] = &[
+    "add",
+    "query",
+    "all",
+    "run",
+    "explain",
+    "list",
+    "retract",
+    "confidence",
+    "load",
+    "save",
+    "help",
+    "quit",
+];
+
+/// A reasoning engine being explored
+pub struct Session {
+    engine: ReasoningEngine,
+    /// The clauses and table declarations the session started with, which
+    /// `save` leaves out
+    initial: Vec<String>,
+    initial_tabled: Vec<super::Predicate>,
+}
+
+impl Session {
+    pub fn new(engine: ReasoningEngine) -> Self {
+        Self {
+            initial: engine.clauses().iter().map(Clause::to_string).collect(),
+            initial_tabled: engine.tabled(),
+            engine,
+        }
+    }
+
+    /// Carry out one line of input, returning what to print, or `None` to
+    /// leave. A line that doesn't start with a command is a query.
+    pub fn command(&mut self, line: &str) -> Result<Option<String>> {
+        let line = line.trim();
+        let line = line.strip_prefix("?-").map_or(line, str::trim_start);
+        let (command, rest) = match line.split_once(char::is_whitespace) {
+            Some((command, rest)) if COMMANDS.contains(&command) => (command, rest.trim()),
+            _ if COMMANDS.contains(&line) || line == "exit" || line.is_empty() => (line, ""),
+            _ => ("query", line),
+        };
+
+        let output = match command {
+            "" => String::new(),
+            "add" => self.add(rest)?,
+            "query" => self.bounded(rest, false)?,
+            "all" => {
+                let goal = goal(rest)?;
+                self.answers(&goal, self.engine.explain(&goal), false)
+            }
+            "run" => {
+                let (n, rest) = rest
+                    .split_once(char::is_whitespace)
+                    .context("usage: run N GOAL")?;
+                let n: usize = n.parse().context("usage: run N GOAL")?;
+                let goal = goal(rest)?;
+                self.answers(&goal, self.engine.prove(Some(n), &goal), false)
+            }
+            "explain" => self.bounded(rest, true)?,
+            "list" => self.list(rest)?,
+            "retract" => self.retract(rest)?,
+            "confidence" => self.confidence(rest)?,
+            "load" => {
+                let path = path(rest)?;
+                let clauses = self.engine.load_file(&path)?;
+                format!("Loaded {} clauses from {}", clauses, path.display())
+            }
+            "save" => self.save(&path(rest)?)?,
+            "help" => HELP.to_string(),
+            "quit" | "exit" => return Ok(None),
+            _ => unreachable!("anything else is a query"),
+        };
+        Ok(Some(output))
+    }
+
+    /// Names of the predicates with clauses, to complete
+    fn completions(&self) -> Vec<String> {
+        let mut names: Vec<String> = self.engine.predicates().into_iter().map(|p| p.0).collect();
+        names.dedup();
+        names
+    }
+
+    fn add(&mut self, source: &str) -> Result<String> {
+        let source = if source.ends_with('.') {
+            source.to_string()
+        } else {
+            format!("{}.", source)
+        };
+        let clauses = self
+            .engine
+            .load(&source)
+            .map_err(|e| anyhow::anyhow!(e.render("input", &source)))?;
+        let count = self.engine.clauses().len();
+        Ok(match clauses {
+            0 => "No clauses added".to_string(),
+            1 => format!("Added as clause {}", count),
+            n => format!("Added as clauses {} to {}", count - n + 1, count),
+        })
+    }
+
+    /// Up to [`QUERY_LIMIT`] answers, most confident first, noting if there
+    /// may be more
+    fn bounded(&self, source: &str, proofs: bool) -> Result<String> {
+        let goal = goal(source)?;
+        let mut answers = self.engine.prove(Some(QUERY_LIMIT + 1), &goal);
+        let more = answers.len() > QUERY_LIMIT;
+        answers.truncate(QUERY_LIMIT);
+        answers.sort_by(|a, b| b.confidence.total_cmp(&a.confidence));
+        let mut out = self.answers(&goal, answers, proofs);
+        if more {
+            let _ = write!(
+                out,
+                "\n(stopped after {} answers; `all {}` finds every one)",
+                QUERY_LIMIT,
+                source.trim().trim_end_matches('.')
+            );
+        }
+        Ok(out)
+    }
+
+    /// One line per answer, binding the goal's named variables
+    fn answers(&self, goal: &Term, answers: Vec<Answer>, proofs: bool) -> String {
+        let names: Vec<String> = variables(goal)
+            .into_iter()
+            .filter(|v| !v.starts_with('_'))
+            .collect();
+        let mut out = String::new();
+        if answers.is_empty() {
+            out.push_str("false.");
+        }
+        for answer in &answers {
+            // Undecided constraints follow the bindings, as Prolog shows them
+            let bindings: Vec<String> = names
+                .iter()
+                .map(|name| format!("{} = {}", name, answer.bindings[name]))
+                .chain(answer.residual.iter().map(Term::to_string))
+                .collect();
+            let shown = if bindings.is_empty() {
+                "true".to_string()
+            } else {
+                bindings.join(", ")
+            };
+            let _ = writeln!(out, "{}  ({:.2})", shown, answer.confidence);
+            if proofs {
+                for proof in &answer.proofs {
+                    for line in proof.to_string().lines() {
+                        let _ = writeln!(out, "    {}", line);
+                    }
+                }
+            }
+        }
+        if self.engine.truncated() {
+            out.push_str("(the search hit its depth or pass limit; answers may be missing)");
+        }
+        out.trim_end().to_string()
+    }
+
+    fn list(&self, predicate: &str) -> Result<String> {
+        let mut out = String::new();
+        if predicate.is_empty() {
+            for (name, arity) in self.engine.predicates() {
+                let count = self
+                    .engine
+                    .clauses()
+                    .iter()
+                    .filter(|c| super::predicate(&c.head) == Some((name.clone(), arity)))
+                    .count();
+                let _ = writeln!(out, "{}/{}  ({} clauses)", name, arity, count);
+            }
+            return Ok(out.trim_end().to_string());
+        }
+
+        let (name, arity) = match predicate.rsplit_once('/') {
+            Some((name, arity)) => (name, Some(arity.parse::<usize>().context("bad arity")?)),
+            None => (predicate, None),
+        };
+        for (i, clause) in self.engine.clauses().iter().enumerate() {
+            let Some((n, a)) = super::predicate(&clause.head) else {
+                continue;
+            };
+            if n == name && arity.is_none_or(|arity| arity == a) {
+                let _ = write!(out, "{:>4}  {}", i + 1, clause);
+                if let Some(id) = &clause.source {
+                    let _ = write!(out, "  % from solution {}", id);
+                }
+                out.push('\n');
+            }
+        }
+        if out.is_empty() {
+            bail!("no clauses for {}", predicate);
+        }
+        Ok(out.trim_end().to_string())
+    }
+
+    fn retract(&mut self, what: &str) -> Result<String> {
+        if let Ok(n) = what.parse::<usize>() {
+            let clause = n
+                .checked_sub(1)
+                .and_then(|i| self.engine.retract(i))
+                .with_context(|| format!("no clause {}", n))?;
+            return Ok(format!("Retracted {}", clause));
+        }
+        let head = goal(what)?;
+        match self.engine.retract_all(&head) {
+            0 => bail!("no clause matches {}", head),
+            n => Ok(format!("Retracted {} clauses", n)),
+        }
+    }
+
+    fn confidence(&mut self, args: &str) -> Result<String> {
+        const USAGE: &str = "usage: confidence N VALUE";
+        let (n, value) = args.split_once(char::is_whitespace).context(USAGE)?;
+        let n: usize = n.parse().context(USAGE)?;
+        let value: f32 = value.trim().parse().context(USAGE)?;
+        if !(0.0..=1.0).contains(&value) {
+            bail!("confidence must be from 0 to 1");
+        }
+        if !n
+            .checked_sub(1)
+            .is_some_and(|i| self.engine.set_confidence(i, value))
+        {
+            bail!("no clause {}", n);
+        }
+        Ok(format!("{}", self.engine.clauses()[n - 1]))
+    }
+
+    /// Write the table declarations and clauses this session added, or
+    /// changed the confidence of; the prelude, installed knowledge base
+    /// files and facts derived from solutions are already loaded elsewhere
+    fn save(&self, path: &Path) -> Result<String> {
+        let mut out = String::from("% Saved from psa reason\n");
+        for (name, arity) in self.engine.tabled() {
+            if !self.initial_tabled.contains(&(name.clone(), arity)) {
+                let _ = writeln!(out, ":- table {}/{}.", name, arity);
+            }
+        }
+        // Each initial clause accounts for one identical clause
+        let mut initial: Vec<&String> = self.initial.iter().collect();
+        let mut saved = 0;
+        for clause in self.engine.clauses() {
+            let text = clause.to_string();
+            if let Some(i) = initial.iter().position(|t| **t == text) {
+                initial.swap_remove(i);
+            } else if clause.source.is_none() {
+                out.push_str(&text);
+                out.push('\n');
+                saved += 1;
+            }
+        }
+        std::fs::write(path, out).with_context(|| format!("Failed to write {}", path.display()))?;
+        Ok(format!("Saved {} clauses to {}", saved, path.display()))
+    }
+}
+
+fn goal(source: &str) -> Result<Term> {
+    parser::parse_term(source).map_err(|e| anyhow::anyhow!(e.render("input", source)))
+}
+
+fn path(arg: &str) -> Result<PathBuf> {
+    if arg.is_empty() {
+        bail!("expected a file path");
+    }
+    let path = match arg.strip_prefix("~/") {
+        Some(rest) => match std::env::var_os("HOME") {
+            Some(home) => Path::new(&home).join(rest),
+            None => PathBuf::from(arg),
+        },
+        None => PathBuf::from(arg),
+    };
+    Ok(path)
+}
+
+/// Completes predicate names, and commands at the start of the line
+struct ReplHelper {
+    predicates: Vec<String>,
+}
+
+impl Completer for ReplHelper {
+    type Candidate = String;
+
+    fn complete(
+        &self,
+        line: &str,
+        pos: usize,
+        _: &rustyline::Context<'_>,
+    ) -> rustyline::Result<(usize, Vec<String>)> {
+        let start = line[..pos]
+            .rfind(|c: char| !(c.is_alphanumeric() || c == '_'))
+            .map_or(0, |i| i + 1);
+        let word = &line[start..pos];
+        let mut candidates: Vec<String> = self
+            .predicates
+            .iter()
+            .filter(|name| name.starts_with(word))
+            .cloned()
+            .collect();
+        if line[..start].trim().is_empty() {
+            candidates.extend(
+                COMMANDS
+                    .iter()
+                    .filter(|name| name.starts_with(word))
+                    .map(|name| name.to_string()),
+            );
+        }
+        Ok((start, candidates))
+    }
+}
+
+impl Hinter for ReplHelper {
+    type Hint = String;
+}
+
+impl Highlighter for ReplHelper {}
+
+impl Validator for ReplHelper {}
+
+impl Helper for ReplHelper {}
+
+/// Run the interactive session until the user quits
+pub async fn run(storage: &Storage) -> Result<()> {
+    let mut session = Session::new(ReasoningEngine::from_storage(storage).await?);
+    let history = crate::dirs::data_dir().join("reason_history");
+
+    let mut editor: Editor<ReplHelper, DefaultHistory> = Editor::new()?;
+    editor.set_helper(Some(ReplHelper {
+        predicates: session.completions(),
+    }));
+    // No history yet on first use
+    let _ = editor.load_history(&history);
+    if let Some(dir) = history.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+
+    println!(
+        "Reasoning over {} clauses; `help` lists the commands.",
+        session.engine.clauses().len()
+    );
+    println!("Changes last for this session unless saved.");
+    loop {
+        let line = match editor.readline("?- ") {
+            Ok(line) => line,
+            Err(ReadlineError::Interrupted) => continue,
+            Err(ReadlineError::Eof) => break,
+            Err(e) => return Err(e.into()),
+        };
+        // Appended right away, so an interrupted session keeps it
+        if !line.trim().is_empty() {
+            let added = editor
+                .add_history_entry(line.as_str())
+                .and_then(|_| editor.append_history(&history));
+            if let Err(e) = added {
+                tracing::warn!("Failed to save history: {}", e);
+            }
+        }
+        match session.command(&line) {
+            Ok(Some(output)) if output.is_empty() => {}
+            Ok(Some(output)) => println!("{}", output),
+            Ok(None) => break,
+            Err(e) => println!("Error: {:#}", e),
+        }
+        if let Some(helper) = editor.helper_mut() {
+            helper.predicates = session.completions();
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::reasoning::facts;
+
+    fn run(session: &mut Session, line: &str) -> String {
+        session.command(line).unwrap().unwrap()
+    }
+
+    #[test]
+    fn test_session_commands() {
+        let mut engine = ReasoningEngine::new();
+        engine.load(facts::PRELUDE).unwrap();
+        let mut session = Session::new(engine);
+
+        assert_eq!(
+            run(&mut session, "add 0.9 :: requires(app, db)"),
+            "Added as clause 3"
+        );
+        run(
+            &mut session,
+            "add requires(db, disk). dep(A, B) :- requires(A, B).",
+        );
+        run(&mut session, "add dep(A, C) :- requires(A, B), dep(B, C).");
+        assert_eq!(
+            run(&mut session, "dep(app, X)"),
+            "X = db  (0.90)\nX = disk  (0.90)"
+        );
+        assert_eq!(run(&mut session, "run 1 dep(app, X)"), "X = db  (0.90)");
+        assert_eq!(run(&mut session, "?- dep(disk, app)."), "false.");
+        assert!(run(&mut session, "explain dep(app, disk)")
+            .contains("├─ requires(app, db)  0.90  [fact]"));
+        assert_eq!(
+            run(&mut session, "list requires/2"),
+            "   3  0.9 :: requires(app, db).\n   4  requires(db, disk)."
+        );
+
+        assert_eq!(
+            run(&mut session, "confidence 3 0.5"),
+            "0.5 :: requires(app, db)."
+        );
+        assert_eq!(run(&mut session, "run 1 dep(app, X)"), "X = db  (0.50)");
+        assert!(session.command("confidence 3 2").is_err());
+        assert_eq!(
+            run(&mut session, "retract requires(db, _)"),
+            "Retracted 1 clauses"
+        );
+        assert_eq!(run(&mut session, "dep(app, X)"), "X = db  (0.50)");
+        assert!(session.command("retract 99").is_err());
+        assert!(session.command("dep(app").is_err());
+        assert_eq!(
+            session.completions(),
+            ["dep", "fix", "requires"].map(String::from)
+        );
+
+        // Saving leaves out the prelude, and the file loads back
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("session.kb");
+        let saved = run(&mut session, &format!("save {}", path.display()));
+        assert!(saved.starts_with("Saved 3 clauses"));
+        let mut reloaded = ReasoningEngine::new();
+        assert_eq!(reloaded.load_file(&path).unwrap(), 3);
+        assert!(session.command("quit").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_queries_are_bounded() {
+        let mut engine = ReasoningEngine::new();
+        engine.load("nat(z).\nnat(s(N)) :- nat(N).\n").unwrap();
+        let mut session = Session::new(engine);
+
+        let out = run(&mut session, "nat(X)");
+        assert_eq!(out.lines().filter(|l| l.starts_with("X = ")).count(), QUERY_LIMIT);
+        assert!(out.contains("`all nat(X)` finds every one"));
+        let out = run(&mut session, "explain nat(s(X))");
+        assert!(out.contains("(stopped after 20 answers"));
+        assert_eq!(run(&mut session, "all nat(s(z))"), "true  (1.00)");
+    }
+
+    #[test]
+    fn test_save_writes_only_session_changes() {
+        // As if from the prelude and an installed knowledge base file
+        let mut engine = ReasoningEngine::new();
+        engine
+            .load(":- table reach/2.\n0.8 :: requires(app, db).\nredundant(storage).\n")
+            .unwrap();
+        let mut session = Session::new(engine);
+        run(&mut session, "add requires(db, disk)");
+        run(&mut session, "add :- table dep/2.");
+        run(&mut session, "confidence 1 0.5");
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("session.kb");
+        let saved = run(&mut session, &format!("save {}", path.display()));
+        assert!(saved.starts_with("Saved 2 clauses"), "{}", saved);
+        let written = std::fs::read_to_string(&path).unwrap();
+        assert!(written.contains(":- table dep/2."));
+        assert!(!written.contains("reach"));
+        assert!(!written.contains("redundant"));
+        assert!(written.contains("0.5 :: requires(app, db)."));
+        assert!(written.contains("requires(db, disk)."));
+    }
+}
@@ -0,0 +1,160 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+//! Answer tables for tabled predicates
+//!
+//! A call to a tabled predicate isn't resolved by recursing into its clauses
+//! again; its answers are computed once, to a fixpoint, and then read from a
+//! table. While a table is being computed, recursive calls see the answers
+//! found so far, and the outermost call (the leader) repeats the evaluation
+//! until a whole pass adds no answer and improves no confidence. That makes
+//! left-recursive rules such as `depends_on(A, C) :- depends_on(A, B),
+//! depends_on(B, C)` terminate, and gives each answer the confidence of its
+//! best derivation. A leader that hits [`MAX_PASSES`] still completes its
+//! tables, but marks them truncated, so later lookups report the answers as
+//! possibly incomplete too.
+
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use super::constraint::Constraint;
+use super::proof::Proof;
+use super::{kanren, Substitution, Term};
+
+/// Passes a leader makes before giving up on reaching a fixpoint, e.g. for a
+/// predicate with infinitely many answers
+pub const MAX_PASSES: usize = 64;
+
+/// An instance of a tabled call with its best derivation found
+#[derive(Debug, Clone)]
+pub struct Answer {
+    pub term: Term,
+    /// Constraints on the answer's variables that were still undecided,
+    /// such as `_0 > 10`
+    pub residual: Vec<Constraint>,
+    pub confidence: f32,
+    pub proof: Rc<Proof>,
+}
+
+/// The answers of one call pattern
+#[derive(Debug, Clone, Default)]
+pub struct Table {
+    pub answers: Vec<Answer>,
+    /// Position of each answer in `answers`, by term and residual
+    index: HashMap<(Term, Vec<Term>), usize>,
+    /// Whether the answers are final
+    pub complete: bool,
+    /// Whether evaluation stopped at the pass limit, so answers may be missing
+    pub truncated: bool,
+    /// Leader pass in which the table was last evaluated
+    pass: usize,
+}
+
+/// The tables of one engine; cleared whenever its clauses change
+#[derive(Debug, Default)]
+pub struct Tables {
+    entries: HashMap<String, Table>,
+    /// Current pass of the leader, 0 when no table is being computed
+    pass: usize,
+    /// Whether the current pass found anything new
+    changed: bool,
+    /// Whether the current leader read a truncated table
+    tainted: bool,
+}
+
+/// What a tabled call should do next
+pub enum Lookup {
+    /// Use these answers; `truncated` if some may be missing
+    Answers {
+        answers: Vec<Answer>,
+        truncated: bool,
+    },
+    /// Evaluate the clauses as the leader
+    Lead,
+    /// Evaluate the clauses once within the leader's pass
+    Evaluate,
+}
+
+/// Calls that are variants of each other (equal up to variable names) share
+/// a table
+pub fn variant_key(call: &Term) -> String {
+    format!("{:?}", kanren::reify(call, &Substitution::new()))
+}
+
+impl Tables {
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    pub fn lookup(&mut self, key: &str) -> Lookup {
+        match self.entries.get(key) {
+            Some(table) if table.complete || table.pass == self.pass => {
+                // Tables built on a truncated one are missing answers too
+                self.tainted |= table.truncated && self.pass > 0;
+                Lookup::Answers {
+                    answers: table.answers.clone(),
+                    truncated: table.truncated,
+                }
+            }
+            _ if self.pass == 0 => Lookup::Lead,
+            _ => Lookup::Evaluate,
+        }
+    }
+
+    /// Start a leader pass; false once the fixpoint is reached or the pass
+    /// limit hit
+    pub fn next_pass(&mut self) -> bool {
+        if self.pass > 0 && (!self.changed || self.pass >= MAX_PASSES) {
+            return false;
+        }
+        self.pass += 1;
+        self.changed = false;
+        true
+    }
+
+    /// Whether the leader stopped at the pass limit rather than a fixpoint,
+    /// or used a table that did
+    pub fn exhausted(&self) -> bool {
+        self.changed || self.tainted
+    }
+
+    /// End the leader's evaluation: everything computed is now final, and
+    /// truncated if the leader was [`Self::exhausted`]
+    pub fn finish(&mut self) {
+        let truncated = self.exhausted();
+        for table in self.entries.values_mut().filter(|t| !t.complete) {
+            table.complete = true;
+            table.truncated = truncated;
+        }
+        self.pass = 0;
+        self.changed = false;
+        self.tainted = false;
+    }
+
+    /// Mark a table as evaluated in this pass, so recursive calls read it
+    pub fn enter(&mut self, key: &str) {
+        let pass = self.pass;
+        self.entries.entry(key.to_string()).or_default().pass = pass;
+    }
+
+    /// Record an answer, keeping the better derivation of a repeated one
+    pub fn add(&mut self, key: &str, answer: Answer) {
+        let table = self.entries.entry(key.to_string()).or_default();
+        let residual = answer.residual.iter().map(Constraint::to_term).collect();
+        let id = (answer.term.clone(), residual);
+        match table.index.get(&id) {
+            Some(&i) if table.answers[i].confidence >= answer.confidence => return,
+            Some(&i) => table.answers[i] = answer,
+            None => {
+                table.index.insert(id, table.answers.len());
+                table.answers.push(answer);
+            }
+        }
+        self.changed = true;
+    }
+
+    pub fn answers(&self, key: &str) -> Vec<Answer> {
+        self.entries
+            .get(key)
+            .map(|t| t.answers.clone())
+            .unwrap_or_default()
+    }
+}
@@ -0,0 +1,527 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+//! Offline security advisory import
+//!
+//! Reads advisory data that has been placed on disk, with no network access:
+//! - OSV JSON (one advisory per file, an array, or a directory of them),
+//!   restricted to distribution ecosystems
+//! - Arch Linux security tracker JSON (`security.archlinux.org/all.json`)
+//! - Debian security tracker JSON (`security-tracker.debian.org/tracker/data/json`)
+//!
+//! Every format is normalised to [`Advisory`], keyed by CVE ID where one exists.
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::cmp::Ordering;
+use std::path::Path;
+
+use super::probe::PackageManager;
+use super::version;
+
+/// A vulnerability and the distribution packages it affects
+#[derive(Debug, Clone, PartialEq)]
+pub struct Advisory {
+    /// CVE ID if known, otherwise the feed's own ID
+    pub id: String,
+    pub summary: String,
+    pub affected: Vec<AffectedPackage>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AffectedPackage {
+    pub name: String,
+    /// Source of the version data, e.g. `Debian:12` or `Arch Linux`
+    pub ecosystem: String,
+    pub ranges: Vec<VersionRange>,
+}
+
+/// Affected versions: `introduced <= v < fixed` (open-ended if `None`)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct VersionRange {
+    pub introduced: Option<String>,
+    pub fixed: Option<String>,
+}
+
+/// The host's distribution and release, from `/etc/os-release`
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct HostDistro {
+    /// `ID`, e.g. `debian`, `fedora`, `arch`
+    pub id: String,
+    /// `ID_LIKE`, for derivatives without their own advisory feed
+    pub id_like: Vec<String>,
+    pub version_id: Option<String>,
+    pub codename: Option<String>,
+}
+
+impl HostDistro {
+    /// Read `/etc/os-release`
+    pub fn detect() -> Option<Self> {
+        Self::parse(&std::fs::read_to_string("/etc/os-release").ok()?)
+    }
+
+    pub fn parse(content: &str) -> Option<Self> {
+        let field = |key: &str| {
+            content
+                .lines()
+                .find_map(|l| l.strip_prefix(key)?.strip_prefix('='))
+                .map(|v| v.trim().trim_matches('"').to_string())
+                .filter(|v| !v.is_empty())
+        };
+        Some(Self {
+            id: field("ID")?,
+            id_like: field("ID_LIKE")
+                .map(|v| v.split_whitespace().map(str::to_string).collect())
+                .unwrap_or_default(),
+            version_id: field("VERSION_ID"),
+            codename: field("VERSION_CODENAME"),
+        })
+    }
+}
+
+/// Advisory ecosystems and the os-release `ID` of their distribution
+const ECOSYSTEM_DISTROS: &[(&str, &str)] = &[
+    ("Debian", "debian"),
+    ("Ubuntu", "ubuntu"),
+    ("Alpine", "alpine"),
+    ("Arch Linux", "arch"),
+    ("AlmaLinux", "almalinux"),
+    ("Rocky Linux", "rocky"),
+    ("Red Hat", "rhel"),
+    ("Fedora", "fedora"),
+    ("openSUSE", "opensuse"),
+    ("SUSE", "sles"),
+    ("Mageia", "mageia"),
+    ("Photon OS", "photon"),
+    ("Wolfi", "wolfi"),
+];
+
+/// Is `id` the distribution `target` or a variant of it (`opensuse-leap`)
+fn same_distro(id: &str, target: &str) -> bool {
+    id == target || id.strip_prefix(target).is_some_and(|rest| rest.starts_with('-'))
+}
+
+impl AffectedPackage {
+    /// Whether this version data is for the host's distribution, and for
+    /// its release where the ecosystem names one (`Debian:12`,
+    /// `Debian:bookworm`, `Alpine:v3.19`)
+    ///
+    /// Fixed versions differ between distributions, so only data that
+    /// applies may be compared with installed versions. Derivatives use
+    /// their parent's data when they have none of their own.
+    pub fn applies_to(&self, distro: &HostDistro) -> bool {
+        let mut parts = self.ecosystem.split(':');
+        let base = parts.next().unwrap_or_default();
+        let Some(&(_, target)) = ECOSYSTEM_DISTROS.iter().find(|(e, _)| *e == base) else {
+            return false;
+        };
+        let own_feed = ECOSYSTEM_DISTROS
+            .iter()
+            .any(|(_, id)| same_distro(&distro.id, id));
+        let distro_matches = same_distro(&distro.id, target)
+            || (!own_feed && distro.id_like.iter().any(|id| same_distro(id, target)));
+        if !distro_matches {
+            return false;
+        }
+
+        let Some(release) = parts.next().filter(|r| !r.is_empty()) else {
+            return true;
+        };
+        let number = release.trim_start_matches('v');
+        if number.starts_with(|c: char| c.is_ascii_digit()) {
+            return distro
+                .version_id
+                .as_deref()
+                .is_some_and(|v| v == number || v.starts_with(&format!("{}.", number)));
+        }
+        // A codename; other qualifiers (`enterprise_linux`) aren't releases
+        distro.codename.as_deref().is_none_or(|c| c == release)
+            || !release.chars().all(|c| c.is_ascii_lowercase())
+    }
+
+    /// Is `installed` inside any affected range
+    ///
+    /// Versions are only comparable within one distribution: check
+    /// [`Self::applies_to`] first. Without range data the package is
+    /// assumed affected.
+    pub fn is_affected(&self, installed: &str, manager: PackageManager) -> bool {
+        if self.ranges.is_empty() {
+            return true;
+        }
+        self.ranges.iter().any(|range| {
+            let introduced = range
+                .introduced
+                .as_deref()
+                .filter(|v| *v != "0")
+                .is_none_or(|v| version::compare(manager, installed, v) != Ordering::Less);
+            let below_fix = range
+                .fixed
+                .as_deref()
+                .is_none_or(|v| version::compare(manager, installed, v) == Ordering::Less);
+            introduced && below_fix
+        })
+    }
+
+    /// Fixed versions named in the ranges
+    pub fn fixed_versions(&self) -> Vec<&str> {
+        self.ranges.iter().filter_map(|r| r.fixed.as_deref()).collect()
+    }
+}
+
+/// Load advisories from a file or (recursively) a directory of `.json` files
+pub fn load_path(path: &Path, debian_release: Option<&str>) -> Result<Vec<Advisory>> {
+    if path.is_dir() {
+        let mut entries: Vec<_> = std::fs::read_dir(path)?
+            .filter_map(|e| e.ok().map(|e| e.path()))
+            .collect();
+        entries.sort();
+
+        let mut advisories = vec![];
+        for entry in entries {
+            if entry.is_dir() || entry.extension().is_some_and(|e| e == "json") {
+                advisories.extend(load_path(&entry, debian_release)?);
+            }
+        }
+        return Ok(advisories);
+    }
+
+    let content = std::fs::read_to_string(path)?;
+    parse(&content, debian_release)
+        .map_err(|e| anyhow::anyhow!("{}: {}", path.display(), e))
+}
+
+/// Parse advisory JSON, detecting the format
+pub fn parse(content: &str, debian_release: Option<&str>) -> Result<Vec<Advisory>> {
+    let value: Value = serde_json::from_str(content)?;
+
+    match &value {
+        Value::Array(items) if items.iter().any(|i| i.get("issues").is_some()) => {
+            Ok(parse_arch(items))
+        }
+        Value::Array(items) => Ok(items.iter().filter_map(parse_osv).collect()),
+        Value::Object(map) if map.contains_key("affected") || map.contains_key("modified") => {
+            Ok(parse_osv(&value).into_iter().collect())
+        }
+        Value::Object(_) => {
+            let release = debian_release
+                .map(str::to_string)
+                .or_else(os_release_codename)
+                .ok_or_else(|| anyhow::anyhow!("Debian tracker data needs a release codename"))?;
+            Ok(parse_debian(&value, &release))
+        }
+        _ => Err(anyhow::anyhow!("Unrecognised advisory format")),
+    }
+}
+
+/// OSV ecosystems that describe distribution packages (as opposed to
+/// language registries, whose package names don't match the host's)
+fn is_distro_ecosystem(ecosystem: &str) -> bool {
+    let base = ecosystem.split(':').next().unwrap_or(ecosystem);
+    ECOSYSTEM_DISTROS.iter().any(|(e, _)| *e == base)
+}
+
+fn parse_osv(value: &Value) -> Option<Advisory> {
+    let own_id = value.get("id")?.as_str()?;
+    let id = value
+        .get("aliases")
+        .and_then(Value::as_array)
+        .and_then(|aliases| {
+            aliases
+                .iter()
+                .filter_map(Value::as_str)
+                .find(|a| a.starts_with("CVE-"))
+        })
+        .unwrap_or(own_id)
+        .to_string();
+    let summary = value
+        .get("summary")
+        .or_else(|| value.get("details"))
+        .and_then(Value::as_str)
+        .unwrap_or_default()
+        .to_string();
+
+    let affected: Vec<AffectedPackage> = value
+        .get("affected")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| {
+            let package = entry.get("package")?;
+            let ecosystem = package.get("ecosystem")?.as_str()?;
+            if !is_distro_ecosystem(ecosystem) {
+                return None;
+            }
+            Some(AffectedPackage {
+                name: package.get("name")?.as_str()?.to_string(),
+                ecosystem: ecosystem.to_string(),
+                ranges: osv_ranges(entry),
+            })
+        })
+        .collect();
+
+    (!affected.is_empty()).then_some(Advisory { id, summary, affected })
+}
+
+/// Turn OSV `ECOSYSTEM` range events into `[introduced, fixed)` pairs
+fn osv_ranges(entry: &Value) -> Vec<VersionRange> {
+    let mut ranges = vec![];
+    for range in entry.get("ranges").and_then(Value::as_array).into_iter().flatten() {
+        if range.get("type").and_then(Value::as_str) != Some("ECOSYSTEM") {
+            continue;
+        }
+        let mut current: Option<VersionRange> = None;
+        for event in range.get("events").and_then(Value::as_array).into_iter().flatten() {
+            if let Some(v) = event.get("introduced").and_then(Value::as_str) {
+                ranges.extend(current.take());
+                current = Some(VersionRange {
+                    introduced: Some(v.to_string()),
+                    fixed: None,
+                });
+            } else if let Some(v) = event.get("fixed").and_then(Value::as_str) {
+                let mut r = current.take().unwrap_or(VersionRange {
+                    introduced: None,
+                    fixed: None,
+                });
+                r.fixed = Some(v.to_string());
+                ranges.push(r);
+            }
+        }
+        ranges.extend(current);
+    }
+    ranges
+}
+
+/// Arch tracker: one AVG per entry, listing packages, CVEs and the fixed version
+fn parse_arch(items: &[Value]) -> Vec<Advisory> {
+    let mut advisories = vec![];
+    for item in items {
+        let packages: Vec<&str> = item
+            .get("packages")
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+            .filter_map(Value::as_str)
+            .collect();
+        let fixed = item.get("fixed").and_then(Value::as_str).map(str::to_string);
+        let summary = item.get("type").and_then(Value::as_str).unwrap_or_default();
+
+        for cve in item.get("issues").and_then(Value::as_array).into_iter().flatten() {
+            let Some(cve) = cve.as_str() else { continue };
+            advisories.push(Advisory {
+                id: cve.to_string(),
+                summary: summary.to_string(),
+                affected: packages
+                    .iter()
+                    .map(|name| AffectedPackage {
+                        name: name.to_string(),
+                        ecosystem: "Arch Linux".to_string(),
+                        ranges: vec![VersionRange {
+                            introduced: None,
+                            fixed: fixed.clone(),
+                        }],
+                    })
+                    .collect(),
+            });
+        }
+    }
+    advisories
+}
+
+/// Debian tracker: `{ package: { CVE: { releases: { codename: { status, fixed_version } } } } }`
+fn parse_debian(value: &Value, release: &str) -> Vec<Advisory> {
+    let mut advisories = vec![];
+    let Some(packages) = value.as_object() else {
+        return advisories;
+    };
+
+    for (package, cves) in packages {
+        for (cve, data) in cves.as_object().into_iter().flatten() {
+            let Some(status) = data.get("releases").and_then(|r| r.get(release)) else {
+                continue;
+            };
+            // "undetermined" and "open" entries have no fixed version yet
+            let fixed = status
+                .get("fixed_version")
+                .and_then(Value::as_str)
+                .filter(|v| *v != "0")
+                .map(str::to_string);
+            if status.get("status").and_then(Value::as_str) == Some("not-affected") {
+                continue;
+            }
+
+            advisories.push(Advisory {
+                id: cve.clone(),
+                summary: data
+                    .get("description")
+                    .and_then(Value::as_str)
+                    .unwrap_or_default()
+                    .to_string(),
+                affected: vec![AffectedPackage {
+                    name: package.clone(),
+                    ecosystem: format!("Debian:{}", release),
+                    ranges: vec![VersionRange {
+                        introduced: None,
+                        fixed,
+                    }],
+                }],
+            });
+        }
+    }
+    advisories
+}
+
+/// `VERSION_CODENAME` from `/etc/os-release`
+fn os_release_codename() -> Option<String> {
+    std::fs::read_to_string("/etc/os-release")
+        .ok()?
+        .lines()
+        .find_map(|l| l.strip_prefix("VERSION_CODENAME="))
+        .map(|v| v.trim_matches('"').to_string())
+        .filter(|v| !v.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const OSV: &str = r#"{
+        "id": "DSA-5417-1",
+        "modified": "2024-01-01T00:00:00Z",
+        "aliases": ["CVE-2023-2650"],
+        "summary": "openssl denial of service",
+        "affected": [
+            {
+                "package": { "ecosystem": "Debian:12", "name": "openssl" },
+                "ranges": [{ "type": "ECOSYSTEM", "events": [
+                    { "introduced": "0" }, { "fixed": "3.0.9-1" }
+                ]}]
+            },
+            {
+                "package": { "ecosystem": "PyPI", "name": "cryptography" },
+                "ranges": [{ "type": "ECOSYSTEM", "events": [{ "introduced": "0" }]}]
+            }
+        ]
+    }"#;
+
+    #[test]
+    fn test_osv() {
+        let advisories = parse(OSV, None).unwrap();
+        assert_eq!(advisories.len(), 1);
+        let advisory = &advisories[0];
+        assert_eq!(advisory.id, "CVE-2023-2650");
+        // Language ecosystems are dropped
+        assert_eq!(advisory.affected.len(), 1);
+
+        let openssl = &advisory.affected[0];
+        assert_eq!(openssl.fixed_versions(), vec!["3.0.9-1"]);
+        assert!(openssl.is_affected("3.0.8-1", PackageManager::Dpkg));
+        assert!(!openssl.is_affected("3.0.9-1", PackageManager::Dpkg));
+        assert!(!openssl.is_affected("3.0.11-1~deb12u2", PackageManager::Dpkg));
+    }
+
+    #[test]
+    fn test_osv_multiple_branches() {
+        let entry: Value = serde_json::from_str(
+            r#"{ "ranges": [{ "type": "ECOSYSTEM", "events": [
+                { "introduced": "1.0" }, { "fixed": "1.5" },
+                { "introduced": "2.0" }, { "fixed": "2.3" }
+            ]}]}"#,
+        )
+        .unwrap();
+        let package = AffectedPackage {
+            name: "foo".to_string(),
+            ecosystem: "Fedora".to_string(),
+            ranges: osv_ranges(&entry),
+        };
+        let rpm = PackageManager::Rpm;
+        assert!(package.is_affected("1.2-1", rpm));
+        assert!(!package.is_affected("1.6-1", rpm));
+        assert!(package.is_affected("2.1-1", rpm));
+        assert!(!package.is_affected("2.3-1", rpm));
+        assert!(!package.is_affected("0.9-1", rpm));
+    }
+
+    #[test]
+    fn test_arch() {
+        let json = r#"[{
+            "name": "AVG-2765", "packages": ["openssl", "lib32-openssl"],
+            "status": "Fixed", "type": "denial of service",
+            "affected": "3.0.7-1", "fixed": "3.0.8-1",
+            "issues": ["CVE-2023-0286", "CVE-2023-0215"]
+        }]"#;
+        let advisories = parse(json, None).unwrap();
+        assert_eq!(advisories.len(), 2);
+        assert_eq!(advisories[0].affected.len(), 2);
+        let pacman = PackageManager::Pacman;
+        assert!(advisories[0].affected[0].is_affected("3.0.7-1", pacman));
+        assert!(!advisories[0].affected[0].is_affected("1:3.0.0-1", pacman));
+    }
+
+    #[test]
+    fn test_applies_to_host_distro() {
+        let bookworm = HostDistro::parse(
+            "PRETTY_NAME=\"Debian GNU/Linux 12 (bookworm)\"\nID=debian\n\
+             VERSION_ID=\"12\"\nVERSION_CODENAME=bookworm\n",
+        )
+        .unwrap();
+        let package = |ecosystem: &str| AffectedPackage {
+            name: "openssl".to_string(),
+            ecosystem: ecosystem.to_string(),
+            ranges: vec![],
+        };
+        assert!(package("Debian:12").applies_to(&bookworm));
+        assert!(package("Debian:bookworm").applies_to(&bookworm));
+        assert!(!package("Debian:11").applies_to(&bookworm));
+        assert!(!package("Debian:bullseye").applies_to(&bookworm));
+        assert!(!package("Fedora").applies_to(&bookworm));
+        assert!(!package("Ubuntu:22.04:LTS").applies_to(&bookworm));
+
+        let fedora = HostDistro {
+            id: "fedora".to_string(),
+            version_id: Some("40".to_string()),
+            ..Default::default()
+        };
+        assert!(!package("Debian:12").applies_to(&fedora));
+        assert!(package("Fedora:40").applies_to(&fedora));
+
+        // Derivatives fall back to their parent's data, but Ubuntu has its own
+        let manjaro = HostDistro {
+            id: "manjaro".to_string(),
+            id_like: vec!["arch".to_string()],
+            ..Default::default()
+        };
+        assert!(package("Arch Linux").applies_to(&manjaro));
+        let ubuntu = HostDistro {
+            id: "ubuntu".to_string(),
+            id_like: vec!["debian".to_string()],
+            version_id: Some("22.04".to_string()),
+            ..Default::default()
+        };
+        assert!(!package("Debian:12").applies_to(&ubuntu));
+        assert!(package("Ubuntu:22.04:LTS").applies_to(&ubuntu));
+
+        let alpine = HostDistro {
+            id: "alpine".to_string(),
+            version_id: Some("3.19.1".to_string()),
+            ..Default::default()
+        };
+        assert!(package("Alpine:v3.19").applies_to(&alpine));
+        assert!(!package("Alpine:v3.18").applies_to(&alpine));
+    }
+
+    #[test]
+    fn test_debian() {
+        let json = r#"{ "openssl": {
+            "CVE-2023-0286": { "description": "X.400 type confusion",
+                "releases": { "bookworm": { "status": "resolved", "fixed_version": "3.0.8-1" },
+                              "bullseye": { "status": "open" } } },
+            "CVE-2010-0001": { "releases": { "bookworm": { "status": "not-affected" } } }
+        } }"#;
+        let advisories = parse(json, Some("bookworm")).unwrap();
+        assert_eq!(advisories.len(), 1);
+        assert_eq!(advisories[0].affected[0].fixed_versions(), vec!["3.0.8-1"]);
+
+        let open = parse(json, Some("bullseye")).unwrap();
+        assert_eq!(open.len(), 1);
+        assert!(open[0].affected[0].is_affected("9.9", PackageManager::Dpkg));
+    }
+}
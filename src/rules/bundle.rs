@@ -0,0 +1,478 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+//! Signed rule bundles for distributing vetted rule packs
+//!
+//! A bundle is a tar archive containing:
+//! - `manifest.json` - bundle provenance and the SHA-256 of every rule file
+//! - `manifest.sig` - hex ed25519 signature over the exact manifest bytes
+//! - `rules/<id>.toml` - one rule per file, including its provenance
+//!
+//! Signing the manifest covers every rule through its digest. Import only
+//! accepts bundles signed by a key listed in `trusted_keys.toml` in the
+//! config directory.
+
+use anyhow::Result;
+use ring::signature::{Ed25519KeyPair, KeyPair, UnparsedPublicKey, ED25519};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+
+use super::Rule;
+use crate::audit::sha256_hex;
+use crate::validation::validate_rule_id;
+
+/// Bundle format version written to the manifest
+const FORMAT_VERSION: u32 = 1;
+
+const MANIFEST_PATH: &str = "manifest.json";
+const SIGNATURE_PATH: &str = "manifest.sig";
+
+/// Largest archive entry accepted on import
+const MAX_ENTRY_BYTES: u64 = 1024 * 1024;
+
+/// Bundle-level provenance, signed as a whole
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BundleManifest {
+    pub format_version: u32,
+    pub created_at: String,
+    pub created_by: String,
+    pub host: String,
+    pub tag_filter: Option<String>,
+    pub signer: BundleSigner,
+    pub rules: Vec<BundleEntry>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BundleSigner {
+    pub name: String,
+    /// Hex-encoded ed25519 public key
+    pub public_key: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BundleEntry {
+    pub id: String,
+    pub version: String,
+    pub path: String,
+    pub sha256: String,
+}
+
+/// A bundle read from disk; rules are only usable after [`Bundle::verify`]
+#[derive(Debug)]
+pub struct Bundle {
+    pub manifest: BundleManifest,
+    manifest_bytes: Vec<u8>,
+    signature: Vec<u8>,
+    files: HashMap<String, Vec<u8>>,
+}
+
+/// Local ed25519 key used to sign exported bundles
+pub struct SigningKey {
+    pair: Ed25519KeyPair,
+    pub name: String,
+}
+
+impl SigningKey {
+    /// Default key location in the config directory
+    pub fn default_path() -> PathBuf {
+        crate::dirs::config_dir().join("keys").join("rules-ed25519.pk8")
+    }
+
+    /// Load the PKCS#8 key at `path`, generating one (mode 0600) if missing
+    pub fn load_or_generate(path: &Path, name: &str) -> Result<Self> {
+        if !path.exists() {
+            let rng = ring::rand::SystemRandom::new();
+            let pkcs8 = Ed25519KeyPair::generate_pkcs8(&rng)
+                .map_err(|_| anyhow::anyhow!("Failed to generate signing key"))?;
+
+            if let Some(parent) = path.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            // Created owner-only, so the key is never readable by others
+            let mut options = std::fs::OpenOptions::new();
+            options.write(true).create_new(true);
+            #[cfg(unix)]
+            {
+                use std::os::unix::fs::OpenOptionsExt;
+                options.mode(0o600);
+            }
+            let mut file = options.open(path)?;
+            file.write_all(pkcs8.as_ref())?;
+            file.sync_all()?;
+            tracing::info!("Generated rule signing key at {:?}", path);
+        }
+
+        let bytes = std::fs::read(path)?;
+        let pair = Ed25519KeyPair::from_pkcs8(&bytes)
+            .map_err(|_| anyhow::anyhow!("Invalid signing key: {}", path.display()))?;
+
+        Ok(Self {
+            pair,
+            name: name.to_string(),
+        })
+    }
+
+    /// Hex-encoded public key, for sharing with `psa rules trust`
+    pub fn public_key_hex(&self) -> String {
+        hex_encode(self.pair.public_key().as_ref())
+    }
+
+    fn sign(&self, message: &[u8]) -> Vec<u8> {
+        self.pair.sign(message).as_ref().to_vec()
+    }
+}
+
+/// Public keys whose bundles may be imported
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TrustedKeys {
+    #[serde(default, rename = "key")]
+    pub keys: Vec<TrustedKey>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrustedKey {
+    pub name: String,
+    pub public_key: String,
+}
+
+impl TrustedKeys {
+    /// Default trusted-keys file in the config directory
+    pub fn default_path() -> PathBuf {
+        crate::dirs::config_dir().join("trusted_keys.toml")
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let content = std::fs::read_to_string(path)?;
+        Ok(toml::from_str(&content)?)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, toml::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    /// Trust a key, replacing any existing entry with the same name
+    pub fn add(&mut self, name: &str, public_key: &str) -> Result<()> {
+        let public_key = public_key.trim().to_lowercase();
+        if hex_decode(&public_key).map(|k| k.len()) != Some(32) {
+            return Err(anyhow::anyhow!("Public key must be 64 hex characters"));
+        }
+
+        self.keys.retain(|k| k.name != name);
+        self.keys.push(TrustedKey {
+            name: name.to_string(),
+            public_key,
+        });
+        Ok(())
+    }
+
+    fn find(&self, public_key: &str) -> Option<&TrustedKey> {
+        self.keys
+            .iter()
+            .find(|k| k.public_key.eq_ignore_ascii_case(public_key))
+    }
+}
+
+/// Build a signed bundle from a set of rules
+pub fn export(rules: &[&Rule], tag_filter: Option<&str>, key: &SigningKey) -> Result<Vec<u8>> {
+    let mut entries = vec![];
+    let mut files = vec![];
+
+    for rule in rules {
+        validate_rule_id(&rule.id)
+            .map_err(|e| anyhow::anyhow!("Cannot export rule '{}': {}", rule.id, e))?;
+
+        let path = format!("rules/{}.toml", rule.id);
+        let content = toml::to_string_pretty(rule)?.into_bytes();
+        entries.push(BundleEntry {
+            id: rule.id.clone(),
+            version: rule.version.clone(),
+            path: path.clone(),
+            sha256: sha256_hex(&content),
+        });
+        files.push((path, content));
+    }
+
+    let manifest = BundleManifest {
+        format_version: FORMAT_VERSION,
+        created_at: chrono::Utc::now().to_rfc3339(),
+        created_by: key.name.clone(),
+        host: hostname(),
+        tag_filter: tag_filter.map(str::to_string),
+        signer: BundleSigner {
+            name: key.name.clone(),
+            public_key: key.public_key_hex(),
+        },
+        rules: entries,
+    };
+    let manifest_bytes = serde_json::to_vec_pretty(&manifest)?;
+    let signature = hex_encode(&key.sign(&manifest_bytes)).into_bytes();
+
+    let mut builder = tar::Builder::new(Vec::new());
+    append_file(&mut builder, MANIFEST_PATH, &manifest_bytes)?;
+    append_file(&mut builder, SIGNATURE_PATH, &signature)?;
+    for (path, content) in &files {
+        append_file(&mut builder, path, content)?;
+    }
+
+    Ok(builder.into_inner()?)
+}
+
+fn append_file(builder: &mut tar::Builder<Vec<u8>>, path: &str, content: &[u8]) -> Result<()> {
+    let mut header = tar::Header::new_gnu();
+    header.set_size(content.len() as u64);
+    header.set_mode(0o644);
+    header.set_mtime(chrono::Utc::now().timestamp().max(0) as u64);
+    header.set_cksum();
+    builder.append_data(&mut header, path, content)?;
+    Ok(())
+}
+
+impl Bundle {
+    /// Read a bundle archive into memory without extracting anything to disk
+    pub fn read(reader: impl Read) -> Result<Self> {
+        let mut archive = tar::Archive::new(reader);
+        let mut files = HashMap::new();
+
+        for entry in archive.entries()? {
+            let entry = entry?;
+            if !entry.header().entry_type().is_file() {
+                continue;
+            }
+            if entry.size() > MAX_ENTRY_BYTES {
+                return Err(anyhow::anyhow!("Bundle entry too large: {} bytes", entry.size()));
+            }
+
+            let path = entry.path()?.to_string_lossy().to_string();
+            let mut content = vec![];
+            entry.take(MAX_ENTRY_BYTES).read_to_end(&mut content)?;
+            files.insert(path, content);
+        }
+
+        let manifest_bytes = files
+            .remove(MANIFEST_PATH)
+            .ok_or_else(|| anyhow::anyhow!("Bundle has no {}", MANIFEST_PATH))?;
+        let signature_hex = files
+            .remove(SIGNATURE_PATH)
+            .ok_or_else(|| anyhow::anyhow!("Bundle is unsigned (no {})", SIGNATURE_PATH))?;
+
+        let manifest: BundleManifest = serde_json::from_slice(&manifest_bytes)?;
+        if manifest.format_version != FORMAT_VERSION {
+            return Err(anyhow::anyhow!(
+                "Unsupported bundle format version {}",
+                manifest.format_version
+            ));
+        }
+
+        let signature = hex_decode(String::from_utf8_lossy(&signature_hex).trim())
+            .ok_or_else(|| anyhow::anyhow!("Malformed bundle signature"))?;
+
+        Ok(Self {
+            manifest,
+            manifest_bytes,
+            signature,
+            files,
+        })
+    }
+
+    /// Check the signature against trusted keys and every rule against its digest
+    pub fn verify(&self, trusted: &TrustedKeys) -> Result<(TrustedKey, Vec<Rule>)> {
+        let signer = &self.manifest.signer;
+        let key = trusted.find(&signer.public_key).ok_or_else(|| {
+            anyhow::anyhow!(
+                "Bundle signed by untrusted key {} ('{}'). Trust it with: psa rules trust <name> {}",
+                signer.public_key,
+                signer.name,
+                signer.public_key
+            )
+        })?;
+
+        let public_key = hex_decode(&key.public_key)
+            .ok_or_else(|| anyhow::anyhow!("Malformed trusted key '{}'", key.name))?;
+        UnparsedPublicKey::new(&ED25519, public_key)
+            .verify(&self.manifest_bytes, &self.signature)
+            .map_err(|_| anyhow::anyhow!("Bundle signature is invalid"))?;
+
+        let mut rules = vec![];
+        for entry in &self.manifest.rules {
+            validate_rule_id(&entry.id)
+                .map_err(|e| anyhow::anyhow!("Bundle rule '{}': {}", entry.id, e))?;
+
+            let content = self
+                .files
+                .get(&entry.path)
+                .ok_or_else(|| anyhow::anyhow!("Bundle is missing {}", entry.path))?;
+            if sha256_hex(content) != entry.sha256 {
+                return Err(anyhow::anyhow!("Digest mismatch for {}", entry.path));
+            }
+
+            let rule: Rule = toml::from_str(std::str::from_utf8(content)?)?;
+            if rule.id != entry.id {
+                return Err(anyhow::anyhow!(
+                    "{} declares rule '{}' but manifest lists '{}'",
+                    entry.path,
+                    rule.id,
+                    entry.id
+                ));
+            }
+            rules.push(rule);
+        }
+
+        Ok((key.clone(), rules))
+    }
+}
+
+/// Best-effort host name for bundle provenance
+fn hostname() -> String {
+    std::fs::read_to_string("/proc/sys/kernel/hostname")
+        .map(|h| h.trim().to_string())
+        .unwrap_or_else(|_| "unknown".to_string())
+}
+
+fn hex_encode(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn hex_decode(s: &str) -> Option<Vec<u8>> {
+    s.as_bytes()
+        .chunks(2)
+        .map(|pair| {
+            let pair = std::str::from_utf8(pair).ok().filter(|p| p.len() == 2)?;
+            u8::from_str_radix(pair, 16).ok()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rules::{Action, Provenance, RuleSource, RuleStats};
+
+    fn temp_key() -> SigningKey {
+        let path = std::env::temp_dir()
+            .join(format!("psa-bundle-{}", uuid::Uuid::new_v4()))
+            .join("key.pk8");
+        SigningKey::load_or_generate(&path, "tester").unwrap()
+    }
+
+    fn sample_rule(id: &str) -> Rule {
+        Rule {
+            id: id.to_string(),
+            name: "Restart resolver".to_string(),
+            version: "1.0.0".to_string(),
+            when: vec![],
+            then: vec![Action::RestartService {
+                name: "systemd-resolved".to_string(),
+            }],
+            provenance: Provenance {
+                source: RuleSource::Manual {
+                    author: "tester".to_string(),
+                },
+                original_problem: "DNS stops resolving".to_string(),
+                solution_id: None,
+                created_at: chrono::Utc::now().to_rfc3339(),
+                created_by: "tester".to_string(),
+                decision_path: vec![],
+                history: vec![],
+            },
+            stats: RuleStats::default(),
+            enabled: true,
+            tags: vec!["networking".to_string()],
+        }
+    }
+
+    fn trusting(key: &SigningKey) -> TrustedKeys {
+        let mut trusted = TrustedKeys::default();
+        trusted.add("tester", &key.public_key_hex()).unwrap();
+        trusted
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        let key = temp_key();
+        let rule = sample_rule("rule-dns");
+        let bytes = export(&[&rule], Some("networking"), &key).unwrap();
+
+        let bundle = Bundle::read(bytes.as_slice()).unwrap();
+        let (signer, rules) = bundle.verify(&trusting(&key)).unwrap();
+        assert_eq!(signer.name, "tester");
+        assert_eq!(rules.len(), 1);
+        assert_eq!(rules[0].id, "rule-dns");
+        assert_eq!(bundle.manifest.tag_filter.as_deref(), Some("networking"));
+    }
+
+    #[test]
+    fn test_rejects_untrusted_signer() {
+        let key = temp_key();
+        let other = temp_key();
+        let bytes = export(&[&sample_rule("rule-dns")], None, &key).unwrap();
+
+        let bundle = Bundle::read(bytes.as_slice()).unwrap();
+        assert!(bundle.verify(&trusting(&other)).is_err());
+        assert!(bundle.verify(&TrustedKeys::default()).is_err());
+    }
+
+    #[test]
+    fn test_rejects_tampered_rule() {
+        let key = temp_key();
+        let bytes = export(&[&sample_rule("rule-dns")], None, &key).unwrap();
+
+        let mut bundle = Bundle::read(bytes.as_slice()).unwrap();
+        let content = bundle.files.get_mut("rules/rule-dns.toml").unwrap();
+        let tampered = String::from_utf8_lossy(content).replace("systemd-resolved", "sshd");
+        *content = tampered.into_bytes();
+
+        assert!(bundle.verify(&trusting(&key)).is_err());
+    }
+
+    #[test]
+    fn test_rejects_tampered_manifest() {
+        let key = temp_key();
+        let bytes = export(&[&sample_rule("rule-dns")], None, &key).unwrap();
+
+        let mut bundle = Bundle::read(bytes.as_slice()).unwrap();
+        bundle.manifest_bytes = String::from_utf8_lossy(&bundle.manifest_bytes)
+            .replace("tester", "mallory")
+            .into_bytes();
+
+        assert!(bundle.verify(&trusting(&key)).is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_key_is_owner_only() {
+        use std::os::unix::fs::PermissionsExt;
+        let path = std::env::temp_dir()
+            .join(format!("psa-bundle-{}", uuid::Uuid::new_v4()))
+            .join("key.pk8");
+        let key = SigningKey::load_or_generate(&path, "tester").unwrap();
+        let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+
+        let reloaded = SigningKey::load_or_generate(&path, "tester").unwrap();
+        assert_eq!(reloaded.public_key_hex(), key.public_key_hex());
+    }
+
+    #[test]
+    fn test_trusted_key_validation() {
+        let mut trusted = TrustedKeys::default();
+        assert!(trusted.add("bad", "not-hex").is_err());
+        assert!(trusted.add("short", "abcd").is_err());
+        assert!(trusted.add("ok", &"ab".repeat(32)).is_ok());
+        assert_eq!(trusted.keys.len(), 1);
+    }
+
+    #[test]
+    fn test_hex_roundtrip() {
+        let bytes = vec![0u8, 1, 0xab, 0xff];
+        assert_eq!(hex_decode(&hex_encode(&bytes)), Some(bytes));
+        assert_eq!(hex_decode("abc"), None);
+        assert_eq!(hex_decode("zz"), None);
+    }
+}
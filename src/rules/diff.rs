@@ -0,0 +1,543 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+//! Structural diffing of rule conditions and actions
+//!
+//! Changes are classified so the lifecycle manager can tell a parameter
+//! tweak (a threshold value, file content, module options) from a semantic
+//! change (a different service, command, path or condition structure).
+//! Parameter-only changes bump the minor version, semantic ones the major.
+
+use super::{Action, Condition};
+
+/// How much a change alters what a rule does
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ChangeKind {
+    /// Same target, tuned parameter
+    Parameter,
+    /// Different target or behaviour
+    Semantic,
+}
+
+/// A single difference between two rule versions
+#[derive(Debug, Clone, PartialEq)]
+pub struct Change {
+    pub kind: ChangeKind,
+    /// Location, e.g. `when[1].value` or `then[0]`
+    pub path: String,
+    pub description: String,
+    /// Relative size of a numeric change (|new - old| / |old|)
+    pub magnitude: Option<f64>,
+}
+
+/// All differences between two rule versions
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RuleDiff {
+    pub changes: Vec<Change>,
+}
+
+impl RuleDiff {
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+
+    /// Any change that alters what the rule targets or does
+    pub fn is_semantic(&self) -> bool {
+        self.changes.iter().any(|c| c.kind == ChangeKind::Semantic)
+    }
+
+    pub fn count(&self, kind: ChangeKind) -> usize {
+        self.changes.iter().filter(|c| c.kind == kind).count()
+    }
+
+    /// Human-readable summary for `RuleVersion.diff_summary`
+    pub fn summary(&self) -> String {
+        if self.changes.is_empty() {
+            return "No changes".to_string();
+        }
+        self.changes
+            .iter()
+            .map(|c| format!("{}: {}", c.path, c.description))
+            .collect::<Vec<_>>()
+            .join("; ")
+    }
+
+    /// Next version: major for semantic changes, minor for parameter tweaks
+    pub fn bump(&self, version: &str) -> String {
+        let mut parts = version
+            .split('.')
+            .map(|p| p.parse::<u64>().unwrap_or(0))
+            .chain(std::iter::repeat(0))
+            .take(3)
+            .collect::<Vec<_>>();
+
+        if self.is_semantic() {
+            parts = vec![parts[0] + 1, 0, 0];
+        } else if !self.is_empty() {
+            parts = vec![parts[0], parts[1] + 1, 0];
+        }
+        format!("{}.{}.{}", parts[0], parts[1], parts[2])
+    }
+}
+
+/// Diff two versions of a rule's conditions and actions
+pub fn diff_rule(
+    old_when: &[Condition],
+    old_then: &[Action],
+    new_when: &[Condition],
+    new_then: &[Action],
+) -> RuleDiff {
+    let mut changes = vec![];
+    diff_list(old_when, new_when, "when", &mut changes, diff_condition);
+    diff_list(old_then, new_then, "then", &mut changes, diff_action);
+    RuleDiff { changes }
+}
+
+/// Align two lists by longest common subsequence, then compare leftovers
+/// position-by-position within each gap so edits read as edits, not
+/// remove+add pairs.
+fn diff_list<T: PartialEq + std::fmt::Debug>(
+    old: &[T],
+    new: &[T],
+    prefix: &str,
+    out: &mut Vec<Change>,
+    compare: impl Fn(&T, &T, &str, &mut Vec<Change>),
+) {
+    // LCS table over exact equality
+    let (n, m) = (old.len(), new.len());
+    let mut lcs = vec![vec![0usize; m + 1]; n + 1];
+    for i in (0..n).rev() {
+        for j in (0..m).rev() {
+            lcs[i][j] = if old[i] == new[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let mut removed = vec![];
+    let mut added = vec![];
+    let flush = |removed: &mut Vec<usize>, added: &mut Vec<usize>, out: &mut Vec<Change>| {
+        let paired = removed.len().min(added.len());
+        for k in 0..paired {
+            compare(
+                &old[removed[k]],
+                &new[added[k]],
+                &format!("{}[{}]", prefix, added[k]),
+                out,
+            );
+        }
+        for &i in &removed[paired..] {
+            out.push(Change {
+                kind: ChangeKind::Semantic,
+                path: format!("{}[{}]", prefix, i),
+                description: format!("removed {}", describe(&old[i])),
+                magnitude: None,
+            });
+        }
+        for &j in &added[paired..] {
+            out.push(Change {
+                kind: ChangeKind::Semantic,
+                path: format!("{}[{}]", prefix, j),
+                description: format!("added {}", describe(&new[j])),
+                magnitude: None,
+            });
+        }
+        removed.clear();
+        added.clear();
+    };
+
+    let (mut i, mut j) = (0, 0);
+    while i < n || j < m {
+        if i < n && j < m && old[i] == new[j] {
+            flush(&mut removed, &mut added, out);
+            i += 1;
+            j += 1;
+        } else if j < m && (i == n || lcs[i][j + 1] >= lcs[i + 1][j]) {
+            added.push(j);
+            j += 1;
+        } else {
+            removed.push(i);
+            i += 1;
+        }
+    }
+    flush(&mut removed, &mut added, out);
+}
+
+/// Short description of a list element: variant plus fields
+fn describe<T: std::fmt::Debug>(item: &T) -> String {
+    format!("{:?}", item)
+}
+
+fn semantic(
+    out: &mut Vec<Change>,
+    path: &str,
+    field: &str,
+    old: &dyn std::fmt::Display,
+    new: &dyn std::fmt::Display,
+) {
+    out.push(Change {
+        kind: ChangeKind::Semantic,
+        path: format!("{}.{}", path, field),
+        description: format!("'{}' -> '{}'", old, new),
+        magnitude: None,
+    });
+}
+
+fn parameter(
+    out: &mut Vec<Change>,
+    path: &str,
+    field: &str,
+    old: &dyn std::fmt::Display,
+    new: &dyn std::fmt::Display,
+) {
+    out.push(Change {
+        kind: ChangeKind::Parameter,
+        path: format!("{}.{}", path, field),
+        description: format!("'{}' -> '{}'", old, new),
+        magnitude: None,
+    });
+}
+
+/// Compare a field and record a change of the given kind if it differs
+macro_rules! field {
+    ($out:expr, $path:expr, $kind:ident, $name:literal, $a:expr, $b:expr) => {
+        if $a != $b {
+            $kind($out, $path, $name, &$a, &$b);
+        }
+    };
+}
+
+fn opt(v: &Option<String>) -> String {
+    v.clone().unwrap_or_else(|| "<none>".to_string())
+}
+
+fn diff_condition(a: &Condition, b: &Condition, path: &str, out: &mut Vec<Change>) {
+    use Condition::*;
+
+    match (a, b) {
+        (ProcessRunning { name: n1 }, ProcessRunning { name: n2 }) => {
+            field!(out, path, semantic, "name", n1, n2);
+        }
+        (
+            ServiceState {
+                name: n1,
+                state: s1,
+            },
+            ServiceState {
+                name: n2,
+                state: s2,
+            },
+        ) => {
+            field!(out, path, semantic, "name", n1, n2);
+            field!(out, path, semantic, "state", s1, s2);
+        }
+        (FileExists { path: p1 }, FileExists { path: p2 }) => {
+            field!(out, path, semantic, "path", p1, p2);
+        }
+        (
+            FileContains {
+                path: p1,
+                pattern: r1,
+            },
+            FileContains {
+                path: p2,
+                pattern: r2,
+            },
+        ) => {
+            field!(out, path, semantic, "path", p1, p2);
+            field!(out, path, parameter, "pattern", r1, r2);
+        }
+        (
+            MetricThreshold {
+                metric: m1,
+                op: o1,
+                value: v1,
+            },
+            MetricThreshold {
+                metric: m2,
+                op: o2,
+                value: v2,
+            },
+        ) => {
+            field!(out, path, semantic, "metric", m1, m2);
+            if o1 != o2 {
+                // Relaxing or tightening the same bound is a tweak; flipping direction is not
+                let same_direction = o1.trim_end_matches('=') == o2.trim_end_matches('=')
+                    && matches!(o1.chars().next(), Some('<') | Some('>'));
+                if same_direction {
+                    parameter(out, path, "op", o1, o2);
+                } else {
+                    semantic(out, path, "op", o1, o2);
+                }
+            }
+            if v1 != v2 {
+                let magnitude = if *v1 != 0.0 {
+                    (v2 - v1).abs() / v1.abs()
+                } else {
+                    f64::INFINITY
+                };
+                out.push(Change {
+                    kind: ChangeKind::Parameter,
+                    path: format!("{}.value", path),
+                    description: format!("{} -> {}", v1, v2),
+                    magnitude: Some(magnitude),
+                });
+            }
+        }
+        (
+            PortOpen {
+                port: p1,
+                protocol: r1,
+            },
+            PortOpen {
+                port: p2,
+                protocol: r2,
+            },
+        ) => {
+            field!(out, path, semantic, "port", p1, p2);
+            field!(out, path, semantic, "protocol", r1, r2);
+        }
+        (PackageInstalled { name: n1 }, PackageInstalled { name: n2 }) => {
+            field!(out, path, semantic, "name", n1, n2);
+        }
+        (ModuleLoaded { name: n1 }, ModuleLoaded { name: n2 }) => {
+            field!(out, path, semantic, "name", n1, n2);
+        }
+        (ShellCheck { command: c1 }, ShellCheck { command: c2 }) => {
+            field!(out, path, semantic, "command", c1, c2);
+        }
+        (All { conditions: c1 }, All { conditions: c2 })
+        | (Any { conditions: c1 }, Any { conditions: c2 }) => {
+            diff_list(c1, c2, &format!("{}.conditions", path), out, diff_condition);
+        }
+        (Not { condition: c1 }, Not { condition: c2 }) => {
+            diff_condition(c1, c2, &format!("{}.not", path), out);
+        }
+        _ => out.push(Change {
+            kind: ChangeKind::Semantic,
+            path: path.to_string(),
+            description: format!("{} -> {}", describe(a), describe(b)),
+            magnitude: None,
+        }),
+    }
+}
+
+fn diff_action(a: &Action, b: &Action, path: &str, out: &mut Vec<Change>) {
+    use Action::*;
+
+    match (a, b) {
+        (
+            Shell {
+                command: c1,
+                sudo: s1,
+            },
+            Shell {
+                command: c2,
+                sudo: s2,
+            },
+        ) => {
+            field!(out, path, semantic, "command", c1, c2);
+            field!(out, path, semantic, "sudo", s1, s2);
+        }
+        (RestartService { name: n1 }, RestartService { name: n2 })
+        | (EnableService { name: n1 }, EnableService { name: n2 })
+        | (InstallPackage { name: n1 }, InstallPackage { name: n2 }) => {
+            field!(out, path, semantic, "name", n1, n2);
+        }
+        (
+            WriteFile {
+                path: p1,
+                content: c1,
+                mode: m1,
+            },
+            WriteFile {
+                path: p2,
+                content: c2,
+                mode: m2,
+            },
+        ) => {
+            field!(out, path, semantic, "path", p1, p2);
+            if c1 != c2 {
+                let (l1, l2) = (c1.lines().count(), c2.lines().count());
+                parameter(
+                    out,
+                    path,
+                    "content",
+                    &format!("{} lines", l1),
+                    &format!("{} lines", l2),
+                );
+            }
+            field!(out, path, parameter, "mode", opt(m1), opt(m2));
+        }
+        (
+            LoadModule {
+                name: n1,
+                options: o1,
+            },
+            LoadModule {
+                name: n2,
+                options: o2,
+            },
+        ) => {
+            field!(out, path, semantic, "name", n1, n2);
+            field!(out, path, parameter, "options", opt(o1), opt(o2));
+        }
+        (
+            Log {
+                level: l1,
+                message: m1,
+            },
+            Log {
+                level: l2,
+                message: m2,
+            },
+        ) => {
+            field!(out, path, parameter, "level", l1, l2);
+            field!(out, path, parameter, "message", m1, m2);
+        }
+        (
+            Notify {
+                title: t1,
+                body: b1,
+            },
+            Notify {
+                title: t2,
+                body: b2,
+            },
+        ) => {
+            field!(out, path, parameter, "title", t1, t2);
+            field!(out, path, parameter, "body", b1, b2);
+        }
+        (Escalate { reason: r1 }, Escalate { reason: r2 }) => {
+            field!(out, path, parameter, "reason", r1, r2);
+        }
+        _ => out.push(Change {
+            kind: ChangeKind::Semantic,
+            path: path.to_string(),
+            description: format!("{} -> {}", describe(a), describe(b)),
+            magnitude: None,
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn threshold(value: f64) -> Condition {
+        Condition::MetricThreshold {
+            metric: "mem_pct".to_string(),
+            op: ">".to_string(),
+            value,
+        }
+    }
+
+    fn restart(name: &str) -> Action {
+        Action::RestartService {
+            name: name.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_identical_is_empty() {
+        let diff = diff_rule(
+            &[threshold(90.0)],
+            &[restart("nginx")],
+            &[threshold(90.0)],
+            &[restart("nginx")],
+        );
+        assert!(diff.is_empty());
+        assert_eq!(diff.bump("1.2.3"), "1.2.3");
+    }
+
+    #[test]
+    fn test_threshold_tweak_is_parameter() {
+        let diff = diff_rule(
+            &[threshold(90.0)],
+            &[restart("nginx")],
+            &[threshold(95.0)],
+            &[restart("nginx")],
+        );
+        assert_eq!(diff.changes.len(), 1);
+        assert!(!diff.is_semantic());
+        assert_eq!(diff.changes[0].path, "when[0].value");
+        assert!((diff.changes[0].magnitude.unwrap() - 5.0 / 90.0).abs() < 1e-9);
+        assert_eq!(diff.bump("1.2.3"), "1.3.0");
+    }
+
+    #[test]
+    fn test_different_service_is_semantic() {
+        let diff = diff_rule(&[], &[restart("nginx")], &[], &[restart("httpd")]);
+        assert!(diff.is_semantic());
+        assert_eq!(diff.summary(), "then[0].name: 'nginx' -> 'httpd'");
+        assert_eq!(diff.bump("1.2.3"), "2.0.0");
+    }
+
+    #[test]
+    fn test_different_command_is_semantic() {
+        let a = Action::Shell {
+            command: "akmods --force".to_string(),
+            sudo: true,
+        };
+        let b = Action::Shell {
+            command: "dkms autoinstall".to_string(),
+            sudo: true,
+        };
+        let diff = diff_rule(&[], &[a], &[], &[b]);
+        assert!(diff.is_semantic());
+        assert_eq!(diff.changes[0].path, "then[0].command");
+    }
+
+    #[test]
+    fn test_insertion_keeps_alignment() {
+        let old = vec![restart("a"), restart("b")];
+        let new = vec![
+            restart("a"),
+            Action::Log {
+                level: "info".into(),
+                message: "x".into(),
+            },
+            restart("b"),
+        ];
+        let diff = diff_rule(&[], &old, &[], &new);
+        assert_eq!(diff.changes.len(), 1);
+        assert!(diff.changes[0].description.starts_with("added"));
+    }
+
+    #[test]
+    fn test_nested_conditions() {
+        let old = vec![Condition::Not {
+            condition: Box::new(threshold(80.0)),
+        }];
+        let new = vec![Condition::Not {
+            condition: Box::new(threshold(85.0)),
+        }];
+        let diff = diff_rule(&old, &[], &new, &[]);
+        assert_eq!(diff.changes[0].path, "when[0].not.value");
+        assert!(!diff.is_semantic());
+    }
+
+    #[test]
+    fn test_operator_direction() {
+        let mk = |op: &str| Condition::MetricThreshold {
+            metric: "cpu_pct".into(),
+            op: op.into(),
+            value: 90.0,
+        };
+        assert!(!diff_rule(&[mk(">")], &[], &[mk(">=")], &[]).is_semantic());
+        assert!(diff_rule(&[mk(">")], &[], &[mk("<")], &[]).is_semantic());
+    }
+
+    #[test]
+    fn test_variant_change_is_semantic() {
+        let diff = diff_rule(
+            &[],
+            &[restart("nginx")],
+            &[],
+            &[Action::EnableService {
+                name: "nginx".into(),
+            }],
+        );
+        assert!(diff.is_semantic());
+    }
+}
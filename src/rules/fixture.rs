@@ -0,0 +1,347 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+//! Declarative rule tests evaluated against a simulated system
+//!
+//! A `*.test.toml` file beside the rules describes system facts and what a
+//! rule is expected to do with them. Nothing on the real host is touched:
+//! conditions are evaluated against [`FixtureProbe`], and shell checks only
+//! succeed if the fixture says so.
+//!
+//! ```toml
+//! rule = "rule-nvidia-akmods"
+//!
+//! [[case]]
+//! name = "rebuilds driver when module is missing"
+//!
+//! [case.system]
+//! processes = ["Xorg"]
+//! modules = []
+//! ports = ["22/tcp"]
+//! packages = { akmod-nvidia = "550.54.14-1" }
+//! services = { nvidia-persistenced = "failed" }
+//! files = { "/etc/modprobe.d/blacklist.conf" = "blacklist nouveau" }
+//! metrics = { mem_pct = 42.0 }
+//! shell = { "lspci | grep -qi nvidia" = true }
+//!
+//! [case.expect]
+//! matches = true
+//! actions = [{ type = "Shell", command = "akmods --force", sudo = true }]
+//! ```
+
+use anyhow::Result;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use super::probe::SystemProbe;
+use super::{evaluate_all, Action, ProblemContext, Rule};
+
+/// Suffix identifying rule test files
+pub const FIXTURE_SUFFIX: &str = ".test.toml";
+
+/// A rule test file
+#[derive(Debug, Clone, Deserialize)]
+pub struct RuleTest {
+    /// ID of the rule under test
+    pub rule: String,
+    #[serde(rename = "case")]
+    pub cases: Vec<TestCase>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct TestCase {
+    pub name: String,
+    #[serde(default)]
+    pub system: SimulatedSystem,
+    pub expect: Expectation,
+}
+
+/// Facts about the simulated system; anything not listed is absent
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct SimulatedSystem {
+    /// Running process names (matched by substring, like pgrep)
+    pub processes: Vec<String>,
+    /// Unit name -> active state
+    pub services: HashMap<String, String>,
+    /// Path -> file content
+    pub files: HashMap<String, String>,
+    /// Loaded kernel modules
+    pub modules: Vec<String>,
+    /// Open ports as `<port>/<protocol>`, e.g. `22/tcp`
+    pub ports: Vec<String>,
+    /// Installed package -> version
+    pub packages: HashMap<String, String>,
+    /// Metric name -> value
+    pub metrics: HashMap<String, f64>,
+    /// Shell check command -> result
+    pub shell: HashMap<String, bool>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Expectation {
+    /// Whether the rule's conditions should match
+    pub matches: bool,
+    /// Actions the rule should plan; unchecked if omitted
+    pub actions: Option<Vec<Action>>,
+}
+
+/// Probe answering from a [`SimulatedSystem`]
+pub struct FixtureProbe {
+    system: SimulatedSystem,
+}
+
+impl FixtureProbe {
+    pub fn new(system: SimulatedSystem) -> Self {
+        Self { system }
+    }
+}
+
+impl SystemProbe for FixtureProbe {
+    fn process_running(&self, name: &str) -> bool {
+        self.system.processes.iter().any(|p| p.contains(name))
+    }
+
+    fn service_state(&self, name: &str) -> Option<String> {
+        self.system.services.get(name).cloned()
+    }
+
+    fn file_exists(&self, path: &str) -> bool {
+        self.system.files.contains_key(path)
+    }
+
+    fn read_file(&self, path: &str) -> Option<String> {
+        self.system.files.get(path).cloned()
+    }
+
+    fn module_loaded(&self, name: &str) -> bool {
+        self.system.modules.iter().any(|m| m == name)
+    }
+
+    fn port_open(&self, port: u16, protocol: &str) -> bool {
+        let wanted = format!("{}/{}", port, protocol.to_lowercase());
+        self.system.ports.iter().any(|p| p.to_lowercase() == wanted)
+    }
+
+    fn package_version(&self, name: &str) -> Option<String> {
+        self.system.packages.get(name).cloned()
+    }
+
+    fn metric(&self, name: &str) -> Option<f64> {
+        self.system.metrics.get(name).copied()
+    }
+
+    fn shell_check(&self, command: &str) -> bool {
+        self.system.shell.get(command).copied().unwrap_or(false)
+    }
+}
+
+/// Outcome of one test case
+#[derive(Debug, Clone)]
+pub struct CaseResult {
+    pub file: PathBuf,
+    pub rule: String,
+    pub case: String,
+    pub failures: Vec<String>,
+}
+
+impl CaseResult {
+    pub fn passed(&self) -> bool {
+        self.failures.is_empty()
+    }
+}
+
+/// Evaluate one case against a rule; the rule's `enabled` flag is ignored
+pub fn run_case(rule: &Rule, case: &TestCase) -> Vec<String> {
+    let probe = FixtureProbe::new(case.system.clone());
+    let context = ProblemContext::default();
+    let matched = evaluate_all(&probe, &rule.when, &context);
+
+    let mut failures = vec![];
+    if matched != case.expect.matches {
+        failures.push(format!(
+            "expected rule to {}match, but it {}",
+            if case.expect.matches { "" } else { "not " },
+            if matched { "matched" } else { "did not match" }
+        ));
+    }
+
+    if let Some(expected) = &case.expect.actions {
+        let planned: &[Action] = if matched { &rule.then } else { &[] };
+        if planned != expected.as_slice() {
+            failures.push(format!(
+                "planned actions differ\n      expected: {:?}\n      planned:  {:?}",
+                expected, planned
+            ));
+        }
+    }
+
+    failures
+}
+
+/// Find every `*.test.toml` in a directory, sorted by name
+pub fn discover(dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut files = vec![];
+    if !dir.exists() {
+        return Ok(files);
+    }
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path
+            .file_name()
+            .is_some_and(|n| n.to_string_lossy().ends_with(FIXTURE_SUFFIX))
+        {
+            files.push(path);
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// Run every case in a test file against the loaded rules
+pub fn run_file(path: &Path, rules: &[Rule]) -> Vec<CaseResult> {
+    let result = |rule: &str, case: &str, failures: Vec<String>| CaseResult {
+        file: path.to_path_buf(),
+        rule: rule.to_string(),
+        case: case.to_string(),
+        failures,
+    };
+
+    let test: RuleTest = match std::fs::read_to_string(path)
+        .map_err(anyhow::Error::from)
+        .and_then(|c| toml::from_str(&c).map_err(anyhow::Error::from))
+    {
+        Ok(t) => t,
+        Err(e) => return vec![result("?", "(parse)", vec![format!("invalid test file: {}", e)])],
+    };
+
+    let Some(rule) = rules.iter().find(|r| r.id == test.rule) else {
+        return vec![result(&test.rule, "(setup)", vec![format!("rule '{}' not found", test.rule)])];
+    };
+
+    test.cases
+        .iter()
+        .map(|case| result(&test.rule, &case.name, run_case(rule, case)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rules::{Condition, Provenance, RuleSource, RuleStats};
+
+    fn nvidia_rule() -> Rule {
+        Rule {
+            id: "rule-nvidia".to_string(),
+            name: "Rebuild NVIDIA driver".to_string(),
+            version: "1.0.0".to_string(),
+            when: vec![
+                Condition::PackageInstalled {
+                    name: "akmod-nvidia".to_string(),
+                },
+                Condition::Not {
+                    condition: Box::new(Condition::ModuleLoaded {
+                        name: "nvidia".to_string(),
+                    }),
+                },
+                Condition::MetricThreshold {
+                    metric: "mem_pct".to_string(),
+                    op: "<".to_string(),
+                    value: 90.0,
+                },
+            ],
+            then: vec![Action::Shell {
+                command: "akmods --force".to_string(),
+                sudo: true,
+            }],
+            provenance: Provenance {
+                source: RuleSource::Manual {
+                    author: "tester".to_string(),
+                },
+                original_problem: "nvidia driver not loading".to_string(),
+                solution_id: None,
+                created_at: String::new(),
+                created_by: "tester".to_string(),
+                decision_path: vec![],
+                history: vec![],
+            },
+            stats: RuleStats::default(),
+            enabled: false,
+            tags: vec![],
+        }
+    }
+
+    const FIXTURE: &str = r#"
+rule = "rule-nvidia"
+
+[[case]]
+name = "module missing"
+[case.system]
+packages = { akmod-nvidia = "550.54.14-1" }
+metrics = { mem_pct = 40.0 }
+[case.expect]
+matches = true
+actions = [{ type = "Shell", command = "akmods --force", sudo = true }]
+
+[[case]]
+name = "module already loaded"
+[case.system]
+packages = { akmod-nvidia = "550.54.14-1" }
+modules = ["nvidia"]
+metrics = { mem_pct = 40.0 }
+[case.expect]
+matches = false
+actions = []
+"#;
+
+    #[test]
+    fn test_fixture_cases_pass() {
+        let test: RuleTest = toml::from_str(FIXTURE).unwrap();
+        let rule = nvidia_rule();
+        for case in &test.cases {
+            let failures = run_case(&rule, case);
+            assert!(failures.is_empty(), "{}: {:?}", case.name, failures);
+        }
+    }
+
+    #[test]
+    fn test_wrong_expectation_fails() {
+        let mut test: RuleTest = toml::from_str(FIXTURE).unwrap();
+        test.cases[0].expect.matches = false;
+        test.cases[1].expect.actions = Some(vec![Action::Log {
+            level: "info".to_string(),
+            message: "x".to_string(),
+        }]);
+
+        let rule = nvidia_rule();
+        assert_eq!(run_case(&rule, &test.cases[0]).len(), 1);
+        assert_eq!(run_case(&rule, &test.cases[1]).len(), 1);
+    }
+
+    #[test]
+    fn test_unlisted_facts_are_absent() {
+        let probe = FixtureProbe::new(SimulatedSystem::default());
+        assert!(!probe.process_running("sshd"));
+        assert!(!probe.file_exists("/etc/passwd"));
+        assert!(!probe.shell_check("true"));
+        assert!(!probe.port_open(22, "tcp"));
+        assert_eq!(probe.service_state("sshd"), None);
+    }
+
+    #[test]
+    fn test_run_file_reports_missing_rule() {
+        let dir = std::env::temp_dir().join(format!("psa-fixture-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("nvidia.test.toml");
+        std::fs::write(&path, FIXTURE).unwrap();
+
+        assert_eq!(discover(&dir).unwrap(), vec![path.clone()]);
+
+        let results = run_file(&path, &[nvidia_rule()]);
+        assert_eq!(results.len(), 2);
+        assert!(results.iter().all(CaseResult::passed));
+
+        let results = run_file(&path, &[]);
+        assert_eq!(results.len(), 1);
+        assert!(!results[0].passed());
+    }
+}
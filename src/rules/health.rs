@@ -0,0 +1,217 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+//! Time-windowed rule health statistics
+//!
+//! Rule health is judged on the executions inside the tolerance window rather
+//! than lifetime counters. The success rate carries a Wilson score interval,
+//! so a handful of samples can neither condemn nor clear a rule, and the trend
+//! is the least-squares slope of outcome against time.
+
+use chrono::{DateTime, Utc};
+
+use super::ExecutionOutcome;
+
+/// z-score for a two-sided 95% interval
+pub const Z_95: f64 = 1.96;
+
+/// Success statistics over a time window
+#[derive(Debug, Clone, PartialEq)]
+pub struct WindowStats {
+    pub samples: u32,
+    pub successes: u32,
+    /// Point estimate of the success rate (0 when there are no samples)
+    pub rate: f64,
+    /// Lower bound of the 95% Wilson interval
+    pub lower: f64,
+    /// Upper bound of the 95% Wilson interval
+    pub upper: f64,
+    /// Change in success probability across the window, from a linear fit
+    /// (-0.2 means the rate fell by 20 points from start to end)
+    pub trend: f64,
+    /// Samples in the first and second half of the window (both 0 for
+    /// plain counters)
+    pub early: u32,
+    pub late: u32,
+}
+
+impl WindowStats {
+    /// Statistics for the outcomes in `(now - window_secs, now]`
+    pub fn from_outcomes(
+        outcomes: &[ExecutionOutcome],
+        now: DateTime<Utc>,
+        window_secs: u64,
+    ) -> Self {
+        let start = now - chrono::Duration::seconds(window_secs as i64);
+        let points: Vec<(f64, f64)> = outcomes
+            .iter()
+            .filter(|o| o.at > start && o.at <= now)
+            .map(|o| {
+                let x = (o.at - start).num_milliseconds() as f64 / 1000.0;
+                (x, if o.success { 1.0 } else { 0.0 })
+            })
+            .collect();
+
+        let successes = points.iter().filter(|(_, y)| *y > 0.0).count() as u32;
+        let mut stats = Self::from_counts(successes, points.len() as u32);
+        stats.trend = trend(&points, window_secs as f64);
+        let half = window_secs as f64 / 2.0;
+        stats.early = points.iter().filter(|(x, _)| *x < half).count() as u32;
+        stats.late = stats.samples - stats.early;
+        stats
+    }
+
+    /// Statistics from plain counters (no time information, so no trend)
+    pub fn from_counts(successes: u32, samples: u32) -> Self {
+        let (lower, upper) = wilson_interval(successes, samples, Z_95);
+        Self {
+            samples,
+            successes,
+            rate: if samples > 0 {
+                successes as f64 / samples as f64
+            } else {
+                0.0
+            },
+            lower,
+            upper,
+            trend: 0.0,
+            early: 0,
+            late: 0,
+        }
+    }
+
+    pub fn failures(&self) -> u32 {
+        self.samples - self.successes
+    }
+
+    /// Whether both halves of the window hold at least `min` samples, so a
+    /// change between them is more than a couple of executions
+    pub fn both_halves(&self, min: u32) -> bool {
+        self.early >= min && self.late >= min
+    }
+}
+
+/// Wilson score interval for a binomial proportion
+///
+/// Unlike the normal approximation it stays inside [0, 1] and behaves at
+/// 0% and 100%. With no samples the interval is the whole range.
+pub fn wilson_interval(successes: u32, samples: u32, z: f64) -> (f64, f64) {
+    if samples == 0 {
+        return (0.0, 1.0);
+    }
+    let n = samples as f64;
+    let p = successes as f64 / n;
+    let z2 = z * z;
+
+    let denominator = 1.0 + z2 / n;
+    let centre = (p + z2 / (2.0 * n)) / denominator;
+    let margin = z * (p * (1.0 - p) / n + z2 / (4.0 * n * n)).sqrt() / denominator;
+
+    ((centre - margin).max(0.0), (centre + margin).min(1.0))
+}
+
+/// Least-squares slope of `y` against `x`, scaled to `span` and clamped to [-1, 1]
+///
+/// Zero with fewer than two points or when all points share one `x`.
+pub fn trend(points: &[(f64, f64)], span: f64) -> f64 {
+    if points.len() < 2 {
+        return 0.0;
+    }
+    let n = points.len() as f64;
+    let mean_x = points.iter().map(|(x, _)| x).sum::<f64>() / n;
+    let mean_y = points.iter().map(|(_, y)| y).sum::<f64>() / n;
+
+    let sxx: f64 = points.iter().map(|(x, _)| (x - mean_x).powi(2)).sum();
+    if sxx == 0.0 {
+        return 0.0;
+    }
+    let sxy: f64 = points.iter().map(|(x, y)| (x - mean_x) * (y - mean_y)).sum();
+
+    (sxy / sxx * span).clamp(-1.0, 1.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DAY: u64 = 86_400;
+
+    fn outcome(now: DateTime<Utc>, days_ago: f64, success: bool) -> ExecutionOutcome {
+        ExecutionOutcome {
+            at: now - chrono::Duration::seconds((days_ago * DAY as f64) as i64),
+            success,
+            duration_ms: 10.0,
+        }
+    }
+
+    #[test]
+    fn test_wilson_edges() {
+        assert_eq!(wilson_interval(0, 0, Z_95), (0.0, 1.0));
+
+        let (lower, upper) = wilson_interval(10, 10, Z_95);
+        assert!((lower - 0.7225).abs() < 1e-3, "{}", lower);
+        assert_eq!(upper, 1.0);
+
+        let (lower, upper) = wilson_interval(0, 10, Z_95);
+        assert_eq!(lower, 0.0);
+        assert!((upper - 0.2775).abs() < 1e-3, "{}", upper);
+
+        // More samples at the same rate narrow the interval
+        let (l1, u1) = wilson_interval(8, 10, Z_95);
+        let (l2, u2) = wilson_interval(80, 100, Z_95);
+        assert!(l1 < l2 && u2 < u1);
+        assert!(l2 < 0.8 && 0.8 < u2);
+    }
+
+    #[test]
+    fn test_trend_edges() {
+        assert_eq!(trend(&[], 1.0), 0.0);
+        assert_eq!(trend(&[(1.0, 1.0)], 1.0), 0.0);
+        // Same timestamp: no slope defined
+        assert_eq!(trend(&[(5.0, 1.0), (5.0, 0.0)], 10.0), 0.0);
+        // Flat
+        assert_eq!(trend(&[(0.0, 1.0), (5.0, 1.0), (10.0, 1.0)], 10.0), 0.0);
+        // Perfect decline across the span
+        assert!((trend(&[(0.0, 1.0), (10.0, 0.0)], 10.0) + 1.0).abs() < 1e-9);
+        // Steeper than the span allows is clamped
+        assert_eq!(trend(&[(0.0, 0.0), (1.0, 1.0)], 10.0), 1.0);
+    }
+
+    #[test]
+    fn test_window_excludes_old_and_future() {
+        let now = Utc::now();
+        let outcomes = vec![
+            outcome(now, 30.0, false),
+            outcome(now, 3.0, true),
+            outcome(now, 1.0, true),
+            outcome(now, -1.0, false),
+        ];
+        let stats = WindowStats::from_outcomes(&outcomes, now, 7 * DAY);
+        assert_eq!((stats.samples, stats.successes), (2, 2));
+        assert_eq!((stats.early, stats.late), (0, 2));
+        assert_eq!(stats.rate, 1.0);
+        assert_eq!(stats.trend, 0.0);
+    }
+
+    #[test]
+    fn test_window_trend_direction() {
+        let now = Utc::now();
+        let declining: Vec<_> = (0..14)
+            .map(|i| outcome(now, 6.5 - i as f64 * 0.5, i < 7))
+            .collect();
+        let stats = WindowStats::from_outcomes(&declining, now, 7 * DAY);
+        assert_eq!(stats.samples, 14);
+        assert!(stats.trend < -0.5, "{}", stats.trend);
+
+        let improving: Vec<_> = (0..14)
+            .map(|i| outcome(now, 6.5 - i as f64 * 0.5, i >= 7))
+            .collect();
+        assert!(WindowStats::from_outcomes(&improving, now, 7 * DAY).trend > 0.5);
+    }
+
+    #[test]
+    fn test_empty_window() {
+        let stats = WindowStats::from_outcomes(&[], Utc::now(), DAY);
+        assert_eq!(stats.samples, 0);
+        assert_eq!(stats.failures(), 0);
+        assert_eq!((stats.lower, stats.upper), (0.0, 1.0));
+    }
+}
@@ -11,6 +11,14 @@
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use super::advisory::{Advisory, AffectedPackage, HostDistro};
+use super::diff::{diff_rule, ChangeKind, RuleDiff};
+use super::health::WindowStats;
+use super::probe::{HostProbe, PackageManager, SystemProbe};
+use crate::reasoning::ReasoningEngine;
+use crate::storage::Storage;
 
 /// Tolerance configuration for rule updates
 #[derive(Debug, Clone)]
@@ -56,6 +64,28 @@ pub enum RuleHealth {
     Probationary { applications: u32, required: u32 },
 }
 
+impl std::fmt::Display for RuleHealth {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RuleHealth::Healthy => write!(f, "healthy"),
+            RuleHealth::Degrading { current_rate, trend } => write!(
+                f,
+                "degrading ({:.0}% success, trend {:+.0}%)",
+                current_rate * 100.0,
+                trend * 100.0
+            ),
+            RuleHealth::NeedsReview { reason } => write!(f, "needs review: {}", reason),
+            RuleHealth::PossiblyObsolete { reason } => write!(f, "possibly obsolete: {}", reason),
+            RuleHealth::Obsolete { reason, retired_at } => {
+                write!(f, "retired {}: {}", retired_at, reason)
+            }
+            RuleHealth::Probationary { applications, required } => {
+                write!(f, "probationary ({}/{} applications)", applications, required)
+            }
+        }
+    }
+}
+
 /// Reasons a rule might become obsolete
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum ObsolescenceReason {
@@ -73,6 +103,27 @@ pub enum ObsolescenceReason {
     NoRecentActivity { last_applied: String },
 }
 
+impl std::fmt::Display for ObsolescenceReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ObsolescenceReason::CveFixed { cve_id, fixed_version } => {
+                write!(f, "{} fixed in {}", cve_id, fixed_version)
+            }
+            ObsolescenceReason::PackageUpdated { package, old_version, new_version } => {
+                write!(f, "{} updated from {} to {}", package, old_version, new_version)
+            }
+            ObsolescenceReason::ConditionInvalid { condition } => write!(f, "{}", condition),
+            ObsolescenceReason::Superseded { new_rule_id } => write!(f, "superseded by {}", new_rule_id),
+            ObsolescenceReason::ManualDeprecation { reason, by } => {
+                write!(f, "deprecated by {}: {}", by, reason)
+            }
+            ObsolescenceReason::NoRecentActivity { last_applied } => {
+                write!(f, "not applied since {}", last_applied)
+            }
+        }
+    }
+}
+
 /// A proposed new rule (not yet crystallized)
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RuleProposal {
@@ -84,6 +135,111 @@ pub struct RuleProposal {
     pub confidence: f32,
     pub status: ProposalStatus,
     pub created_at: String,
+    /// Who approved or rejected it
+    #[serde(default)]
+    pub review: Option<ProposalReview>,
+}
+
+impl RuleProposal {
+    /// Evidence entries recording a successful outcome
+    pub fn successes(&self) -> u32 {
+        self.evidence
+            .iter()
+            .filter(|e| matches!(e.outcome, EvidenceOutcome::Success))
+            .count() as u32
+    }
+
+    /// Recompute confidence and move a gathering proposal to review once it
+    /// has enough successes at an acceptable success rate. Returns true if
+    /// the proposal just became ready for review.
+    fn update_status(&mut self, min_success_rate: f32) -> bool {
+        let successes = self.successes();
+        self.confidence = successes as f32 / self.evidence.len().max(1) as f32;
+
+        let ProposalStatus::Gathering { required, .. } = self.status else {
+            return false;
+        };
+        if successes >= required && self.confidence >= min_success_rate {
+            self.status = ProposalStatus::PendingReview;
+            true
+        } else {
+            self.status = ProposalStatus::Gathering {
+                count: successes,
+                required,
+            };
+            false
+        }
+    }
+
+    /// The proven solution this proposal stands for, as input to crystallization
+    fn as_solution(&self) -> crate::storage::Solution {
+        let solution_id = self
+            .evidence
+            .iter()
+            .rev()
+            .find_map(|e| e.context.get("solution_id").cloned())
+            .unwrap_or_else(|| self.id.clone());
+        let category = self
+            .evidence
+            .iter()
+            .rev()
+            .find_map(|e| e.context.get("category").cloned())
+            .unwrap_or_else(|| "general".to_string());
+        let created_at = chrono::DateTime::parse_from_rfc3339(&self.created_at)
+            .map(|t| t.with_timezone(&chrono::Utc))
+            .unwrap_or_else(|_| chrono::Utc::now());
+        let successes = self.successes();
+
+        crate::storage::Solution {
+            id: solution_id,
+            category,
+            problem: self.problem_pattern.clone(),
+            solution: format!("Rule proposal {}", self.id),
+            commands: self
+                .suggested_actions
+                .iter()
+                .filter_map(|a| match a {
+                    super::Action::Shell { command, .. } => Some(command.clone()),
+                    _ => None,
+                })
+                .collect(),
+            tags: vec![],
+            success_count: successes,
+            failure_count: self.evidence.len() as u32 - successes,
+            source: crate::storage::SolutionSource::Local,
+            created_at,
+            updated_at: chrono::Utc::now(),
+        }
+    }
+}
+
+/// What feedback on a knowledge base solution led to
+#[derive(Debug, Clone, PartialEq)]
+pub enum FeedbackEffect {
+    /// Counts updated only
+    Recorded,
+    /// Added as evidence to the solution's open proposal
+    Evidence { proposal_id: String },
+    /// The solution is now proven and a rule proposal was opened
+    Proposed { proposal_id: String },
+}
+
+/// Reviewer decision on a proposal
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProposalReview {
+    pub by: String,
+    pub at: String,
+    pub note: Option<String>,
+}
+
+impl ProposalReview {
+    fn new(by: &str, note: Option<&str>) -> Self {
+        Self {
+            by: by.to_string(),
+            at: chrono::Utc::now().to_rfc3339(),
+            note: note.map(str::to_string),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -92,6 +248,11 @@ pub struct ProposalEvidence {
     pub source: String,
     pub outcome: EvidenceOutcome,
     pub context: HashMap<String, String>,
+    /// Reconstructed from outcome counts recorded before the proposal
+    /// existed. When it happened is unknown (`timestamp` is the solution's
+    /// creation), so it must not feed anything judged over a time window.
+    #[serde(default)]
+    pub backfilled: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -115,6 +276,12 @@ pub enum ProposalStatus {
     Crystallized { rule_id: String },
 }
 
+/// Fall in success rate across the window that marks a rule as degrading
+const DEGRADING_TREND: f64 = 0.2;
+
+/// Lifecycle state file in the data dir
+const STATE_FILE: &str = "lifecycle.json";
+
 /// Rule lifecycle manager
 pub struct LifecycleManager {
     tolerance: ToleranceConfig,
@@ -122,17 +289,62 @@ pub struct LifecycleManager {
     proposals: HashMap<String, RuleProposal>,
     /// Rule health cache
     health_cache: HashMap<String, RuleHealth>,
-    /// CVE tracking (would integrate with NIST NVD or similar)
+    /// CVE tracking, filled from offline advisory data
     known_cves: HashMap<String, CveStatus>,
+    /// Package versions seen when each rule was first checked
+    package_baselines: HashMap<String, PackageBaseline>,
+    /// Where state is persisted; `None` keeps it in memory only
+    state_path: Option<PathBuf>,
+    /// State as last read from or written to disk, to tell this process's
+    /// changes from other writers' when saving
+    saved: LifecycleState,
+    /// Source of installed package versions and other host facts
+    probe: Box<dyn SystemProbe>,
+    /// Version ordering to compare installed packages with advisories
+    package_manager: Option<PackageManager>,
+    /// Host distribution, to pick the advisory data that applies to it
+    distro: Option<HostDistro>,
 }
 
-#[derive(Debug, Clone)]
+/// Persisted part of [`LifecycleManager`]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct LifecycleState {
+    #[serde(default)]
+    proposals: HashMap<String, RuleProposal>,
+    #[serde(default)]
+    health_cache: HashMap<String, RuleHealth>,
+    #[serde(default)]
+    known_cves: HashMap<String, CveStatus>,
+    #[serde(default)]
+    package_baselines: HashMap<String, PackageBaseline>,
+}
+
+/// Versions of the packages a rule checks for, as of a rule version
+///
+/// A major upgrade relative to the baseline flags the rule for review;
+/// amending the rule (a new version) takes a fresh baseline.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct PackageBaseline {
+    rule_version: String,
+    versions: HashMap<String, String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CveStatus {
     pub id: String,
     pub affected_packages: Vec<String>,
     pub fixed_in: Option<String>,
     pub patched_locally: bool,
+    /// Rules naming the CVE: retired once it is fixed on the host
     pub related_rule_ids: Vec<String>,
+    /// Rules that only touch an affected package, listed for review
+    #[serde(default)]
+    pub package_rule_ids: Vec<String>,
+    /// Affected version ranges per package, from advisory data
+    #[serde(default)]
+    pub affected: Vec<AffectedPackage>,
+    #[serde(default)]
+    pub summary: String,
 }
 
 impl LifecycleManager {
@@ -142,11 +354,108 @@ pub fn new(tolerance: ToleranceConfig) -> Self {
             proposals: HashMap::new(),
             health_cache: HashMap::new(),
             known_cves: HashMap::new(),
+            package_baselines: HashMap::new(),
+            state_path: None,
+            saved: LifecycleState::default(),
+            probe: Box::new(HostProbe),
+            package_manager: PackageManager::detect(),
+            distro: HostDistro::detect(),
         }
     }
 
+    /// Replace the host probe and version ordering (for tests and fixtures)
+    pub fn set_probe(
+        &mut self,
+        probe: Box<dyn SystemProbe>,
+        package_manager: Option<PackageManager>,
+    ) {
+        self.probe = probe;
+        self.package_manager = package_manager;
+    }
+
+    /// Replace the host distribution advisories are matched against
+    pub fn set_distro(&mut self, distro: Option<HostDistro>) {
+        self.distro = distro;
+    }
+
+    /// Load persisted state, starting empty if the file does not exist yet
+    pub fn load(path: &Path, tolerance: ToleranceConfig) -> Result<Self> {
+        let state = read_state(path)?;
+
+        Ok(Self {
+            proposals: state.proposals.clone(),
+            health_cache: state.health_cache.clone(),
+            known_cves: state.known_cves.clone(),
+            package_baselines: state.package_baselines.clone(),
+            state_path: Some(path.to_path_buf()),
+            saved: state,
+            ..Self::new(tolerance)
+        })
+    }
+
+    /// Load the lifecycle state from the data dir
+    pub fn open_default() -> Result<Self> {
+        Self::load(&crate::dirs::data_dir().join(STATE_FILE), ToleranceConfig::default())
+    }
+
+    /// Persist state (write to a temp file, then rename over the old one)
+    ///
+    /// The daemon and CLI commands share the file, so under an exclusive
+    /// lock the current file is re-read and only the entries this manager
+    /// changed since it last loaded or saved are written over it. Entries
+    /// other processes added or changed meanwhile are kept, and picked up
+    /// in memory too.
+    pub fn save(&mut self) -> Result<()> {
+        let Some(path) = self.state_path.clone() else {
+            return Ok(());
+        };
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        // Lock a sibling file: the state file itself is replaced by rename
+        let lock = std::fs::OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(path.with_extension("json.lock"))?;
+        lock.lock()?;
+
+        let mut state = read_state(&path)?;
+        merge(&mut state.proposals, &self.proposals, &self.saved.proposals)?;
+        merge(&mut state.health_cache, &self.health_cache, &self.saved.health_cache)?;
+        merge(&mut state.known_cves, &self.known_cves, &self.saved.known_cves)?;
+        merge(
+            &mut state.package_baselines,
+            &self.package_baselines,
+            &self.saved.package_baselines,
+        )?;
+
+        let tmp = path.with_extension("json.tmp");
+        std::fs::write(&tmp, serde_json::to_vec_pretty(&state)?)?;
+        std::fs::rename(&tmp, &path)?;
+        lock.unlock()?;
+
+        self.proposals = state.proposals.clone();
+        self.health_cache = state.health_cache.clone();
+        self.known_cves = state.known_cves.clone();
+        self.package_baselines = state.package_baselines.clone();
+        self.saved = state;
+        Ok(())
+    }
+
     /// Assess the health of a rule based on its statistics
     pub fn assess_health(&mut self, rule: &super::Rule) -> RuleHealth {
+        let health = self.health_from_stats(rule);
+        self.health_cache.insert(rule.id.clone(), health.clone());
+        health
+    }
+
+    fn health_from_stats(&self, rule: &super::Rule) -> RuleHealth {
+        self.health_at(rule, chrono::Utc::now())
+    }
+
+    fn health_at(&self, rule: &super::Rule, now: chrono::DateTime<chrono::Utc>) -> RuleHealth {
         let stats = &rule.stats;
 
         // New rule in probationary period
@@ -157,21 +466,6 @@ pub fn assess_health(&mut self, rule: &super::Rule) -> RuleHealth {
             };
         }
 
-        // Calculate success rate
-        let success_rate = stats.success_count as f32 / stats.applied_count as f32;
-
-        // Check for recent failures
-        if stats.failure_count >= self.tolerance.failure_review_threshold
-            && stats.failure_count as f32 / stats.applied_count as f32 > 0.3
-        {
-            return RuleHealth::NeedsReview {
-                reason: format!(
-                    "High failure rate: {} failures out of {} applications",
-                    stats.failure_count, stats.applied_count
-                ),
-            };
-        }
-
         // Check for escalations (rule couldn't handle, had to ask AI)
         if stats.escalation_count > stats.success_count / 2 {
             return RuleHealth::NeedsReview {
@@ -185,7 +479,7 @@ pub fn assess_health(&mut self, rule: &super::Rule) -> RuleHealth {
         // Check for no recent activity (possibly obsolete)
         if let Some(last) = &stats.last_applied {
             if let Ok(last_time) = chrono::DateTime::parse_from_rfc3339(last) {
-                let age = chrono::Utc::now().signed_duration_since(last_time.with_timezone(&chrono::Utc));
+                let age = now.signed_duration_since(last_time.with_timezone(&chrono::Utc));
                 if age.num_seconds() > self.tolerance.rate_window_secs as i64 * 4 {
                     return RuleHealth::PossiblyObsolete {
                         reason: format!("No applications in {} days", age.num_days()),
@@ -194,57 +488,176 @@ pub fn assess_health(&mut self, rule: &super::Rule) -> RuleHealth {
             }
         }
 
-        // Check for degrading performance (would need historical data)
-        if success_rate < self.tolerance.min_success_rate {
+        let window = self.window_stats(rule, now);
+        if window.samples == 0 {
+            // Applied before, but not within the window: nothing to judge
+            return RuleHealth::Healthy;
+        }
+        let min_rate = self.tolerance.min_success_rate as f64;
+
+        // Enough failures, and confidently below the required success rate
+        if window.failures() >= self.tolerance.failure_review_threshold && window.upper < min_rate {
+            return RuleHealth::NeedsReview {
+                reason: format!(
+                    "High failure rate: {} failures out of {} applications in the last {} days \
+                     (success {:.0}%, 95% CI {:.0}-{:.0}%)",
+                    window.failures(),
+                    window.samples,
+                    self.tolerance.rate_window_secs / 86_400,
+                    window.rate * 100.0,
+                    window.lower * 100.0,
+                    window.upper * 100.0
+                ),
+            };
+        }
+
+        // Not confidently meeting the target, and either below it or falling,
+        // judged only with enough executions early and late in the window
+        let min_samples = self.tolerance.min_samples;
+        let enough = if stats.outcomes.is_empty() {
+            window.samples >= min_samples
+        } else {
+            window.both_halves(min_samples / 2)
+        };
+        if enough
+            && window.lower < min_rate
+            && (window.rate < min_rate || window.trend <= -DEGRADING_TREND)
+        {
             return RuleHealth::Degrading {
-                current_rate: success_rate,
-                trend: -0.1, // Would calculate from history
+                current_rate: window.rate as f32,
+                trend: window.trend as f32,
             };
         }
 
-        // Cache and return
-        let health = RuleHealth::Healthy;
-        self.health_cache.insert(rule.id.clone(), health.clone());
-        health
+        RuleHealth::Healthy
     }
 
-    /// Check if a proposed change is within tolerance (don't update for minor variations)
-    pub fn within_tolerance(
+    /// Success statistics over the tolerance window
+    ///
+    /// Rules recorded before per-execution outcomes were kept fall back to
+    /// their lifetime counters.
+    pub fn window_stats(
         &self,
-        existing: &super::Rule,
-        proposed_conditions: &[super::Condition],
-        proposed_actions: &[super::Action],
-    ) -> bool {
-        // Count differences in conditions
-        let condition_diff = self.count_condition_differences(&existing.when, proposed_conditions);
-        let action_diff = self.count_action_differences(&existing.then, proposed_actions);
+        rule: &super::Rule,
+        now: chrono::DateTime<chrono::Utc>,
+    ) -> WindowStats {
+        let stats = &rule.stats;
+        if stats.outcomes.is_empty() {
+            WindowStats::from_counts(stats.success_count, stats.applied_count)
+        } else {
+            WindowStats::from_outcomes(&stats.outcomes, now, self.tolerance.rate_window_secs)
+        }
+    }
+
+    /// Re-assess every rule: statistics, condition validity and fixed CVEs
+    ///
+    /// Retired rules keep their verdict, and health entries for rules that
+    /// no longer exist are dropped. Returns the rules whose health changed.
+    pub fn run_checks(&mut self, rules: &[super::Rule]) -> Vec<(String, RuleHealth)> {
+        let mut changed = vec![];
+        self.link_rules(rules);
+
+        for rule in rules {
+            let previous = self.health_cache.get(&rule.id).cloned();
+            if matches!(previous, Some(RuleHealth::Obsolete { .. })) {
+                continue;
+            }
 
-        // If differences are minor (e.g., just parameter tweaks), consider within tolerance
-        let total_elements = existing.when.len() + existing.then.len();
-        let total_diff = condition_diff + action_diff;
+            self.record_baseline(rule);
+            let mut health = self.health_from_stats(rule);
+            let obsolete = match self.check_cve_obsolescence(rule) {
+                Some(reason) => Some(reason),
+                None => self.check_condition_validity(rule),
+            };
+            if let Some(reason) = obsolete {
+                health = RuleHealth::PossiblyObsolete {
+                    reason: reason.to_string(),
+                };
+            }
 
-        if total_elements == 0 {
-            return total_diff == 0;
+            if previous.as_ref() != Some(&health) {
+                changed.push((rule.id.clone(), health.clone()));
+            }
+            self.health_cache.insert(rule.id.clone(), health);
         }
 
-        let diff_ratio = total_diff as f32 / total_elements as f32;
-        diff_ratio <= self.tolerance.variance_threshold
+        self.health_cache.retain(|id, _| rules.iter().any(|r| &r.id == id));
+        self.package_baselines.retain(|id, _| rules.iter().any(|r| &r.id == id));
+        changed
     }
 
-    fn count_condition_differences(&self, a: &[super::Condition], b: &[super::Condition]) -> usize {
-        // Simple count - real implementation would do semantic comparison
-        if a.len() != b.len() {
-            return a.len().abs_diff(b.len());
+    /// Take a package version baseline for a new or amended rule
+    fn record_baseline(&mut self, rule: &super::Rule) {
+        if self
+            .package_baselines
+            .get(&rule.id)
+            .is_some_and(|b| b.rule_version == rule.version)
+        {
+            return;
         }
-        // Would compare each condition semantically
-        0
+        let versions = required_packages(rule)
+            .into_iter()
+            .filter_map(|name| {
+                let version = self.probe.package_version(&name)?;
+                Some((name, version))
+            })
+            .collect();
+        self.package_baselines.insert(
+            rule.id.clone(),
+            PackageBaseline {
+                rule_version: rule.version.clone(),
+                versions,
+            },
+        );
     }
 
-    fn count_action_differences(&self, a: &[super::Action], b: &[super::Action]) -> usize {
-        if a.len() != b.len() {
-            return a.len().abs_diff(b.len());
-        }
-        0
+    /// Last recorded health of a rule
+    pub fn health(&self, rule_id: &str) -> Option<&RuleHealth> {
+        self.health_cache.get(rule_id)
+    }
+
+    /// Mark a rule as retired; it is skipped by later checks
+    pub fn retire(&mut self, rule_id: &str, reason: &str) {
+        self.health_cache.insert(
+            rule_id.to_string(),
+            RuleHealth::Obsolete {
+                reason: reason.to_string(),
+                retired_at: chrono::Utc::now().to_rfc3339(),
+            },
+        );
+    }
+
+    /// Check if a proposed change is within tolerance (don't update for minor variations)
+    ///
+    /// Only numeric parameter tweaks qualify, and they must stay within
+    /// `variance_threshold` of the existing value. Non-numeric parameters
+    /// (a pattern, file content, module options, a comparison operator) have
+    /// no notion of "close", so any change to them is a real update, as is
+    /// every semantic change (a different service, command, path or
+    /// condition structure).
+    pub fn within_tolerance(
+        &self,
+        existing: &super::Rule,
+        proposed_conditions: &[super::Condition],
+        proposed_actions: &[super::Action],
+    ) -> bool {
+        let diff = self.diff(existing, proposed_conditions, proposed_actions);
+        let threshold = self.tolerance.variance_threshold as f64;
+
+        diff.changes.iter().all(|change| {
+            change.kind == ChangeKind::Parameter
+                && change.magnitude.is_some_and(|m| m <= threshold)
+        })
+    }
+
+    /// Structural diff between a rule and a proposed revision of it
+    pub fn diff(
+        &self,
+        existing: &super::Rule,
+        proposed_conditions: &[super::Condition],
+        proposed_actions: &[super::Action],
+    ) -> RuleDiff {
+        diff_rule(&existing.when, &existing.then, proposed_conditions, proposed_actions)
     }
 
     /// Propose a new rule based on observed solutions
@@ -257,106 +670,453 @@ pub fn propose_rule(
     ) -> String {
         let id = format!("proposal-{}", uuid::Uuid::new_v4());
 
-        let proposal = RuleProposal {
+        let mut proposal = RuleProposal {
             id: id.clone(),
             problem_pattern: problem_pattern.to_string(),
             suggested_conditions: conditions,
             suggested_actions: actions,
             evidence: vec![initial_evidence],
-            confidence: 0.1,
-            status: ProposalStatus::Gathering { count: 1, required: 5 },
+            confidence: 0.0,
+            status: ProposalStatus::Gathering {
+                count: 0,
+                required: super::CRYSTALLIZATION_THRESHOLD,
+            },
             created_at: chrono::Utc::now().to_rfc3339(),
+            review: None,
         };
+        proposal.update_status(self.tolerance.min_success_rate);
 
         tracing::info!("New rule proposal created: {} for '{}'", id, problem_pattern);
         self.proposals.insert(id.clone(), proposal);
         id
     }
 
+    /// Feed a user's report on a knowledge base solution into proposals
+    ///
+    /// `solution` carries the counts after the outcome was recorded. The
+    /// report becomes evidence on the solution's open proposal; once
+    /// [`super::should_crystallize`] holds and the solution has no proposal
+    /// yet, one is opened with inferred conditions and actions and the
+    /// recorded outcomes as its evidence.
+    pub fn solution_feedback(
+        &mut self,
+        solution: &crate::storage::Solution,
+        worked: bool,
+        note: Option<&str>,
+    ) -> Result<FeedbackEffect> {
+        let evidence = |success: bool, note: Option<&str>, backfilled: bool| {
+            let mut context = HashMap::from([
+                ("solution_id".to_string(), solution.id.clone()),
+                ("category".to_string(), solution.category.clone()),
+            ]);
+            if let Some(note) = note {
+                context.insert("note".to_string(), note.to_string());
+            }
+            let timestamp = if backfilled {
+                solution.created_at
+            } else {
+                chrono::Utc::now()
+            };
+            ProposalEvidence {
+                timestamp: timestamp.to_rfc3339(),
+                source: "kb-feedback".to_string(),
+                outcome: if success {
+                    EvidenceOutcome::Success
+                } else {
+                    EvidenceOutcome::Failure {
+                        error: note.unwrap_or("reported as not working").to_string(),
+                    }
+                },
+                context,
+                backfilled,
+            }
+        };
+
+        let existing = self
+            .proposals()
+            .into_iter()
+            .rev()
+            .find(|p| {
+                p.evidence
+                    .iter()
+                    .any(|e| e.context.get("solution_id") == Some(&solution.id))
+            })
+            .map(|p| (p.id.clone(), p.status.clone()));
+        match existing {
+            Some((id, ProposalStatus::Gathering { .. } | ProposalStatus::PendingReview)) => {
+                self.add_evidence(&id, evidence(worked, note, false))?;
+                Ok(FeedbackEffect::Evidence { proposal_id: id })
+            }
+            // Already decided on; feedback only updates the solution
+            Some(_) => Ok(FeedbackEffect::Recorded),
+            None if super::should_crystallize(solution) => {
+                // Earlier outcomes only exist as counts; the latest carries the note
+                let mut successes = solution.success_count;
+                let mut failures = solution.failure_count;
+                if worked {
+                    successes = successes.saturating_sub(1);
+                } else {
+                    failures = failures.saturating_sub(1);
+                }
+                let (conditions, actions) = super::infer_rule(solution);
+                let id = self.propose_rule(
+                    &solution.problem,
+                    conditions,
+                    actions,
+                    evidence(worked, note, false),
+                );
+                let proposal = self.proposals.get_mut(&id).expect("just proposed");
+                proposal.evidence.splice(
+                    0..0,
+                    (0..successes)
+                        .map(|_| evidence(true, None, true))
+                        .chain((0..failures).map(|_| evidence(false, None, true))),
+                );
+                proposal.update_status(self.tolerance.min_success_rate);
+                Ok(FeedbackEffect::Proposed { proposal_id: id })
+            }
+            None => Ok(FeedbackEffect::Recorded),
+        }
+    }
+
     /// Add evidence to a proposal
+    ///
+    /// Only successful outcomes count toward the review threshold; failures
+    /// and partial results lower confidence and can hold a proposal back.
     pub fn add_evidence(&mut self, proposal_id: &str, evidence: ProposalEvidence) -> Result<()> {
+        let min_success_rate = self.tolerance.min_success_rate;
         let proposal = self
             .proposals
             .get_mut(proposal_id)
             .ok_or_else(|| anyhow::anyhow!("Proposal not found: {}", proposal_id))?;
 
-        proposal.evidence.push(evidence.clone());
+        proposal.evidence.push(evidence);
+        if proposal.update_status(min_success_rate) {
+            tracing::info!(
+                "Proposal {} ready for review (confidence: {:.1}%)",
+                proposal_id,
+                proposal.confidence * 100.0
+            );
+        }
 
-        // Recalculate confidence
-        let successes = proposal
-            .evidence
-            .iter()
-            .filter(|e| matches!(e.outcome, EvidenceOutcome::Success))
-            .count();
-        proposal.confidence = successes as f32 / proposal.evidence.len() as f32;
-
-        // Check if ready for review
-        if let ProposalStatus::Gathering { count, required } = &proposal.status {
-            let new_count = *count + 1;
-            if new_count >= *required {
-                proposal.status = ProposalStatus::PendingReview;
-                tracing::info!(
-                    "Proposal {} ready for review (confidence: {:.1}%)",
+        Ok(())
+    }
+
+    /// Look up a proposal
+    pub fn proposal(&self, proposal_id: &str) -> Option<&RuleProposal> {
+        self.proposals.get(proposal_id)
+    }
+
+    /// Replace a proposal's problem pattern, conditions and actions before approval
+    pub fn edit_proposal(
+        &mut self,
+        proposal_id: &str,
+        problem_pattern: String,
+        conditions: Vec<super::Condition>,
+        actions: Vec<super::Action>,
+    ) -> Result<()> {
+        let proposal = self.open_proposal(proposal_id)?;
+        proposal.problem_pattern = problem_pattern;
+        proposal.suggested_conditions = conditions;
+        proposal.suggested_actions = actions;
+        Ok(())
+    }
+
+    /// Approve a proposal pending review and crystallize it into a rule
+    ///
+    /// Proposals without conditions are refused until edited.
+    /// The proposal stays `Approved { by }` if crystallization fails, so
+    /// approving again retries it. With a `reasoning` engine, its proofs
+    /// that the proposed commands fix the problem go into the rule's
+    /// decision path. Returns the new rule ID.
+    pub fn approve_proposal(
+        &mut self,
+        proposal_id: &str,
+        reviewer: &str,
+        engine: &mut super::RulesEngine,
+        reasoning: Option<&ReasoningEngine>,
+    ) -> Result<String> {
+        let proposal = self.open_proposal(proposal_id)?;
+        match &proposal.status {
+            ProposalStatus::PendingReview | ProposalStatus::Approved { .. } => {}
+            ProposalStatus::Gathering { count, required } => {
+                return Err(anyhow::anyhow!(
+                    "Proposal {} is still gathering evidence ({}/{} successes)",
                     proposal_id,
-                    proposal.confidence * 100.0
-                );
-            } else {
-                proposal.status = ProposalStatus::Gathering {
-                    count: new_count,
-                    required: *required,
-                };
+                    count,
+                    required
+                ));
             }
+            _ => unreachable!("open_proposal rejects closed proposals"),
+        }
+        // A rule without conditions would match every problem
+        if proposal.suggested_conditions.is_empty() {
+            anyhow::bail!(
+                "Proposal {} has no conditions; edit it to say when the rule applies",
+                proposal_id
+            );
         }
 
+        proposal.status = ProposalStatus::Approved {
+            by: reviewer.to_string(),
+        };
+        proposal.review = Some(ProposalReview::new(reviewer, None));
+
+        let solution = proposal.as_solution();
+        let (conditions, actions) = (
+            proposal.suggested_conditions.clone(),
+            proposal.suggested_actions.clone(),
+        );
+        let proofs: Vec<_> = reasoning
+            .into_iter()
+            .flat_map(|r| {
+                solution
+                    .commands
+                    .iter()
+                    .filter_map(|c| r.explain_fix(&solution.problem, c))
+            })
+            .collect();
+        let rule_id =
+            engine.crystallize(&solution, conditions, actions, Some(reviewer), &proofs)?;
+
+        if let Some(proposal) = self.proposals.get_mut(proposal_id) {
+            proposal.status = ProposalStatus::Crystallized {
+                rule_id: rule_id.clone(),
+            };
+        }
+        tracing::info!("Proposal {} approved by {} as {}", proposal_id, reviewer, rule_id);
+        Ok(rule_id)
+    }
+
+    /// Reject a proposal
+    pub fn reject_proposal(&mut self, proposal_id: &str, reviewer: &str, reason: &str) -> Result<()> {
+        let proposal = self.open_proposal(proposal_id)?;
+        proposal.status = ProposalStatus::Rejected {
+            reason: reason.to_string(),
+        };
+        proposal.review = Some(ProposalReview::new(reviewer, Some(reason)));
+        tracing::info!("Proposal {} rejected by {}: {}", proposal_id, reviewer, reason);
         Ok(())
     }
 
-    /// Check if a rule has become obsolete due to CVE fix
+    /// A proposal that can still be edited or reviewed
+    fn open_proposal(&mut self, proposal_id: &str) -> Result<&mut RuleProposal> {
+        let proposal = self
+            .proposals
+            .get_mut(proposal_id)
+            .ok_or_else(|| anyhow::anyhow!("Proposal not found: {}", proposal_id))?;
+        match &proposal.status {
+            ProposalStatus::Rejected { .. } => {
+                Err(anyhow::anyhow!("Proposal {} was rejected", proposal_id))
+            }
+            ProposalStatus::Crystallized { rule_id } => Err(anyhow::anyhow!(
+                "Proposal {} was already crystallized as {}",
+                proposal_id,
+                rule_id
+            )),
+            _ => Ok(proposal),
+        }
+    }
+
+    /// Check if a rule has become obsolete because a CVE it addresses is fixed
+    ///
+    /// Applies to rules naming the CVE (see [`Self::link_rules`]) or in
+    /// their original problem. With advisory data the installed packages
+    /// must be outside every affected range of the host's distribution;
+    /// CVEs registered by hand count as fixed once marked so.
     pub fn check_cve_obsolescence(&self, rule: &super::Rule) -> Option<ObsolescenceReason> {
-        // Check if rule was created for a CVE
-        if let super::RuleSource::Forum { url, .. } = &rule.provenance.source {
-            // Check for CVE pattern in URL or problem
-            for (cve_id, status) in &self.known_cves {
-                if rule.provenance.original_problem.contains(cve_id) {
-                    if let Some(fixed_in) = &status.fixed_in {
-                        // Check if current package version >= fixed version
-                        // Would use package manager to check
-                        return Some(ObsolescenceReason::CveFixed {
-                            cve_id: cve_id.clone(),
-                            fixed_version: fixed_in.clone(),
-                        });
-                    }
-                }
+        let mut cves: Vec<&CveStatus> = self
+            .known_cves
+            .values()
+            .filter(|c| {
+                c.related_rule_ids.contains(&rule.id)
+                    || rule.provenance.original_problem.contains(&c.id)
+            })
+            .collect();
+        cves.sort_by(|a, b| a.id.cmp(&b.id));
+
+        for status in cves {
+            let fixed_version = if status.patched_locally {
+                Some(status.fixed_in.clone().unwrap_or_else(|| "local patch".to_string()))
+            } else if status.affected.is_empty() {
+                status.fixed_in.clone()
+            } else {
+                self.fixed_on_host(status)
+            };
+            if let Some(fixed_version) = fixed_version {
+                return Some(ObsolescenceReason::CveFixed {
+                    cve_id: status.id.clone(),
+                    fixed_version,
+                });
             }
         }
         None
     }
 
+    /// If every installed affected package is outside the affected ranges,
+    /// describe the installed fixed versions
+    fn fixed_on_host(&self, status: &CveStatus) -> Option<String> {
+        let manager = self.package_manager?;
+        let mut fixed = vec![];
+        for package in self.host_packages(status)? {
+            let Some(installed) = self.probe.package_version(&package.name) else {
+                continue;
+            };
+            if package.is_affected(&installed, manager) {
+                return None;
+            }
+            fixed.push(format!("{} {}", package.name, installed));
+        }
+        fixed.sort();
+        fixed.dedup();
+        (!fixed.is_empty()).then(|| fixed.join(", "))
+    }
+
     /// Check if rule conditions are still valid
-    pub async fn check_condition_validity(&self, rule: &super::Rule) -> Option<ObsolescenceReason> {
+    pub fn check_condition_validity(&self, rule: &super::Rule) -> Option<ObsolescenceReason> {
         for condition in &rule.when {
             match condition {
-                super::Condition::FileExists { path } => {
-                    // If rule expects a file that no longer exists and it was about fixing something
-                    // the fix may have removed the problematic file
-                    if !std::path::Path::new(path).exists() {
-                        // Only obsolete if this was a "file exists" condition for a problem indicator
-                        // Not if it's a requirement for the fix
+                // If rule expects a file that no longer exists and it was about fixing something
+                // the fix may have removed the problematic file
+                super::Condition::FileExists { path } if !self.probe.file_exists(path) => {
+                    // Only obsolete if this was a "file exists" condition for a problem indicator
+                    // Not if it's a requirement for the fix
+                    return Some(ObsolescenceReason::ConditionInvalid {
+                        condition: format!("File no longer exists: {}", path),
+                    });
+                }
+                super::Condition::PackageInstalled { name } => {
+                    let Some(current) = self.probe.package_version(name) else {
                         return Some(ObsolescenceReason::ConditionInvalid {
-                            condition: format!("File no longer exists: {}", path),
+                            condition: format!("Package no longer installed: {}", name),
                         });
+                    };
+                    let baseline = self
+                        .package_baselines
+                        .get(&rule.id)
+                        .and_then(|b| b.versions.get(name));
+                    if let Some(old) = baseline {
+                        if major_version(old) != major_version(&current) {
+                            return Some(ObsolescenceReason::PackageUpdated {
+                                package: name.clone(),
+                                old_version: old.clone(),
+                                new_version: current,
+                            });
+                        }
                     }
                 }
-                super::Condition::PackageInstalled { name } => {
-                    // Check if package version changed significantly
-                    // Would integrate with package manager
-                }
                 _ => {}
             }
         }
         None
     }
 
+    /// Merge advisories into the CVE registry; returns how many CVEs are new
+    ///
+    /// Data for the same CVE from several feeds is combined, and local
+    /// state (patched locally, linked rules) is kept.
+    pub fn import_advisories(&mut self, advisories: Vec<Advisory>) -> usize {
+        let mut added = 0;
+        for advisory in advisories {
+            let status = self.known_cves.entry(advisory.id.clone()).or_insert_with(|| {
+                added += 1;
+                CveStatus {
+                    id: advisory.id.clone(),
+                    affected_packages: vec![],
+                    fixed_in: None,
+                    patched_locally: false,
+                    related_rule_ids: vec![],
+                    package_rule_ids: vec![],
+                    affected: vec![],
+                    summary: String::new(),
+                }
+            });
+
+            if status.summary.is_empty() {
+                status.summary = advisory.summary;
+            }
+            for package in advisory.affected {
+                status
+                    .affected
+                    .retain(|p| !(p.name == package.name && p.ecosystem == package.ecosystem));
+                status.affected.push(package);
+            }
+            status.affected_packages = status.affected.iter().map(|p| p.name.clone()).collect();
+            status.affected_packages.sort();
+            status.affected_packages.dedup();
+
+            let mut fixed: Vec<&str> =
+                status.affected.iter().flat_map(|p| p.fixed_versions()).collect();
+            fixed.sort();
+            fixed.dedup();
+            if !fixed.is_empty() {
+                status.fixed_in = Some(fixed.join(", "));
+            }
+        }
+        added
+    }
+
+    /// Link rules to the CVEs they relate to. Returns the number of links.
+    ///
+    /// Rules naming the CVE (in their problem, name or tags) are linked and
+    /// retired once it is fixed. Rules that merely check for, install or
+    /// restart an affected package are only listed as related: sharing a
+    /// package says nothing about which vulnerability a rule works around.
+    pub fn link_rules(&mut self, rules: &[super::Rule]) -> usize {
+        let mut links = 0;
+        for status in self.known_cves.values_mut() {
+            let (named, others): (Vec<&super::Rule>, Vec<&super::Rule>) =
+                rules.iter().partition(|rule| {
+                    rule.provenance.original_problem.contains(&status.id)
+                        || rule.name.contains(&status.id)
+                        || rule.tags.contains(&status.id)
+                });
+            status.related_rule_ids = named.iter().map(|rule| rule.id.clone()).collect();
+            status.package_rule_ids = others
+                .iter()
+                .filter(|rule| {
+                    referenced_packages(rule)
+                        .iter()
+                        .any(|p| status.affected_packages.contains(p))
+                })
+                .map(|rule| rule.id.clone())
+                .collect();
+            links += status.related_rule_ids.len() + status.package_rule_ids.len();
+        }
+        links
+    }
+
+    /// Tracked CVEs, sorted by ID
+    pub fn cves(&self) -> Vec<&CveStatus> {
+        let mut cves: Vec<_> = self.known_cves.values().collect();
+        cves.sort_by(|a, b| a.id.cmp(&b.id));
+        cves
+    }
+
+    /// Whether the host is affected by a CVE: `None` if no affected package
+    /// is installed or versions can't be compared
+    pub fn host_affected(&self, status: &CveStatus) -> Option<bool> {
+        let manager = self.package_manager?;
+        let installed: Vec<bool> = self
+            .host_packages(status)?
+            .filter_map(|p| {
+                let version = self.probe.package_version(&p.name)?;
+                Some(p.is_affected(&version, manager))
+            })
+            .collect();
+        (!installed.is_empty()).then(|| installed.iter().any(|a| *a))
+    }
+
+    /// The advisory's affected packages whose version data is for the host's
+    /// distribution; `None` if the distribution is unknown
+    fn host_packages<'s>(
+        &self,
+        status: &'s CveStatus,
+    ) -> Option<impl Iterator<Item = &'s AffectedPackage>> {
+        let distro = self.distro.clone()?;
+        Some(status.affected.iter().filter(move |p| p.applies_to(&distro)))
+    }
+
     /// Register a CVE that might affect rules
     pub fn register_cve(&mut self, cve_id: &str, affected_packages: Vec<String>) {
         self.known_cves.insert(
@@ -367,6 +1127,9 @@ pub fn register_cve(&mut self, cve_id: &str, affected_packages: Vec<String>) {
                 fixed_in: None,
                 patched_locally: false,
                 related_rule_ids: vec![],
+                package_rule_ids: vec![],
+                affected: vec![],
+                summary: String::new(),
             },
         );
         tracing::info!("Registered CVE: {}", cve_id);
@@ -396,6 +1159,13 @@ pub fn mark_cve_fixed(&mut self, cve_id: &str, fixed_version: Option<String>, lo
         }
     }
 
+    /// All proposals, oldest first
+    pub fn proposals(&self) -> Vec<&RuleProposal> {
+        let mut proposals: Vec<_> = self.proposals.values().collect();
+        proposals.sort_by(|a, b| a.created_at.cmp(&b.created_at));
+        proposals
+    }
+
     /// Get all proposals pending review
     pub fn pending_proposals(&self) -> Vec<&RuleProposal> {
         self.proposals
@@ -449,6 +1219,86 @@ pub fn generate_report(&self) -> LifecycleReport {
     }
 }
 
+/// Read persisted state, empty if the file does not exist yet
+fn read_state(path: &Path) -> Result<LifecycleState> {
+    match std::fs::read_to_string(path) {
+        Ok(content) => serde_json::from_str(&content)
+            .map_err(|e| anyhow::anyhow!("Corrupt lifecycle state {}: {}", path.display(), e)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(LifecycleState::default()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Apply the entries of `ours` that differ from `base` (added, changed or
+/// removed since it was read) to the state on disk
+fn merge<T: Serialize + Clone>(
+    disk: &mut HashMap<String, T>,
+    ours: &HashMap<String, T>,
+    base: &HashMap<String, T>,
+) -> Result<()> {
+    for (key, value) in ours {
+        let unchanged = match base.get(key) {
+            Some(old) => serde_json::to_value(old)? == serde_json::to_value(value)?,
+            None => false,
+        };
+        if !unchanged {
+            disk.insert(key.clone(), value.clone());
+        }
+    }
+    for key in base.keys().filter(|k| !ours.contains_key(*k)) {
+        disk.remove(key);
+    }
+    Ok(())
+}
+
+/// Packages a rule requires to be installed (top-level conditions)
+fn required_packages(rule: &super::Rule) -> Vec<String> {
+    rule.when
+        .iter()
+        .filter_map(|c| match c {
+            super::Condition::PackageInstalled { name } => Some(name.clone()),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Every package a rule mentions, in conditions at any depth or in actions
+fn referenced_packages(rule: &super::Rule) -> Vec<String> {
+    fn walk(condition: &super::Condition, out: &mut Vec<String>) {
+        match condition {
+            super::Condition::PackageInstalled { name } => out.push(name.clone()),
+            super::Condition::All { conditions } | super::Condition::Any { conditions } => {
+                conditions.iter().for_each(|c| walk(c, out))
+            }
+            super::Condition::Not { condition } => walk(condition, out),
+            _ => {}
+        }
+    }
+
+    let mut packages = vec![];
+    rule.when.iter().for_each(|c| walk(c, &mut packages));
+    packages.extend(rule.then.iter().filter_map(|a| match a {
+        super::Action::InstallPackage { name } => Some(name.clone()),
+        _ => None,
+    }));
+    packages
+}
+
+/// Epoch and leading numeric component: `1:2.4.58-1` -> `(1, 2)`
+fn major_version(version: &str) -> (u64, u64) {
+    let (epoch, rest) = match version.split_once(':') {
+        Some((e, rest)) => (e.parse().unwrap_or(0), rest),
+        None => (0, version),
+    };
+    let major = rest
+        .chars()
+        .take_while(char::is_ascii_digit)
+        .collect::<String>()
+        .parse()
+        .unwrap_or(0);
+    (epoch, major)
+}
+
 #[derive(Debug, Clone, Serialize)]
 pub struct LifecycleReport {
     pub timestamp: String,
@@ -460,3 +1310,939 @@ pub struct LifecycleReport {
     pub pending_proposals: usize,
     pub tracked_cves: usize,
 }
+
+/// Lifecycle CLI actions
+#[derive(Debug, Clone)]
+pub enum LifecycleAction {
+    /// Show rule health and proposal summary
+    Report,
+    /// List rule proposals
+    Proposals { all: bool },
+    /// Retire a rule: disable it and stop assessing it
+    Retire { rule: String, reason: Option<String> },
+    /// Import offline advisory data (OSV, Arch, Debian tracker JSON)
+    ImportAdvisories { paths: Vec<PathBuf>, release: Option<String> },
+    /// List tracked CVEs with host status and linked rules
+    Cves,
+}
+
+pub async fn handle(action: LifecycleAction) -> Result<()> {
+    let rules_dir = crate::dirs::data_dir().join("rules");
+    let mut manager = LifecycleManager::open_default()?;
+
+    match action {
+        LifecycleAction::Report => {
+            let engine = super::RulesEngine::new(&rules_dir)?;
+            manager.run_checks(engine.list());
+            manager.save()?;
+            show_report(&manager);
+        }
+        LifecycleAction::Proposals { all } => show_proposals(&manager, all),
+        LifecycleAction::Retire { rule, reason } => {
+            let mut engine = super::RulesEngine::new(&rules_dir)?;
+            let reason = reason.unwrap_or_else(|| "Retired manually".to_string());
+            retire_rule(&mut engine, &mut manager, &rule, &reason)?;
+            manager.save()?;
+            println!("Retired rule {}", rule);
+        }
+        LifecycleAction::ImportAdvisories { paths, release } => {
+            let mut advisories = vec![];
+            for path in &paths {
+                advisories.extend(super::advisory::load_path(path, release.as_deref())?);
+            }
+            let total = advisories.len();
+            let added = manager.import_advisories(advisories);
+
+            let engine = super::RulesEngine::new(&rules_dir)?;
+            let links = manager.link_rules(engine.list());
+            manager.save()?;
+            println!(
+                "Imported {} advisories ({} new CVEs), {} rule links",
+                total, added, links
+            );
+        }
+        LifecycleAction::Cves => show_cves(&manager),
+    }
+    Ok(())
+}
+
+fn show_cves(manager: &LifecycleManager) {
+    let cves = manager.cves();
+    if cves.is_empty() {
+        println!("No tracked CVEs");
+        return;
+    }
+
+    println!("{:<20} {:<12} {:<30} RULES (related)", "CVE", "HOST", "PACKAGES");
+    println!("{}", "-".repeat(90));
+    for cve in cves {
+        let host = if cve.patched_locally {
+            "patched"
+        } else {
+            match manager.host_affected(cve) {
+                Some(true) => "AFFECTED",
+                Some(false) => "fixed",
+                None => "-",
+            }
+        };
+        let related = if cve.package_rule_ids.is_empty() {
+            String::new()
+        } else {
+            format!(" ({})", cve.package_rule_ids.join(","))
+        };
+        println!(
+            "{:<20} {:<12} {:<30} {}{}",
+            cve.id,
+            host,
+            cve.affected_packages.join(","),
+            cve.related_rule_ids.join(","),
+            related
+        );
+    }
+}
+
+fn show_report(manager: &LifecycleManager) {
+    let report = manager.generate_report();
+
+    println!("Rule Lifecycle Report");
+    println!("{}", "=".repeat(50));
+    println!("Rules tracked:       {}", report.total_rules);
+    println!("  Healthy:           {}", report.healthy);
+    println!("  Degrading:         {}", report.degrading);
+    println!("  Needs review:      {}", report.needs_review);
+    println!("  Possibly obsolete: {}", report.possibly_obsolete);
+    println!("Pending proposals:   {}", report.pending_proposals);
+    println!("Tracked CVEs:        {}", report.tracked_cves);
+
+    let mut attention = manager.rules_needing_attention();
+    if !attention.is_empty() {
+        attention.sort_by(|a, b| a.0.cmp(b.0));
+        println!();
+        println!("Needing attention:");
+        println!("{}", "-".repeat(50));
+        for (id, health) in attention {
+            println!("  {:<40} {}", id, health);
+        }
+    }
+}
+
+fn show_proposals(manager: &LifecycleManager, all: bool) {
+    let proposals: Vec<_> = manager
+        .proposals()
+        .into_iter()
+        .filter(|p| {
+            all || !matches!(
+                p.status,
+                ProposalStatus::Rejected { .. } | ProposalStatus::Crystallized { .. }
+            )
+        })
+        .collect();
+
+    if proposals.is_empty() {
+        println!("No rule proposals");
+        return;
+    }
+
+    println!("{:<46} {:<16} {:>6} {:>8} PROBLEM", "ID", "STATUS", "CONF", "EVIDENCE");
+    println!("{}", "-".repeat(100));
+    for p in proposals {
+        let status = match &p.status {
+            ProposalStatus::Gathering { count, required } => format!("gathering {}/{}", count, required),
+            ProposalStatus::PendingReview => "pending review".to_string(),
+            ProposalStatus::Approved { .. } => "approved".to_string(),
+            ProposalStatus::Rejected { .. } => "rejected".to_string(),
+            ProposalStatus::Crystallized { .. } => "crystallized".to_string(),
+        };
+        println!(
+            "{:<46} {:<16} {:>5.0}% {:>8} {}",
+            p.id,
+            status,
+            p.confidence * 100.0,
+            p.evidence.len(),
+            p.problem_pattern
+        );
+    }
+}
+
+/// Disable a rule, record why in its provenance, and mark it retired
+fn retire_rule(
+    engine: &mut super::RulesEngine,
+    manager: &mut LifecycleManager,
+    rule_id: &str,
+    reason: &str,
+) -> Result<()> {
+    let mut rule = engine
+        .get(rule_id)
+        .cloned()
+        .ok_or_else(|| anyhow::anyhow!("Rule not found: {}", rule_id))?;
+
+    let rate = if rule.stats.applied_count > 0 {
+        rule.stats.success_count as f32 / rule.stats.applied_count as f32
+    } else {
+        0.0
+    };
+    rule.enabled = false;
+    rule.provenance.decision_path.push(super::DecisionStep {
+        timestamp: chrono::Utc::now().to_rfc3339(),
+        description: "Retired".to_string(),
+        confidence_before: rate,
+        confidence_after: 0.0,
+        reason: reason.to_string(),
+    });
+
+    engine.upsert(rule)?;
+    engine.commit(&[rule_id], &format!("Retire rule: {}", rule_id))?;
+    manager.retire(rule_id, reason);
+    Ok(())
+}
+
+/// Proposal review CLI actions
+#[derive(Debug, Clone)]
+pub enum ProposalsAction {
+    /// Walk through proposals pending review (or one proposal)
+    Review { id: Option<String> },
+    /// Approve a proposal and crystallize it into a rule
+    Approve { id: String },
+    /// Reject a proposal
+    Reject { id: String, reason: String },
+}
+
+pub async fn handle_proposals(action: ProposalsAction, storage: &Storage) -> Result<()> {
+    let rules_dir = crate::dirs::data_dir().join("rules");
+    let mut manager = LifecycleManager::open_default()?;
+    let reviewer = super::local_identity();
+
+    match action {
+        ProposalsAction::Review { id } => {
+            let mut engine = super::RulesEngine::new(&rules_dir)?;
+            let reasoning = reasoning_engine(storage).await;
+            let ids: Vec<String> = match id {
+                Some(id) => vec![id],
+                None => manager.pending_proposals().iter().map(|p| p.id.clone()).collect(),
+            };
+            if ids.is_empty() {
+                println!("No proposals pending review");
+                return Ok(());
+            }
+            for id in ids {
+                let keep_going = review_proposal(
+                    &mut manager,
+                    &mut engine,
+                    reasoning.as_ref(),
+                    &id,
+                    &reviewer,
+                )?;
+                // Save after every decision so quitting midway loses nothing
+                manager.save()?;
+                if !keep_going {
+                    break;
+                }
+            }
+        }
+        ProposalsAction::Approve { id } => {
+            let mut engine = super::RulesEngine::new(&rules_dir)?;
+            let reasoning = reasoning_engine(storage).await;
+            let result = manager.approve_proposal(&id, &reviewer, &mut engine, reasoning.as_ref());
+            manager.save()?;
+            println!("Approved {}; crystallized as {}", id, result?);
+        }
+        ProposalsAction::Reject { id, reason } => {
+            manager.reject_proposal(&id, &reviewer, &reason)?;
+            manager.save()?;
+            println!("Rejected {}", id);
+        }
+    }
+    Ok(())
+}
+
+/// The reasoning engine over the knowledge base, to explain approved
+/// proposals; approval goes ahead without it if it can't be built
+async fn reasoning_engine(storage: &Storage) -> Option<ReasoningEngine> {
+    match ReasoningEngine::from_storage(storage).await {
+        Ok(engine) => Some(engine),
+        Err(e) => {
+            tracing::warn!("Reasoning unavailable, approving without proofs: {:#}", e);
+            None
+        }
+    }
+}
+
+/// Editable part of a proposal, round-tripped through `$EDITOR`
+#[derive(Debug, Serialize, Deserialize)]
+struct ProposalDraft {
+    problem: String,
+    #[serde(default)]
+    when: Vec<super::Condition>,
+    #[serde(default)]
+    then: Vec<super::Action>,
+}
+
+/// Interactively review one proposal; returns false if the reviewer quit
+fn review_proposal(
+    manager: &mut LifecycleManager,
+    engine: &mut super::RulesEngine,
+    reasoning: Option<&ReasoningEngine>,
+    id: &str,
+    reviewer: &str,
+) -> Result<bool> {
+    loop {
+        let proposal = manager
+            .proposal(id)
+            .ok_or_else(|| anyhow::anyhow!("Proposal not found: {}", id))?;
+        print_proposal(proposal)?;
+
+        match prompt("[a]pprove, [e]dit, [r]eject, [s]kip, [q]uit? ")?.as_str() {
+            "a" | "approve" if proposal.suggested_conditions.is_empty() => {
+                println!("No conditions yet: [e]dit the proposal to say when it applies");
+            }
+            "a" | "approve" => {
+                let rule_id = manager.approve_proposal(id, reviewer, engine, reasoning)?;
+                println!("Approved; crystallized as {}", rule_id);
+                return Ok(true);
+            }
+            "e" | "edit" => {
+                let draft = ProposalDraft {
+                    problem: proposal.problem_pattern.clone(),
+                    when: proposal.suggested_conditions.clone(),
+                    then: proposal.suggested_actions.clone(),
+                };
+                let (old_when, old_then) = (draft.when.clone(), draft.then.clone());
+                match crate::editor::edit_toml(id, &draft) {
+                    Ok(edited) => {
+                        let diff =
+                            super::diff::diff_rule(&old_when, &old_then, &edited.when, &edited.then);
+                        if !diff.is_empty() {
+                            println!("Changes: {}", diff.summary());
+                        }
+                        manager.edit_proposal(id, edited.problem, edited.when, edited.then)?;
+                    }
+                    Err(e) => eprintln!("Edit discarded: {}", e),
+                }
+            }
+            "r" | "reject" => {
+                let reason = prompt("Reason: ")?;
+                let reason = if reason.is_empty() {
+                    "Rejected on review".to_string()
+                } else {
+                    reason
+                };
+                manager.reject_proposal(id, reviewer, &reason)?;
+                println!("Rejected");
+                return Ok(true);
+            }
+            "s" | "skip" | "" => return Ok(true),
+            "q" | "quit" => return Ok(false),
+            other => println!("Unknown choice: {}", other),
+        }
+    }
+}
+
+fn print_proposal(proposal: &RuleProposal) -> Result<()> {
+    println!();
+    println!("Proposal {}", proposal.id);
+    println!("{}", "=".repeat(50));
+    println!("Problem:    {}", proposal.problem_pattern);
+    println!("Created:    {}", proposal.created_at);
+    println!(
+        "Evidence:   {} ({} successful, confidence {:.0}%)",
+        proposal.evidence.len(),
+        proposal.successes(),
+        proposal.confidence * 100.0
+    );
+    for evidence in &proposal.evidence {
+        let outcome = match &evidence.outcome {
+            EvidenceOutcome::Success => "success".to_string(),
+            EvidenceOutcome::Failure { error } => format!("failure: {}", error),
+            EvidenceOutcome::Partial { details } => format!("partial: {}", details),
+        };
+        let when = if evidence.backfilled {
+            "(earlier, backfilled)"
+        } else {
+            &evidence.timestamp
+        };
+        println!("  {}  {:<12} {}", when, evidence.source, outcome);
+    }
+    println!("{}", "-".repeat(50));
+    let draft = ProposalDraft {
+        problem: proposal.problem_pattern.clone(),
+        when: proposal.suggested_conditions.clone(),
+        then: proposal.suggested_actions.clone(),
+    };
+    println!("{}", toml::to_string_pretty(&draft)?);
+    Ok(())
+}
+
+fn prompt(question: &str) -> Result<String> {
+    use std::io::Write;
+
+    print!("{}", question);
+    std::io::stdout().flush()?;
+    let mut answer = String::new();
+    std::io::stdin().read_line(&mut answer)?;
+    Ok(answer.trim().to_lowercase())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rules::advisory::VersionRange;
+    use crate::rules::fixture::{FixtureProbe, SimulatedSystem};
+    use crate::rules::{Action, Condition, Provenance, Rule, RuleSource, RuleStats};
+
+    fn memory_rule() -> Rule {
+        Rule {
+            id: "rule-mem".to_string(),
+            name: "Restart leaking service".to_string(),
+            version: "1.0.0".to_string(),
+            when: vec![Condition::MetricThreshold {
+                metric: "mem_pct".to_string(),
+                op: ">".to_string(),
+                value: 90.0,
+            }],
+            then: vec![Action::RestartService {
+                name: "nginx".to_string(),
+            }],
+            provenance: Provenance {
+                source: RuleSource::Manual {
+                    author: "tester".to_string(),
+                },
+                original_problem: "nginx leaks memory".to_string(),
+                solution_id: None,
+                created_at: String::new(),
+                created_by: "tester".to_string(),
+                decision_path: vec![],
+                history: vec![],
+            },
+            stats: RuleStats::default(),
+            enabled: true,
+            tags: vec![],
+        }
+    }
+
+    fn threshold(value: f64) -> Vec<Condition> {
+        vec![Condition::MetricThreshold {
+            metric: "mem_pct".to_string(),
+            op: ">".to_string(),
+            value,
+        }]
+    }
+
+    #[test]
+    fn test_within_tolerance() {
+        let manager = LifecycleManager::new(ToleranceConfig::default());
+        let rule = memory_rule();
+
+        // Identical and small numeric tweaks are tolerated
+        assert!(manager.within_tolerance(&rule, &rule.when, &rule.then));
+        assert!(manager.within_tolerance(&rule, &threshold(92.0), &rule.then));
+
+        // Large tweaks and semantic changes are not
+        assert!(!manager.within_tolerance(&rule, &threshold(70.0), &rule.then));
+        let other = vec![Action::RestartService {
+            name: "httpd".to_string(),
+        }];
+        assert!(!manager.within_tolerance(&rule, &rule.when, &other));
+
+        // Non-numeric parameters are tolerated only when unchanged
+        let relaxed = vec![Condition::MetricThreshold {
+            metric: "mem_pct".to_string(),
+            op: ">=".to_string(),
+            value: 90.0,
+        }];
+        assert!(!manager.within_tolerance(&rule, &relaxed, &rule.then));
+    }
+
+    #[test]
+    fn test_same_length_change_is_not_tolerated() {
+        let manager = LifecycleManager::new(ToleranceConfig::default());
+        let rule = memory_rule();
+        let shell = vec![Action::Shell {
+            command: "rm -rf /var/cache/nginx".to_string(),
+            sudo: true,
+        }];
+        assert!(!manager.within_tolerance(&rule, &rule.when, &shell));
+        assert_eq!(manager.diff(&rule, &rule.when, &shell).bump(&rule.version), "2.0.0");
+    }
+
+    #[test]
+    fn test_state_survives_reload() {
+        let dir = std::env::temp_dir().join(format!("psa-lifecycle-{}", uuid::Uuid::new_v4()));
+        let path = dir.join(STATE_FILE);
+
+        let mut manager = LifecycleManager::load(&path, ToleranceConfig::default()).unwrap();
+        let evidence = ProposalEvidence {
+            timestamp: chrono::Utc::now().to_rfc3339(),
+            source: "test".to_string(),
+            outcome: EvidenceOutcome::Success,
+            context: HashMap::new(),
+            backfilled: false,
+        };
+        let id = manager.propose_rule("nginx leaks memory", vec![], vec![], evidence);
+        manager.register_cve("CVE-2024-0001", vec!["nginx".to_string()]);
+        manager.assess_health(&memory_rule());
+        manager.save().unwrap();
+
+        let reloaded = LifecycleManager::load(&path, ToleranceConfig::default()).unwrap();
+        assert_eq!(reloaded.proposals().len(), 1);
+        assert_eq!(reloaded.proposals()[0].id, id);
+        assert!(reloaded.known_cves.contains_key("CVE-2024-0001"));
+        assert!(matches!(
+            reloaded.health("rule-mem"),
+            Some(RuleHealth::Probationary { .. })
+        ));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_save_keeps_other_writers_changes() {
+        let dir = std::env::temp_dir().join(format!("psa-lifecycle-{}", uuid::Uuid::new_v4()));
+        let path = dir.join(STATE_FILE);
+        let evidence = || ProposalEvidence {
+            timestamp: chrono::Utc::now().to_rfc3339(),
+            source: "test".to_string(),
+            outcome: EvidenceOutcome::Success,
+            context: HashMap::new(),
+            backfilled: false,
+        };
+
+        // A long-lived manager (the daemon) and a CLI process open the same file
+        let mut daemon = LifecycleManager::load(&path, ToleranceConfig::default()).unwrap();
+        let mut cli = LifecycleManager::load(&path, ToleranceConfig::default()).unwrap();
+        let id = cli.propose_rule("nginx leaks memory", vec![], vec![], evidence());
+        cli.save().unwrap();
+
+        daemon.assess_health(&memory_rule());
+        daemon.save().unwrap();
+        assert_eq!(daemon.proposals().len(), 1);
+
+        cli.reject_proposal(&id, "tester", "not reproducible").unwrap();
+        cli.save().unwrap();
+        daemon.retire("rule-mem", "replaced");
+        daemon.save().unwrap();
+
+        let reloaded = LifecycleManager::load(&path, ToleranceConfig::default()).unwrap();
+        assert!(matches!(
+            reloaded.proposals()[0].status,
+            ProposalStatus::Rejected { .. }
+        ));
+        assert!(matches!(
+            reloaded.health("rule-mem"),
+            Some(RuleHealth::Obsolete { .. })
+        ));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_retired_rules_are_not_reassessed() {
+        let mut manager = LifecycleManager::new(ToleranceConfig::default());
+        let rule = memory_rule();
+        manager.retire(&rule.id, "replaced by systemd MemoryMax");
+
+        let changed = manager.run_checks(std::slice::from_ref(&rule));
+        assert!(changed.is_empty());
+        assert!(matches!(manager.health(&rule.id), Some(RuleHealth::Obsolete { .. })));
+
+        // Health entries for rules that no longer exist are dropped
+        manager.run_checks(&[]);
+        assert!(manager.health(&rule.id).is_none());
+    }
+
+    fn openssl_rule() -> Rule {
+        Rule {
+            id: "rule-ssl".to_string(),
+            name: "Work around TLS renegotiation crash".to_string(),
+            when: vec![Condition::PackageInstalled {
+                name: "openssl".to_string(),
+            }],
+            ..memory_rule()
+        }
+    }
+
+    fn host_with(packages: &[(&str, &str)]) -> Box<FixtureProbe> {
+        let system = SimulatedSystem {
+            packages: packages
+                .iter()
+                .map(|(n, v)| (n.to_string(), v.to_string()))
+                .collect(),
+            ..Default::default()
+        };
+        Box::new(FixtureProbe::new(system))
+    }
+
+    fn openssl_advisory() -> Advisory {
+        Advisory {
+            id: "CVE-2023-0464".to_string(),
+            summary: "Excessive resource use verifying policy constraints".to_string(),
+            affected: vec![AffectedPackage {
+                name: "openssl".to_string(),
+                ecosystem: "Debian:12".to_string(),
+                ranges: vec![VersionRange {
+                    introduced: None,
+                    fixed: Some("3.0.9-1".to_string()),
+                }],
+            }],
+        }
+    }
+
+    fn bookworm() -> Option<HostDistro> {
+        Some(HostDistro {
+            id: "debian".to_string(),
+            version_id: Some("12".to_string()),
+            codename: Some("bookworm".to_string()),
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn test_advisories_link_rules_and_track_host_versions() {
+        let mut manager = LifecycleManager::new(ToleranceConfig::default());
+        manager.set_distro(bookworm());
+        assert_eq!(manager.import_advisories(vec![openssl_advisory()]), 1);
+        // Re-importing merges rather than duplicating
+        assert_eq!(manager.import_advisories(vec![openssl_advisory()]), 0);
+
+        // Only the rule naming the CVE is linked; one touching the package is related
+        let mut named = openssl_rule();
+        named.id = "rule-cve".to_string();
+        named.tags = vec!["CVE-2023-0464".to_string()];
+        let rules = vec![openssl_rule(), memory_rule(), named];
+        assert_eq!(manager.link_rules(&rules), 2);
+        let cve = manager.cves()[0].clone();
+        assert_eq!(cve.related_rule_ids, vec!["rule-cve"]);
+        assert_eq!(cve.package_rule_ids, vec!["rule-ssl"]);
+        assert_eq!(cve.fixed_in.as_deref(), Some("3.0.9-1"));
+
+        manager.set_probe(host_with(&[("openssl", "3.0.8-1")]), Some(PackageManager::Dpkg));
+        assert_eq!(manager.host_affected(&cve), Some(true));
+        assert!(manager.check_cve_obsolescence(&rules[2]).is_none());
+
+        manager.set_probe(
+            host_with(&[("openssl", "3.0.11-1~deb12u2")]),
+            Some(PackageManager::Dpkg),
+        );
+        assert!(matches!(
+            manager.check_cve_obsolescence(&rules[2]),
+            Some(ObsolescenceReason::CveFixed { fixed_version, .. })
+                if fixed_version == "openssl 3.0.11-1~deb12u2"
+        ));
+        assert!(manager.check_cve_obsolescence(&rules[0]).is_none());
+        assert!(manager.check_cve_obsolescence(&rules[1]).is_none());
+    }
+
+    #[test]
+    fn test_advisories_for_other_distros_are_ignored() {
+        let mut manager = LifecycleManager::new(ToleranceConfig::default());
+        manager.import_advisories(vec![openssl_advisory()]);
+        let mut rule = openssl_rule();
+        rule.tags = vec!["CVE-2023-0464".to_string()];
+        manager.link_rules(std::slice::from_ref(&rule));
+        let cve = manager.cves()[0].clone();
+
+        // A Debian fixed version says nothing about an RPM install
+        manager.set_distro(Some(HostDistro {
+            id: "fedora".to_string(),
+            version_id: Some("40".to_string()),
+            ..Default::default()
+        }));
+        manager.set_probe(host_with(&[("openssl", "3.1.4-2.fc40")]), Some(PackageManager::Rpm));
+        assert_eq!(manager.host_affected(&cve), None);
+        assert!(manager.check_cve_obsolescence(&rule).is_none());
+
+        // Nor is another Debian release's
+        manager.set_distro(Some(HostDistro {
+            id: "debian".to_string(),
+            version_id: Some("11".to_string()),
+            ..Default::default()
+        }));
+        manager.set_probe(host_with(&[("openssl", "3.0.11-1")]), Some(PackageManager::Dpkg));
+        assert!(manager.check_cve_obsolescence(&rule).is_none());
+    }
+
+    #[test]
+    fn test_package_conditions_checked_against_baseline() {
+        let mut manager = LifecycleManager::new(ToleranceConfig::default());
+        let rule = openssl_rule();
+
+        manager.set_probe(host_with(&[("openssl", "1.1.1w-0+deb11u1")]), None);
+        manager.run_checks(std::slice::from_ref(&rule));
+        assert!(manager.check_condition_validity(&rule).is_none());
+
+        // Minor updates are fine, a major upgrade needs a look
+        manager.set_probe(host_with(&[("openssl", "1.1.1x-0+deb11u1")]), None);
+        assert!(manager.check_condition_validity(&rule).is_none());
+        manager.set_probe(host_with(&[("openssl", "3.0.11-1")]), None);
+        assert!(matches!(
+            manager.check_condition_validity(&rule),
+            Some(ObsolescenceReason::PackageUpdated { old_version, .. })
+                if old_version == "1.1.1w-0+deb11u1"
+        ));
+
+        // Amending the rule takes a new baseline
+        let amended = Rule {
+            version: "1.1.0".to_string(),
+            ..rule.clone()
+        };
+        manager.run_checks(std::slice::from_ref(&amended));
+        assert!(manager.check_condition_validity(&amended).is_none());
+
+        manager.set_probe(host_with(&[]), None);
+        assert!(matches!(
+            manager.check_condition_validity(&amended),
+            Some(ObsolescenceReason::ConditionInvalid { .. })
+        ));
+    }
+
+    fn evidence(outcome: EvidenceOutcome) -> ProposalEvidence {
+        ProposalEvidence {
+            timestamp: chrono::Utc::now().to_rfc3339(),
+            source: "test".to_string(),
+            outcome,
+            context: HashMap::from([("solution_id".to_string(), "sol-1".to_string())]),
+            backfilled: false,
+        }
+    }
+
+    fn failure() -> EvidenceOutcome {
+        EvidenceOutcome::Failure {
+            error: "still broken".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_only_successes_advance_proposals() {
+        let mut manager = LifecycleManager::new(ToleranceConfig::default());
+        let id = manager.propose_rule("wifi drops", vec![], vec![], evidence(failure()));
+        assert_eq!(
+            manager.proposal(&id).unwrap().status,
+            ProposalStatus::Gathering { count: 0, required: 5 }
+        );
+
+        for _ in 0..4 {
+            manager.add_evidence(&id, evidence(failure())).unwrap();
+        }
+        assert_eq!(
+            manager.proposal(&id).unwrap().status,
+            ProposalStatus::Gathering { count: 0, required: 5 }
+        );
+
+        // Five successes, but 5/10 is below the 80% success floor
+        for _ in 0..5 {
+            manager.add_evidence(&id, evidence(EvidenceOutcome::Success)).unwrap();
+        }
+        assert_eq!(
+            manager.proposal(&id).unwrap().status,
+            ProposalStatus::Gathering { count: 5, required: 5 }
+        );
+
+        for _ in 0..15 {
+            manager.add_evidence(&id, evidence(EvidenceOutcome::Success)).unwrap();
+        }
+        assert_eq!(manager.proposal(&id).unwrap().status, ProposalStatus::PendingReview);
+        assert_eq!(manager.pending_proposals().len(), 1);
+
+        let solution = manager.proposal(&id).unwrap().as_solution();
+        assert_eq!(solution.id, "sol-1");
+        assert_eq!((solution.success_count, solution.failure_count), (20, 5));
+    }
+
+    #[test]
+    fn test_review_requires_open_proposal() {
+        let mut manager = LifecycleManager::new(ToleranceConfig::default());
+        let id = manager.propose_rule("wifi drops", vec![], vec![], evidence(EvidenceOutcome::Success));
+
+        manager
+            .edit_proposal(&id, "wifi drops after suspend".to_string(), vec![], vec![])
+            .unwrap();
+        manager.reject_proposal(&id, "alice", "too broad").unwrap();
+
+        let proposal = manager.proposal(&id).unwrap();
+        assert_eq!(proposal.problem_pattern, "wifi drops after suspend");
+        assert_eq!(proposal.review.as_ref().unwrap().by, "alice");
+        assert!(manager.edit_proposal(&id, String::new(), vec![], vec![]).is_err());
+        assert!(manager.reject_proposal(&id, "alice", "again").is_err());
+    }
+
+    #[test]
+    fn test_approval_requires_conditions() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut engine = crate::rules::RulesEngine {
+            rules: vec![],
+            rules_dir: dir.path().to_path_buf(),
+            index: HashMap::new(),
+            audit: crate::audit::AuditLog::open(dir.path()).unwrap(),
+            probe: std::sync::Arc::new(FixtureProbe::new(SimulatedSystem::default())),
+        };
+        let mut manager = LifecycleManager::new(ToleranceConfig::default());
+        let id = manager.propose_rule("wifi drops", vec![], vec![], evidence(EvidenceOutcome::Success));
+        for _ in 0..4 {
+            manager.add_evidence(&id, evidence(EvidenceOutcome::Success)).unwrap();
+        }
+        assert_eq!(manager.proposal(&id).unwrap().status, ProposalStatus::PendingReview);
+
+        // Without conditions the rule would match everything
+        assert!(manager.approve_proposal(&id, "alice", &mut engine, None).is_err());
+        assert_eq!(manager.proposal(&id).unwrap().status, ProposalStatus::PendingReview);
+        assert!(engine.list().is_empty());
+
+        let rule = memory_rule();
+        manager
+            .edit_proposal(&id, "wifi drops".to_string(), rule.when.clone(), rule.then.clone())
+            .unwrap();
+        let rule_id = manager.approve_proposal(&id, "alice", &mut engine, None).unwrap();
+        assert_eq!(engine.get(&rule_id).unwrap().when.len(), 1);
+    }
+
+    fn with_outcomes(pattern: &[(f64, bool)], now: chrono::DateTime<chrono::Utc>) -> Rule {
+        let mut rule = memory_rule();
+        for (days_ago, success) in pattern {
+            let at = now - chrono::Duration::seconds((days_ago * 86_400.0) as i64);
+            rule.stats.record(*success, 5.0, at);
+        }
+        rule
+    }
+
+    fn kb_solution(successes: u32, failures: u32) -> crate::storage::Solution {
+        let now = chrono::Utc::now();
+        crate::storage::Solution {
+            id: "sol-wifi".to_string(),
+            category: "network".to_string(),
+            problem: "wifi drops after suspend".to_string(),
+            solution: String::new(),
+            commands: vec![
+                "sudo systemctl restart NetworkManager".to_string(),
+                "sudo modprobe iwlwifi power_save=0".to_string(),
+                "nmcli radio wifi on".to_string(),
+            ],
+            tags: vec![],
+            success_count: successes,
+            failure_count: failures,
+            source: crate::storage::SolutionSource::Manual,
+            created_at: now,
+            updated_at: now,
+        }
+    }
+
+    #[test]
+    fn test_feedback_opens_proposal_once_proven() {
+        let mut manager = LifecycleManager::new(ToleranceConfig::default());
+        assert_eq!(
+            manager.solution_feedback(&kb_solution(4, 0), true, None).unwrap(),
+            FeedbackEffect::Recorded
+        );
+
+        let FeedbackEffect::Proposed { proposal_id } = manager
+            .solution_feedback(&kb_solution(5, 1), true, Some("after kernel update"))
+            .unwrap()
+        else {
+            panic!("expected a proposal");
+        };
+        let proposal = manager.proposal(&proposal_id).unwrap();
+        // Earlier outcomes are carried over as evidence, marked as backfilled
+        assert_eq!(proposal.evidence.len(), 6);
+        assert_eq!(proposal.evidence.iter().filter(|e| e.backfilled).count(), 5);
+        assert!(!proposal.evidence.last().unwrap().backfilled);
+        assert_eq!(proposal.status, ProposalStatus::PendingReview);
+        assert_eq!(
+            proposal.suggested_actions,
+            [
+                Action::RestartService {
+                    name: "NetworkManager".to_string()
+                },
+                Action::LoadModule {
+                    name: "iwlwifi".to_string(),
+                    options: Some("power_save=0".to_string()),
+                },
+                Action::Shell {
+                    command: "nmcli radio wifi on".to_string(),
+                    sudo: false,
+                },
+            ]
+        );
+        // The guards are alternatives: each covers its own action
+        assert!(matches!(
+            proposal.suggested_conditions.as_slice(),
+            [Condition::Any { conditions }] if conditions.len() == 2
+        ));
+        assert_eq!(proposal.as_solution().id, "sol-wifi");
+
+        // Later reports go to the same proposal, until it is decided on
+        assert_eq!(
+            manager.solution_feedback(&kb_solution(5, 2), false, None).unwrap(),
+            FeedbackEffect::Evidence {
+                proposal_id: proposal_id.clone()
+            }
+        );
+        manager.reject_proposal(&proposal_id, "tester", "too broad").unwrap();
+        assert_eq!(
+            manager.solution_feedback(&kb_solution(6, 2), true, None).unwrap(),
+            FeedbackEffect::Recorded
+        );
+        assert_eq!(manager.proposals().len(), 1);
+    }
+
+    #[test]
+    fn test_health_uses_window_and_interval() {
+        let manager = LifecycleManager::new(ToleranceConfig::default());
+        let now = chrono::Utc::now();
+
+        // Twelve straight successes: healthy even though the interval is wide
+        let steady: Vec<_> = (0..12).map(|i| (6.0 - i as f64 * 0.5, true)).collect();
+        assert_eq!(manager.health_at(&with_outcomes(&steady, now), now), RuleHealth::Healthy);
+
+        // Old failures outside the window don't count
+        let mut recovered: Vec<_> = (0..10).map(|i| (20.0 + i as f64, false)).collect();
+        recovered.extend((0..10).map(|i| (5.0 - i as f64 * 0.5, true)));
+        assert_eq!(manager.health_at(&with_outcomes(&recovered, now), now), RuleHealth::Healthy);
+
+        // Mostly failing in the window: confidently below 80%
+        let failing: Vec<_> = (0..12).map(|i| (6.0 - i as f64 * 0.5, i % 3 == 0)).collect();
+        assert!(matches!(
+            manager.health_at(&with_outcomes(&failing, now), now),
+            RuleHealth::NeedsReview { .. }
+        ));
+    }
+
+    #[test]
+    fn test_health_detects_decline() {
+        let manager = LifecycleManager::new(ToleranceConfig::default());
+        let now = chrono::Utc::now();
+
+        // Perfect for most of the week, then two failures at the end:
+        // overall rate 83% is above target, but the trend is falling
+        let pattern: Vec<_> = (0..12).map(|i| (6.0 - i as f64 * 0.5, i < 10)).collect();
+        match manager.health_at(&with_outcomes(&pattern, now), now) {
+            RuleHealth::Degrading { current_rate, trend } => {
+                assert!(current_rate > 0.8);
+                assert!(trend < -0.2);
+            }
+            other => panic!("expected degrading, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_few_recent_failures_are_not_degrading() {
+        let manager = LifecycleManager::new(ToleranceConfig::default());
+        let now = chrono::Utc::now();
+
+        // A long-proven rule with only a couple of executions this week
+        let mut rule = with_outcomes(&[(5.0, true), (1.0, false), (0.5, false)], now);
+        rule.stats.applied_count = 50;
+        rule.stats.success_count = 48;
+        assert_eq!(manager.health_at(&rule, now), RuleHealth::Healthy);
+    }
+
+    #[test]
+    fn test_health_falls_back_to_counters() {
+        let mut manager = LifecycleManager::new(ToleranceConfig::default());
+        let mut rule = memory_rule();
+        rule.stats.applied_count = 20;
+        rule.stats.success_count = 8;
+        rule.stats.failure_count = 12;
+
+        let stats = manager.window_stats(&rule, chrono::Utc::now());
+        assert_eq!((stats.samples, stats.successes), (20, 8));
+        assert!(matches!(manager.assess_health(&rule), RuleHealth::NeedsReview { .. }));
+    }
+}
@@ -13,16 +13,38 @@
 //! - Version history (git-like commits)
 //! - Success/failure counts post-crystallization
 
+// Allow dead code - matching and execution are driven by the daemon, not the CLI binary
+#![allow(dead_code)]
+
+pub mod advisory;
+pub mod bundle;
+pub mod diff;
+pub mod fixture;
+pub mod health;
+pub mod lifecycle;
+pub mod probe;
+pub mod version;
+
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
 
-use crate::validation::{validate_pattern, validate_service_name};
+use crate::audit::{AuditEntry, AuditLog};
+use crate::validation::{
+    validate_module_name, validate_module_options, validate_package_name, validate_safe_path,
+    validate_service_name,
+};
+use probe::{HostProbe, PackageManager, SystemProbe};
 
 /// Confidence threshold for crystallizing a solution into a rule
 const CRYSTALLIZATION_THRESHOLD: u32 = 5;
 
+/// Least confidence a solution needs to be crystallized: at five successes,
+/// at most one failure
+const CRYSTALLIZATION_CONFIDENCE: f64 = 0.7;
+
 /// A crystallized rule - simple, deterministic, inspectable
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Rule {
@@ -47,7 +69,7 @@ pub struct Rule {
 }
 
 /// A condition that must be true for a rule to apply
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(tag = "type")]
 pub enum Condition {
     /// Check if a process is running
@@ -77,7 +99,7 @@ pub enum Condition {
 }
 
 /// An action to take when conditions are met
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(tag = "type")]
 pub enum Action {
     /// Run a shell command
@@ -100,6 +122,43 @@ pub enum Action {
     Escalate { reason: String },
 }
 
+impl Action {
+    /// Variant name, as written in the rule's `type` field
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Action::Shell { .. } => "Shell",
+            Action::RestartService { .. } => "RestartService",
+            Action::EnableService { .. } => "EnableService",
+            Action::WriteFile { .. } => "WriteFile",
+            Action::LoadModule { .. } => "LoadModule",
+            Action::InstallPackage { .. } => "InstallPackage",
+            Action::Log { .. } => "Log",
+            Action::Notify { .. } => "Notify",
+            Action::Escalate { .. } => "Escalate",
+        }
+    }
+
+    /// Command line this action spawns, empty if it runs no process
+    pub fn argv(&self) -> Vec<String> {
+        let argv: Vec<&str> = match self {
+            Action::Shell { command, sudo: true } => vec!["sudo", "sh", "-c", command],
+            Action::Shell { command, sudo: false } => vec!["sh", "-c", command],
+            Action::RestartService { name } => vec!["systemctl", "restart", name],
+            Action::EnableService { name } => vec!["systemctl", "enable", name],
+            Action::LoadModule { name, options } => std::iter::once("modprobe")
+                .chain(std::iter::once(name.as_str()))
+                .chain(options.iter().flat_map(|o| o.split_whitespace()))
+                .collect(),
+            Action::InstallPackage { name } => PackageManager::detect()
+                .map(|pm| pm.install_argv(name))
+                .unwrap_or_default(),
+            Action::Notify { title, body } => vec!["notify-send", title, body],
+            _ => vec![],
+        };
+        argv.into_iter().map(str::to_string).collect()
+    }
+}
+
 /// Full provenance tracking for auditability
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Provenance {
@@ -159,6 +218,46 @@ pub struct RuleStats {
     pub escalation_count: u32,
     pub last_applied: Option<String>,
     pub average_duration_ms: Option<f64>,
+    /// Most recent executions, oldest first (capped at `MAX_OUTCOMES`)
+    #[serde(default)]
+    pub outcomes: Vec<ExecutionOutcome>,
+}
+
+/// Number of per-execution outcomes kept for windowed health
+pub const MAX_OUTCOMES: usize = 256;
+
+/// One execution of a rule
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ExecutionOutcome {
+    pub at: chrono::DateTime<chrono::Utc>,
+    pub success: bool,
+    pub duration_ms: f64,
+}
+
+impl RuleStats {
+    /// Record an execution in the counters and the outcome series
+    pub fn record(&mut self, success: bool, duration_ms: f64, at: chrono::DateTime<chrono::Utc>) {
+        self.applied_count += 1;
+        if success {
+            self.success_count += 1;
+        } else {
+            self.failure_count += 1;
+        }
+        self.last_applied = Some(at.to_rfc3339());
+
+        let previous = self.average_duration_ms.unwrap_or(0.0) * (self.applied_count - 1) as f64;
+        self.average_duration_ms = Some((previous + duration_ms) / self.applied_count as f64);
+
+        self.outcomes.push(ExecutionOutcome {
+            at,
+            success,
+            duration_ms,
+        });
+        if self.outcomes.len() > MAX_OUTCOMES {
+            let excess = self.outcomes.len() - MAX_OUTCOMES;
+            self.outcomes.drain(..excess);
+        }
+    }
 }
 
 /// The rules engine - manages loading, matching, and executing rules
@@ -169,6 +268,10 @@ pub struct RulesEngine {
     rules_dir: PathBuf,
     /// Index for fast matching
     index: HashMap<String, Vec<usize>>,
+    /// Append-only record of every executed action
+    audit: AuditLog,
+    /// Source of system facts for condition evaluation
+    probe: Arc<dyn SystemProbe>,
 }
 
 impl RulesEngine {
@@ -178,6 +281,8 @@ pub fn new(rules_dir: &Path) -> Result<Self> {
             rules: vec![],
             rules_dir: rules_dir.to_path_buf(),
             index: HashMap::new(),
+            audit: AuditLog::open_default()?,
+            probe: Arc::new(HostProbe),
         };
 
         engine.load_rules()?;
@@ -196,7 +301,10 @@ fn load_rules(&mut self) -> Result<()> {
             let entry = entry?;
             let path = entry.path();
 
-            if path.extension().is_some_and(|e| e == "toml") {
+            let is_fixture = path
+                .file_name()
+                .is_some_and(|n| n.to_string_lossy().ends_with(fixture::FIXTURE_SUFFIX));
+            if path.extension().is_some_and(|e| e == "toml") && !is_fixture {
                 if let Ok(content) = std::fs::read_to_string(&path) {
                     if let Ok(rule) = toml::from_str::<Rule>(&content) {
                         self.add_rule(rule);
@@ -262,86 +370,52 @@ pub fn find_matching(&self, context: &ProblemContext) -> Vec<&Rule> {
         }
 
         // Sort by specificity (more conditions = more specific)
-        matches.sort_by(|a, b| b.when.len().cmp(&a.when.len()));
+        matches.sort_by_key(|r| std::cmp::Reverse(r.when.len()));
+        matches
+    }
+
+    /// Find matching rules from async code
+    ///
+    /// Probes block: they spawn `systemctl` and `pgrep`, run shell checks and
+    /// sample `cpu_pct` over an interval. The conditions are evaluated on the
+    /// blocking pool so the runtime keeps serving other tasks meanwhile.
+    pub async fn find_matching_async(&self, context: &ProblemContext) -> Vec<&Rule> {
+        let probe = self.probe.clone();
+        let candidates: Vec<(usize, Vec<Condition>)> = self
+            .rules
+            .iter()
+            .enumerate()
+            .filter(|(_, rule)| rule.enabled)
+            .map(|(i, rule)| (i, rule.when.clone()))
+            .collect();
+        let context = context.clone();
+
+        let matched = tokio::task::spawn_blocking(move || {
+            candidates
+                .into_iter()
+                .filter(|(_, when)| evaluate_all(probe.as_ref(), when, &context))
+                .map(|(i, _)| i)
+                .collect::<Vec<_>>()
+        })
+        .await
+        .unwrap_or_else(|e| {
+            tracing::error!("Rule evaluation failed: {}", e);
+            vec![]
+        });
+
+        let mut matches: Vec<&Rule> = matched.into_iter().map(|i| &self.rules[i]).collect();
+        matches.sort_by_key(|r| std::cmp::Reverse(r.when.len()));
         matches
     }
 
     /// Evaluate conditions against current system state
     fn evaluate_conditions(&self, conditions: &[Condition], context: &ProblemContext) -> bool {
-        conditions.iter().all(|c| self.evaluate_condition(c, context))
+        evaluate_all(self.probe.as_ref(), conditions, context)
     }
 
-    fn evaluate_condition(&self, condition: &Condition, _context: &ProblemContext) -> bool {
-        match condition {
-            Condition::ProcessRunning { name } => {
-                // SECURITY: Validate process name pattern before passing to pgrep
-                let safe_name = match validate_pattern(name) {
-                    Ok(n) => n,
-                    Err(e) => {
-                        tracing::warn!("Invalid process pattern '{}': {}", name, e);
-                        return false;
-                    }
-                };
-                std::process::Command::new("pgrep")
-                    .arg(safe_name)
-                    .output()
-                    .map(|o| o.status.success())
-                    .unwrap_or(false)
-            }
-            Condition::ServiceState { name, state } => {
-                // SECURITY: Validate service name before passing to systemctl
-                let safe_name = match validate_service_name(name) {
-                    Ok(n) => n,
-                    Err(e) => {
-                        tracing::warn!("Invalid service name '{}': {}", name, e);
-                        return false;
-                    }
-                };
-                std::process::Command::new("systemctl")
-                    .args(["is-active", safe_name])
-                    .output()
-                    .map(|o| {
-                        String::from_utf8_lossy(&o.stdout)
-                            .trim()
-                            .eq_ignore_ascii_case(state)
-                    })
-                    .unwrap_or(false)
-            }
-            Condition::FileExists { path } => Path::new(path).exists(),
-            Condition::FileContains { path, pattern } => {
-                std::fs::read_to_string(path)
-                    .map(|content| content.contains(pattern))
-                    .unwrap_or(false)
-            }
-            Condition::ModuleLoaded { name } => {
-                std::fs::read_to_string("/proc/modules")
-                    .map(|content| content.lines().any(|l| l.starts_with(name)))
-                    .unwrap_or(false)
-            }
-            Condition::ShellCheck { command } => {
-                // SECURITY NOTE: ShellCheck intentionally executes arbitrary shell commands.
-                // This is a feature, not a vulnerability. The security model relies on:
-                // 1. Rule files being protected by filesystem permissions
-                // 2. Crystallization only from trusted solution sources
-                // 3. Human review of rules before enabling
-                std::process::Command::new("sh")
-                    .args(["-c", command])
-                    .output()
-                    .map(|o| o.status.success())
-                    .unwrap_or(false)
-            }
-            Condition::All { conditions } => {
-                conditions.iter().all(|c| self.evaluate_condition(c, _context))
-            }
-            Condition::Any { conditions } => {
-                conditions.iter().any(|c| self.evaluate_condition(c, _context))
-            }
-            Condition::Not { condition } => !self.evaluate_condition(condition, _context),
-            _ => {
-                // TODO: Implement remaining conditions
-                false
-            }
-        }
+    /// Replace the probe used to read system facts
+    pub fn set_probe(&mut self, probe: Box<dyn SystemProbe>) {
+        self.probe = Arc::from(probe);
     }
 
     /// Execute a rule's actions
@@ -357,7 +431,7 @@ pub async fn execute(&mut self, rule_id: &str) -> Result<ExecutionResult> {
         let mut result = ExecutionResult::default();
 
         for action in &rule.then {
-            match self.execute_action(action).await {
+            match self.execute_action(&rule, action).await {
                 Ok(output) => {
                     result.outputs.push(output);
                 }
@@ -371,21 +445,45 @@ pub async fn execute(&mut self, rule_id: &str) -> Result<ExecutionResult> {
 
         result.duration_ms = start.elapsed().as_millis() as f64;
 
-        // Update stats
+        // Update stats; persisted uncommitted, like any runtime state in the rule file
         if let Some(r) = self.rules.iter_mut().find(|r| r.id == rule_id) {
-            r.stats.applied_count += 1;
-            if result.success {
-                r.stats.success_count += 1;
-            } else {
-                r.stats.failure_count += 1;
+            r.stats.record(result.success, result.duration_ms, chrono::Utc::now());
+            let updated = r.clone();
+            if let Err(e) = self.write_rule(&updated) {
+                tracing::error!("Failed to persist stats for rule {}: {}", rule_id, e);
             }
-            r.stats.last_applied = Some(chrono::Utc::now().to_rfc3339());
         }
 
         Ok(result)
     }
 
-    async fn execute_action(&self, action: &Action) -> Result<String> {
+    /// Execute one action and append the outcome to the audit log
+    async fn execute_action(&self, rule: &Rule, action: &Action) -> Result<String> {
+        let start = std::time::Instant::now();
+        let (exit_status, result) = self.run_action(action).await;
+
+        let entry = AuditEntry {
+            rule_id: rule.id.clone(),
+            rule_version: rule.version.clone(),
+            action: action.kind().to_string(),
+            argv: action.argv(),
+            exit_status,
+            success: result.is_ok(),
+            output: match &result {
+                Ok(output) => output.clone(),
+                Err(e) => e.to_string(),
+            },
+            duration_ms: start.elapsed().as_secs_f64() * 1000.0,
+        };
+        if let Err(e) = self.audit.append(entry) {
+            tracing::error!("Failed to write audit record for rule {}: {}", rule.id, e);
+        }
+
+        result
+    }
+
+    /// Run an action, returning the process exit status (if one was spawned) and output
+    async fn run_action(&self, action: &Action) -> (Option<i32>, Result<String>) {
         match action {
             Action::Shell { command, sudo } => {
                 // SECURITY NOTE: Shell action intentionally executes arbitrary shell commands.
@@ -398,37 +496,52 @@ async fn execute_action(&self, action: &Action) -> Result<String> {
                     tokio::process::Command::new("sudo")
                         .args(["sh", "-c", command])
                         .output()
-                        .await?
+                        .await
                 } else {
                     tokio::process::Command::new("sh")
                         .args(["-c", command])
                         .output()
-                        .await?
+                        .await
                 };
 
-                if output.status.success() {
-                    Ok(String::from_utf8_lossy(&output.stdout).to_string())
-                } else {
-                    Err(anyhow::anyhow!(
-                        "Command failed: {}",
-                        String::from_utf8_lossy(&output.stderr)
-                    ))
+                match output {
+                    Ok(output) if output.status.success() => (
+                        output.status.code(),
+                        Ok(String::from_utf8_lossy(&output.stdout).to_string()),
+                    ),
+                    Ok(output) => (
+                        output.status.code(),
+                        Err(anyhow::anyhow!(
+                            "Command failed: {}",
+                            String::from_utf8_lossy(&output.stderr)
+                        )),
+                    ),
+                    Err(e) => (None, Err(e.into())),
                 }
             }
             Action::RestartService { name } => {
                 // SECURITY: Validate service name before passing to systemctl
-                let safe_name = validate_service_name(name)
-                    .map_err(|e| anyhow::anyhow!("Invalid service name '{}': {}", name, e))?;
+                let safe_name = match validate_service_name(name) {
+                    Ok(n) => n,
+                    Err(e) => {
+                        return (None, Err(anyhow::anyhow!("Invalid service name '{}': {}", name, e)));
+                    }
+                };
 
-                let output = tokio::process::Command::new("systemctl")
+                match tokio::process::Command::new("systemctl")
                     .args(["restart", safe_name])
                     .output()
-                    .await?;
-
-                if output.status.success() {
-                    Ok(format!("Restarted service: {}", safe_name))
-                } else {
-                    Err(anyhow::anyhow!("Failed to restart {}", safe_name))
+                    .await
+                {
+                    Ok(output) if output.status.success() => (
+                        output.status.code(),
+                        Ok(format!("Restarted service: {}", safe_name)),
+                    ),
+                    Ok(output) => (
+                        output.status.code(),
+                        Err(anyhow::anyhow!("Failed to restart {}", safe_name)),
+                    ),
+                    Err(e) => (None, Err(e.into())),
                 }
             }
             Action::Log { level, message } => {
@@ -438,34 +551,91 @@ async fn execute_action(&self, action: &Action) -> Result<String> {
                     "debug" => tracing::debug!("{}", message),
                     _ => tracing::info!("{}", message),
                 }
-                Ok(format!("[{}] {}", level, message))
+                (None, Ok(format!("[{}] {}", level, message)))
             }
             Action::Notify { title, body } => {
                 // Use notify-send if available
-                let _ = tokio::process::Command::new("notify-send")
+                let status = tokio::process::Command::new("notify-send")
                     .args([title, body])
                     .output()
-                    .await;
-                Ok(format!("Notification: {} - {}", title, body))
+                    .await
+                    .ok()
+                    .and_then(|o| o.status.code());
+                (status, Ok(format!("Notification: {} - {}", title, body)))
             }
             Action::Escalate { reason } => {
-                Err(anyhow::anyhow!("Escalation required: {}", reason))
+                (None, Err(anyhow::anyhow!("Escalation required: {}", reason)))
             }
-            _ => {
-                // TODO: Implement remaining actions
-                Ok("Action not implemented".to_string())
+            Action::EnableService { name } => match validate_service_name(name) {
+                Ok(_) => run_argv(&action.argv(), format!("Enabled service: {}", name)).await,
+                Err(e) => (None, Err(anyhow::anyhow!("Invalid service name '{}': {}", name, e))),
+            },
+            Action::LoadModule { name, options } => {
+                let valid = validate_module_name(name).and_then(|_| {
+                    options.as_deref().map_or(Ok(""), validate_module_options)
+                });
+                match valid {
+                    Ok(_) => run_argv(&action.argv(), format!("Loaded module: {}", name)).await,
+                    Err(e) => (None, Err(anyhow::anyhow!("Invalid module '{}': {}", name, e))),
+                }
+            }
+            Action::InstallPackage { name } => {
+                if let Err(e) = validate_package_name(name) {
+                    return (None, Err(anyhow::anyhow!("Invalid package name '{}': {}", name, e)));
+                }
+                if PackageManager::detect().is_none() {
+                    let e = anyhow::anyhow!("No supported package manager to install {}", name);
+                    return (None, Err(e));
+                }
+                run_argv(&action.argv(), format!("Installed package: {}", name)).await
             }
+            _ => (None, Err(anyhow::anyhow!("{} actions are not supported yet", action.kind()))),
         }
     }
 
     /// Crystallize a proven solution into a rule
+    ///
+    /// `reviewer` is the person who approved it, if it went through review;
+    /// they are recorded in the decision path and as author of the first version.
+    /// `proofs` are the reasoning engine's derivations of the rule's fixes;
+    /// each clause they used becomes a step of the decision path.
     pub fn crystallize(
         &mut self,
         solution: &crate::storage::Solution,
         conditions: Vec<Condition>,
         actions: Vec<Action>,
+        reviewer: Option<&str>,
+        proofs: &[std::rc::Rc<crate::reasoning::proof::Proof>],
     ) -> Result<String> {
+        if conditions.is_empty() {
+            anyhow::bail!("A crystallized rule needs at least one condition");
+        }
         let rule_id = format!("rule-{}", uuid::Uuid::new_v4());
+        let now = chrono::Utc::now().to_rfc3339();
+        let confidence = crate::storage::confidence(solution) as f32;
+
+        let mut decision_path = vec![DecisionStep {
+            timestamp: now.clone(),
+            description: "Crystallized from proven solution".to_string(),
+            confidence_before: 0.0,
+            confidence_after: confidence,
+            reason: format!(
+                "Solution proven with {} successes, {} failures",
+                solution.success_count, solution.failure_count
+            ),
+        }];
+        for proof in proofs {
+            decision_path.extend(proof_steps(proof, &now));
+        }
+        if let Some(reviewer) = reviewer {
+            decision_path.push(DecisionStep {
+                timestamp: now.clone(),
+                description: "Approved on review".to_string(),
+                confidence_before: confidence,
+                confidence_after: confidence,
+                reason: format!("Approved by {}", reviewer),
+            });
+        }
 
         let rule = Rule {
             id: rule_id.clone(),
@@ -476,28 +646,17 @@ pub fn crystallize(
             provenance: Provenance {
                 source: RuleSource::Crystallized {
                     solution_id: solution.id.clone(),
-                    confidence: solution.success_count as f32
-                        / (solution.success_count + solution.failure_count + 1) as f32,
+                    confidence,
                 },
                 original_problem: solution.problem.clone(),
                 solution_id: Some(solution.id.clone()),
-                created_at: chrono::Utc::now().to_rfc3339(),
+                created_at: now.clone(),
                 created_by: "psa-auto".to_string(),
-                decision_path: vec![DecisionStep {
-                    timestamp: chrono::Utc::now().to_rfc3339(),
-                    description: "Crystallized from proven solution".to_string(),
-                    confidence_before: 0.0,
-                    confidence_after: solution.success_count as f32
-                        / (solution.success_count + solution.failure_count + 1) as f32,
-                    reason: format!(
-                        "Solution proven with {} successes, {} failures",
-                        solution.success_count, solution.failure_count
-                    ),
-                }],
+                decision_path,
                 history: vec![RuleVersion {
                     version: "1.0.0".to_string(),
-                    timestamp: chrono::Utc::now().to_rfc3339(),
-                    author: "psa-auto".to_string(),
+                    timestamp: now,
+                    author: reviewer.unwrap_or("psa-auto").to_string(),
                     message: "Initial crystallization".to_string(),
                     diff_summary: "Created from solution".to_string(),
                 }],
@@ -507,30 +666,112 @@ pub fn crystallize(
             tags: solution.tags.clone(),
         };
 
-        // Save to file
-        let rule_path = self.rules_dir.join(format!("{}.toml", rule_id));
-        let content = toml::to_string_pretty(&rule)?;
+        self.write_rule(&rule)?;
+        self.git_commit(&[rule_file_name(&rule_id)], &format!("Crystallize rule: {}", rule.name))?;
+
+        self.add_rule(rule);
+
+        tracing::info!("Crystallized new rule: {}", rule_id);
+        Ok(rule_id)
+    }
+
+    /// Insert or replace a rule, persisting it to the rules directory (uncommitted)
+    pub fn upsert(&mut self, rule: Rule) -> Result<()> {
+        self.write_rule(&rule)?;
+
+        if let Some(existing) = self.rules.iter_mut().find(|r| r.id == rule.id) {
+            *existing = rule;
+            self.rebuild_index();
+        } else {
+            self.add_rule(rule);
+        }
+        Ok(())
+    }
+
+    /// Revise a rule's conditions and actions
+    ///
+    /// The version is bumped from the structural diff (major for semantic
+    /// changes, minor for parameter tweaks) and the diff is recorded in the
+    /// rule's history. Returns the diff; nothing is written if it is empty.
+    pub fn amend(
+        &mut self,
+        rule_id: &str,
+        when: Vec<Condition>,
+        then: Vec<Action>,
+        author: &str,
+        message: &str,
+    ) -> Result<diff::RuleDiff> {
+        let mut rule = self
+            .get(rule_id)
+            .cloned()
+            .ok_or_else(|| anyhow::anyhow!("Rule not found: {}", rule_id))?;
+
+        let diff = diff::diff_rule(&rule.when, &rule.then, &when, &then);
+        if diff.is_empty() {
+            return Ok(diff);
+        }
+
+        rule.version = diff.bump(&rule.version);
+        rule.when = when;
+        rule.then = then;
+        rule.provenance.history.push(RuleVersion {
+            version: rule.version.clone(),
+            timestamp: chrono::Utc::now().to_rfc3339(),
+            author: author.to_string(),
+            message: message.to_string(),
+            diff_summary: diff.summary(),
+        });
+
+        let verb = if diff.is_semantic() { "Revise" } else { "Tune" };
+        let commit_message = format!("{} rule {} to {}: {}", verb, rule.id, rule.version, message);
+        self.upsert(rule)?;
+        self.commit(&[rule_id], &commit_message)?;
+        Ok(diff)
+    }
+
+    /// Commit rule files to the rules store's git history
+    pub fn commit(&self, rule_ids: &[&str], message: &str) -> Result<()> {
+        let files: Vec<String> = rule_ids.iter().map(|id| rule_file_name(id)).collect();
+        self.git_commit(&files, message)
+    }
+
+    /// Serialize a rule to `<id>.toml` in the rules directory
+    fn write_rule(&self, rule: &Rule) -> Result<()> {
+        let rule_path = self.rules_dir.join(rule_file_name(&rule.id));
+        let content = toml::to_string_pretty(rule)?;
         std::fs::write(&rule_path, &content)?;
+        Ok(())
+    }
 
-        // Git commit
+    fn git_commit(&self, files: &[String], message: &str) -> Result<()> {
         std::process::Command::new("git")
-            .args(["add", &format!("{}.toml", rule_id)])
+            .arg("add")
+            .args(files)
             .current_dir(&self.rules_dir)
             .output()?;
 
         std::process::Command::new("git")
-            .args([
-                "commit",
-                "-m",
-                &format!("Crystallize rule: {}", rule.name),
-            ])
+            .args(["commit", "-m", message])
             .current_dir(&self.rules_dir)
             .output()?;
+        Ok(())
+    }
 
-        self.add_rule(rule);
+    fn rebuild_index(&mut self) {
+        self.index.clear();
+        for (idx, rule) in self.rules.iter().enumerate() {
+            for tag in &rule.tags {
+                self.index.entry(tag.clone()).or_default().push(idx);
+            }
+        }
+    }
 
-        tracing::info!("Crystallized new rule: {}", rule_id);
-        Ok(rule_id)
+    /// Rules carrying a tag
+    pub fn with_tag(&self, tag: &str) -> Vec<&Rule> {
+        self.index
+            .get(tag)
+            .map(|idxs| idxs.iter().map(|&i| &self.rules[i]).collect())
+            .unwrap_or_default()
     }
 
     /// List all rules
@@ -549,8 +790,74 @@ pub fn get_provenance(&self, id: &str) -> Option<&Provenance> {
     }
 }
 
+/// Evaluate every condition against a probe (logical AND)
+pub fn evaluate_all(probe: &dyn SystemProbe, conditions: &[Condition], context: &ProblemContext) -> bool {
+    conditions.iter().all(|c| evaluate_condition(probe, c, context))
+}
+
+/// Evaluate a single condition against a probe
+pub fn evaluate_condition(probe: &dyn SystemProbe, condition: &Condition, context: &ProblemContext) -> bool {
+    match condition {
+        Condition::ProcessRunning { name } => probe.process_running(name),
+        Condition::ServiceState { name, state } => probe
+            .service_state(name)
+            .is_some_and(|s| s.eq_ignore_ascii_case(state)),
+        Condition::FileExists { path } => probe.file_exists(path),
+        Condition::FileContains { path, pattern } => probe
+            .read_file(path)
+            .is_some_and(|content| content.contains(pattern)),
+        Condition::MetricThreshold { metric, op, value } => {
+            // Metrics supplied with the problem take precedence over live readings
+            let current = context
+                .metrics
+                .get(metric)
+                .copied()
+                .or_else(|| probe.metric(metric));
+            match current {
+                Some(current) => compare(current, op, *value),
+                None => {
+                    tracing::warn!("Unknown metric '{}'", metric);
+                    false
+                }
+            }
+        }
+        Condition::PortOpen { port, protocol } => probe.port_open(*port, protocol),
+        Condition::PackageInstalled { name } => probe.package_version(name).is_some(),
+        Condition::ModuleLoaded { name } => probe.module_loaded(name),
+        Condition::ShellCheck { command } => probe.shell_check(command),
+        Condition::All { conditions } => {
+            conditions.iter().all(|c| evaluate_condition(probe, c, context))
+        }
+        Condition::Any { conditions } => {
+            conditions.iter().any(|c| evaluate_condition(probe, c, context))
+        }
+        Condition::Not { condition } => !evaluate_condition(probe, condition, context),
+    }
+}
+
+/// Apply a comparison operator from a `MetricThreshold` condition
+fn compare(current: f64, op: &str, value: f64) -> bool {
+    match op {
+        ">" => current > value,
+        ">=" => current >= value,
+        "<" => current < value,
+        "<=" => current <= value,
+        "==" => (current - value).abs() < f64::EPSILON,
+        "!=" => (current - value).abs() >= f64::EPSILON,
+        _ => {
+            tracing::warn!("Unknown comparison operator '{}'", op);
+            false
+        }
+    }
+}
+
+/// File name of a rule inside the rules directory
+fn rule_file_name(rule_id: &str) -> String {
+    format!("{}.toml", rule_id)
+}
+
 /// Context for matching rules against current state
-#[derive(Debug, Default)]
+#[derive(Debug, Clone, Default)]
 pub struct ProblemContext {
     pub problem_text: String,
     pub category: Option<String>,
@@ -578,8 +885,485 @@ fn default() -> Self {
     }
 }
 
-/// Check if a solution should be crystallized
+/// Decision steps for a reasoning proof, one per clause it used, each after
+/// the steps for its premises
+fn proof_steps(proof: &crate::reasoning::proof::Proof, timestamp: &str) -> Vec<DecisionStep> {
+    proof
+        .derivation()
+        .into_iter()
+        .map(|node| DecisionStep {
+            timestamp: timestamp.to_string(),
+            description: format!("Inferred {}", node.goal),
+            confidence_before: node.premise_confidence(),
+            confidence_after: node.confidence,
+            reason: node.justification(),
+        })
+        .collect()
+}
+
+/// Check if a solution should be crystallized: enough successes, and enough
+/// [`crate::storage::confidence`] that it keeps working
 pub fn should_crystallize(solution: &crate::storage::Solution) -> bool {
     solution.success_count >= CRYSTALLIZATION_THRESHOLD
-        && solution.failure_count < solution.success_count / 2
+        && crate::storage::confidence(solution) >= CRYSTALLIZATION_CONFIDENCE
+}
+
+/// Best-effort conditions and actions for a rule from a solution's commands
+///
+/// Well-known commands on valid names become typed actions with a guard condition
+/// (`systemctl restart X` runs when X has failed, `modprobe X` when X isn't
+/// loaded, a package install when the package is missing); anything else
+/// becomes a shell action. Each guard only speaks for its own action, so
+/// several are combined with `any`: the rule fires when one of them holds.
+/// A reviewer is expected to refine the result.
+pub fn infer_rule(solution: &crate::storage::Solution) -> (Vec<Condition>, Vec<Action>) {
+    let mut conditions = vec![];
+    let mut actions = vec![];
+
+    for command in &solution.commands {
+        let (command, sudo) = match command.trim().strip_prefix("sudo ") {
+            Some(rest) => (rest.trim(), true),
+            None => (command.trim(), false),
+        };
+        let words: Vec<&str> = command.split_whitespace().collect();
+        match words.as_slice() {
+            ["systemctl", "restart", name] if validate_service_name(name).is_ok() => {
+                conditions.push(Condition::ServiceState {
+                    name: name.to_string(),
+                    state: "failed".to_string(),
+                });
+                actions.push(Action::RestartService { name: name.to_string() });
+            }
+            ["systemctl", "enable", name] if validate_service_name(name).is_ok() => {
+                actions.push(Action::EnableService { name: name.to_string() });
+            }
+            ["modprobe", name, options @ ..]
+                if validate_module_name(name).is_ok()
+                    && validate_module_options(&options.join(" ")).is_ok() =>
+            {
+                conditions.push(Condition::Not {
+                    condition: Box::new(Condition::ModuleLoaded { name: name.to_string() }),
+                });
+                actions.push(Action::LoadModule {
+                    name: name.to_string(),
+                    options: (!options.is_empty()).then(|| options.join(" ")),
+                });
+            }
+            [manager, "install", packages @ ..]
+                if matches!(*manager, "dnf" | "yum" | "apt" | "apt-get" | "zypper")
+                    && packages
+                        .iter()
+                        .filter(|p| !p.starts_with('-'))
+                        .all(|p| validate_package_name(p).is_ok()) =>
+            {
+                for package in packages.iter().filter(|p| !p.starts_with('-')) {
+                    conditions.push(Condition::Not {
+                        condition: Box::new(Condition::PackageInstalled {
+                            name: package.to_string(),
+                        }),
+                    });
+                    actions.push(Action::InstallPackage { name: package.to_string() });
+                }
+            }
+            [] => {}
+            _ => actions.push(Action::Shell {
+                command: command.to_string(),
+                sudo,
+            }),
+        }
+    }
+    if conditions.len() > 1 {
+        conditions = vec![Condition::Any { conditions }];
+    }
+    (conditions, actions)
+}
+
+/// Spawn `argv` directly (no shell), returning its exit status and `done` on success
+async fn run_argv(argv: &[String], done: String) -> (Option<i32>, Result<String>) {
+    let Some((program, args)) = argv.split_first() else {
+        return (None, Err(anyhow::anyhow!("Nothing to run")));
+    };
+    match tokio::process::Command::new(program).args(args).output().await {
+        Ok(output) if output.status.success() => (output.status.code(), Ok(done)),
+        Ok(output) => (
+            output.status.code(),
+            Err(anyhow::anyhow!(
+                "{} failed: {}",
+                argv.join(" "),
+                String::from_utf8_lossy(&output.stderr).trim()
+            )),
+        ),
+        Err(e) => (None, Err(e.into())),
+    }
+}
+
+/// Rules action types
+#[derive(Debug, Clone)]
+pub enum RulesAction {
+    List { tag: Option<String> },
+    Enable { id: String },
+    Disable { id: String },
+    Export { tag: Option<String>, output: Option<String> },
+    Import { bundle: String, dry_run: bool },
+    Key,
+    Trust { name: String, public_key: String },
+    Test { filter: Option<String> },
+}
+
+/// Handle rules subcommands
+pub async fn handle(action: RulesAction) -> Result<()> {
+    let rules_dir = crate::dirs::data_dir().join("rules");
+
+    match action {
+        RulesAction::List { tag } => list_rules(&RulesEngine::new(&rules_dir)?, tag.as_deref()),
+        RulesAction::Enable { id } => set_enabled(&mut RulesEngine::new(&rules_dir)?, &id, true),
+        RulesAction::Disable { id } => set_enabled(&mut RulesEngine::new(&rules_dir)?, &id, false),
+        RulesAction::Export { tag, output } => {
+            export_rules(&RulesEngine::new(&rules_dir)?, tag.as_deref(), output.as_deref())
+        }
+        RulesAction::Import { bundle, dry_run } => {
+            import_rules(&mut RulesEngine::new(&rules_dir)?, &bundle, dry_run)
+        }
+        RulesAction::Key => show_signing_key(),
+        RulesAction::Trust { name, public_key } => trust_key(&name, &public_key),
+        RulesAction::Test { filter } => {
+            test_rules(&RulesEngine::new(&rules_dir)?, &rules_dir, filter.as_deref())
+        }
+    }
+}
+
+fn list_rules(engine: &RulesEngine, tag: Option<&str>) -> Result<()> {
+    let rules: Vec<&Rule> = match tag {
+        Some(tag) => engine.with_tag(tag),
+        None => engine.list().iter().collect(),
+    };
+
+    println!("{:<42} {:<8} {:<8} {:>7} NAME", "ID", "VERSION", "ENABLED", "APPLIED");
+    println!("{}", "-".repeat(90));
+    for rule in rules {
+        println!(
+            "{:<42} {:<8} {:<8} {:>7} {}",
+            rule.id,
+            rule.version,
+            if rule.enabled { "yes" } else { "no" },
+            rule.stats.applied_count,
+            rule.name
+        );
+    }
+    Ok(())
+}
+
+fn set_enabled(engine: &mut RulesEngine, id: &str, enabled: bool) -> Result<()> {
+    let mut rule = engine
+        .get(id)
+        .cloned()
+        .ok_or_else(|| anyhow::anyhow!("Rule not found: {}", id))?;
+    rule.enabled = enabled;
+    engine.upsert(rule)?;
+
+    let verb = if enabled { "Enable" } else { "Disable" };
+    engine.commit(&[id], &format!("{} rule: {}", verb, id))?;
+    println!("{}d rule {}", verb, id);
+    Ok(())
+}
+
+/// Name recorded as bundle signer and importer
+fn local_identity() -> String {
+    std::env::var("USER").unwrap_or_else(|_| "psa".to_string())
+}
+
+fn export_rules(engine: &RulesEngine, tag: Option<&str>, output: Option<&str>) -> Result<()> {
+    let rules: Vec<&Rule> = match tag {
+        Some(tag) => engine.with_tag(tag),
+        None => engine.list().iter().collect(),
+    };
+    if rules.is_empty() {
+        return Err(anyhow::anyhow!("No rules to export"));
+    }
+
+    let key = bundle::SigningKey::load_or_generate(&bundle::SigningKey::default_path(), &local_identity())?;
+    let bytes = bundle::export(&rules, tag, &key)?;
+
+    // Status goes to stderr so the bundle can be redirected from stdout
+    match output {
+        Some(path) => {
+            let safe_path = validate_safe_path(path)
+                .map_err(|e| anyhow::anyhow!("Invalid output path: {}", e))?;
+            std::fs::write(safe_path, &bytes)?;
+            eprintln!("Exported {} rules to {}", rules.len(), safe_path);
+        }
+        None => {
+            use std::io::Write;
+            std::io::stdout().write_all(&bytes)?;
+            eprintln!("Exported {} rules", rules.len());
+        }
+    }
+    eprintln!("Signed with key {}", key.public_key_hex());
+    Ok(())
+}
+
+/// The parts of a rule compared on import; local stats and provenance are ignored
+#[derive(Serialize)]
+struct RuleBody<'a> {
+    name: &'a str,
+    version: &'a str,
+    tags: &'a [String],
+    when: &'a [Condition],
+    then: &'a [Action],
+}
+
+impl<'a> From<&'a Rule> for RuleBody<'a> {
+    fn from(rule: &'a Rule) -> Self {
+        Self {
+            name: &rule.name,
+            version: &rule.version,
+            tags: &rule.tags,
+            when: &rule.when,
+            then: &rule.then,
+        }
+    }
+}
+
+fn import_rules(engine: &mut RulesEngine, path: &str, dry_run: bool) -> Result<()> {
+    let safe_path = validate_safe_path(path)
+        .map_err(|e| anyhow::anyhow!("Invalid bundle path: {}", e))?;
+
+    let bundle = bundle::Bundle::read(std::fs::File::open(safe_path)?)?;
+    let trusted = bundle::TrustedKeys::load(&bundle::TrustedKeys::default_path())?;
+    let (signer, rules) = bundle.verify(&trusted)?;
+
+    println!("Rule Bundle: {}", safe_path);
+    println!("{}", "=".repeat(50));
+    println!("Signed by:  {} ({})", signer.name, signer.public_key);
+    println!("Created:    {} on {}", bundle.manifest.created_at, bundle.manifest.host);
+    println!("Rules:      {}", rules.len());
+
+    let mut to_import = vec![];
+    for rule in rules {
+        let incoming = toml::to_string_pretty(&RuleBody::from(&rule))?;
+        match engine.get(&rule.id) {
+            None => {
+                println!("\n+ {} (new): {}", rule.id, rule.name);
+                to_import.push((rule, false));
+            }
+            Some(existing) => {
+                let current = toml::to_string_pretty(&RuleBody::from(existing))?;
+                if current == incoming {
+                    println!("\n= {} (unchanged)", rule.id);
+                    continue;
+                }
+                println!("\n~ {} (changed):", rule.id);
+                let diff = similar::TextDiff::from_lines(&current, &incoming);
+                print!("{}", diff.unified_diff().context_radius(3).header("local", "bundle"));
+                to_import.push((rule, true));
+            }
+        }
+    }
+
+    if dry_run {
+        println!("\nDry run - {} rules would be imported (disabled)", to_import.len());
+        return Ok(());
+    }
+    if to_import.is_empty() {
+        println!("\nNothing to import");
+        return Ok(());
+    }
+
+    let bundle_name = Path::new(safe_path)
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| safe_path.to_string());
+    let now = chrono::Utc::now().to_rfc3339();
+    let mut ids = vec![];
+
+    for (mut rule, replaces_local) in to_import {
+        let original_source = format!("{:?}", rule.provenance.source);
+
+        // Imported rules never run until a human enables them
+        rule.enabled = false;
+        rule.stats = RuleStats::default();
+        rule.provenance.source = RuleSource::Import {
+            source: format!("bundle {} signed by {}", bundle_name, signer.name),
+        };
+        rule.provenance.decision_path.push(DecisionStep {
+            timestamp: now.clone(),
+            description: "Imported from signed rule bundle".to_string(),
+            confidence_before: 0.0,
+            confidence_after: 0.0,
+            reason: format!(
+                "Signature by trusted key '{}' verified; original source: {}",
+                signer.name, original_source
+            ),
+        });
+        rule.provenance.history.push(RuleVersion {
+            version: rule.version.clone(),
+            timestamp: now.clone(),
+            author: local_identity(),
+            message: format!("Imported from {} (disabled pending review)", bundle_name),
+            diff_summary: if replaces_local {
+                "Replaced local rule".to_string()
+            } else {
+                "New rule".to_string()
+            },
+        });
+
+        ids.push(rule.id.clone());
+        engine.upsert(rule)?;
+    }
+
+    let id_refs: Vec<&str> = ids.iter().map(String::as_str).collect();
+    engine.commit(
+        &id_refs,
+        &format!("Import {} rules from {} (signed by {})", ids.len(), bundle_name, signer.name),
+    )?;
+
+    println!("\nImported {} rules (disabled). Review and enable with: psa rules enable <id>", ids.len());
+    Ok(())
+}
+
+fn show_signing_key() -> Result<()> {
+    let path = bundle::SigningKey::default_path();
+    let key = bundle::SigningKey::load_or_generate(&path, &local_identity())?;
+
+    println!("Rule signing key: {}", path.display());
+    println!("Public key:       {}", key.public_key_hex());
+    println!("\nShare the public key so others can run:");
+    println!("  psa rules trust {} {}", key.name, key.public_key_hex());
+    Ok(())
+}
+
+fn trust_key(name: &str, public_key: &str) -> Result<()> {
+    let path = bundle::TrustedKeys::default_path();
+    let mut trusted = bundle::TrustedKeys::load(&path)?;
+    trusted.add(name, public_key)?;
+    trusted.save(&path)?;
+
+    println!("Trusted key '{}' added to {}", name, path.display());
+    Ok(())
+}
+
+fn test_rules(engine: &RulesEngine, rules_dir: &Path, filter: Option<&str>) -> Result<()> {
+    let files = fixture::discover(rules_dir)?;
+    if files.is_empty() {
+        println!("No rule tests (*{}) found in {}", fixture::FIXTURE_SUFFIX, rules_dir.display());
+        return Ok(());
+    }
+
+    let mut passed = 0usize;
+    let mut failed = 0usize;
+
+    for file in &files {
+        for result in fixture::run_file(file, engine.list()) {
+            if filter.is_some_and(|f| !result.rule.contains(f) && !result.case.contains(f)) {
+                continue;
+            }
+            let name = file.file_name().unwrap_or_default().to_string_lossy();
+            if result.passed() {
+                passed += 1;
+                println!("PASS  {} :: {} :: {}", name, result.rule, result.case);
+            } else {
+                failed += 1;
+                println!("FAIL  {} :: {} :: {}", name, result.rule, result.case);
+                for failure in &result.failures {
+                    println!("      {}", failure);
+                }
+            }
+        }
+    }
+
+    println!("\n{} passed, {} failed", passed, failed);
+    if failed > 0 {
+        return Err(anyhow::anyhow!("{} rule test(s) failed", failed));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_outcome_series_is_capped() {
+        let now = chrono::Utc::now();
+        let mut stats = RuleStats::default();
+        for i in 0..MAX_OUTCOMES + 10 {
+            stats.record(i % 2 == 0, 4.0, now);
+        }
+        assert_eq!(stats.outcomes.len(), MAX_OUTCOMES);
+        assert_eq!(stats.applied_count as usize, MAX_OUTCOMES + 10);
+        assert_eq!(stats.average_duration_ms, Some(4.0));
+    }
+
+    #[test]
+    fn test_inferred_actions_only_type_valid_names() {
+        let now = chrono::Utc::now();
+        let solution = crate::storage::Solution {
+            id: "sol-1".to_string(),
+            category: "system".to_string(),
+            problem: "wifi drops".to_string(),
+            solution: String::new(),
+            commands: vec![
+                "sudo systemctl enable --now sshd".to_string(),
+                "modprobe iwlwifi power_save=$(id)".to_string(),
+                "dnf install -y iw".to_string(),
+            ],
+            tags: vec![],
+            success_count: 1,
+            failure_count: 0,
+            source: crate::storage::SolutionSource::Manual,
+            created_at: now,
+            updated_at: now,
+        };
+        let (_, actions) = infer_rule(&solution);
+        assert_eq!(
+            actions,
+            [
+                Action::Shell {
+                    command: "systemctl enable --now sshd".to_string(),
+                    sudo: true,
+                },
+                Action::Shell {
+                    command: "modprobe iwlwifi power_save=$(id)".to_string(),
+                    sudo: false,
+                },
+                Action::InstallPackage {
+                    name: "iw".to_string()
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_unsupported_and_invalid_actions_fail() {
+        let dir = tempfile::tempdir().unwrap();
+        let engine = RulesEngine {
+            rules: vec![],
+            rules_dir: dir.path().to_path_buf(),
+            index: HashMap::new(),
+            audit: AuditLog::open(dir.path()).unwrap(),
+            probe: Arc::new(HostProbe),
+        };
+        let runtime = tokio::runtime::Builder::new_current_thread().build().unwrap();
+        let run = |action: Action| runtime.block_on(engine.run_action(&action)).1;
+
+        assert!(run(Action::WriteFile {
+            path: "/tmp/x".to_string(),
+            content: String::new(),
+            mode: None,
+        })
+        .is_err());
+        assert!(run(Action::LoadModule {
+            name: "iwlwifi".to_string(),
+            options: Some("power_save=0;reboot".to_string()),
+        })
+        .is_err());
+        assert!(run(Action::EnableService {
+            name: "--global".to_string()
+        })
+        .is_err());
+        assert!(run(Action::InstallPackage {
+            name: "-y".to_string()
+        })
+        .is_err());
+    }
 }
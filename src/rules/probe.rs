@@ -0,0 +1,263 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+//! System probes used to evaluate rule conditions
+//!
+//! Every fact the rules engine reads about the machine goes through
+//! [`SystemProbe`], so rules can be evaluated against simulated systems
+//! (see the `fixture` module) as well as the real host.
+
+use std::path::Path;
+
+use crate::validation::{validate_package_name, validate_pattern, validate_service_name};
+
+/// Source of system facts for condition evaluation
+pub trait SystemProbe: Send + Sync {
+    /// Is a process matching `name` running (pgrep semantics)
+    fn process_running(&self, name: &str) -> bool;
+    /// Active state of a systemd unit (`active`, `failed`, ...)
+    fn service_state(&self, name: &str) -> Option<String>;
+    fn file_exists(&self, path: &str) -> bool;
+    fn read_file(&self, path: &str) -> Option<String>;
+    /// Is a kernel module loaded
+    fn module_loaded(&self, name: &str) -> bool;
+    /// Is something listening on a port (`tcp` or `udp`)
+    fn port_open(&self, port: u16, protocol: &str) -> bool;
+    /// Installed version of a package, `None` if not installed
+    fn package_version(&self, name: &str) -> Option<String>;
+    /// Current value of a named system metric
+    ///
+    /// On the host, `cpu_pct` blocks for the sampling interval; async code
+    /// evaluates conditions through `RulesEngine::find_matching_async`.
+    fn metric(&self, name: &str) -> Option<f64>;
+    /// Run a shell check (exit 0 = true)
+    fn shell_check(&self, command: &str) -> bool;
+}
+
+/// Probe backed by the real host
+#[derive(Debug, Default)]
+pub struct HostProbe;
+
+impl SystemProbe for HostProbe {
+    fn process_running(&self, name: &str) -> bool {
+        // SECURITY: Validate process name pattern before passing to pgrep
+        let safe_name = match validate_pattern(name) {
+            Ok(n) => n,
+            Err(e) => {
+                tracing::warn!("Invalid process pattern '{}': {}", name, e);
+                return false;
+            }
+        };
+        std::process::Command::new("pgrep")
+            .arg(safe_name)
+            .output()
+            .map(|o| o.status.success())
+            .unwrap_or(false)
+    }
+
+    fn service_state(&self, name: &str) -> Option<String> {
+        // SECURITY: Validate service name before passing to systemctl
+        let safe_name = match validate_service_name(name) {
+            Ok(n) => n,
+            Err(e) => {
+                tracing::warn!("Invalid service name '{}': {}", name, e);
+                return None;
+            }
+        };
+        std::process::Command::new("systemctl")
+            .args(["is-active", safe_name])
+            .output()
+            .ok()
+            .map(|o| String::from_utf8_lossy(&o.stdout).trim().to_string())
+    }
+
+    fn file_exists(&self, path: &str) -> bool {
+        Path::new(path).exists()
+    }
+
+    fn read_file(&self, path: &str) -> Option<String> {
+        std::fs::read_to_string(path).ok()
+    }
+
+    fn module_loaded(&self, name: &str) -> bool {
+        std::fs::read_to_string("/proc/modules")
+            .map(|content| modules_contain(&content, name))
+            .unwrap_or(false)
+    }
+
+    fn port_open(&self, port: u16, protocol: &str) -> bool {
+        let tables: &[&str] = match protocol.to_lowercase().as_str() {
+            "udp" => &["/proc/net/udp", "/proc/net/udp6"],
+            _ => &["/proc/net/tcp", "/proc/net/tcp6"],
+        };
+        let udp = protocol.eq_ignore_ascii_case("udp");
+
+        tables.iter().any(|table| {
+            std::fs::read_to_string(table)
+                .map(|content| socket_table_has_port(&content, port, udp))
+                .unwrap_or(false)
+        })
+    }
+
+    fn package_version(&self, name: &str) -> Option<String> {
+        let safe_name = match validate_package_name(name) {
+            Ok(n) => n,
+            Err(e) => {
+                tracing::warn!("Invalid package name '{}': {}", name, e);
+                return None;
+            }
+        };
+        PackageManager::detect()?.installed_version(safe_name)
+    }
+
+    fn metric(&self, name: &str) -> Option<f64> {
+        let mut sys = sysinfo::System::new();
+        match name {
+            "cpu_pct" => {
+                sys.refresh_cpu_usage();
+                std::thread::sleep(sysinfo::MINIMUM_CPU_UPDATE_INTERVAL);
+                sys.refresh_cpu_usage();
+                Some(sys.global_cpu_usage() as f64)
+            }
+            "mem_pct" => {
+                sys.refresh_memory();
+                percent(sys.used_memory(), sys.total_memory())
+            }
+            "swap_pct" => {
+                sys.refresh_memory();
+                percent(sys.used_swap(), sys.total_swap())
+            }
+            "load1" => Some(sysinfo::System::load_average().one),
+            "load5" => Some(sysinfo::System::load_average().five),
+            "load15" => Some(sysinfo::System::load_average().fifteen),
+            _ => None,
+        }
+    }
+
+    fn shell_check(&self, command: &str) -> bool {
+        // SECURITY NOTE: ShellCheck intentionally executes arbitrary shell commands.
+        // This is a feature, not a vulnerability. The security model relies on:
+        // 1. Rule files being protected by filesystem permissions
+        // 2. Crystallization only from trusted solution sources
+        // 3. Human review of rules before enabling
+        std::process::Command::new("sh")
+            .args(["-c", command])
+            .output()
+            .map(|o| o.status.success())
+            .unwrap_or(false)
+    }
+}
+
+fn percent(used: u64, total: u64) -> Option<f64> {
+    (total > 0).then(|| used as f64 / total as f64 * 100.0)
+}
+
+/// Native package manager of the host
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PackageManager {
+    Rpm,
+    Dpkg,
+    Pacman,
+}
+
+impl PackageManager {
+    /// Detect the package manager from its database directory
+    pub fn detect() -> Option<Self> {
+        if Path::new("/var/lib/dpkg/status").exists() {
+            Some(Self::Dpkg)
+        } else if Path::new("/var/lib/pacman/local").exists() {
+            Some(Self::Pacman)
+        } else if Path::new("/var/lib/rpm").exists() || Path::new("/usr/lib/sysimage/rpm").exists() {
+            Some(Self::Rpm)
+        } else {
+            None
+        }
+    }
+
+    /// Command line installing a package non-interactively
+    pub fn install_argv<'a>(&self, name: &'a str) -> Vec<&'a str> {
+        match self {
+            Self::Rpm => vec!["dnf", "install", "-y", name],
+            Self::Dpkg => vec!["apt-get", "install", "-y", name],
+            Self::Pacman => vec!["pacman", "-S", "--noconfirm", name],
+        }
+    }
+
+    /// Query the installed version (`[epoch:]version-release` as the manager reports it)
+    pub fn installed_version(&self, name: &str) -> Option<String> {
+        let output = match self {
+            Self::Rpm => std::process::Command::new("rpm")
+                .args(["-q", "--qf", "%{EPOCH}:%{VERSION}-%{RELEASE}", name])
+                .output(),
+            Self::Dpkg => std::process::Command::new("dpkg-query")
+                .args(["-W", "-f=${Status}\t${Version}", name])
+                .output(),
+            Self::Pacman => std::process::Command::new("pacman").args(["-Q", name]).output(),
+        }
+        .ok()?;
+
+        if !output.status.success() {
+            return None;
+        }
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let stdout = stdout.trim();
+
+        match self {
+            Self::Rpm => Some(stdout.trim_start_matches("(none):").to_string()),
+            Self::Dpkg => {
+                let (status, version) = stdout.split_once('\t')?;
+                status.ends_with("installed").then(|| version.to_string())
+            }
+            Self::Pacman => stdout.split_whitespace().nth(1).map(str::to_string),
+        }
+    }
+}
+
+/// Check `/proc/modules` content for an exact module name
+fn modules_contain(content: &str, name: &str) -> bool {
+    content
+        .lines()
+        .any(|l| l.split_whitespace().next() == Some(name))
+}
+
+/// Check a `/proc/net/{tcp,udp}[6]` table for a listening/bound local port
+fn socket_table_has_port(content: &str, port: u16, udp: bool) -> bool {
+    // TCP_LISTEN is 0A; unconnected UDP sockets report 07 (TCP_CLOSE)
+    let wanted_state = if udp { "07" } else { "0A" };
+
+    content.lines().skip(1).any(|line| {
+        let parts: Vec<&str> = line.split_whitespace().collect();
+        if parts.len() < 4 || parts[3] != wanted_state {
+            return false;
+        }
+        parts[1]
+            .rsplit(':')
+            .next()
+            .and_then(|p| u16::from_str_radix(p, 16).ok())
+            == Some(port)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_modules_exact_match() {
+        let content = "nvidia_drm 77824 2 - Live 0x0\nnvidia 56197120 1 nvidia_drm, Live 0x0\n";
+        assert!(modules_contain(content, "nvidia"));
+        assert!(modules_contain(content, "nvidia_drm"));
+        assert!(!modules_contain(content, "nvidia_uvm"));
+        assert!(!modules_contain(content, "nvid"));
+    }
+
+    #[test]
+    fn test_socket_table() {
+        let tcp = "  sl  local_address rem_address   st\n   0: 00000000:0016 00000000:0000 0A\n   1: 0100007F:1F90 0100007F:C350 01\n";
+        assert!(socket_table_has_port(tcp, 22, false));
+        // Established, not listening
+        assert!(!socket_table_has_port(tcp, 8080, false));
+
+        let udp = "  sl  local_address rem_address   st\n   0: 00000000:0035 00000000:0000 07\n";
+        assert!(socket_table_has_port(udp, 53, true));
+        assert!(!socket_table_has_port(udp, 53, false));
+    }
+}
@@ -0,0 +1,266 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+//! Package version comparison with each package manager's own rules
+//!
+//! rpm and pacman share `rpmvercmp` over `[epoch:]version[-release]`; dpkg
+//! compares `[epoch:]upstream[-revision]` with its own character ordering
+//! (`~` sorts before everything, including the end of the string).
+
+use std::cmp::Ordering;
+
+use super::probe::PackageManager;
+
+/// Compare two versions the way `manager` would
+pub fn compare(manager: PackageManager, a: &str, b: &str) -> Ordering {
+    match manager {
+        PackageManager::Rpm | PackageManager::Pacman => compare_evr(a, b),
+        PackageManager::Dpkg => compare_dpkg(a, b),
+    }
+}
+
+/// Split `[epoch:]version[-release]`; a missing epoch is 0
+fn split_evr(v: &str) -> (u64, &str, Option<&str>) {
+    let (epoch, rest) = match v.split_once(':') {
+        Some((e, rest)) if !e.is_empty() && e.bytes().all(|c| c.is_ascii_digit()) => {
+            (e.parse().unwrap_or(0), rest)
+        }
+        _ => (0, v),
+    };
+    match rest.rsplit_once('-') {
+        Some((version, release)) => (epoch, version, Some(release)),
+        None => (epoch, rest, None),
+    }
+}
+
+/// rpm/pacman comparison: epoch, then version, then release if both have one
+fn compare_evr(a: &str, b: &str) -> Ordering {
+    let (ea, va, ra) = split_evr(a);
+    let (eb, vb, rb) = split_evr(b);
+    ea.cmp(&eb).then_with(|| rpmvercmp(va, vb)).then_with(|| match (ra, rb) {
+        (Some(ra), Some(rb)) => rpmvercmp(ra, rb),
+        _ => Ordering::Equal,
+    })
+}
+
+/// rpm's segment-wise version comparison
+///
+/// Versions are split into alternating numeric and alphabetic segments;
+/// numeric segments compare as numbers and beat alphabetic ones. `~` sorts
+/// before anything (pre-releases) and `^` after the base version but before
+/// any further segment (post-release snapshots).
+pub fn rpmvercmp(a: &str, b: &str) -> Ordering {
+    if a == b {
+        return Ordering::Equal;
+    }
+    let (mut a, mut b) = (a.as_bytes(), b.as_bytes());
+    let separator = |c: u8| !c.is_ascii_alphanumeric() && c != b'~' && c != b'^';
+
+    while !a.is_empty() || !b.is_empty() {
+        while a.first().is_some_and(|&c| separator(c)) {
+            a = &a[1..];
+        }
+        while b.first().is_some_and(|&c| separator(c)) {
+            b = &b[1..];
+        }
+
+        if a.first() == Some(&b'~') || b.first() == Some(&b'~') {
+            if a.first() != Some(&b'~') {
+                return Ordering::Greater;
+            }
+            if b.first() != Some(&b'~') {
+                return Ordering::Less;
+            }
+            a = &a[1..];
+            b = &b[1..];
+            continue;
+        }
+
+        if a.first() == Some(&b'^') || b.first() == Some(&b'^') {
+            if a.is_empty() {
+                return Ordering::Less;
+            }
+            if b.is_empty() {
+                return Ordering::Greater;
+            }
+            if a.first() != Some(&b'^') {
+                return Ordering::Greater;
+            }
+            if b.first() != Some(&b'^') {
+                return Ordering::Less;
+            }
+            a = &a[1..];
+            b = &b[1..];
+            continue;
+        }
+
+        if a.is_empty() || b.is_empty() {
+            break;
+        }
+
+        let numeric = a[0].is_ascii_digit();
+        let take = |s: &[u8]| {
+            s.iter()
+                .take_while(|c| if numeric { c.is_ascii_digit() } else { c.is_ascii_alphabetic() })
+                .count()
+        };
+        let (la, lb) = (take(a), take(b));
+        let (seg_a, seg_b) = (&a[..la], &b[..lb]);
+
+        // Segments of different types: numeric is newer
+        if seg_b.is_empty() {
+            return if numeric { Ordering::Greater } else { Ordering::Less };
+        }
+
+        let ordering = if numeric {
+            let (na, nb) = (strip_zeros(seg_a), strip_zeros(seg_b));
+            na.len().cmp(&nb.len()).then_with(|| na.cmp(nb))
+        } else {
+            seg_a.cmp(seg_b)
+        };
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+
+        a = &a[la..];
+        b = &b[lb..];
+    }
+
+    match (a.is_empty(), b.is_empty()) {
+        (true, true) => Ordering::Equal,
+        (false, _) => Ordering::Greater,
+        (_, false) => Ordering::Less,
+    }
+}
+
+fn strip_zeros(s: &[u8]) -> &[u8] {
+    let zeros = s.iter().take_while(|&&c| c == b'0').count();
+    &s[zeros..]
+}
+
+/// dpkg comparison: epoch, then upstream version, then Debian revision
+fn compare_dpkg(a: &str, b: &str) -> Ordering {
+    let (ea, ua, ra) = split_evr(a);
+    let (eb, ub, rb) = split_evr(b);
+    ea.cmp(&eb)
+        .then_with(|| verrevcmp(ua, ub))
+        .then_with(|| verrevcmp(ra.unwrap_or(""), rb.unwrap_or("")))
+}
+
+/// dpkg's character weight for the non-digit parts of a version
+fn dpkg_order(c: Option<u8>) -> i32 {
+    match c {
+        None => 0,
+        Some(c) if c.is_ascii_digit() => 0,
+        Some(c) if c.is_ascii_alphabetic() => c as i32,
+        Some(b'~') => -1,
+        Some(c) => c as i32 + 256,
+    }
+}
+
+/// dpkg's `verrevcmp`
+fn verrevcmp(a: &str, b: &str) -> Ordering {
+    let (mut a, mut b) = (a.as_bytes(), b.as_bytes());
+
+    while !a.is_empty() || !b.is_empty() {
+        while a.first().is_some_and(|c| !c.is_ascii_digit())
+            || b.first().is_some_and(|c| !c.is_ascii_digit())
+        {
+            let (oa, ob) = (dpkg_order(a.first().copied()), dpkg_order(b.first().copied()));
+            if oa != ob {
+                return oa.cmp(&ob);
+            }
+            a = a.get(1..).unwrap_or_default();
+            b = b.get(1..).unwrap_or_default();
+        }
+
+        while a.first() == Some(&b'0') {
+            a = &a[1..];
+        }
+        while b.first() == Some(&b'0') {
+            b = &b[1..];
+        }
+
+        let mut first_diff = Ordering::Equal;
+        while a.first().is_some_and(u8::is_ascii_digit)
+            && b.first().is_some_and(u8::is_ascii_digit)
+        {
+            if first_diff == Ordering::Equal {
+                first_diff = a[0].cmp(&b[0]);
+            }
+            a = &a[1..];
+            b = &b[1..];
+        }
+        if a.first().is_some_and(u8::is_ascii_digit) {
+            return Ordering::Greater;
+        }
+        if b.first().is_some_and(u8::is_ascii_digit) {
+            return Ordering::Less;
+        }
+        if first_diff != Ordering::Equal {
+            return first_diff;
+        }
+    }
+    Ordering::Equal
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use Ordering::*;
+
+    #[test]
+    fn test_rpmvercmp() {
+        let cases = [
+            ("1.0", "1.0", Equal),
+            ("1.0", "2.0", Less),
+            ("2.0.1", "2.0", Greater),
+            ("1.10", "1.9", Greater),
+            ("1.05", "1.5", Equal),
+            ("1.0a", "1.0", Greater),
+            ("2.0a", "2.0b", Less),
+            ("1.0", "1.0.a", Less),
+            ("5.5p10", "5.5p1", Greater),
+            ("1.0~rc1", "1.0", Less),
+            ("1.0~rc1", "1.0~rc2", Less),
+            ("1.0^20240101", "1.0", Greater),
+            ("1.0^20240101", "1.0.1", Less),
+            ("1_0", "1.0", Equal),
+        ];
+        for (a, b, expected) in cases {
+            assert_eq!(rpmvercmp(a, b), expected, "{} vs {}", a, b);
+            assert_eq!(rpmvercmp(b, a), expected.reverse(), "{} vs {}", b, a);
+        }
+    }
+
+    #[test]
+    fn test_rpm_evr() {
+        let rpm = PackageManager::Rpm;
+        assert_eq!(compare(rpm, "1:1.0-1", "2.0-1"), Greater);
+        assert_eq!(compare(rpm, "3.0.7-1.fc39", "3.0.9-1.fc39"), Less);
+        assert_eq!(compare(rpm, "3.0.9-2.fc39", "3.0.9-10.fc39"), Less);
+        // Release only compared when both sides have one
+        assert_eq!(compare(rpm, "3.0.9", "3.0.9-10.fc39"), Equal);
+        assert_eq!(compare(PackageManager::Pacman, "1:6.5-2", "6.6-1"), Greater);
+    }
+
+    #[test]
+    fn test_dpkg() {
+        let dpkg = PackageManager::Dpkg;
+        let cases = [
+            ("1.0", "1.0", Equal),
+            ("1.0-1", "1.0-2", Less),
+            ("1:0.9", "1.0", Greater),
+            ("1.0~rc1", "1.0", Less),
+            ("1.0~~", "1.0~", Less),
+            ("1.0+b1", "1.0", Greater),
+            ("1.0a", "1.0+", Less),
+            ("3.0.11-1~deb12u2", "3.0.11-1", Less),
+            ("3.0.11-1~deb12u2", "3.0.11-1~deb12u1", Greater),
+            ("2.36-9+deb12u4", "2.36-9+deb12u10", Less),
+            ("1.001", "1.1", Equal),
+        ];
+        for (a, b, expected) in cases {
+            assert_eq!(compare(dpkg, a, b), expected, "{} vs {}", a, b);
+            assert_eq!(compare(dpkg, b, a), expected.reverse(), "{} vs {}", b, a);
+        }
+    }
+}
@@ -0,0 +1,384 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+//! ArangoDB knowledge base
+//!
+//! Solutions are documents in `solutions`; each distinct (normalized)
+//! problem is a vertex in `problems`, linked to the solutions that fix it by
+//! `problem_relations` edges. Text search goes through the `solutions_view`
+//! ArangoSearch view, and `find_related` is an AQL traversal over the edges.
+//! Earlier states of edited solutions are documents in `solution_revisions`.
+
+use anyhow::{bail, Context, Result};
+use arangors::client::reqwest::ReqwestClient;
+use arangors::{ClientError, Connection, Database};
+use async_trait::async_trait;
+use serde::de::DeserializeOwned;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+
+use super::{
+    normalize_problem, KnowledgeStore, ProblemRelation, Solution, SolutionRevision, StorageConfig,
+};
+
+pub const SOLUTIONS: &str = "solutions";
+pub const PROBLEMS: &str = "problems";
+pub const RELATIONS: &str = "problem_relations";
+pub const REVISIONS: &str = "solution_revisions";
+pub const SEARCH_VIEW: &str = "solutions_view";
+
+/// Most results returned by a text search
+const SEARCH_LIMIT: usize = 50;
+
+/// Analyzer for the search view (ships with ArangoDB)
+const ANALYZER: &str = "text_en";
+
+/// ArangoDB-backed [`KnowledgeStore`]
+pub struct ArangoStore {
+    db: Database<ReqwestClient>,
+}
+
+impl ArangoStore {
+    /// Connect, creating the database, collections and search view if needed
+    pub async fn connect(config: &StorageConfig) -> Result<Self> {
+        let url = config.url();
+        let conn = if config.username.is_empty() {
+            Connection::establish_without_auth(&url).await
+        } else {
+            Connection::establish_basic_auth(&url, &config.username, &config.password).await
+        }
+        .with_context(|| format!("Failed to connect to ArangoDB at {}", url))?;
+
+        let db = match conn.db(&config.database).await {
+            Ok(db) => db,
+            Err(_) => conn
+                .create_database(&config.database)
+                .await
+                .with_context(|| format!("Failed to create database {}", config.database))?,
+        };
+
+        if db.collection(SOLUTIONS).await.is_err() {
+            db.create_collection(SOLUTIONS).await?;
+        }
+        if db.collection(PROBLEMS).await.is_err() {
+            db.create_collection(PROBLEMS).await?;
+        }
+        if db.collection(RELATIONS).await.is_err() {
+            db.create_edge_collection(RELATIONS).await?;
+        }
+        if db.collection(REVISIONS).await.is_err() {
+            db.create_collection(REVISIONS).await?;
+        }
+        ensure_view(config).await?;
+
+        tracing::info!("Connected to ArangoDB {} ({})", url, config.database);
+        Ok(Self { db })
+    }
+
+    async fn query<T: DeserializeOwned>(&self, aql: &str, vars: Value) -> Result<Vec<T>> {
+        let vars: HashMap<&str, Value> = vars
+            .as_object()
+            .map(|o| o.iter().map(|(k, v)| (k.as_str(), v.clone())).collect())
+            .unwrap_or_default();
+        self.db
+            .aql_bind_vars(aql, vars)
+            .await
+            .map_err(|e: ClientError| anyhow::anyhow!("AQL query failed: {}", e))
+    }
+
+    /// Upsert the vertex for a problem and an edge from it to a solution
+    async fn link(
+        &self,
+        problem: &str,
+        solution_id: &str,
+        confidence: f32,
+        context: &[String],
+    ) -> Result<()> {
+        let problem = normalize_problem(problem);
+        self.query::<Value>(
+            "UPSERT { _key: @key } INSERT { _key: @key, text: @text } UPDATE {} IN problems",
+            json!({ "key": problem_key(&problem), "text": problem }),
+        )
+        .await?;
+        self.query::<Value>(
+            "UPSERT { _from: @from, _to: @to }
+             INSERT { _from: @from, _to: @to, confidence: @confidence, context: @context }
+             UPDATE { confidence: @confidence, context: @context }
+             IN problem_relations",
+            json!({
+                "from": format!("{}/{}", PROBLEMS, problem_key(&problem)),
+                "to": format!("{}/{}", SOLUTIONS, solution_id),
+                "confidence": confidence,
+                "context": context,
+            }),
+        )
+        .await?;
+        Ok(())
+    }
+}
+
+/// Document key for a problem vertex: keys are restricted to a small
+/// character set, so the normalized text is hashed
+fn problem_key(normalized: &str) -> String {
+    let digest = ring::digest::digest(&ring::digest::SHA256, normalized.as_bytes());
+    digest.as_ref()[..16]
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+/// Create the ArangoSearch view over `solutions` through the HTTP API
+async fn ensure_view(config: &StorageConfig) -> Result<()> {
+    let url = format!("{}/_db/{}/_api/view", config.url(), config.database);
+    let client = reqwest::Client::new();
+    let auth = |req: reqwest::RequestBuilder| {
+        if config.username.is_empty() {
+            req
+        } else {
+            req.basic_auth(&config.username, Some(&config.password))
+        }
+    };
+
+    let existing = auth(client.get(format!("{}/{}", url, SEARCH_VIEW)))
+        .send()
+        .await?;
+    if existing.status().is_success() {
+        return Ok(());
+    }
+
+    let field = json!({ "analyzers": [ANALYZER] });
+    let body = json!({
+        "name": SEARCH_VIEW,
+        "type": "arangosearch",
+        "links": {
+            SOLUTIONS: {
+                "fields": { "problem": field, "solution": field, "tags": field }
+            }
+        }
+    });
+    let response = auth(client.post(&url)).json(&body).send().await?;
+    // 409: created concurrently by another client
+    if !response.status().is_success() && response.status() != reqwest::StatusCode::CONFLICT {
+        bail!(
+            "Failed to create search view {}: {}",
+            SEARCH_VIEW,
+            response.text().await.unwrap_or_default()
+        );
+    }
+    Ok(())
+}
+
+#[async_trait]
+impl KnowledgeStore for ArangoStore {
+    async fn store_solution(&self, solution: &Solution) -> Result<String> {
+        self.query::<Value>(
+            "UPSERT { _key: @key }
+             INSERT MERGE(@doc, { _key: @key })
+             UPDATE UNSET(@doc, 'created_at')
+             IN solutions",
+            json!({ "key": solution.id, "doc": serde_json::to_value(solution)? }),
+        )
+        .await?;
+        if !normalize_problem(&solution.problem).is_empty() {
+            self.link(&solution.problem, &solution.id, 1.0, &solution.tags)
+                .await?;
+        }
+        Ok(solution.id.clone())
+    }
+
+    async fn relate(&self, relation: &ProblemRelation) -> Result<()> {
+        let found: Vec<String> = self
+            .query(
+                "FOR s IN solutions FILTER s._key == @key RETURN s._key",
+                json!({ "key": relation.to_solution }),
+            )
+            .await?;
+        if found.is_empty() {
+            bail!("Solution not found: {}", relation.to_solution);
+        }
+        self.link(
+            &relation.from_problem,
+            &relation.to_solution,
+            relation.confidence,
+            &relation.context,
+        )
+        .await
+    }
+
+    async fn find_by_category(&self, category: &str) -> Result<Vec<Solution>> {
+        self.query(
+            "FOR s IN solutions FILTER s.category == @category
+             SORT s.success_count DESC, s.updated_at DESC
+             RETURN s",
+            json!({ "category": category }),
+        )
+        .await
+    }
+
+    async fn search(&self, query: &str) -> Result<Vec<Solution>> {
+        if query.trim().is_empty() {
+            return Ok(vec![]);
+        }
+        self.query(
+            "FOR s IN solutions_view
+             SEARCH ANALYZER(
+                 s.problem IN TOKENS(@query, @analyzer)
+                 OR s.solution IN TOKENS(@query, @analyzer)
+                 OR s.tags IN TOKENS(@query, @analyzer),
+                 @analyzer)
+             SORT BM25(s) DESC
+             LIMIT @limit
+             RETURN s",
+            json!({ "query": query, "analyzer": ANALYZER, "limit": SEARCH_LIMIT }),
+        )
+        .await
+    }
+
+    async fn find_related(&self, problem: &str, depth: u32) -> Result<Vec<Solution>> {
+        if depth == 0 {
+            return Ok(vec![]);
+        }
+        // The graph alternates problem and solution vertices, so `depth`
+        // solution hops are 2 * depth - 1 edges. Breadth-first with global
+        // uniqueness visits each solution first on its shortest path.
+        self.query(
+            "FOR v, e, p IN 1..@edges ANY @start problem_relations
+                 OPTIONS { order: 'bfs', uniqueVertices: 'global' }
+                 FILTER IS_SAME_COLLECTION('solutions', v)
+                 SORT LENGTH(p.edges), v.success_count DESC, v._key
+                 RETURN v",
+            json!({
+                "edges": 2 * depth - 1,
+                "start": format!("{}/{}", PROBLEMS, problem_key(&normalize_problem(problem))),
+            }),
+        )
+        .await
+    }
+
+    async fn record_outcome(&self, solution_id: &str, success: bool) -> Result<()> {
+        let counter = if success {
+            "success_count"
+        } else {
+            "failure_count"
+        };
+        let updated: Vec<String> = self
+            .query(
+                "FOR s IN solutions FILTER s._key == @key
+                 UPDATE s WITH { [@counter]: s[@counter] + 1, updated_at: @now } IN solutions
+                 RETURN NEW._key",
+                json!({
+                    "key": solution_id,
+                    "counter": counter,
+                    "now": chrono::Utc::now(),
+                }),
+            )
+            .await?;
+        if updated.is_empty() {
+            bail!("Solution not found: {}", solution_id);
+        }
+        Ok(())
+    }
+
+    async fn get(&self, id: &str) -> Result<Option<Solution>> {
+        let found: Vec<Solution> = self
+            .query(
+                "FOR s IN solutions FILTER s._key == @key RETURN s",
+                json!({ "key": id }),
+            )
+            .await?;
+        Ok(found.into_iter().next())
+    }
+
+    async fn all_solutions(&self) -> Result<Vec<Solution>> {
+        self.query(
+            "FOR s IN solutions SORT s.created_at, s._key RETURN s",
+            json!({}),
+        )
+        .await
+    }
+
+    async fn update_solution(&self, solution: &Solution, note: &str) -> Result<u32> {
+        // One query, so the snapshot and the update commit together
+        let revisions: Vec<u32> = self
+            .query(
+                "LET current = DOCUMENT('solutions', @key)
+                 FILTER current != null
+                 LET revision = LENGTH(
+                     FOR r IN solution_revisions FILTER r.solution_id == @key RETURN 1
+                 ) + 1
+                 INSERT {
+                     solution_id: @key,
+                     revision: revision,
+                     replaced_at: @now,
+                     note: @note,
+                     solution: UNSET(current, '_key', '_id', '_rev')
+                 } INTO solution_revisions
+                 UPDATE current WITH UNSET(@doc, 'created_at') IN solutions
+                 RETURN revision + 1",
+                json!({
+                    "key": solution.id,
+                    "now": chrono::Utc::now(),
+                    "note": note,
+                    "doc": serde_json::to_value(solution)?,
+                }),
+            )
+            .await?;
+        revisions
+            .into_iter()
+            .next()
+            .ok_or_else(|| anyhow::anyhow!("Solution not found: {}", solution.id))
+    }
+
+    async fn history(&self, id: &str) -> Result<Vec<SolutionRevision>> {
+        self.query(
+            "FOR r IN solution_revisions FILTER r.solution_id == @key
+             SORT r.revision
+             RETURN UNSET(r, '_key', '_id', '_rev', 'solution_id')",
+            json!({ "key": id }),
+        )
+        .await
+    }
+
+    async fn relations(&self, solution_id: &str) -> Result<Vec<ProblemRelation>> {
+        self.query(
+            "FOR e IN problem_relations FILTER e._to == @to
+             LET problem = DOCUMENT(e._from)
+             SORT e.confidence DESC, problem.text
+             RETURN {
+                 from_problem: problem.text,
+                 to_solution: @key,
+                 confidence: e.confidence,
+                 context: e.context
+             }",
+            json!({
+                "key": solution_id,
+                "to": format!("{}/{}", SOLUTIONS, solution_id),
+            }),
+        )
+        .await
+    }
+
+    async fn delete_solution(&self, id: &str) -> Result<()> {
+        let to = format!("{}/{}", SOLUTIONS, id);
+        self.query::<Value>(
+            "FOR e IN problem_relations FILTER e._to == @to REMOVE e IN problem_relations",
+            json!({ "to": to }),
+        )
+        .await?;
+        self.query::<Value>(
+            "FOR r IN solution_revisions FILTER r.solution_id == @key
+             REMOVE r IN solution_revisions",
+            json!({ "key": id }),
+        )
+        .await?;
+        let removed: Vec<String> = self
+            .query(
+                "FOR s IN solutions FILTER s._key == @key
+                 REMOVE s IN solutions RETURN OLD._key",
+                json!({ "key": id }),
+            )
+            .await?;
+        if removed.is_empty() {
+            bail!("Solution not found: {}", id);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,202 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+//! Near-duplicate detection for solutions
+//!
+//! Two solutions in the same category are duplicates when their problems
+//! read alike (word-set Jaccard over the normalized text) and they run
+//! largely the same commands. The same problem with different commands is
+//! an alternative fix, not a duplicate, and is kept separately.
+
+use std::collections::BTreeSet;
+
+use super::{normalize_problem, Solution};
+
+/// Least problem similarity for a near duplicate
+pub const PROBLEM_THRESHOLD: f64 = 0.6;
+
+/// Least command-set similarity for a near duplicate
+pub const COMMAND_THRESHOLD: f64 = 0.5;
+
+fn words(text: &str) -> BTreeSet<String> {
+    normalize_problem(text)
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+fn jaccard(a: &BTreeSet<String>, b: &BTreeSet<String>) -> f64 {
+    let union = a.union(b).count();
+    if union == 0 {
+        return 0.0;
+    }
+    a.intersection(b).count() as f64 / union as f64
+}
+
+/// Word-set similarity of two problem descriptions, 0 to 1
+pub fn problem_similarity(a: &str, b: &str) -> f64 {
+    jaccard(&words(a), &words(b))
+}
+
+/// Commands compare with whitespace collapsed and any `sudo` dropped
+fn normalize_command(command: &str) -> String {
+    let command = command.split_whitespace().collect::<Vec<_>>().join(" ");
+    command
+        .strip_prefix("sudo ")
+        .map(str::to_string)
+        .unwrap_or(command)
+}
+
+fn command_set(commands: &[String]) -> BTreeSet<String> {
+    commands
+        .iter()
+        .map(|c| normalize_command(c))
+        .filter(|c| !c.is_empty())
+        .collect()
+}
+
+/// Similarity of two command lists, 0 to 1
+///
+/// Two solutions without commands are alike; one without commands gives
+/// no evidence either way and scores the threshold.
+pub fn command_similarity(a: &[String], b: &[String]) -> f64 {
+    let (a, b) = (command_set(a), command_set(b));
+    match (a.is_empty(), b.is_empty()) {
+        (true, true) => 1.0,
+        (true, false) | (false, true) => COMMAND_THRESHOLD,
+        (false, false) => jaccard(&a, &b),
+    }
+}
+
+/// Score `candidate` as a duplicate of `existing`, if it is one
+pub fn duplicate_score(candidate: &Solution, existing: &Solution) -> Option<f64> {
+    if candidate.id == existing.id || candidate.category != existing.category {
+        return None;
+    }
+    let normalized = normalize_problem(&candidate.problem);
+    if normalized.is_empty() {
+        return None;
+    }
+    let problem = if normalized == normalize_problem(&existing.problem) {
+        1.0
+    } else {
+        problem_similarity(&candidate.problem, &existing.problem)
+    };
+    let commands = command_similarity(&candidate.commands, &existing.commands);
+
+    (problem >= PROBLEM_THRESHOLD && commands >= COMMAND_THRESHOLD)
+        .then_some((problem + commands) / 2.0)
+}
+
+/// The closest near duplicate of `candidate` among `existing`
+pub fn find_duplicate<'a>(
+    candidate: &Solution,
+    existing: &'a [Solution],
+) -> Option<(&'a Solution, f64)> {
+    existing
+        .iter()
+        .filter_map(|s| duplicate_score(candidate, s).map(|score| (s, score)))
+        .max_by(|a, b| a.1.total_cmp(&b.1))
+}
+
+/// Fold `other` into `keep`: outcome counts add up, and tags and commands
+/// are unioned (keeping `keep`'s order first)
+pub fn absorb(keep: &mut Solution, other: &Solution) {
+    keep.success_count += other.success_count;
+    keep.failure_count += other.failure_count;
+    for tag in &other.tags {
+        if !keep.tags.contains(tag) {
+            keep.tags.push(tag.clone());
+        }
+    }
+    let mut known = command_set(&keep.commands);
+    for command in &other.commands {
+        if known.insert(normalize_command(command)) {
+            keep.commands.push(command.clone());
+        }
+    }
+    keep.updated_at = keep.updated_at.max(other.updated_at);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::SolutionSource;
+
+    fn solution(id: &str, problem: &str, commands: &[&str]) -> Solution {
+        let now = chrono::Utc::now();
+        Solution {
+            id: id.to_string(),
+            category: "network".to_string(),
+            problem: problem.to_string(),
+            solution: String::new(),
+            commands: commands.iter().map(|c| c.to_string()).collect(),
+            tags: vec!["network".to_string()],
+            success_count: 1,
+            failure_count: 0,
+            source: SolutionSource::Manual,
+            created_at: now,
+            updated_at: now,
+        }
+    }
+
+    #[test]
+    fn test_duplicates_need_similar_problem_and_commands() {
+        let existing = vec![
+            solution(
+                "a",
+                "WiFi drops after suspend",
+                &["sudo modprobe -r iwlwifi", "sudo modprobe iwlwifi"],
+            ),
+            solution("b", "DNS lookups time out", &["resolvectl flush-caches"]),
+        ];
+
+        let same = solution(
+            "new",
+            "wifi drops after  SUSPEND",
+            &["modprobe -r iwlwifi", "modprobe iwlwifi"],
+        );
+        let (found, score) = find_duplicate(&same, &existing).unwrap();
+        assert_eq!((found.id.as_str(), score), ("a", 1.0));
+
+        let reworded = solution(
+            "new",
+            "WiFi drops after resume from suspend",
+            &["sudo modprobe -r iwlwifi"],
+        );
+        assert_eq!(find_duplicate(&reworded, &existing).unwrap().0.id, "a");
+
+        // Same problem, different fix: an alternative, not a duplicate
+        let alternative = solution(
+            "new",
+            "WiFi drops after suspend",
+            &["nmcli radio wifi off", "nmcli radio wifi on"],
+        );
+        assert!(find_duplicate(&alternative, &existing).is_none());
+
+        let mut other_category = same.clone();
+        other_category.category = "hardware".to_string();
+        assert!(find_duplicate(&other_category, &existing).is_none());
+
+        assert!(find_duplicate(&solution("new", "", &[]), &existing).is_none());
+    }
+
+    #[test]
+    fn test_absorb_merges_counts_and_sets() {
+        let mut keep = solution("a", "wifi drops", &["sudo modprobe -r iwlwifi"]);
+        let mut other = solution(
+            "b",
+            "wifi drops",
+            &["modprobe -r iwlwifi", "modprobe iwlwifi"],
+        );
+        other.failure_count = 2;
+        other.tags.push("suspend".to_string());
+
+        absorb(&mut keep, &other);
+        assert_eq!((keep.success_count, keep.failure_count), (2, 2));
+        assert_eq!(
+            keep.commands,
+            ["sudo modprobe -r iwlwifi", "modprobe iwlwifi"]
+        );
+        assert_eq!(keep.tags, ["network", "suspend"]);
+    }
+}
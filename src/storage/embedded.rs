@@ -0,0 +1,715 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+//! Embedded SQLite knowledge base
+//!
+//! The default backend: a single file under the data directory, so the
+//! knowledge base works without any server. Text search uses an FTS5 index
+//! kept in sync by triggers, and problem relations are walked with a
+//! recursive query. The schema is versioned with `PRAGMA user_version`.
+
+use anyhow::{bail, Context, Result};
+use async_trait::async_trait;
+use rusqlite::{params, Connection, OptionalExtension, Row};
+use std::path::Path;
+use std::sync::Mutex;
+
+use super::{normalize_problem, KnowledgeStore, ProblemRelation, Solution, SolutionRevision};
+
+/// Default file name in the data directory
+pub const DB_FILE: &str = "knowledge.db";
+
+/// Most results returned by a text search
+const SEARCH_LIMIT: usize = 50;
+
+/// Schema migrations; entry `i` brings the schema to version `i + 1`.
+/// Append only: released migrations must never change.
+const MIGRATIONS: &[&str] = &[
+    // 1: solutions and the problem -> solution graph
+    "CREATE TABLE solutions (
+        id            TEXT PRIMARY KEY,
+        category      TEXT NOT NULL,
+        problem       TEXT NOT NULL,
+        solution      TEXT NOT NULL,
+        commands      TEXT NOT NULL,
+        tags          TEXT NOT NULL,
+        success_count INTEGER NOT NULL DEFAULT 0,
+        failure_count INTEGER NOT NULL DEFAULT 0,
+        source        TEXT NOT NULL,
+        created_at    TEXT NOT NULL,
+        updated_at    TEXT NOT NULL
+    );
+    CREATE INDEX solutions_category ON solutions(category);
+    CREATE TABLE problem_relations (
+        from_problem TEXT NOT NULL,
+        to_solution  TEXT NOT NULL REFERENCES solutions(id) ON DELETE CASCADE,
+        confidence   REAL NOT NULL,
+        context      TEXT NOT NULL,
+        PRIMARY KEY (from_problem, to_solution)
+    );
+    CREATE INDEX problem_relations_solution ON problem_relations(to_solution);",
+    // 2: full-text index over problem, solution and tags
+    "CREATE VIRTUAL TABLE solutions_fts USING fts5(
+        problem, solution, tags, content='solutions', content_rowid='rowid'
+    );
+    CREATE TRIGGER solutions_fts_insert AFTER INSERT ON solutions BEGIN
+        INSERT INTO solutions_fts(rowid, problem, solution, tags)
+        VALUES (new.rowid, new.problem, new.solution, new.tags);
+    END;
+    CREATE TRIGGER solutions_fts_delete AFTER DELETE ON solutions BEGIN
+        INSERT INTO solutions_fts(solutions_fts, rowid, problem, solution, tags)
+        VALUES ('delete', old.rowid, old.problem, old.solution, old.tags);
+    END;
+    CREATE TRIGGER solutions_fts_update AFTER UPDATE ON solutions BEGIN
+        INSERT INTO solutions_fts(solutions_fts, rowid, problem, solution, tags)
+        VALUES ('delete', old.rowid, old.problem, old.solution, old.tags);
+        INSERT INTO solutions_fts(rowid, problem, solution, tags)
+        VALUES (new.rowid, new.problem, new.solution, new.tags);
+    END;
+    INSERT INTO solutions_fts(solutions_fts) VALUES ('rebuild');",
+    // 3: earlier states of edited and merged solutions
+    "CREATE TABLE solution_revisions (
+        solution_id TEXT NOT NULL REFERENCES solutions(id) ON DELETE CASCADE,
+        revision    INTEGER NOT NULL,
+        replaced_at TEXT NOT NULL,
+        note        TEXT NOT NULL,
+        snapshot    TEXT NOT NULL,
+        PRIMARY KEY (solution_id, revision)
+    );",
+];
+
+const COLUMNS: &str = "s.id, s.category, s.problem, s.solution, s.commands, s.tags, \
+    s.success_count, s.failure_count, s.source, s.created_at, s.updated_at";
+
+/// SQLite-backed [`KnowledgeStore`]
+///
+/// Queries are short and local, so they run inline under a mutex rather
+/// than on a blocking thread pool.
+pub struct SqliteStore {
+    conn: Mutex<Connection>,
+}
+
+impl SqliteStore {
+    /// Open (creating and migrating if needed) the database at `path`
+    pub fn open(path: &Path) -> Result<Self> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let conn = Connection::open(path)
+            .with_context(|| format!("Failed to open knowledge base {}", path.display()))?;
+        conn.pragma_update(None, "journal_mode", "WAL")?;
+        Self::init(conn)
+    }
+
+    /// A private in-memory database (for tests)
+    pub fn open_in_memory() -> Result<Self> {
+        Self::init(Connection::open_in_memory()?)
+    }
+
+    fn init(mut conn: Connection) -> Result<Self> {
+        conn.pragma_update(None, "foreign_keys", true)?;
+        migrate(&mut conn)?;
+        Ok(Self {
+            conn: Mutex::new(conn),
+        })
+    }
+
+    /// Current schema version
+    pub fn schema_version(&self) -> Result<usize> {
+        schema_version(&self.conn())
+    }
+
+    fn conn(&self) -> std::sync::MutexGuard<'_, Connection> {
+        // A panic mid-query leaves nothing half-written outside a transaction
+        self.conn.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn query_solutions(&self, sql: &str, params: impl rusqlite::Params) -> Result<Vec<Solution>> {
+        let conn = self.conn();
+        let mut stmt = conn.prepare(sql)?;
+        let rows = stmt.query_map(params, solution_from_row)?;
+        Ok(rows.collect::<rusqlite::Result<_>>()?)
+    }
+}
+
+fn schema_version(conn: &Connection) -> Result<usize> {
+    let version: i64 = conn.pragma_query_value(None, "user_version", |r| r.get(0))?;
+    Ok(version as usize)
+}
+
+/// Apply pending migrations, each in its own transaction
+fn migrate(conn: &mut Connection) -> Result<()> {
+    let current = schema_version(conn)?;
+    if current > MIGRATIONS.len() {
+        bail!(
+            "Knowledge base schema v{} is newer than this psa supports (v{})",
+            current,
+            MIGRATIONS.len()
+        );
+    }
+    for (i, sql) in MIGRATIONS.iter().enumerate().skip(current) {
+        let tx = conn.transaction()?;
+        tx.execute_batch(sql)
+            .with_context(|| format!("Knowledge base migration to v{} failed", i + 1))?;
+        tx.pragma_update(None, "user_version", (i + 1) as i64)?;
+        tx.commit()?;
+        tracing::info!("Migrated knowledge base to schema v{}", i + 1);
+    }
+    Ok(())
+}
+
+fn json_column<T: serde::de::DeserializeOwned>(row: &Row, idx: usize) -> rusqlite::Result<T> {
+    let text: String = row.get(idx)?;
+    serde_json::from_str(&text).map_err(|e| {
+        rusqlite::Error::FromSqlConversionFailure(idx, rusqlite::types::Type::Text, Box::new(e))
+    })
+}
+
+fn get_solution(conn: &Connection, id: &str) -> Result<Option<Solution>> {
+    Ok(conn
+        .query_row(
+            &format!("SELECT {} FROM solutions s WHERE s.id = ?1", COLUMNS),
+            [id],
+            solution_from_row,
+        )
+        .optional()?)
+}
+
+fn solution_from_row(row: &Row) -> rusqlite::Result<Solution> {
+    Ok(Solution {
+        id: row.get(0)?,
+        category: row.get(1)?,
+        problem: row.get(2)?,
+        solution: row.get(3)?,
+        commands: json_column(row, 4)?,
+        tags: json_column(row, 5)?,
+        success_count: row.get(6)?,
+        failure_count: row.get(7)?,
+        source: json_column(row, 8)?,
+        created_at: row.get(9)?,
+        updated_at: row.get(10)?,
+    })
+}
+
+/// Turn free text into an FTS5 query: any of the words, each quoted so
+/// punctuation and FTS operators in the input are taken literally
+fn fts_query(text: &str) -> Option<String> {
+    let terms: Vec<String> = text
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|t| !t.is_empty())
+        .map(|t| format!("\"{}\"", t))
+        .collect();
+    (!terms.is_empty()).then(|| terms.join(" OR "))
+}
+
+/// Link a problem to an existing solution
+fn relate_in(conn: &Connection, relation: &ProblemRelation) -> Result<()> {
+    let known: Option<i64> = conn
+        .query_row(
+            "SELECT 1 FROM solutions WHERE id = ?1",
+            [&relation.to_solution],
+            |r| r.get(0),
+        )
+        .optional()?;
+    if known.is_none() {
+        bail!("Solution not found: {}", relation.to_solution);
+    }
+    conn.execute(
+        "INSERT INTO problem_relations (from_problem, to_solution, confidence, context)
+         VALUES (?1, ?2, ?3, ?4)
+         ON CONFLICT(from_problem, to_solution) DO UPDATE SET
+             confidence = excluded.confidence, context = excluded.context",
+        params![
+            normalize_problem(&relation.from_problem),
+            relation.to_solution,
+            relation.confidence,
+            serde_json::to_string(&relation.context)?,
+        ],
+    )?;
+    Ok(())
+}
+
+/// Replace a solution, keeping its current state as a revision
+fn update_in(conn: &Connection, solution: &Solution, note: &str) -> Result<u32> {
+    let Some(current) = get_solution(conn, &solution.id)? else {
+        bail!("Solution not found: {}", solution.id);
+    };
+    let revision: u32 = conn.query_row(
+        "SELECT COALESCE(MAX(revision), 0) + 1 FROM solution_revisions WHERE solution_id = ?1",
+        [&solution.id],
+        |r| r.get(0),
+    )?;
+    conn.execute(
+        "INSERT INTO solution_revisions (solution_id, revision, replaced_at, note, snapshot)
+         VALUES (?1, ?2, ?3, ?4, ?5)",
+        params![
+            solution.id,
+            revision,
+            chrono::Utc::now(),
+            note,
+            serde_json::to_string(&current)?,
+        ],
+    )?;
+    conn.execute(
+        "UPDATE solutions SET category = ?2, problem = ?3, solution = ?4, commands = ?5,
+             tags = ?6, success_count = ?7, failure_count = ?8, source = ?9, updated_at = ?10
+         WHERE id = ?1",
+        params![
+            solution.id,
+            solution.category,
+            solution.problem,
+            solution.solution,
+            serde_json::to_string(&solution.commands)?,
+            serde_json::to_string(&solution.tags)?,
+            solution.success_count,
+            solution.failure_count,
+            serde_json::to_string(&solution.source)?,
+            solution.updated_at,
+        ],
+    )?;
+    Ok(revision + 1)
+}
+
+#[async_trait]
+impl KnowledgeStore for SqliteStore {
+    async fn store_solution(&self, solution: &Solution) -> Result<String> {
+        let mut conn = self.conn();
+        let tx = conn.transaction()?;
+        tx.execute(
+            "INSERT INTO solutions (id, category, problem, solution, commands, tags,
+                 success_count, failure_count, source, created_at, updated_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)
+             ON CONFLICT(id) DO UPDATE SET
+                 category = excluded.category, problem = excluded.problem,
+                 solution = excluded.solution, commands = excluded.commands,
+                 tags = excluded.tags, success_count = excluded.success_count,
+                 failure_count = excluded.failure_count, source = excluded.source,
+                 updated_at = excluded.updated_at",
+            params![
+                solution.id,
+                solution.category,
+                solution.problem,
+                solution.solution,
+                serde_json::to_string(&solution.commands)?,
+                serde_json::to_string(&solution.tags)?,
+                solution.success_count,
+                solution.failure_count,
+                serde_json::to_string(&solution.source)?,
+                solution.created_at,
+                solution.updated_at,
+            ],
+        )?;
+
+        let problem = normalize_problem(&solution.problem);
+        if !problem.is_empty() {
+            tx.execute(
+                "INSERT INTO problem_relations (from_problem, to_solution, confidence, context)
+                 VALUES (?1, ?2, 1.0, ?3)
+                 ON CONFLICT DO NOTHING",
+                params![problem, solution.id, serde_json::to_string(&solution.tags)?],
+            )?;
+        }
+        tx.commit()?;
+        Ok(solution.id.clone())
+    }
+
+    async fn relate(&self, relation: &ProblemRelation) -> Result<()> {
+        relate_in(&self.conn(), relation)
+    }
+
+    async fn find_by_category(&self, category: &str) -> Result<Vec<Solution>> {
+        self.query_solutions(
+            &format!(
+                "SELECT {} FROM solutions s WHERE s.category = ?1
+                 ORDER BY s.success_count DESC, s.updated_at DESC",
+                COLUMNS
+            ),
+            [category],
+        )
+    }
+
+    async fn search(&self, query: &str) -> Result<Vec<Solution>> {
+        let Some(fts) = fts_query(query) else {
+            return Ok(vec![]);
+        };
+        self.query_solutions(
+            &format!(
+                "SELECT {} FROM solutions_fts f JOIN solutions s ON s.rowid = f.rowid
+                 WHERE solutions_fts MATCH ?1
+                 ORDER BY bm25(solutions_fts) LIMIT ?2",
+                COLUMNS
+            ),
+            params![fts, SEARCH_LIMIT],
+        )
+    }
+
+    async fn find_related(&self, problem: &str, depth: u32) -> Result<Vec<Solution>> {
+        if depth == 0 {
+            return Ok(vec![]);
+        }
+        // Depth 1 is the solutions recorded for the problem; each further
+        // level follows another problem that shares a solution with the last.
+        self.query_solutions(
+            &format!(
+                "WITH RECURSIVE reach(solution, depth) AS (
+                     SELECT to_solution, 1 FROM problem_relations WHERE from_problem = ?1
+                     UNION
+                     SELECT r2.to_solution, reach.depth + 1
+                     FROM reach
+                     JOIN problem_relations r1 ON r1.to_solution = reach.solution
+                     JOIN problem_relations r2 ON r2.from_problem = r1.from_problem
+                     WHERE reach.depth < ?2
+                 )
+                 SELECT {} FROM solutions s
+                 JOIN (SELECT solution, MIN(depth) AS depth FROM reach GROUP BY solution) r
+                     ON r.solution = s.id
+                 ORDER BY r.depth, s.success_count DESC, s.id",
+                COLUMNS
+            ),
+            params![normalize_problem(problem), depth],
+        )
+    }
+
+    async fn record_outcome(&self, solution_id: &str, success: bool) -> Result<()> {
+        let column = if success {
+            "success_count"
+        } else {
+            "failure_count"
+        };
+        let updated = self.conn().execute(
+            &format!(
+                "UPDATE solutions SET {0} = {0} + 1, updated_at = ?2 WHERE id = ?1",
+                column
+            ),
+            params![solution_id, chrono::Utc::now()],
+        )?;
+        if updated == 0 {
+            bail!("Solution not found: {}", solution_id);
+        }
+        Ok(())
+    }
+
+    async fn get(&self, id: &str) -> Result<Option<Solution>> {
+        get_solution(&self.conn(), id)
+    }
+
+    async fn all_solutions(&self) -> Result<Vec<Solution>> {
+        self.query_solutions(
+            &format!("SELECT {} FROM solutions s ORDER BY s.created_at, s.id", COLUMNS),
+            [],
+        )
+    }
+
+    async fn update_solution(&self, solution: &Solution, note: &str) -> Result<u32> {
+        let mut conn = self.conn();
+        let tx = conn.transaction()?;
+        let revision = update_in(&tx, solution, note)?;
+        tx.commit()?;
+        Ok(revision)
+    }
+
+    async fn history(&self, id: &str) -> Result<Vec<SolutionRevision>> {
+        let conn = self.conn();
+        let mut stmt = conn.prepare(
+            "SELECT revision, replaced_at, note, snapshot FROM solution_revisions
+             WHERE solution_id = ?1 ORDER BY revision",
+        )?;
+        let rows = stmt.query_map([id], |row| {
+            Ok(SolutionRevision {
+                revision: row.get(0)?,
+                replaced_at: row.get(1)?,
+                note: row.get(2)?,
+                solution: json_column(row, 3)?,
+            })
+        })?;
+        Ok(rows.collect::<rusqlite::Result<_>>()?)
+    }
+
+    async fn relations(&self, solution_id: &str) -> Result<Vec<ProblemRelation>> {
+        let conn = self.conn();
+        let mut stmt = conn.prepare(
+            "SELECT from_problem, to_solution, confidence, context FROM problem_relations
+             WHERE to_solution = ?1 ORDER BY confidence DESC, from_problem",
+        )?;
+        let rows = stmt.query_map([solution_id], |row| {
+            Ok(ProblemRelation {
+                from_problem: row.get(0)?,
+                to_solution: row.get(1)?,
+                confidence: row.get(2)?,
+                context: json_column(row, 3)?,
+            })
+        })?;
+        Ok(rows.collect::<rusqlite::Result<_>>()?)
+    }
+
+    async fn delete_solution(&self, id: &str) -> Result<()> {
+        // Relations and revisions go with it (ON DELETE CASCADE)
+        if self
+            .conn()
+            .execute("DELETE FROM solutions WHERE id = ?1", [id])?
+            == 0
+        {
+            bail!("Solution not found: {}", id);
+        }
+        Ok(())
+    }
+
+    async fn merge_solutions(
+        &self,
+        merged: &Solution,
+        note: &str,
+        other: &str,
+        relations: &[ProblemRelation],
+    ) -> Result<()> {
+        // One transaction: a failure leaves both solutions as they were
+        let mut conn = self.conn();
+        let tx = conn.transaction()?;
+        update_in(&tx, merged, note)?;
+        for relation in relations {
+            relate_in(&tx, relation)?;
+        }
+        if tx.execute("DELETE FROM solutions WHERE id = ?1", [other])? == 0 {
+            bail!("Solution not found: {}", other);
+        }
+        tx.commit()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::SolutionSource;
+
+    fn solution(id: &str, category: &str, problem: &str, text: &str) -> Solution {
+        let now = chrono::Utc::now();
+        Solution {
+            id: id.to_string(),
+            category: category.to_string(),
+            problem: problem.to_string(),
+            solution: text.to_string(),
+            commands: vec![],
+            tags: vec![category.to_string()],
+            success_count: 0,
+            failure_count: 0,
+            source: SolutionSource::Forum("askubuntu.com".to_string()),
+            created_at: now,
+            updated_at: now,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_store_search_and_outcomes() {
+        let store = SqliteStore::open_in_memory().unwrap();
+        store
+            .store_solution(&solution(
+                "s1",
+                "network",
+                "WiFi drops after suspend",
+                "Reload iwlwifi with power_save=0",
+            ))
+            .await
+            .unwrap();
+        store
+            .store_solution(&solution("s2", "disk", "Disk full", "Vacuum the journal"))
+            .await
+            .unwrap();
+
+        let found = store.search("wifi (suspend)").await.unwrap();
+        assert_eq!(found.len(), 1);
+        assert!(matches!(&found[0].source, SolutionSource::Forum(d) if d == "askubuntu.com"));
+        assert!(store.search("iwlwifi").await.unwrap().len() == 1);
+        assert!(store.search("\"; DROP").await.unwrap().is_empty());
+        assert!(store.search("  ").await.unwrap().is_empty());
+
+        store.record_outcome("s2", true).await.unwrap();
+        store.record_outcome("s2", false).await.unwrap();
+        store.record_outcome("s2", true).await.unwrap();
+        let disk = store.find_by_category("disk").await.unwrap();
+        assert_eq!((disk[0].success_count, disk[0].failure_count), (2, 1));
+        assert!(store.record_outcome("missing", true).await.is_err());
+
+        // Storing again updates in place, and the index follows
+        let mut edited = solution("s2", "disk", "Disk full", "Clean the package cache");
+        edited.success_count = 2;
+        store.store_solution(&edited).await.unwrap();
+        assert!(store.search("journal").await.unwrap().is_empty());
+        assert_eq!(store.search("cache").await.unwrap()[0].id, "s2");
+    }
+
+    #[tokio::test]
+    async fn test_find_related_respects_depth() {
+        let store = SqliteStore::open_in_memory().unwrap();
+        for (id, problem) in [
+            ("a", "no sound"),
+            ("b", "HDMI audio silent"),
+            ("c", "mic muted"),
+        ] {
+            store
+                .store_solution(&solution(id, "audio", problem, "restart pipewire"))
+                .await
+                .unwrap();
+        }
+        // "no sound" is also fixed by b, and "hdmi audio silent" also by c
+        for (problem, to) in [("No sound", "b"), ("hdmi audio silent", "c")] {
+            store
+                .relate(&ProblemRelation {
+                    from_problem: problem.to_string(),
+                    to_solution: to.to_string(),
+                    confidence: 0.6,
+                    context: vec![],
+                })
+                .await
+                .unwrap();
+        }
+
+        let ids = |v: Vec<Solution>| v.into_iter().map(|s| s.id).collect::<Vec<_>>();
+        assert!(store.find_related("no sound", 0).await.unwrap().is_empty());
+        assert_eq!(
+            ids(store.find_related("  No Sound ", 1).await.unwrap()),
+            ["a", "b"]
+        );
+        assert_eq!(
+            ids(store.find_related("no sound", 2).await.unwrap()),
+            ["a", "b", "c"]
+        );
+        // Walking back from c reaches b's problem, then a's
+        assert_eq!(
+            ids(store.find_related("mic muted", 1).await.unwrap()),
+            ["c"]
+        );
+        assert_eq!(
+            ids(store.find_related("mic muted", 3).await.unwrap()),
+            ["c", "b", "a"]
+        );
+
+        let dangling = ProblemRelation {
+            from_problem: "x".to_string(),
+            to_solution: "nope".to_string(),
+            confidence: 1.0,
+            context: vec![],
+        };
+        assert!(store.relate(&dangling).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_learn_edit_and_merge_keep_history() {
+        use crate::storage::{Learned, Storage, StorageConfig};
+
+        let storage = Storage::with_backend(
+            Box::new(SqliteStore::open_in_memory().unwrap()),
+            StorageConfig::default(),
+        );
+        let mut first = solution(
+            "s1",
+            "network",
+            "WiFi drops after suspend",
+            "reload iwlwifi",
+        );
+        first.commands = vec!["sudo modprobe -r iwlwifi".to_string()];
+        assert_eq!(
+            storage.learn(first.clone()).await.unwrap(),
+            Learned::New("s1".to_string())
+        );
+
+        // Learning it again, reworded, strengthens the original
+        let mut again = solution("s2", "network", "wifi drops after suspend", "same fix");
+        again.commands = vec!["modprobe -r iwlwifi".to_string()];
+        assert!(matches!(
+            storage.learn(again).await.unwrap(),
+            Learned::Strengthened { id, .. } if id == "s1"
+        ));
+        assert!(storage.get("s2").await.unwrap().is_none());
+        assert_eq!(storage.require("s1").await.unwrap().success_count, 1);
+        assert!(storage.history("s1").await.unwrap().is_empty());
+
+        let mut edited = storage.require("s1").await.unwrap();
+        edited.solution = "Reload iwlwifi with power_save=0".to_string();
+        assert_eq!(storage.edit(&edited, "Add power_save").await.unwrap(), 2);
+        let history = storage.history("s1").await.unwrap();
+        assert_eq!(history.len(), 1);
+        assert_eq!(history[0].solution.solution, "reload iwlwifi");
+        assert_eq!(history[0].note, "Add power_save");
+
+        // Merging moves counts and problem links, and deletes the other
+        let mut other = solution("s3", "network", "Wireless gone on resume", "reload driver");
+        other.failure_count = 2;
+        storage.store_solution(&other).await.unwrap();
+        let merged = storage.merge("s1", "s3").await.unwrap();
+        assert_eq!((merged.success_count, merged.failure_count), (1, 2));
+        assert!(storage.get("s3").await.unwrap().is_none());
+        assert_eq!(storage.history("s1").await.unwrap().len(), 2);
+        let problems: Vec<_> = storage
+            .relations("s1")
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|r| r.from_problem)
+            .collect();
+        assert!(problems.contains(&"wireless gone on resume".to_string()));
+        assert_eq!(
+            storage
+                .find_related("Wireless gone on resume", 1)
+                .await
+                .unwrap()[0]
+                .id,
+            "s1"
+        );
+        assert!(storage.merge("s1", "s1").await.is_err());
+
+        // A merge that stopped after absorbing is finished, not repeated
+        let mut late = solution("s4", "network", "WiFi gone after lid close", "reload");
+        late.failure_count = 5;
+        storage.store_solution(&late).await.unwrap();
+        let mut absorbed = storage.require("s1").await.unwrap();
+        absorbed.failure_count += 5;
+        storage.edit(&absorbed, "Merged s4").await.unwrap();
+        let merged = storage.merge("s1", "s4").await.unwrap();
+        assert_eq!(merged.failure_count, 7);
+        assert!(storage.get("s4").await.unwrap().is_none());
+        assert!(storage.merge("s1", "s4").await.is_err());
+    }
+
+    #[test]
+    fn test_migrations_are_incremental() {
+        let dir = std::env::temp_dir().join(format!("psa-kb-{}", uuid::Uuid::new_v4()));
+        let path = dir.join(DB_FILE);
+
+        // A v1 database from an older release gains the search index
+        {
+            std::fs::create_dir_all(&dir).unwrap();
+            let mut conn = Connection::open(&path).unwrap();
+            let tx = conn.transaction().unwrap();
+            tx.execute_batch(MIGRATIONS[0]).unwrap();
+            tx.pragma_update(None, "user_version", 1).unwrap();
+            tx.execute(
+                "INSERT INTO solutions VALUES
+                 ('old', 'boot', 'grub rescue', 'reinstall grub', '[]', '[]', 3, 0,
+                  '\"Manual\"', '2024-01-01T00:00:00Z', '2024-01-01T00:00:00Z')",
+                [],
+            )
+            .unwrap();
+            tx.commit().unwrap();
+        }
+
+        let store = SqliteStore::open(&path).unwrap();
+        assert_eq!(store.schema_version().unwrap(), MIGRATIONS.len());
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+        let found = runtime.block_on(store.search("grub")).unwrap();
+        assert_eq!(found[0].success_count, 3);
+        drop(store);
+
+        // Reopening is a no-op; a newer schema is refused
+        assert_eq!(
+            SqliteStore::open(&path).unwrap().schema_version().unwrap(),
+            MIGRATIONS.len()
+        );
+        Connection::open(&path)
+            .unwrap()
+            .pragma_update(None, "user_version", 99)
+            .unwrap();
+        assert!(SqliteStore::open(&path).is_err());
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+}
@@ -0,0 +1,354 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+//! `psa kb`: inspect and curate the knowledge base
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+use super::portable::{self, ConflictStrategy, Dump, Step};
+use super::{Solution, Storage};
+use crate::rules::lifecycle::{FeedbackEffect, LifecycleManager, ProposalStatus};
+
+/// Knowledge base CLI actions
+#[derive(Debug, Clone)]
+pub enum KbAction {
+    /// Show a solution with its outcome counts and linked problems
+    Show { id: String },
+    /// Edit a solution in `$EDITOR`
+    Edit { id: String, message: Option<String> },
+    /// Show how a solution changed over time
+    History { id: String },
+    /// Fold one solution into another
+    Merge { keep: String, other: String },
+    /// Record whether a solution worked when applied
+    Feedback {
+        id: String,
+        worked: bool,
+        note: Option<String>,
+    },
+    /// Write a portable dump to a file or stdout
+    Export {
+        output: Option<PathBuf>,
+        category: Option<String>,
+    },
+    /// Merge a portable dump
+    Import {
+        file: PathBuf,
+        on_conflict: String,
+        dry_run: bool,
+    },
+}
+
+/// The editable part of a solution, round-tripped through `$EDITOR`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct SolutionDraft {
+    category: String,
+    problem: String,
+    solution: String,
+    #[serde(default)]
+    commands: Vec<String>,
+    #[serde(default)]
+    tags: Vec<String>,
+}
+
+impl From<&Solution> for SolutionDraft {
+    fn from(s: &Solution) -> Self {
+        Self {
+            category: s.category.clone(),
+            problem: s.problem.clone(),
+            solution: s.solution.clone(),
+            commands: s.commands.clone(),
+            tags: s.tags.clone(),
+        }
+    }
+}
+
+pub async fn handle(action: KbAction, storage: &Storage) -> Result<()> {
+    match action {
+        KbAction::Show { id } => show(storage, &id).await,
+        KbAction::Edit { id, message } => edit(storage, &id, message).await,
+        KbAction::History { id } => history(storage, &id).await,
+        KbAction::Merge { keep, other } => {
+            let merged = storage.merge(&keep, &other).await?;
+            println!(
+                "Merged {} into {} ({} successes, {} failures)",
+                other, keep, merged.success_count, merged.failure_count
+            );
+            Ok(())
+        }
+        KbAction::Feedback { id, worked, note } => {
+            feedback(storage, &id, worked, note.as_deref()).await
+        }
+        KbAction::Export { output, category } => {
+            export(storage, output.as_deref(), category.as_deref()).await
+        }
+        KbAction::Import {
+            file,
+            on_conflict,
+            dry_run,
+        } => import(storage, &file, on_conflict.parse()?, dry_run).await,
+    }
+}
+
+async fn export(storage: &Storage, output: Option<&Path>, category: Option<&str>) -> Result<()> {
+    let mut dump = Dump::default();
+    for solution in storage.all_solutions().await? {
+        if category.is_some_and(|c| c != solution.category) {
+            continue;
+        }
+        dump.relations.extend(storage.relations(&solution.id).await?);
+        dump.solutions.push(solution);
+    }
+
+    match output {
+        Some(path) => {
+            let mut file = std::io::BufWriter::new(
+                std::fs::File::create(path)
+                    .with_context(|| format!("Failed to create {}", path.display()))?,
+            );
+            portable::write(&mut file, &dump)?;
+            std::io::Write::flush(&mut file)?;
+            eprintln!(
+                "Exported {} solutions and {} relations to {}",
+                dump.solutions.len(),
+                dump.relations.len(),
+                path.display()
+            );
+        }
+        None => portable::write(&mut std::io::stdout().lock(), &dump)?,
+    }
+    Ok(())
+}
+
+async fn import(
+    storage: &Storage,
+    path: &Path,
+    strategy: ConflictStrategy,
+    dry_run: bool,
+) -> Result<()> {
+    let file = std::fs::File::open(path)
+        .with_context(|| format!("Failed to open {}", path.display()))?;
+    let dump = portable::read(std::io::BufReader::new(file))
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+
+    let local = storage.all_solutions().await?;
+    let mut known = HashSet::new();
+    for solution in &local {
+        for relation in storage.relations(&solution.id).await? {
+            known.insert((relation.from_problem, solution.id.clone()));
+        }
+    }
+    let plan = portable::plan(dump, &local, &known, strategy);
+
+    if dry_run {
+        println!("Import of {} (dry run)", path.display());
+        println!("{}", "=".repeat(50));
+        for step in &plan.steps {
+            match step {
+                Step::Add(s) => println!("  add      {}  {}", s.id, s.problem),
+                Step::Replace(s) => println!("  replace  {}  {}", s.id, s.problem),
+                Step::Copy(s) => println!("  copy     {}  {}", s.id, s.problem),
+                Step::KeepLocal(id) => println!("  keep     {}", id),
+                Step::Unchanged(_) => {}
+            }
+        }
+        println!("\nWould import: {}", plan.summary());
+        return Ok(());
+    }
+
+    let note = format!("Imported from {}", path.display());
+    for step in &plan.steps {
+        match step {
+            Step::Add(solution) | Step::Copy(solution) => {
+                storage.store_solution(solution).await?;
+            }
+            Step::Replace(solution) => {
+                storage.edit(solution, &note).await?;
+            }
+            Step::KeepLocal(_) | Step::Unchanged(_) => {}
+        }
+    }
+    for relation in &plan.relations {
+        storage.relate(relation).await?;
+    }
+    println!("Imported: {}", plan.summary());
+    Ok(())
+}
+
+/// Count an outcome and let the rule lifecycle react to it
+async fn feedback(storage: &Storage, id: &str, worked: bool, note: Option<&str>) -> Result<()> {
+    storage.record_outcome(id, worked).await?;
+    let solution = storage.require(id).await?;
+    println!(
+        "Recorded: {} {} ({} worked, {} failed)",
+        id,
+        if worked { "worked" } else { "failed" },
+        solution.success_count,
+        solution.failure_count
+    );
+
+    let mut lifecycle = LifecycleManager::open_default()?;
+    let effect = lifecycle.solution_feedback(&solution, worked, note)?;
+    lifecycle.save()?;
+
+    match effect {
+        FeedbackEffect::Recorded => {}
+        FeedbackEffect::Evidence { proposal_id } => {
+            let proposal = lifecycle.proposal(&proposal_id);
+            if proposal.is_some_and(|p| p.status == ProposalStatus::PendingReview) {
+                println!(
+                    "Rule proposal {} is ready: psa proposals review {}",
+                    proposal_id, proposal_id
+                );
+            } else {
+                println!("Added as evidence to rule proposal {}", proposal_id);
+            }
+        }
+        FeedbackEffect::Proposed { proposal_id } => {
+            println!(
+                "This solution is now proven; opened rule proposal {}",
+                proposal_id
+            );
+            let proposal = lifecycle.proposal(&proposal_id);
+            if proposal.is_some_and(|p| p.suggested_conditions.is_empty()) {
+                println!("No conditions could be inferred; add them during review.");
+            }
+            println!("Review it with: psa proposals review {}", proposal_id);
+        }
+    }
+    Ok(())
+}
+
+async fn show(storage: &Storage, id: &str) -> Result<()> {
+    let solution = storage.require(id).await?;
+    let revisions = storage.history(id).await?.len();
+    let total = solution.success_count + solution.failure_count;
+
+    println!("Solution {}", solution.id);
+    println!("{}", "=".repeat(50));
+    println!("Category:  {}", solution.category);
+    println!("Problem:   {}", solution.problem);
+    println!("Source:    {:?}", solution.source);
+    println!(
+        "Outcomes:  {} worked, {} failed{}",
+        solution.success_count,
+        solution.failure_count,
+        if total > 0 {
+            format!(
+                " ({:.0}%)",
+                solution.success_count as f64 / total as f64 * 100.0
+            )
+        } else {
+            String::new()
+        }
+    );
+    println!("Tags:      {}", solution.tags.join(", "));
+    println!(
+        "Revision:  {} (created {}, updated {})",
+        revisions + 1,
+        solution.created_at.format("%Y-%m-%d"),
+        solution.updated_at.format("%Y-%m-%d %H:%M")
+    );
+
+    println!("\n{}", solution.solution.trim_end());
+    if !solution.commands.is_empty() {
+        println!("\nCommands:");
+        for command in &solution.commands {
+            println!("  $ {}", command);
+        }
+    }
+
+    let relations = storage.relations(id).await?;
+    if !relations.is_empty() {
+        println!("\nSolves:");
+        for relation in relations {
+            println!(
+                "  {:>4.0}%  {}",
+                relation.confidence * 100.0,
+                relation.from_problem
+            );
+        }
+    }
+    Ok(())
+}
+
+async fn edit(storage: &Storage, id: &str, message: Option<String>) -> Result<()> {
+    let mut solution = storage.require(id).await?;
+    let draft = SolutionDraft::from(&solution);
+    let edited = crate::editor::edit_toml(id, &draft)?;
+    if edited == draft {
+        println!("No changes");
+        return Ok(());
+    }
+
+    let (before, after) = (
+        toml::to_string_pretty(&draft)?,
+        toml::to_string_pretty(&edited)?,
+    );
+    let diff = similar::TextDiff::from_lines(&before, &after);
+    print!(
+        "{}",
+        diff.unified_diff()
+            .context_radius(2)
+            .header("before", "after")
+    );
+
+    solution.category = edited.category;
+    solution.problem = edited.problem;
+    solution.solution = edited.solution;
+    solution.commands = edited.commands;
+    solution.tags = edited.tags;
+    solution.updated_at = chrono::Utc::now();
+    let revision = storage
+        .edit(&solution, message.as_deref().unwrap_or("Edited"))
+        .await?;
+    println!("Saved {} as revision {}", id, revision);
+    Ok(())
+}
+
+async fn history(storage: &Storage, id: &str) -> Result<()> {
+    let current = storage.require(id).await?;
+    let revisions = storage.history(id).await?;
+
+    println!("History of {}", id);
+    println!("{}", "=".repeat(50));
+    println!(
+        "r1  created {}",
+        current.created_at.format("%Y-%m-%d %H:%M")
+    );
+
+    // Each revision is the state that the next change replaced
+    let states: Vec<&Solution> = revisions
+        .iter()
+        .map(|r| &r.solution)
+        .chain(std::iter::once(&current))
+        .collect();
+    for (revision, pair) in revisions.iter().zip(states.windows(2)) {
+        println!(
+            "\nr{}  {}  {}",
+            revision.revision + 1,
+            revision.replaced_at.format("%Y-%m-%d %H:%M"),
+            revision.note
+        );
+        let before = toml::to_string_pretty(&SolutionDraft::from(pair[0]))?;
+        let after = toml::to_string_pretty(&SolutionDraft::from(pair[1]))?;
+        let diff = similar::TextDiff::from_lines(&before, &after);
+        print!("{}", diff.unified_diff().context_radius(1));
+        if pair[0].success_count != pair[1].success_count
+            || pair[0].failure_count != pair[1].failure_count
+        {
+            println!(
+                "  outcomes: {}/{} -> {}/{}",
+                pair[0].success_count,
+                pair[0].failure_count,
+                pair[1].success_count,
+                pair[1].failure_count
+            );
+        }
+    }
+    if revisions.is_empty() {
+        println!("\nNo edits");
+    }
+    Ok(())
+}
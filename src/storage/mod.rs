@@ -1,11 +1,24 @@
 // SPDX-License-Identifier: AGPL-3.0-or-later
-//! ArangoDB storage layer for knowledge base and solution graph
+//! Knowledge base storage: solutions and the problem -> solution graph
+//!
+//! Backends implement [`KnowledgeStore`]. The default is an embedded SQLite
+//! file in the data directory, so a laptop has a working knowledge base
+//! without running a database server.
 
 // Allow dead code - scaffolding for future database integration
 #![allow(dead_code)]
 
-use anyhow::Result;
+#[cfg(feature = "arangodb")]
+pub mod arango;
+pub mod dedup;
+pub mod embedded;
+pub mod kb;
+pub mod portable;
+
+use anyhow::{Context, Result};
+use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
 
 /// Solution stored in the knowledge base
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -40,16 +53,128 @@ pub struct ProblemRelation {
     pub context: Vec<String>,
 }
 
-/// ArangoDB storage client
+/// An earlier state of a solution, kept when it is edited or merged
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SolutionRevision {
+    /// 1 for the original; the current state is one past the last revision
+    pub revision: u32,
+    /// When this state was replaced
+    pub replaced_at: chrono::DateTime<chrono::Utc>,
+    /// Why it was replaced
+    pub note: String,
+    pub solution: Solution,
+}
+
+/// What [`Storage::learn`] did with a solution
+#[derive(Debug, Clone, PartialEq)]
+pub enum Learned {
+    /// Stored as a new solution
+    New(String),
+    /// Folded into an existing near duplicate
+    Strengthened { id: String, similarity: f64 },
+}
+
+/// A knowledge base backend
+///
+/// Implemented by the embedded SQLite store; a server-backed store can be
+/// swapped in through [`Storage::with_backend`].
+#[async_trait]
+pub trait KnowledgeStore: Send + Sync {
+    /// Insert or update a solution; its problem is linked to it in the graph
+    async fn store_solution(&self, solution: &Solution) -> Result<String>;
+
+    /// Record that a problem is (also) solved by an existing solution
+    async fn relate(&self, relation: &ProblemRelation) -> Result<()>;
+
+    /// Solutions in a category, most successful first
+    async fn find_by_category(&self, category: &str) -> Result<Vec<Solution>>;
+
+    /// Full-text search over problems, solutions and tags, best match first
+    async fn search(&self, query: &str) -> Result<Vec<Solution>>;
+
+    /// Solutions reachable from a problem within `depth` hops, nearest first
+    async fn find_related(&self, problem: &str, depth: u32) -> Result<Vec<Solution>>;
+
+    /// Count a success or failure against a solution
+    async fn record_outcome(&self, solution_id: &str, success: bool) -> Result<()>;
+
+    /// A solution by ID
+    async fn get(&self, id: &str) -> Result<Option<Solution>>;
+
+    /// Every solution, oldest first
+    async fn all_solutions(&self) -> Result<Vec<Solution>>;
+
+    /// Replace an existing solution, keeping its current state as a
+    /// revision; returns the new revision number
+    async fn update_solution(&self, solution: &Solution, note: &str) -> Result<u32>;
+
+    /// Earlier states of a solution, oldest first
+    async fn history(&self, id: &str) -> Result<Vec<SolutionRevision>>;
+
+    /// Problems linked to a solution
+    async fn relations(&self, solution_id: &str) -> Result<Vec<ProblemRelation>>;
+
+    /// Remove a solution with its relations and history
+    async fn delete_solution(&self, id: &str) -> Result<()>;
+
+    /// Finish merging `other` into `merged`: replace `merged`, link the
+    /// relations to it and delete `other`
+    ///
+    /// Backends with transactions do this atomically. The default runs the
+    /// steps in order; [`Storage::merge`] resumes a merge that stopped after
+    /// the update without absorbing `other` twice.
+    async fn merge_solutions(
+        &self,
+        merged: &Solution,
+        note: &str,
+        other: &str,
+        relations: &[ProblemRelation],
+    ) -> Result<()> {
+        self.update_solution(merged, note).await?;
+        for relation in relations {
+            self.relate(relation).await?;
+        }
+        self.delete_solution(other).await
+    }
+}
+
+/// Belief that a solution works: its success rate with one pseudo-success
+/// and one pseudo-failure, so an untried solution starts at 0.5, few samples
+/// don't look certain and each outcome moves it
+pub fn confidence(solution: &Solution) -> f64 {
+    (solution.success_count + 1) as f64
+        / (solution.success_count + solution.failure_count + 2) as f64
+}
+
+/// Problems are matched case- and whitespace-insensitively in the graph
+pub fn normalize_problem(problem: &str) -> String {
+    problem.split_whitespace().collect::<Vec<_>>().join(" ").to_lowercase()
+}
+
+/// Knowledge base handle
 pub struct Storage {
-    // TODO: Add arangors client when ArangoDB is configured
-    // client: arangors::Connection,
-    // db: arangors::Database,
+    backend: Box<dyn KnowledgeStore>,
     config: StorageConfig,
 }
 
-#[derive(Debug, Clone)]
+/// Which knowledge base backend to use
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StorageBackend {
+    /// SQLite file in the data directory
+    #[default]
+    Embedded,
+    /// ArangoDB server (needs the `arangodb` feature)
+    ArangoDb,
+}
+
+/// Storage settings, read from `storage.toml` in the config directory
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct StorageConfig {
+    pub backend: StorageBackend,
+    /// Embedded database file
+    pub path: PathBuf,
     pub host: String,
     pub port: u16,
     pub database: String,
@@ -60,60 +185,245 @@ pub struct StorageConfig {
 impl Default for StorageConfig {
     fn default() -> Self {
         Self {
+            path: crate::dirs::data_dir().join(embedded::DB_FILE),
             host: "localhost".to_string(),
             port: 8529,
             database: "psa".to_string(),
             username: "root".to_string(),
             password: String::new(),
+            backend: StorageBackend::default(),
+        }
+    }
+}
+
+impl StorageConfig {
+    /// Load `storage.toml`, falling back to defaults if it doesn't exist
+    pub fn load() -> Result<Self> {
+        let path = crate::dirs::config_dir().join("storage.toml");
+        match std::fs::read_to_string(&path) {
+            Ok(content) => toml::from_str(&content)
+                .with_context(|| format!("Invalid storage config {}", path.display())),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e.into()),
         }
     }
+
+    /// ArangoDB endpoint
+    pub fn url(&self) -> String {
+        format!("http://{}:{}", self.host, self.port)
+    }
 }
 
 impl Storage {
-    /// Create new storage connection
+    /// Open the configured knowledge base (embedded unless `storage.toml` says otherwise)
     pub async fn new() -> Result<Self> {
-        let config = StorageConfig::default();
+        let config = StorageConfig::load()?;
+        let backend: Box<dyn KnowledgeStore> = match config.backend {
+            StorageBackend::Embedded => {
+                let store = embedded::SqliteStore::open(&config.path)?;
+                tracing::info!("Storage initialized (embedded: {})", config.path.display());
+                Box::new(store)
+            }
+            #[cfg(feature = "arangodb")]
+            StorageBackend::ArangoDb => Box::new(arango::ArangoStore::connect(&config).await?),
+            #[cfg(not(feature = "arangodb"))]
+            StorageBackend::ArangoDb => anyhow::bail!(
+                "storage.toml selects ArangoDB, but psa was built without the `arangodb` feature"
+            ),
+        };
 
-        // TODO: Connect to ArangoDB
-        // For now, use fallback local storage
-        tracing::info!("Storage initialized (local mode - ArangoDB not configured)");
+        Ok(Self::with_backend(backend, config))
+    }
 
-        Ok(Self { config })
+    /// Use a specific backend
+    pub fn with_backend(backend: Box<dyn KnowledgeStore>, config: StorageConfig) -> Self {
+        Self { backend, config }
     }
 
     /// Store a new solution
     pub async fn store_solution(&self, solution: &Solution) -> Result<String> {
         tracing::debug!("Storing solution: {}", solution.id);
-        // TODO: ArangoDB insert
-        Ok(solution.id.clone())
+        self.backend.store_solution(solution).await
+    }
+
+    /// Link a problem to an existing solution
+    pub async fn relate(&self, relation: &ProblemRelation) -> Result<()> {
+        tracing::debug!("Relating '{}' -> {}", relation.from_problem, relation.to_solution);
+        self.backend.relate(relation).await
     }
 
     /// Find solutions by category
     pub async fn find_by_category(&self, category: &str) -> Result<Vec<Solution>> {
         tracing::debug!("Finding solutions in category: {}", category);
-        // TODO: ArangoDB query
-        Ok(vec![])
+        self.backend.find_by_category(category).await
     }
 
     /// Search solutions by text
     pub async fn search(&self, query: &str) -> Result<Vec<Solution>> {
         tracing::debug!("Searching solutions: {}", query);
-        // TODO: ArangoDB fulltext search
-        Ok(vec![])
+        self.backend.search(query).await
     }
 
     /// Get related solutions via graph traversal
     pub async fn find_related(&self, problem: &str, depth: u32) -> Result<Vec<Solution>> {
         tracing::debug!("Finding related solutions for: {} (depth {})", problem, depth);
-        // TODO: ArangoDB graph traversal
-        Ok(vec![])
+        self.backend.find_related(problem, depth).await
     }
 
     /// Record solution success/failure for learning
     pub async fn record_outcome(&self, solution_id: &str, success: bool) -> Result<()> {
         tracing::debug!("Recording outcome for {}: {}", solution_id, success);
-        // TODO: Update success/failure counts
-        Ok(())
+        self.backend.record_outcome(solution_id, success).await
+    }
+
+    /// Store a learned solution, or fold it into an existing near duplicate
+    ///
+    /// Learning the same fix again counts as another success for it, so
+    /// repeated `learn` calls strengthen one solution instead of spreading
+    /// outcomes over copies.
+    pub async fn learn(&self, solution: Solution) -> Result<Learned> {
+        // Duplicates are only sought within the category
+        let candidates = self.backend.find_by_category(&solution.category).await?;
+
+        let Some((existing, similarity)) = dedup::find_duplicate(&solution, &candidates) else {
+            let id = self.store_solution(&solution).await?;
+            return Ok(Learned::New(id));
+        };
+
+        let mut merged = existing.clone();
+        let mut incoming = solution.clone();
+        if incoming.success_count + incoming.failure_count == 0 {
+            incoming.success_count = 1;
+        }
+        dedup::absorb(&mut merged, &incoming);
+
+        if merged.commands != existing.commands || merged.tags != existing.tags {
+            self.backend
+                .update_solution(&merged, "Strengthened by a duplicate learn")
+                .await?;
+        } else {
+            self.backend.store_solution(&merged).await?;
+        }
+        if normalize_problem(&solution.problem) != normalize_problem(&existing.problem) {
+            self.link_problem(&solution.problem, &merged.id, similarity as f32, &solution.tags)
+                .await?;
+        }
+
+        Ok(Learned::Strengthened {
+            id: merged.id,
+            similarity,
+        })
+    }
+
+    /// A solution by ID
+    pub async fn get(&self, id: &str) -> Result<Option<Solution>> {
+        self.backend.get(id).await
+    }
+
+    /// Every solution, oldest first
+    pub async fn all_solutions(&self) -> Result<Vec<Solution>> {
+        self.backend.all_solutions().await
+    }
+
+    /// A solution by ID, or an error naming it
+    pub async fn require(&self, id: &str) -> Result<Solution> {
+        self.get(id)
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("Solution not found: {}", id))
+    }
+
+    /// Amend a solution, keeping its previous state in the history
+    pub async fn edit(&self, solution: &Solution, note: &str) -> Result<u32> {
+        tracing::debug!("Editing solution: {}", solution.id);
+        let revision = self.backend.update_solution(solution, note).await?;
+        self.link_problem(&solution.problem, &solution.id, 1.0, &solution.tags)
+            .await?;
+        Ok(revision)
+    }
+
+    /// Earlier states of a solution, oldest first
+    pub async fn history(&self, id: &str) -> Result<Vec<SolutionRevision>> {
+        self.backend.history(id).await
+    }
+
+    /// Problems linked to a solution
+    pub async fn relations(&self, solution_id: &str) -> Result<Vec<ProblemRelation>> {
+        self.backend.relations(solution_id).await
+    }
+
+    /// Fold `other` into `keep`: counts, tags, commands and problem links
+    /// move over, `keep` records the merge in its history and `other` is
+    /// deleted
+    ///
+    /// Safe to retry: if `keep`'s history already records the merge, an
+    /// earlier attempt stopped after absorbing `other`, so only the links
+    /// are moved and `other` deleted.
+    pub async fn merge(&self, keep: &str, other: &str) -> Result<Solution> {
+        if keep == other {
+            anyhow::bail!("Cannot merge a solution into itself");
+        }
+        let mut merged = self.require(keep).await?;
+        let absorbed = self.require(other).await?;
+
+        let mut relations = self.backend.relations(other).await?;
+        if relations.is_empty() && !normalize_problem(&absorbed.problem).is_empty() {
+            relations.push(ProblemRelation {
+                from_problem: absorbed.problem.clone(),
+                to_solution: other.to_string(),
+                confidence: 1.0,
+                context: absorbed.tags.clone(),
+            });
+        }
+        let relations: Vec<ProblemRelation> = relations
+            .into_iter()
+            .map(|relation| ProblemRelation {
+                to_solution: keep.to_string(),
+                ..relation
+            })
+            .collect();
+
+        let note = format!("Merged {}", other);
+        let resumed = self
+            .backend
+            .history(keep)
+            .await?
+            .iter()
+            .any(|revision| revision.note == note);
+        if resumed {
+            for relation in &relations {
+                self.backend.relate(relation).await?;
+            }
+            self.backend.delete_solution(other).await?;
+            return Ok(merged);
+        }
+
+        dedup::absorb(&mut merged, &absorbed);
+        merged.updated_at = chrono::Utc::now();
+        self.backend
+            .merge_solutions(&merged, &note, other, &relations)
+            .await?;
+        Ok(merged)
+    }
+
+    /// Link a problem to a solution unless it is its own problem or empty
+    async fn link_problem(
+        &self,
+        problem: &str,
+        solution_id: &str,
+        confidence: f32,
+        context: &[String],
+    ) -> Result<()> {
+        if normalize_problem(problem).is_empty() {
+            return Ok(());
+        }
+        self.backend
+            .relate(&ProblemRelation {
+                from_problem: problem.to_string(),
+                to_solution: solution_id.to_string(),
+                confidence,
+                context: context.to_vec(),
+            })
+            .await
     }
 
     /// Get storage config
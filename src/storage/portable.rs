@@ -0,0 +1,401 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+//! Portable knowledge base dumps
+//!
+//! A dump is JSON lines, one record per line, each tagged with `type`:
+//!
+//! ```text
+//! {"type":"header","format":"psa-kb","version":1,"exported_at":"2026-01-05T10:00:00Z"}
+//! {"type":"solution","id":"…","category":"network","problem":"…","solution":"…",
+//!  "commands":["…"],"tags":["…"],"success_count":4,"failure_count":0,
+//!  "source":{"Forum":"askubuntu.com"},"created_at":"…","updated_at":"…"}
+//! {"type":"relation","from_problem":"…","to_solution":"…","confidence":1.0,"context":[]}
+//! ```
+//!
+//! The header comes first; solutions precede the relations that point at
+//! them. `source` is the solution's provenance (`"Local"`, `"Manual"`,
+//! `{"Mesh":"<peer>"}` or `{"Forum":"<site>"}`). The format is independent of
+//! the storage backend, so dumps move between SQLite and ArangoDB.
+
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::io::{BufRead, Write};
+use std::str::FromStr;
+
+use super::{confidence, ProblemRelation, Solution};
+use crate::validation::validate_solution_id;
+
+pub const FORMAT: &str = "psa-kb";
+pub const VERSION: u32 = 1;
+
+/// One line of a dump
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum Record {
+    Header {
+        format: String,
+        version: u32,
+        exported_at: chrono::DateTime<chrono::Utc>,
+    },
+    Solution(Solution),
+    Relation(ProblemRelation),
+}
+
+/// Solutions and relations read from a dump
+#[derive(Debug, Clone, Default)]
+pub struct Dump {
+    pub solutions: Vec<Solution>,
+    pub relations: Vec<ProblemRelation>,
+}
+
+/// Write a dump
+pub fn write(out: &mut impl Write, dump: &Dump) -> Result<()> {
+    let header = Record::Header {
+        format: FORMAT.to_string(),
+        version: VERSION,
+        exported_at: chrono::Utc::now(),
+    };
+    writeln!(out, "{}", serde_json::to_string(&header)?)?;
+    for solution in &dump.solutions {
+        writeln!(
+            out,
+            "{}",
+            serde_json::to_string(&Record::Solution(solution.clone()))?
+        )?;
+    }
+    for relation in &dump.relations {
+        writeln!(
+            out,
+            "{}",
+            serde_json::to_string(&Record::Relation(relation.clone()))?
+        )?;
+    }
+    Ok(())
+}
+
+/// Read a dump, checking the header and the solution IDs
+pub fn read(input: impl BufRead) -> Result<Dump> {
+    let mut dump = Dump::default();
+    let mut header = false;
+
+    for (n, line) in input.lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let record: Record = serde_json::from_str(&line)
+            .with_context(|| format!("line {}: invalid record", n + 1))?;
+        match record {
+            Record::Header {
+                format, version, ..
+            } => {
+                if format != FORMAT {
+                    bail!("line {}: not a knowledge base dump ({})", n + 1, format);
+                }
+                if version > VERSION {
+                    bail!(
+                        "Dump format v{} is newer than this psa supports (v{})",
+                        version,
+                        VERSION
+                    );
+                }
+                header = true;
+            }
+            _ if !header => bail!("line {}: missing {} header", n + 1, FORMAT),
+            Record::Solution(solution) => {
+                validate_solution_id(&solution.id)
+                    .map_err(|e| anyhow::anyhow!("line {}: {}", n + 1, e))?;
+                dump.solutions.push(solution)
+            }
+            Record::Relation(relation) => {
+                validate_solution_id(&relation.to_solution)
+                    .map_err(|e| anyhow::anyhow!("line {}: {}", n + 1, e))?;
+                dump.relations.push(relation)
+            }
+        }
+    }
+    if !header {
+        bail!("Empty dump");
+    }
+    Ok(dump)
+}
+
+/// What to do when an imported solution has the ID of an existing one
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConflictStrategy {
+    /// Keep whichever was updated last
+    Newer,
+    /// Keep whichever has the better success record
+    Confident,
+    /// Keep the local one and import the other under a new ID
+    Both,
+}
+
+impl FromStr for ConflictStrategy {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "newer" => Ok(Self::Newer),
+            "confidence" => Ok(Self::Confident),
+            "both" => Ok(Self::Both),
+            other => bail!(
+                "Unknown conflict strategy '{}' (newer, confidence, both)",
+                other
+            ),
+        }
+    }
+}
+
+/// Planned change for one imported solution
+#[derive(Debug, Clone)]
+pub enum Step {
+    /// Not present locally
+    Add(Solution),
+    /// Replaces the local solution with the same ID
+    Replace(Solution),
+    /// Imported next to the local one under a new ID
+    Copy(Solution),
+    /// The local solution wins
+    KeepLocal(String),
+    /// Identical to the local solution
+    Unchanged(String),
+}
+
+/// The changes an import would make
+#[derive(Debug, Clone, Default)]
+pub struct ImportPlan {
+    pub steps: Vec<Step>,
+    /// Relations to add, pointing at the IDs the solutions will have
+    pub relations: Vec<ProblemRelation>,
+}
+
+impl ImportPlan {
+    pub fn count(&self, f: impl Fn(&Step) -> bool) -> usize {
+        self.steps.iter().filter(|s| f(s)).count()
+    }
+
+    /// One-line summary, e.g. for a dry run
+    pub fn summary(&self) -> String {
+        format!(
+            "{} new, {} replaced, {} copied, {} kept local, {} unchanged, {} relations",
+            self.count(|s| matches!(s, Step::Add(_))),
+            self.count(|s| matches!(s, Step::Replace(_))),
+            self.count(|s| matches!(s, Step::Copy(_))),
+            self.count(|s| matches!(s, Step::KeepLocal(_))),
+            self.count(|s| matches!(s, Step::Unchanged(_))),
+            self.relations.len()
+        )
+    }
+}
+
+fn same_content(a: &Solution, b: &Solution) -> bool {
+    serde_json::to_value(a).ok() == serde_json::to_value(b).ok()
+}
+
+/// Work out how to merge `dump` into a knowledge base holding `local`
+///
+/// `known_relations` are the (normalized problem, solution ID) pairs that
+/// already exist; they are not re-imported, so local confidences stand.
+/// Storing a solution links its own problem to it, so the dump's copy of
+/// that link is skipped too, as are repeats within the dump.
+pub fn plan(
+    dump: Dump,
+    local: &[Solution],
+    known_relations: &HashSet<(String, String)>,
+    strategy: ConflictStrategy,
+) -> ImportPlan {
+    let local: HashMap<&str, &Solution> = local.iter().map(|s| (s.id.as_str(), s)).collect();
+    let mut renamed: HashMap<String, String> = HashMap::new();
+    let mut plan = ImportPlan::default();
+
+    for incoming in dump.solutions {
+        let Some(existing) = local.get(incoming.id.as_str()) else {
+            plan.steps.push(Step::Add(incoming));
+            continue;
+        };
+        if same_content(existing, &incoming) {
+            plan.steps.push(Step::Unchanged(incoming.id));
+            continue;
+        }
+        let step = match strategy {
+            ConflictStrategy::Newer if incoming.updated_at > existing.updated_at => {
+                Step::Replace(incoming)
+            }
+            ConflictStrategy::Confident if confidence(&incoming) > confidence(existing) => {
+                Step::Replace(incoming)
+            }
+            ConflictStrategy::Both => {
+                let id = uuid::Uuid::new_v4().to_string();
+                renamed.insert(incoming.id.clone(), id.clone());
+                Step::Copy(Solution { id, ..incoming })
+            }
+            _ => Step::KeepLocal(incoming.id),
+        };
+        plan.steps.push(step);
+    }
+
+    let mut linked = known_relations.clone();
+    for step in &plan.steps {
+        if let Step::Add(s) | Step::Copy(s) | Step::Replace(s) = step {
+            linked.insert((super::normalize_problem(&s.problem), s.id.clone()));
+        }
+    }
+
+    for relation in dump.relations {
+        let to_solution = renamed
+            .get(&relation.to_solution)
+            .cloned()
+            .unwrap_or(relation.to_solution);
+        let key = (
+            super::normalize_problem(&relation.from_problem),
+            to_solution.clone(),
+        );
+        let target_known = local.contains_key(to_solution.as_str())
+            || plan.steps.iter().any(|s| match s {
+                Step::Add(s) | Step::Copy(s) => s.id == to_solution,
+                _ => false,
+            });
+        if target_known && linked.insert(key) {
+            plan.relations.push(ProblemRelation {
+                to_solution,
+                ..relation
+            });
+        }
+    }
+    plan
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::SolutionSource;
+
+    fn solution(id: &str, successes: u32, updated_days_ago: i64) -> Solution {
+        let at = chrono::Utc::now() - chrono::Duration::days(updated_days_ago);
+        Solution {
+            id: id.to_string(),
+            category: "disk".to_string(),
+            problem: format!("problem {}", id),
+            solution: "fix".to_string(),
+            commands: vec![],
+            tags: vec![],
+            success_count: successes,
+            failure_count: 1,
+            source: SolutionSource::Mesh("laptop".to_string()),
+            created_at: at,
+            updated_at: at,
+        }
+    }
+
+    fn relation(problem: &str, to: &str) -> ProblemRelation {
+        ProblemRelation {
+            from_problem: problem.to_string(),
+            to_solution: to.to_string(),
+            confidence: 0.9,
+            context: vec!["disk".to_string()],
+        }
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let dump = Dump {
+            solutions: vec![solution("a", 3, 1)],
+            relations: vec![relation("problem a", "a")],
+        };
+        let mut out = vec![];
+        write(&mut out, &dump).unwrap();
+        let text = String::from_utf8(out).unwrap();
+        assert!(text.starts_with("{\"type\":\"header\",\"format\":\"psa-kb\""));
+        assert!(text.contains("\"source\":{\"Mesh\":\"laptop\"}"));
+
+        let back = read(text.as_bytes()).unwrap();
+        assert_eq!(back.solutions.len(), 1);
+        assert!(same_content(&back.solutions[0], &dump.solutions[0]));
+        assert_eq!(back.relations[0].to_solution, "a");
+
+        assert!(read("".as_bytes()).is_err());
+        let headless = text.lines().skip(1).collect::<Vec<_>>().join("\n");
+        assert!(read(headless.as_bytes()).is_err());
+        let future = text.replace("\"version\":1", "\"version\":9");
+        assert!(read(future.as_bytes()).is_err());
+    }
+
+    #[test]
+    fn test_conflict_strategies() {
+        let local = vec![solution("a", 5, 10), solution("b", 1, 10)];
+        let dump = || Dump {
+            solutions: vec![
+                solution("a", 1, 0), // newer, less proven
+                local[1].clone(),    // identical
+                solution("c", 1, 0), // new
+            ],
+            relations: vec![
+                relation("Problem A", "a"),
+                relation("elsewhere", "a"),
+                relation("x", "missing"),
+            ],
+        };
+        let known = HashSet::from([("problem a".to_string(), "a".to_string())]);
+
+        let newer = plan(dump(), &local, &known, ConflictStrategy::Newer);
+        assert!(matches!(&newer.steps[0], Step::Replace(s) if s.success_count == 1));
+        assert!(matches!(&newer.steps[1], Step::Unchanged(id) if id == "b"));
+        assert!(matches!(&newer.steps[2], Step::Add(s) if s.id == "c"));
+        // Known and dangling relations are dropped
+        assert_eq!(newer.relations.len(), 1);
+        assert_eq!(newer.relations[0].from_problem, "elsewhere");
+        assert_eq!(
+            newer.summary(),
+            "1 new, 1 replaced, 0 copied, 0 kept local, 1 unchanged, 1 relations"
+        );
+
+        let confident = plan(dump(), &local, &known, ConflictStrategy::Confident);
+        assert!(matches!(&confident.steps[0], Step::KeepLocal(id) if id == "a"));
+
+        let both = plan(dump(), &local, &known, ConflictStrategy::Both);
+        let Step::Copy(copy) = &both.steps[0] else {
+            panic!("expected a copy");
+        };
+        assert_ne!(copy.id, "a");
+        // Relations follow the copy; its own problem is linked when stored
+        assert!(both.relations.iter().all(|r| r.to_solution == copy.id));
+        assert_eq!(both.relations.len(), 1);
+        assert_eq!(both.relations[0].from_problem, "elsewhere");
+    }
+
+    #[test]
+    fn test_import_does_not_double_relations() {
+        let dump = Dump {
+            solutions: vec![solution("c", 1, 0)],
+            relations: vec![
+                relation("Problem C", "c"),
+                relation("other", "c"),
+                relation("  Other ", "c"),
+            ],
+        };
+        let plan = plan(dump, &[], &HashSet::new(), ConflictStrategy::Newer);
+        assert_eq!(plan.relations.len(), 1);
+        assert_eq!(plan.relations[0].from_problem, "other");
+    }
+
+    #[test]
+    fn test_rejects_malformed_ids() {
+        let dump = Dump {
+            solutions: vec![solution("../evil", 1, 0)],
+            relations: vec![],
+        };
+        let mut out = vec![];
+        write(&mut out, &dump).unwrap();
+        let err = read(out.as_slice()).unwrap_err();
+        assert!(err.to_string().contains("line 2"), "{}", err);
+
+        let dump = Dump {
+            solutions: vec![],
+            relations: vec![relation("x", "")],
+        };
+        let mut out = vec![];
+        write(&mut out, &dump).unwrap();
+        assert!(read(out.as_slice()).is_err());
+    }
+}
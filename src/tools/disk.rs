@@ -4,9 +4,8 @@
 //! Security: All user-supplied paths are validated before use in commands
 
 use anyhow::Result;
-use sysinfo::Disks;
 use crate::storage::Storage;
-use crate::cache::Cache;
+use crate::cache::{metrics, Cache};
 use crate::validation::validate_safe_path;
 
 /// Disk action types
@@ -19,9 +18,9 @@ pub enum DiskAction {
     Health,
 }
 
-pub async fn handle(action: DiskAction, _storage: &Storage, _cache: &Cache) -> Result<()> {
+pub async fn handle(action: DiskAction, _storage: &Storage, cache: &Cache) -> Result<()> {
     match action {
-        DiskAction::Usage => show_usage().await?,
+        DiskAction::Usage => show_usage(cache).await?,
         DiskAction::Large { min_size, path } => find_large(&min_size, &path).await?,
         DiskAction::Io => show_io().await?,
         DiskAction::Duplicates { path } => find_duplicates(&path).await?,
@@ -30,25 +29,35 @@ pub async fn handle(action: DiskAction, _storage: &Storage, _cache: &Cache) -> R
     Ok(())
 }
 
-async fn show_usage() -> Result<()> {
-    let disks = Disks::new_with_refreshed_list();
-
-    println!("{:<20} {:>10} {:>10} {:>10} {:>6}", "FILESYSTEM", "SIZE", "USED", "AVAIL", "USE%");
-    println!("{}", "-".repeat(60));
-
-    for disk in disks.list() {
-        let total = disk.total_space();
-        let avail = disk.available_space();
-        let used = total - avail;
-        let use_pct = if total > 0 { (used as f64 / total as f64) * 100.0 } else { 0.0 };
+async fn show_usage(cache: &Cache) -> Result<()> {
+    let metrics = cache.current_metrics(metrics::FRESH).await;
+    // Time to full needs the daemon's usage history; without it the column stays empty
+    let history = crate::history::History::open_default().ok();
+    let now = chrono::Utc::now().timestamp();
+
+    println!(
+        "{:<20} {:>10} {:>10} {:>10} {:>6}  {:>10}",
+        "FILESYSTEM", "SIZE", "USED", "AVAIL", "USE%", "FULL IN"
+    );
+    println!("{}", "-".repeat(72));
+
+    for disk in &metrics.disks {
+        let full_in = match history.as_ref().and_then(|h| h.forecast_fill(&disk.mount_point, now)) {
+            Some(forecast) => forecast
+                .full_in
+                .map(crate::history::format_duration)
+                .unwrap_or_else(|| "stable".to_string()),
+            None => "-".to_string(),
+        };
 
         println!(
-            "{:<20} {:>10} {:>10} {:>10} {:>5.1}%",
-            disk.mount_point().display(),
-            format_size(total),
-            format_size(used),
-            format_size(avail),
-            use_pct
+            "{:<20} {:>10} {:>10} {:>10} {:>5.1}%  {:>10}",
+            disk.mount_point,
+            format_size(disk.total),
+            format_size(disk.used()),
+            format_size(disk.available),
+            disk.used_pct(),
+            full_in
         );
     }
 
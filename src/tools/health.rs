@@ -2,13 +2,11 @@
 //! System health summary
 
 use anyhow::Result;
-use sysinfo::{System, Disks};
 use crate::storage::Storage;
-use crate::cache::Cache;
+use crate::cache::{metrics, Cache};
 
-pub async fn show(_storage: &Storage, _cache: &Cache) -> Result<()> {
-    let mut sys = System::new_all();
-    sys.refresh_all();
+pub async fn show(_storage: &Storage, cache: &Cache) -> Result<()> {
+    let metrics = cache.current_metrics(metrics::FRESH).await;
 
     println!("System Health Summary");
     println!("{}", "=".repeat(50));
@@ -17,39 +15,43 @@ pub async fn show(_storage: &Storage, _cache: &Cache) -> Result<()> {
     let mut issues = vec![];
 
     // CPU check
-    let cpu = sys.global_cpu_usage();
+    let cpu = metrics.cpu_usage;
     if cpu > 90.0 {
         issues.push(format!("High CPU: {:.1}%", cpu));
     }
 
     // Memory check
-    let mem_total = sys.total_memory();
-    let mem_pct = if mem_total > 0 {
-        (sys.used_memory() as f64 / mem_total as f64) * 100.0
-    } else {
-        0.0
-    };
+    let mem_pct = metrics.memory_pct();
     if mem_pct > 90.0 {
         issues.push(format!("High Memory: {:.1}%", mem_pct));
     }
+    if metrics.swap_pct() > 50.0 {
+        issues.push(format!("Heavy swapping: {:.1}% of swap used", metrics.swap_pct()));
+    }
 
     // Disk check
-    let disks = Disks::new_with_refreshed_list();
-    for disk in disks.list() {
-        let total = disk.total_space();
-        if total > 0 {
-            let used_pct = 100.0 - (disk.available_space() as f64 / total as f64 * 100.0);
-            if used_pct > 90.0 {
-                issues.push(format!("Disk {} at {:.1}%", disk.mount_point().display(), used_pct));
-            }
+    for disk in &metrics.disks {
+        if disk.total > 0 && disk.used_pct() > 90.0 {
+            issues.push(format!("Disk {} at {:.1}%", disk.mount_point, disk.used_pct()));
         }
     }
 
     // Load check
-    let load = System::load_average();
-    let cpu_count = sys.cpus().len() as f64;
-    if cpu_count > 0.0 && load.one > cpu_count * 2.0 {
-        issues.push(format!("High load: {:.2}", load.one));
+    let [load1, load5, load15] = metrics.load_avg;
+    let cpu_count = metrics.cpu_count as f64;
+    if cpu_count > 0.0 && load1 > cpu_count * 2.0 {
+        issues.push(format!("High load: {:.2}", load1));
+    }
+
+    // Pressure check: tasks stalled waiting for memory or I/O
+    let pressure = [("memory", metrics.pressure.memory), ("I/O", metrics.pressure.io)];
+    for (resource, pressure) in pressure {
+        if let Some(full) = pressure.and_then(|p| p.full).filter(|f| f.avg60 > 10.0) {
+            issues.push(format!(
+                "Tasks stalled on {} {:.1}% of the last minute",
+                resource, full.avg60
+            ));
+        }
     }
 
     // Display status
@@ -57,7 +59,7 @@ pub async fn show(_storage: &Storage, _cache: &Cache) -> Result<()> {
         println!("\n✓ System is healthy");
         println!("\n  CPU:    {:.1}%", cpu);
         println!("  Memory: {:.1}%", mem_pct);
-        println!("  Load:   {:.2} {:.2} {:.2}", load.one, load.five, load.fifteen);
+        println!("  Load:   {:.2} {:.2} {:.2}", load1, load5, load15);
     } else {
         println!("\n⚠ Issues detected:");
         for issue in &issues {
@@ -65,6 +67,30 @@ pub async fn show(_storage: &Storage, _cache: &Cache) -> Result<()> {
         }
     }
 
+    // Fill forecasts from the daemon's usage history
+    if let Ok(history) = crate::history::History::open_default() {
+        let forecasts: Vec<_> = metrics
+            .disks
+            .iter()
+            .filter_map(|d| {
+                history
+                    .forecast_fill(&d.mount_point, metrics.timestamp)
+                    .map(|f| (d, f))
+            })
+            .collect();
+        if !forecasts.is_empty() {
+            println!("\n  Disk usage trend:");
+            for (disk, forecast) in forecasts {
+                println!(
+                    "    {:<20} {:>5.1}%  {}",
+                    disk.mount_point,
+                    disk.used_pct(),
+                    forecast
+                );
+            }
+        }
+    }
+
     // Check for failed services
     let output = tokio::process::Command::new("systemctl")
         .args(["--user", "--failed", "--no-legend"])
@@ -43,6 +43,9 @@ pub fn validate_service_name(name: &str) -> Result<&str, &'static str> {
     if name.is_empty() {
         return Err("Empty service name not allowed");
     }
+    if name.starts_with('-') {
+        return Err("Service name must not start with a dash");
+    }
 
     for c in name.chars() {
         let is_safe = c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '.' || c == '@';
@@ -74,6 +77,106 @@ pub fn validate_pattern(pattern: &str) -> Result<&str, &'static str> {
     Ok(pattern)
 }
 
+/// Validate a package name before passing it to rpm/dpkg-query/pacman
+pub fn validate_package_name(name: &str) -> Result<&str, &'static str> {
+    if name.is_empty() {
+        return Err("Empty package name not allowed");
+    }
+    if name.starts_with('-') {
+        return Err("Package name must not start with a dash");
+    }
+
+    for c in name.chars() {
+        let is_safe = c.is_ascii_alphanumeric()
+            || c == '-' || c == '_' || c == '.' || c == '+' || c == ':';
+        if !is_safe {
+            return Err("Package name contains invalid character");
+        }
+    }
+
+    Ok(name)
+}
+
+/// Validate a kernel module name before passing it to modprobe
+pub fn validate_module_name(name: &str) -> Result<&str, &'static str> {
+    if name.is_empty() {
+        return Err("Empty module name not allowed");
+    }
+    if name.starts_with('-') {
+        return Err("Module name must not start with a dash");
+    }
+
+    for c in name.chars() {
+        let is_safe = c.is_ascii_alphanumeric() || c == '-' || c == '_';
+        if !is_safe {
+            return Err("Module name contains invalid character");
+        }
+    }
+
+    Ok(name)
+}
+
+/// Validate kernel module parameters (`param=value ...`) for modprobe
+pub fn validate_module_options(options: &str) -> Result<&str, &'static str> {
+    for option in options.split_whitespace() {
+        let Some((param, value)) = option.split_once('=') else {
+            return Err("Module option must be param=value");
+        };
+        if param.is_empty() || !param.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+            return Err("Module parameter contains invalid character");
+        }
+        let value_is_safe = value
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.' | ',' | ':'));
+        if !value_is_safe {
+            return Err("Module option value contains invalid character");
+        }
+    }
+
+    Ok(options)
+}
+
+/// Validate a rule ID before using it as a file name in the rules directory
+pub fn validate_rule_id(id: &str) -> Result<&str, &'static str> {
+    if id.is_empty() {
+        return Err("Empty rule ID not allowed");
+    }
+    if id.starts_with('.') {
+        return Err("Rule ID must not start with a dot");
+    }
+
+    for c in id.chars() {
+        let is_safe = c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '.';
+        if !is_safe {
+            return Err("Rule ID contains invalid character");
+        }
+    }
+
+    Ok(id)
+}
+
+/// Validate a solution ID from outside (a dump, a peer) before storing it
+pub fn validate_solution_id(id: &str) -> Result<&str, &'static str> {
+    if id.is_empty() {
+        return Err("Empty solution ID not allowed");
+    }
+    if id.len() > 128 {
+        return Err("Solution ID too long");
+    }
+    if id.starts_with('.') {
+        return Err("Solution ID must not start with a dot");
+    }
+
+    for c in id.chars() {
+        let is_safe = c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '.';
+        if !is_safe {
+            return Err("Solution ID contains invalid character");
+        }
+    }
+
+    Ok(id)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -100,6 +203,7 @@ fn test_service_name() {
         assert!(validate_service_name("systemd-resolved").is_ok());
         assert!(validate_service_name("user@1000").is_ok());
         assert!(validate_service_name("nginx; rm -rf /").is_err());
+        assert!(validate_service_name("--now").is_err());
     }
 
     #[test]
@@ -109,4 +213,45 @@ fn test_pattern() {
         assert!(validate_pattern("test_process").is_ok());
         assert!(validate_pattern("bad$(id)").is_err());
     }
+
+    #[test]
+    fn test_package_name() {
+        assert!(validate_package_name("akmod-nvidia").is_ok());
+        assert!(validate_package_name("g++").is_ok());
+        assert!(validate_package_name("libc6:amd64").is_ok());
+        assert!(validate_package_name("--force").is_err());
+        assert!(validate_package_name("pkg; reboot").is_err());
+    }
+
+    #[test]
+    fn test_module() {
+        assert!(validate_module_name("iwlwifi").is_ok());
+        assert!(validate_module_name("nvidia_drm").is_ok());
+        assert!(validate_module_name("-r").is_err());
+        assert!(validate_module_name("../evil").is_err());
+        assert!(validate_module_options("power_save=0 debug=0x1,2").is_ok());
+        assert!(validate_module_options("").is_ok());
+        assert!(validate_module_options("-v").is_err());
+        assert!(validate_module_options("opt=$(id)").is_err());
+    }
+
+    #[test]
+    fn test_rule_id() {
+        assert!(validate_rule_id("rule-4f2c9a1e").is_ok());
+        assert!(validate_rule_id("nvidia_driver.v2").is_ok());
+        assert!(validate_rule_id("../etc/passwd").is_err());
+        assert!(validate_rule_id("rules/evil").is_err());
+        assert!(validate_rule_id(".hidden").is_err());
+        assert!(validate_rule_id("").is_err());
+    }
+
+    #[test]
+    fn test_solution_id() {
+        assert!(validate_solution_id("3cfbe9d4-1240-45fb-8a23-57f3e40e4b1c").is_ok());
+        assert!(validate_solution_id("sol-wifi").is_ok());
+        assert!(validate_solution_id("").is_err());
+        assert!(validate_solution_id("a/b").is_err());
+        assert!(validate_solution_id("x y").is_err());
+        assert!(validate_solution_id(&"a".repeat(129)).is_err());
+    }
 }
@@ -0,0 +1,205 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+//! ArangoDB backend integration tests
+//!
+//! Run against a throwaway server with `just test-arangodb`, or point
+//! `PSA_TEST_ARANGODB` at one (e.g. `http://127.0.0.1:18529`, no auth).
+//! Each test uses its own database. Without the variable the tests pass
+//! vacuously.
+#![cfg(feature = "arangodb")]
+
+use personal_sysadmin::storage::arango::ArangoStore;
+use personal_sysadmin::storage::{
+    KnowledgeStore, ProblemRelation, Solution, SolutionSource, Storage, StorageBackend,
+    StorageConfig,
+};
+
+async fn store() -> Option<ArangoStore> {
+    let Ok(url) = std::env::var("PSA_TEST_ARANGODB") else {
+        eprintln!("PSA_TEST_ARANGODB not set, skipping");
+        return None;
+    };
+    let url = url.trim_start_matches("http://");
+    let (host, port) = url
+        .trim_end_matches('/')
+        .split_once(':')
+        .unwrap_or((url, "8529"));
+    let config = StorageConfig {
+        backend: StorageBackend::ArangoDb,
+        host: host.to_string(),
+        port: port.parse().expect("PSA_TEST_ARANGODB port"),
+        database: format!("psa_test_{}", uuid::Uuid::new_v4().simple()),
+        username: String::new(),
+        ..Default::default()
+    };
+    Some(
+        ArangoStore::connect(&config)
+            .await
+            .expect("connect to test ArangoDB"),
+    )
+}
+
+fn solution(id: &str, category: &str, problem: &str, text: &str) -> Solution {
+    let now = chrono::Utc::now();
+    Solution {
+        id: id.to_string(),
+        category: category.to_string(),
+        problem: problem.to_string(),
+        solution: text.to_string(),
+        commands: vec![],
+        tags: vec![category.to_string()],
+        success_count: 0,
+        failure_count: 0,
+        source: SolutionSource::Manual,
+        created_at: now,
+        updated_at: now,
+    }
+}
+
+fn ids(solutions: Vec<Solution>) -> Vec<String> {
+    solutions.into_iter().map(|s| s.id).collect()
+}
+
+#[tokio::test]
+async fn test_store_and_outcomes() {
+    let Some(store) = store().await else { return };
+
+    store
+        .store_solution(&solution("s1", "disk", "Disk full", "Vacuum the journal"))
+        .await
+        .unwrap();
+    store.record_outcome("s1", true).await.unwrap();
+    store.record_outcome("s1", false).await.unwrap();
+    store.record_outcome("s1", true).await.unwrap();
+    assert!(store.record_outcome("missing", true).await.is_err());
+
+    let disk = store.find_by_category("disk").await.unwrap();
+    assert_eq!(disk.len(), 1);
+    assert_eq!((disk[0].success_count, disk[0].failure_count), (2, 1));
+    assert!(matches!(disk[0].source, SolutionSource::Manual));
+
+    // Storing again updates in place
+    let mut edited = solution("s1", "disk", "Disk full", "Clean the package cache");
+    edited.success_count = 2;
+    store.store_solution(&edited).await.unwrap();
+    let disk = store.find_by_category("disk").await.unwrap();
+    assert_eq!(disk.len(), 1);
+    assert_eq!(disk[0].solution, "Clean the package cache");
+}
+
+#[tokio::test]
+async fn test_search_view() {
+    let Some(store) = store().await else { return };
+
+    store
+        .store_solution(&solution(
+            "wifi",
+            "network",
+            "WiFi drops after suspend",
+            "Reload iwlwifi with power_save=0",
+        ))
+        .await
+        .unwrap();
+    store
+        .store_solution(&solution("disk", "disk", "Disk full", "Vacuum the journal"))
+        .await
+        .unwrap();
+
+    // ArangoSearch commits asynchronously; wait for the view to catch up
+    let mut found = vec![];
+    for _ in 0..50 {
+        found = store.search("wifi (suspend)").await.unwrap();
+        if !found.is_empty() {
+            break;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+    }
+    assert_eq!(ids(found), ["wifi"]);
+    assert!(store.search("  ").await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn test_find_related_traverses_by_depth() {
+    let Some(store) = store().await else { return };
+
+    for (id, problem) in [
+        ("a", "no sound"),
+        ("b", "HDMI audio silent"),
+        ("c", "mic muted"),
+    ] {
+        store
+            .store_solution(&solution(id, "audio", problem, "restart pipewire"))
+            .await
+            .unwrap();
+    }
+    for (problem, to) in [("No sound", "b"), ("hdmi audio silent", "c")] {
+        store
+            .relate(&ProblemRelation {
+                from_problem: problem.to_string(),
+                to_solution: to.to_string(),
+                confidence: 0.6,
+                context: vec![],
+            })
+            .await
+            .unwrap();
+    }
+
+    assert!(store.find_related("no sound", 0).await.unwrap().is_empty());
+    assert_eq!(
+        ids(store.find_related("  No Sound ", 1).await.unwrap()),
+        ["a", "b"]
+    );
+    assert_eq!(
+        ids(store.find_related("no sound", 2).await.unwrap()),
+        ["a", "b", "c"]
+    );
+    assert_eq!(
+        ids(store.find_related("mic muted", 3).await.unwrap()),
+        ["c", "b", "a"]
+    );
+    assert!(store.find_related("unknown", 3).await.unwrap().is_empty());
+
+    let dangling = ProblemRelation {
+        from_problem: "x".to_string(),
+        to_solution: "nope".to_string(),
+        confidence: 1.0,
+        context: vec![],
+    };
+    assert!(store.relate(&dangling).await.is_err());
+}
+
+#[tokio::test]
+async fn test_revisions_and_merge() {
+    let Some(store) = store().await else { return };
+    let storage = Storage::with_backend(Box::new(store), StorageConfig::default());
+
+    storage
+        .store_solution(&solution("a", "disk", "Disk full", "Vacuum the journal"))
+        .await
+        .unwrap();
+    let mut edited = storage.require("a").await.unwrap();
+    edited.solution = "journalctl --vacuum-size=200M".to_string();
+    assert_eq!(storage.edit(&edited, "Be specific").await.unwrap(), 2);
+    let history = storage.history("a").await.unwrap();
+    assert_eq!(history.len(), 1);
+    assert_eq!(history[0].solution.solution, "Vacuum the journal");
+
+    storage
+        .store_solution(&solution(
+            "b",
+            "disk",
+            "Root partition full",
+            "Clean caches",
+        ))
+        .await
+        .unwrap();
+    storage.merge("a", "b").await.unwrap();
+    assert!(storage.get("b").await.unwrap().is_none());
+    let problems: Vec<_> = storage
+        .relations("a")
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|r| r.from_problem)
+        .collect();
+    assert!(problems.contains(&"root partition full".to_string()));
+}